@@ -0,0 +1,160 @@
+use super::geometry::Vector2Helpers;
+use super::transformable::MirrorAxis;
+use super::{color::Color, curves};
+
+use serde::{Deserialize, Serialize};
+use svg::node::element::path;
+
+/// The options of a calligraphy stroke, drawn with a flat nib. The width of the stroke depends on its direction relative to the nib
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "calligraphy_options")]
+pub struct CalligraphyOptions {
+    /// The width of the nib
+    #[serde(rename = "width")]
+    pub width: f64,
+    #[serde(rename = "stroke_color")]
+    pub stroke_color: Option<Color>,
+    /// The angle of the nib in radians, measured clockwise from the x axis
+    #[serde(rename = "nib_angle")]
+    pub nib_angle: f64,
+    /// The thickness of the nib relative to its width, between 0.0 and 1.0.
+    /// It is the width of the stroke when drawing parallel to the nib
+    #[serde(rename = "thickness")]
+    pub thickness: f64,
+    /// Wether the nib is turned along the tilt direction of the stylus, when the tilt is available
+    #[serde(rename = "follow_tilt")]
+    pub follow_tilt: bool,
+}
+
+impl Default for CalligraphyOptions {
+    fn default() -> Self {
+        Self {
+            width: Self::WIDTH_DEFAULT,
+            stroke_color: Some(Self::COLOR_DEFAULT),
+            nib_angle: Self::NIB_ANGLE_DEFAULT,
+            thickness: Self::THICKNESS_DEFAULT,
+            follow_tilt: false,
+        }
+    }
+}
+
+impl CalligraphyOptions {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 6.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    /// The default nib angle, the common angle for italic hands
+    pub const NIB_ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_4;
+    /// The default thickness
+    pub const THICKNESS_DEFAULT: f64 = 0.1;
+    /// The number of splits when approximating a segment of the stroke
+    const N_SPLITS: i32 = 8;
+
+    /// The unit vector along the nib. When following the tilt, the nib lies perpendicular to the direction the stylus is tilted to
+    pub fn nib_direction(&self, azimuth: Option<f64>) -> na::Vector2<f64> {
+        let angle = match azimuth {
+            Some(azimuth) if self.follow_tilt => azimuth + std::f64::consts::FRAC_PI_2,
+            _ => self.nib_angle,
+        };
+
+        na::vector![angle.cos(), angle.sin()]
+    }
+
+    /// Reflects the nib angle across the axis, so the nib of the mirrored stroke is the mirror image of the original nib
+    pub fn mirror(&mut self, axis: MirrorAxis) {
+        self.nib_angle = match axis {
+            MirrorAxis::Horizontal => std::f64::consts::PI - self.nib_angle,
+            MirrorAxis::Vertical => -self.nib_angle,
+        };
+    }
+
+    /// The width of the stroke when the nib moves into the given direction. Is the width of the nib if there is no direction
+    pub fn stroke_width(&self, nib: na::Vector2<f64>, direction: na::Vector2<f64>) -> f64 {
+        if direction.magnitude() == 0.0 {
+            return nib.magnitude();
+        }
+        let (half_nib, half_thickness) = self.half_extents(nib);
+        let normal = direction.unit_norm();
+
+        2.0 * (half_nib.dot(&normal).abs() + half_thickness.dot(&normal).abs())
+    }
+
+    /// The half extents of the nib rectangle, along the nib and across it
+    fn half_extents(&self, nib: na::Vector2<f64>) -> (na::Vector2<f64>, na::Vector2<f64>) {
+        let half_nib = nib * 0.5;
+        let half_thickness =
+            nib.unit_norm() * nib.magnitude() * self.thickness.clamp(0.0, 1.0) * 0.5;
+
+        (half_nib, half_thickness)
+    }
+}
+
+/// Composes the filled outline of the nib moved along the cubic bezier.
+/// The nibs are the vectors along the nib at the start and end, with the width of the nib as magnitude. They are interpolated in between.
+pub fn compose_cubbez_w_nib(
+    cubbez: curves::CubicBezier,
+    start_nib: na::Vector2<f64>,
+    end_nib: na::Vector2<f64>,
+    options: &CalligraphyOptions,
+) -> Vec<path::Command> {
+    let mut left = Vec::with_capacity(CalligraphyOptions::N_SPLITS as usize + 1);
+    let mut right = Vec::with_capacity(CalligraphyOptions::N_SPLITS as usize + 1);
+
+    for i in 0..=CalligraphyOptions::N_SPLITS {
+        let t = f64::from(i) / f64::from(CalligraphyOptions::N_SPLITS);
+
+        let pos = curves::cubbez_calc(cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end, t);
+        let normal =
+            curves::cubbez_derive_calc(cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end, t)
+                .unit_norm();
+        let (half_nib, half_thickness) = options.half_extents(start_nib.lerp(&end_nib, t));
+
+        // The outline is traced by the corner of the nib rectangle that lies farthest along the normal
+        let corner = half_nib * half_nib.dot(&normal).signum()
+            + half_thickness * half_thickness.dot(&normal).signum();
+
+        left.push(pos + corner);
+        right.push(pos - corner);
+    }
+
+    let mut commands = Vec::with_capacity(left.len() + right.len() + 1);
+
+    for (i, point) in left.iter().chain(right.iter().rev()).enumerate() {
+        if i == 0 {
+            commands.push(path::Command::Move(
+                path::Position::Absolute,
+                path::Parameters::from((point[0], point[1])),
+            ));
+        } else {
+            commands.push(path::Command::Line(
+                path::Position::Absolute,
+                path::Parameters::from((point[0], point[1])),
+            ));
+        }
+    }
+    commands.push(path::Command::Close);
+
+    commands
+}
+
+/// Composes the filled outline of the nib moved along the line. See `compose_cubbez_w_nib()`
+pub fn compose_line_w_nib(
+    line: curves::Line,
+    start_nib: na::Vector2<f64>,
+    end_nib: na::Vector2<f64>,
+    options: &CalligraphyOptions,
+) -> Vec<path::Command> {
+    let cubbez = curves::CubicBezier {
+        start: line.start,
+        cp1: line.start.lerp(&line.end, 1.0 / 3.0),
+        cp2: line.start.lerp(&line.end, 2.0 / 3.0),
+        end: line.end,
+    };
+
+    compose_cubbez_w_nib(cubbez, start_nib, end_nib, options)
+}
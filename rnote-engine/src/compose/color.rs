@@ -3,7 +3,7 @@ use rnote_fileformats::xoppformat;
 use gtk4::gdk;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "color")]
 pub struct Color {
     #[serde(rename = "r")]
@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use super::geometry::AABBHelpers;
 use super::shapes::Rectangle;
-use super::transformable::{Transform, Transformable};
+use super::transformable::{shear_point, MirrorAxis, Transform, Transformable};
 
 #[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 #[serde(default, rename = "line")]
@@ -35,6 +35,16 @@ impl Transformable for Line {
         self.start = mid - half_vec.component_mul(&scale);
         self.end = mid + half_vec.component_mul(&scale);
     }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: nalgebra::Point2<f64>) {
+        self.start = axis.mirror_point(self.start, center);
+        self.end = axis.mirror_point(self.end, center);
+    }
+
+    fn shear(&mut self, shear: nalgebra::Vector2<f64>, center: nalgebra::Point2<f64>) {
+        self.start = shear_point(self.start, shear, center);
+        self.end = shear_point(self.end, shear, center);
+    }
 }
 
 impl Line {
@@ -50,6 +60,7 @@ impl Line {
         Rectangle {
             cuboid: p2d::shape::Cuboid::new(na::vector![magn / 2.0, width / 2.0]),
             transform: Transform::new_w_isometry(na::Isometry2::new(self.start + vec / 2.0, angle)),
+            corner_radius: 0.0,
         }
     }
 }
@@ -71,6 +82,93 @@ pub struct CubicBezier {
     pub end: na::Vector2<f64>,
 }
 
+impl Transformable for CubicBezier {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.start += offset;
+        self.cp1 += offset;
+        self.cp2 += offset;
+        self.end += offset;
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        let mut isometry = na::Isometry2::identity();
+        isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+        self.start = (isometry * na::Point2::from(self.start)).coords;
+        self.cp1 = (isometry * na::Point2::from(self.cp1)).coords;
+        self.cp2 = (isometry * na::Point2::from(self.cp2)).coords;
+        self.end = (isometry * na::Point2::from(self.end)).coords;
+    }
+
+    fn scale(&mut self, scale: nalgebra::Vector2<f64>) {
+        let center = self.global_aabb().center().coords;
+
+        self.start = center + (self.start - center).component_mul(&scale);
+        self.cp1 = center + (self.cp1 - center).component_mul(&scale);
+        self.cp2 = center + (self.cp2 - center).component_mul(&scale);
+        self.end = center + (self.end - center).component_mul(&scale);
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: nalgebra::Point2<f64>) {
+        self.start = axis.mirror_point(self.start, center);
+        self.cp1 = axis.mirror_point(self.cp1, center);
+        self.cp2 = axis.mirror_point(self.cp2, center);
+        self.end = axis.mirror_point(self.end, center);
+    }
+
+    fn shear(&mut self, shear: nalgebra::Vector2<f64>, center: nalgebra::Point2<f64>) {
+        self.start = shear_point(self.start, shear, center);
+        self.cp1 = shear_point(self.cp1, shear, center);
+        self.cp2 = shear_point(self.cp2, shear, center);
+        self.end = shear_point(self.end, shear, center);
+    }
+}
+
+impl CubicBezier {
+    /// The bounds of the curve, calculated from its extremes
+    pub fn global_aabb(&self) -> AABB {
+        let mut points = vec![na::Point2::from(self.start), na::Point2::from(self.end)];
+
+        // The extremes are where the derivative a * t^2 + b * t + c is zero, for each dimension
+        let a = 3.0 * (self.end - 3.0 * self.cp2 + 3.0 * self.cp1 - self.start);
+        let b = 6.0 * (self.cp2 - 2.0 * self.cp1 + self.start);
+        let c = 3.0 * (self.cp1 - self.start);
+
+        for dim in 0..2 {
+            for t in solve_quadratic(a[dim], b[dim], c[dim]) {
+                if t > 0.0 && t < 1.0 {
+                    points.push(na::Point2::from(cubbez_calc(
+                        self.start, self.cp1, self.cp2, self.end, t,
+                    )));
+                }
+            }
+        }
+
+        AABB::from_points(&points)
+    }
+}
+
+/// The real solutions of a * x^2 + b * x + c = 0
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < f64::EPSILON {
+        if b.abs() < f64::EPSILON {
+            return vec![];
+        }
+        return vec![-c / b];
+    }
+
+    let discriminant = b.powi(2) - 4.0 * a * c;
+    if discriminant < 0.0 {
+        vec![]
+    } else {
+        let sqrt_discriminant = discriminant.sqrt();
+        vec![
+            (-b + sqrt_discriminant) / (2.0 * a),
+            (-b - sqrt_discriminant) / (2.0 * a),
+        ]
+    }
+}
+
 /// Bezier Curves
 
 // Coefficient a of quadratic bezier in polynomial form: C = a * t^2 + b * t + c
@@ -129,7 +227,7 @@ fn quadbez_derive_calc(
     quad_bezier_derive_coeff_a(p0, p1, p2) * t + quadbez_derive_coeff_b(p0, p1)
 }
 
-fn cubbez_calc(
+pub fn cubbez_calc(
     p0: na::Vector2<f64>,
     p1: na::Vector2<f64>,
     p2: na::Vector2<f64>,
@@ -153,6 +251,19 @@ fn cubbez_calc(
         .transpose()
 }
 
+// calculating the derivative of the cubic bezier curve for t: between 0.0 and 1.0
+pub fn cubbez_derive_calc(
+    p0: na::Vector2<f64>,
+    p1: na::Vector2<f64>,
+    p2: na::Vector2<f64>,
+    p3: na::Vector2<f64>,
+    t: f64,
+) -> na::Vector2<f64> {
+    3.0 * (1.0 - t).powi(2) * (p1 - p0)
+        + 6.0 * (1.0 - t) * t * (p2 - p1)
+        + 3.0 * t.powi(2) * (p3 - p2)
+}
+
 /// Returns (t1, t2) with t1, t2 between 0.0 and 1.0
 fn quadbez_solve_critical_points(
     a: na::Vector2<f64>,
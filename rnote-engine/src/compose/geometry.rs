@@ -362,3 +362,91 @@ pub fn scale_inner_bounds_to_new_outer_bounds(
         ],
     )
 }
+
+/// Simplifies the polyline with the Ramer-Douglas-Peucker algorithm.
+/// Returns the indices of the points which are kept, which always include the first and the last point
+pub fn simplify_polyline(points: &[na::Vector2<f64>], tolerance: f64) -> Vec<usize> {
+    if points.len() <= 2 {
+        return (0..points.len()).collect();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|i| (i, dist_to_segment(points[i], points[first], points[last])))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        if let Some((i, distance)) = farthest {
+            if distance > tolerance {
+                keep[i] = true;
+                ranges.push((first, i));
+                ranges.push((i, last));
+            }
+        }
+    }
+
+    keep.iter()
+        .enumerate()
+        .filter_map(|(i, &keep)| if keep { Some(i) } else { None })
+        .collect()
+}
+
+/// The distance of the point to the line segment between start and end
+pub fn dist_to_segment(
+    point: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> f64 {
+    let segment = end - start;
+    let len_squared = segment.norm_squared();
+    if len_squared == 0.0 {
+        return (point - start).magnitude();
+    }
+
+    let t = ((point - start).dot(&segment) / len_squared).clamp(0.0, 1.0);
+    (point - (start + segment * t)).magnitude()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simplify_polyline_keeps_short_polylines() {
+        assert!(simplify_polyline(&[], 1.0).is_empty());
+        assert_eq!(simplify_polyline(&[na::vector![0.0, 0.0]], 1.0), vec![0]);
+        assert_eq!(
+            simplify_polyline(&[na::vector![0.0, 0.0], na::vector![0.0, 0.0]], 1.0),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn simplify_polyline_removes_collinear_points() {
+        let points = (0..=10)
+            .map(|i| na::vector![f64::from(i), f64::from(i) * 0.5])
+            .collect::<Vec<_>>();
+
+        assert_eq!(simplify_polyline(&points, 0.1), vec![0, 10]);
+    }
+
+    #[test]
+    fn simplify_polyline_keeps_corners() {
+        // The small deviations are removed, the corner is kept
+        let points = [
+            na::vector![0.0, 0.0],
+            na::vector![5.0, 0.5],
+            na::vector![10.0, 0.0],
+            na::vector![10.5, 5.0],
+            na::vector![10.0, 10.0],
+        ];
+
+        assert_eq!(simplify_polyline(&points, 1.0), vec![0, 2, 4]);
+        // All points deviate more than a smaller tolerance
+        assert_eq!(simplify_polyline(&points, 0.1), vec![0, 1, 2, 3, 4]);
+    }
+}
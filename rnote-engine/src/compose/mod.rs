@@ -3,9 +3,11 @@ use rand::Rng;
 use rand::SeedableRng;
 use svg::node::{self, element};
 
+pub mod calligraphy;
 pub mod color;
 pub mod curves;
 pub mod geometry;
+pub mod pressurecurve;
 pub mod rough;
 pub mod shapes;
 pub mod smooth;
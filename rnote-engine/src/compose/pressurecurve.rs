@@ -0,0 +1,224 @@
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+
+/// The shape of a pressure curve
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "PressureCurveStyle")]
+#[serde(rename = "pressure_curve_style")]
+pub enum PressureCurveStyle {
+    #[enum_value(name = "Linear", nick = "linear")]
+    #[serde(rename = "linear")]
+    Linear,
+    #[enum_value(name = "EaseIn", nick = "ease-in")]
+    #[serde(rename = "ease_in")]
+    EaseIn,
+    #[enum_value(name = "EaseOut", nick = "ease-out")]
+    #[serde(rename = "ease_out")]
+    EaseOut,
+    #[enum_value(name = "EaseInOut", nick = "ease-in-out")]
+    #[serde(rename = "ease_in_out")]
+    EaseInOut,
+    #[enum_value(name = "Custom", nick = "custom")]
+    #[serde(rename = "custom")]
+    Custom,
+}
+
+impl Default for PressureCurveStyle {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+/// Maps the pressure of the input to the pressure the strokes are drawn with.
+/// The curves are cubic beziers from (0.0, 0.0) to (1.0, 1.0) in the same way as the css `cubic-bezier()` timing functions,
+/// with the input pressure on the x axis and the mapped pressure on the y axis.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename = "pressure_curve")]
+pub struct PressureCurve {
+    #[serde(rename = "style")]
+    pub style: PressureCurveStyle,
+    /// The first control point of the custom curve, inside the unit square
+    #[serde(rename = "custom_cp1")]
+    pub custom_cp1: na::Vector2<f64>,
+    /// The second control point of the custom curve, inside the unit square
+    #[serde(rename = "custom_cp2")]
+    pub custom_cp2: na::Vector2<f64>,
+    /// The mapped pressure is scaled into the range between min and max
+    #[serde(rename = "min")]
+    pub min: f64,
+    #[serde(rename = "max")]
+    pub max: f64,
+    /// Wether the mapped pressure also sets the opacity of the stroke
+    #[serde(rename = "map_to_opacity")]
+    pub map_to_opacity: bool,
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self {
+            style: PressureCurveStyle::default(),
+            custom_cp1: na::vector![0.25, 0.25],
+            custom_cp2: na::vector![0.75, 0.75],
+            min: 0.0,
+            max: 1.0,
+            map_to_opacity: false,
+        }
+    }
+}
+
+impl PressureCurve {
+    /// The minimum opacity when the pressure is mapped to the opacity, so that strokes don't disappear completely
+    pub const OPACITY_MIN: f64 = 0.1;
+    /// The iterations when solving the curve for the input pressure
+    const SOLVE_ITERATIONS: usize = 24;
+
+    /// The control points of the curve
+    pub fn control_points(&self) -> (na::Vector2<f64>, na::Vector2<f64>) {
+        match self.style {
+            PressureCurveStyle::Linear => (na::vector![0.0, 0.0], na::vector![1.0, 1.0]),
+            PressureCurveStyle::EaseIn => (na::vector![0.42, 0.0], na::vector![1.0, 1.0]),
+            PressureCurveStyle::EaseOut => (na::vector![0.0, 0.0], na::vector![0.58, 1.0]),
+            PressureCurveStyle::EaseInOut => (na::vector![0.42, 0.0], na::vector![0.58, 1.0]),
+            PressureCurveStyle::Custom => (
+                na::vector![
+                    self.custom_cp1[0].clamp(0.0, 1.0),
+                    self.custom_cp1[1].clamp(0.0, 1.0)
+                ],
+                na::vector![
+                    self.custom_cp2[0].clamp(0.0, 1.0),
+                    self.custom_cp2[1].clamp(0.0, 1.0)
+                ],
+            ),
+        }
+    }
+
+    /// Evaluates the curve for the input pressure, without the min / max range
+    pub fn eval_curve(&self, pressure: f64) -> f64 {
+        let x = pressure.clamp(0.0, 1.0);
+        if self.style == PressureCurveStyle::Linear {
+            return x;
+        }
+        let (cp1, cp2) = self.control_points();
+
+        let bezier = |t: f64, p1: f64, p2: f64| {
+            let mt = 1.0 - t;
+            3.0 * mt * mt * t * p1 + 3.0 * mt * t * t * p2 + t * t * t
+        };
+
+        // x(t) is monotonic because the control points are inside the unit square, so bisecting it finds the t for the pressure
+        let (mut t_min, mut t_max) = (0.0, 1.0);
+        for _ in 0..Self::SOLVE_ITERATIONS {
+            let t = (t_min + t_max) * 0.5;
+            if bezier(t, cp1[0], cp2[0]) < x {
+                t_min = t;
+            } else {
+                t_max = t;
+            }
+        }
+
+        bezier((t_min + t_max) * 0.5, cp1[1], cp2[1]).clamp(0.0, 1.0)
+    }
+
+    /// Maps the input pressure through the curve into the min / max range
+    pub fn apply(&self, pressure: f64) -> f64 {
+        let min = self.min.clamp(0.0, 1.0);
+        let max = self.max.clamp(min, 1.0);
+
+        min + (max - min) * self.eval_curve(pressure)
+    }
+
+    /// The opacity for the input pressure. Is always 1.0 if the pressure is not mapped to the opacity
+    pub fn opacity(&self, pressure: f64) -> f64 {
+        if self.map_to_opacity {
+            self.apply(pressure).max(Self::OPACITY_MIN)
+        } else {
+            1.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 1e-6;
+    const STYLES: [PressureCurveStyle; 5] = [
+        PressureCurveStyle::Linear,
+        PressureCurveStyle::EaseIn,
+        PressureCurveStyle::EaseOut,
+        PressureCurveStyle::EaseInOut,
+        PressureCurveStyle::Custom,
+    ];
+
+    fn curve_w_style(style: PressureCurveStyle) -> PressureCurve {
+        PressureCurve {
+            style,
+            ..PressureCurve::default()
+        }
+    }
+
+    #[test]
+    fn curves_are_monotonic_and_keep_the_ends() {
+        for style in STYLES {
+            let curve = curve_w_style(style);
+
+            assert!(curve.eval_curve(0.0).abs() < TOLERANCE, "{:?}", style);
+            assert!(
+                (curve.eval_curve(1.0) - 1.0).abs() < TOLERANCE,
+                "{:?}",
+                style
+            );
+
+            let mut prev = curve.eval_curve(0.0);
+            for i in 1..=100 {
+                let pressure = f64::from(i) / 100.0;
+                let mapped = curve.eval_curve(pressure);
+
+                assert!(
+                    mapped >= prev - TOLERANCE,
+                    "{:?} decreases at pressure {}",
+                    style,
+                    pressure
+                );
+                prev = mapped;
+            }
+        }
+    }
+
+    #[test]
+    fn min_above_max_maps_to_min() {
+        let curve = PressureCurve {
+            min: 0.8,
+            max: 0.2,
+            ..PressureCurve::default()
+        };
+
+        for pressure in [0.0, 0.3, 1.0] {
+            assert!((curve.apply(pressure) - 0.8).abs() < TOLERANCE);
+        }
+
+        let curve = PressureCurve {
+            min: -0.5,
+            max: 1.5,
+            ..PressureCurve::default()
+        };
+        assert!(curve.apply(0.0).abs() < TOLERANCE);
+        assert!((curve.apply(1.0) - 1.0).abs() < TOLERANCE);
+    }
+
+    #[test]
+    fn opacity_does_not_drop_below_floor() {
+        let mut curve = PressureCurve::default();
+        assert_eq!(curve.opacity(0.0), 1.0);
+
+        curve.map_to_opacity = true;
+        assert!((curve.opacity(0.0) - PressureCurve::OPACITY_MIN).abs() < TOLERANCE);
+        assert!((curve.opacity(0.05) - PressureCurve::OPACITY_MIN).abs() < TOLERANCE);
+        assert!((curve.opacity(0.5) - 0.5).abs() < TOLERANCE);
+        assert!((curve.opacity(1.0) - 1.0).abs() < TOLERANCE);
+
+        curve.min = 0.4;
+        assert!((curve.opacity(0.0) - 0.4).abs() < TOLERANCE);
+    }
+}
@@ -0,0 +1,495 @@
+use super::roughoptions::{FillStyle, RoughOptions};
+use super::roughshapes;
+use rand::Rng;
+use std::cmp::Ordering;
+use svg::node::element::path;
+
+/* The fillers of rough.js (hachure, zigzag, zigzag-line, hatch, dot, sunburst and dashed), which fill polygons with sketched lines or dots.
+All of them are based on the scan line hachure lines.
+*/
+
+type Line = [na::Vector2<f64>; 2];
+
+/// The minimum of the gaps, offsets and dash lengths, so that a fill of zero sized steps can't produce endless lines
+const MIN_STEP: f64 = 0.1;
+
+/// Generating the commands for the pattern fill of the polygons, according to the fill style of the options.
+/// The solid fill style is not a pattern and produces no commands
+pub(super) fn pattern_fill_polygons<R>(
+    polygons: Vec<Vec<na::Vector2<f64>>>,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    match options.fill_style {
+        FillStyle::Solid => Vec::new(),
+        FillStyle::Hachure => hachure(&polygons, options, rng),
+        FillStyle::Zigzag => zigzag(&polygons, options, rng),
+        FillStyle::ZigzagLine => zigzag_line(&polygons, options, rng),
+        FillStyle::Crosshatch => crosshatch(&polygons, options, rng),
+        FillStyle::Dots => dots(&polygons, options, rng),
+        FillStyle::Sunburst => sunburst(&polygons, options, rng),
+        FillStyle::Dashed => dashed(&polygons, options, rng),
+    }
+}
+
+/// The gap between the hachure lines. Negative gaps fall back to a multiple of the stroke width
+fn hachure_gap(options: &RoughOptions) -> f64 {
+    let gap = if options.hachure_gap < 0.0 {
+        options.stroke_width * 4.0
+    } else {
+        options.hachure_gap
+    };
+
+    gap.max(MIN_STEP)
+}
+
+/// The width of the fill lines and the diameter of the fill dots. Negative weights fall back to half of the stroke width
+pub(super) fn fill_weight(options: &RoughOptions) -> f64 {
+    if options.fill_weight < 0.0 {
+        options.stroke_width / 2.0
+    } else {
+        options.fill_weight
+    }
+}
+
+fn hachure<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let lines = polygon_hachure_lines(polygons, options);
+
+    render_lines(&lines, options, rng)
+}
+
+fn zigzag<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let gap = hachure_gap(options);
+    let mut gap_options = options.clone();
+    gap_options.hachure_gap = gap;
+
+    let lines = polygon_hachure_lines(polygons, &gap_options);
+
+    let zigzag_angle = options.hachure_angle.to_radians();
+    let dg = na::vector![
+        gap * 0.5 * zigzag_angle.cos(),
+        -gap * 0.5 * zigzag_angle.sin()
+    ];
+
+    let zigzag_lines = lines
+        .into_iter()
+        .filter(|[start, end]| (end - start).magnitude() > 0.0)
+        .flat_map(|[start, end]| [[start - dg, end], [start + dg, end]])
+        .collect::<Vec<Line>>();
+
+    render_lines(&zigzag_lines, options, rng)
+}
+
+fn zigzag_line<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let gap = hachure_gap(options);
+    let zigzag_offset = if options.zigzag_offset < 0.0 {
+        gap
+    } else {
+        options.zigzag_offset.max(MIN_STEP)
+    };
+    let mut gap_options = options.clone();
+    gap_options.hachure_gap = gap + zigzag_offset;
+
+    let lines = polygon_hachure_lines(polygons, &gap_options);
+
+    let mut commands = Vec::new();
+    for line in lines {
+        let length = (line[1] - line[0]).magnitude();
+        let count = (length / (2.0 * zigzag_offset)).round() as usize;
+        let (start, alpha) = line_start_and_angle(line);
+        let direction = na::vector![alpha.cos(), alpha.sin()];
+        let zig_direction = na::vector![
+            (alpha + std::f64::consts::FRAC_PI_4).cos(),
+            (alpha + std::f64::consts::FRAC_PI_4).sin()
+        ];
+        let dz = (2.0 * zigzag_offset.powi(2)).sqrt();
+
+        for i in 0..count {
+            let zig_start = start + direction * (i as f64 * 2.0 * zigzag_offset);
+            let zig_end = start + direction * ((i + 1) as f64 * 2.0 * zigzag_offset);
+            let zig_middle = zig_start + zig_direction * dz;
+
+            commands.append(&mut doubleline_fill(zig_start, zig_middle, options, rng));
+            commands.append(&mut doubleline_fill(zig_middle, zig_end, options, rng));
+        }
+    }
+
+    commands
+}
+
+fn crosshatch<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let mut commands = hachure(polygons, options, rng);
+
+    let mut crossed_options = options.clone();
+    crossed_options.hachure_angle = options.hachure_angle + 90.0;
+    commands.append(&mut hachure(polygons, &crossed_options, rng));
+
+    commands
+}
+
+fn dots<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    // The dots are placed on vertical lines
+    let mut vertical_options = options.clone();
+    vertical_options.hachure_angle = 0.0;
+    let lines = polygon_hachure_lines(polygons, &vertical_options);
+
+    let gap = hachure_gap(options);
+    let radius = fill_weight(options) / 2.0;
+    let randomness_offset = gap / 4.0;
+
+    let mut commands = Vec::new();
+    for line in lines {
+        let length = (line[1] - line[0]).magnitude();
+        let count = ((length / gap).ceil() - 1.0).max(0.0) as usize;
+        let offset = length - (count as f64 * gap);
+        let x = (line[0][0] + line[1][0]) / 2.0 - gap / 4.0;
+        let min_y = line[0][1].min(line[1][1]);
+
+        for i in 0..count {
+            let y = min_y + offset + (i as f64 * gap);
+            let center = na::vector![
+                x - randomness_offset + rng.gen_range(0.0..1.0) * 2.0 * randomness_offset,
+                y - randomness_offset + rng.gen_range(0.0..1.0) * 2.0 * randomness_offset
+            ];
+
+            commands
+                .append(&mut roughshapes::ellipse(center, radius, radius, options, rng).commands);
+        }
+    }
+
+    commands
+}
+
+fn sunburst<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let points = polygons.iter().flatten().copied().collect::<Vec<_>>();
+    if points.is_empty() {
+        return Vec::new();
+    }
+
+    // The rays start at the center of the vertices
+    let center = points
+        .iter()
+        .fold(na::Vector2::<f64>::zeros(), |acc, point| acc + point)
+        / points.len() as f64;
+    let max_radius = points
+        .iter()
+        .map(|point| (point - center).magnitude())
+        .fold(0.0, f64::max);
+    if max_radius <= 0.0 {
+        return Vec::new();
+    }
+
+    // The rays are spaced so that their distance at the farthest vertex is the hachure gap
+    let gap = hachure_gap(options);
+    let count = ((2.0 * std::f64::consts::PI * max_radius) / gap)
+        .ceil()
+        .max(4.0) as usize;
+    let start_angle = options.hachure_angle.to_radians();
+
+    let edges = polygon_edges(polygons);
+
+    let mut lines = Vec::new();
+    for i in 0..count {
+        let angle = start_angle + 2.0 * std::f64::consts::PI * i as f64 / count as f64;
+        let direction = na::vector![angle.cos(), angle.sin()];
+
+        // The parameters along the ray where it crosses the edges
+        let mut crossings = edges
+            .iter()
+            .filter_map(|&[first, second]| {
+                ray_segment_intersection(center, direction, first, second)
+            })
+            .collect::<Vec<f64>>();
+        crossings.sort_by(|first, second| first.partial_cmp(second).unwrap_or(Ordering::Equal));
+
+        // With an odd number of crossings, the center is inside and the first part of the ray starts there
+        if crossings.len() % 2 == 1 {
+            crossings.insert(0, 0.0);
+        }
+
+        for pair in crossings.chunks_exact(2) {
+            lines.push([center + direction * pair[0], center + direction * pair[1]]);
+        }
+    }
+
+    render_lines(&lines, options, rng)
+}
+
+fn dashed<R>(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let lines = polygon_hachure_lines(polygons, options);
+
+    let fallback_gap = hachure_gap(options);
+    let dash_length = if options.dash_offset < 0.0 {
+        fallback_gap
+    } else {
+        options.dash_offset.max(MIN_STEP)
+    };
+    let dash_gap = if options.dash_gap < 0.0 {
+        fallback_gap
+    } else {
+        options.dash_gap
+    };
+
+    let mut commands = Vec::new();
+    for line in lines {
+        let length = (line[1] - line[0]).magnitude();
+        let count = (length / (dash_length + dash_gap)).floor().max(0.0) as usize;
+        let start_offset = (length + dash_gap - (count as f64 * (dash_length + dash_gap))) / 2.0;
+        let (start, alpha) = line_start_and_angle(line);
+        let direction = na::vector![alpha.cos(), alpha.sin()];
+
+        for i in 0..count {
+            let dash_start = i as f64 * (dash_length + dash_gap);
+            let dash_end = dash_start + dash_length;
+
+            commands.append(&mut doubleline_fill(
+                start + direction * (dash_start + start_offset),
+                start + direction * (dash_end + start_offset),
+                options,
+                rng,
+            ));
+        }
+    }
+
+    commands
+}
+
+/// The hachure lines filling the polygons, rotated by the hachure angle
+pub(super) fn polygon_hachure_lines(
+    polygons: &[Vec<na::Vector2<f64>>],
+    options: &RoughOptions,
+) -> Vec<Line> {
+    let angle = options.hachure_angle + 90.0;
+    let gap = hachure_gap(options);
+
+    // The scan lines are horizontal, so the polygons are rotated into the scan direction and the lines back
+    let rotation = na::Rotation2::new(angle.to_radians());
+    let rotated_polygons = polygons
+        .iter()
+        .map(|polygon| {
+            polygon
+                .iter()
+                .map(|&point| rotation * point)
+                .collect::<Vec<na::Vector2<f64>>>()
+        })
+        .collect::<Vec<Vec<na::Vector2<f64>>>>();
+
+    let inverse_rotation = rotation.inverse();
+    straight_hachure_lines(&rotated_polygons, gap)
+        .into_iter()
+        .map(|[start, end]| [inverse_rotation * start, inverse_rotation * end])
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    y_min: f64,
+    y_max: f64,
+    x: f64,
+    inverse_slope: f64,
+}
+
+/// Horizontal scan lines with the given gap, clipped to the inside of the polygons (even-odd rule)
+fn straight_hachure_lines(polygons: &[Vec<na::Vector2<f64>>], gap: f64) -> Vec<Line> {
+    let mut edges = polygon_edges(polygons)
+        .into_iter()
+        .filter(|[first, second]| first[1] != second[1])
+        .map(|[first, second]| {
+            let y_min = first[1].min(second[1]);
+
+            Edge {
+                y_min,
+                y_max: first[1].max(second[1]),
+                x: if y_min == first[1] {
+                    first[0]
+                } else {
+                    second[0]
+                },
+                inverse_slope: (second[0] - first[0]) / (second[1] - first[1]),
+            }
+        })
+        .collect::<Vec<Edge>>();
+
+    edges.sort_by(|first, second| {
+        first
+            .y_min
+            .partial_cmp(&second.y_min)
+            .unwrap_or(Ordering::Equal)
+            .then(first.x.partial_cmp(&second.x).unwrap_or(Ordering::Equal))
+            .then(
+                first
+                    .y_max
+                    .partial_cmp(&second.y_max)
+                    .unwrap_or(Ordering::Equal),
+            )
+    });
+
+    let mut lines = Vec::new();
+    let mut y = if let Some(first) = edges.first() {
+        first.y_min
+    } else {
+        return lines;
+    };
+
+    let mut edges = edges.into_iter().peekable();
+    let mut active_edges = Vec::<Edge>::new();
+
+    while !active_edges.is_empty() || edges.peek().is_some() {
+        while let Some(edge) = edges.next_if(|edge| edge.y_min <= y) {
+            active_edges.push(edge);
+        }
+
+        active_edges.retain(|edge| edge.y_max > y);
+        active_edges
+            .sort_by(|first, second| first.x.partial_cmp(&second.x).unwrap_or(Ordering::Equal));
+
+        // filling between pairs of edges
+        for pair in active_edges.chunks_exact(2) {
+            lines.push([
+                na::vector![pair[0].x.round(), y],
+                na::vector![pair[1].x.round(), y],
+            ]);
+        }
+
+        y += gap;
+        for edge in active_edges.iter_mut() {
+            edge.x += gap * edge.inverse_slope;
+        }
+    }
+
+    lines
+}
+
+/// The edges of the polygons, which are closed if they are not already
+fn polygon_edges(polygons: &[Vec<na::Vector2<f64>>]) -> Vec<Line> {
+    polygons
+        .iter()
+        .filter(|polygon| polygon.len() > 1)
+        .flat_map(|polygon| {
+            let mut vertices = polygon.clone();
+            if vertices.first() != vertices.last() {
+                vertices.push(vertices[0]);
+            }
+
+            vertices
+                .windows(2)
+                .map(|window| [window[0], window[1]])
+                .collect::<Vec<Line>>()
+        })
+        .collect()
+}
+
+/// The start of the line with the smaller x coordinate, and the angle of the line from there
+fn line_start_and_angle(line: Line) -> (na::Vector2<f64>, f64) {
+    let (start, end) = if line[0][0] > line[1][0] {
+        (line[1], line[0])
+    } else {
+        (line[0], line[1])
+    };
+
+    (start, ((end[1] - start[1]) / (end[0] - start[0])).atan())
+}
+
+/// The parameter along the ray where it intersects the segment, if it does
+fn ray_segment_intersection(
+    origin: na::Vector2<f64>,
+    direction: na::Vector2<f64>,
+    first: na::Vector2<f64>,
+    second: na::Vector2<f64>,
+) -> Option<f64> {
+    let segment = second - first;
+    let denominator = direction.perp(&segment);
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let diff = first - origin;
+    let t = diff.perp(&segment) / denominator;
+    let u = diff.perp(&direction) / denominator;
+
+    // The end of the segment is excluded, so that rays through a vertex only cross once
+    if t >= 0.0 && (0.0..1.0).contains(&u) {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+fn render_lines<R>(lines: &[Line], options: &RoughOptions, rng: &mut R) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    lines
+        .iter()
+        .flat_map(|&[start, end]| doubleline_fill(start, end, options, rng))
+        .collect()
+}
+
+/// Like the double line of the outlines, but respecting the multistroke option for fills
+fn doubleline_fill<R>(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    if options.disable_multistroke_fill {
+        roughshapes::line(start, end, true, false, options, rng)
+    } else {
+        roughshapes::doubleline(start, end, options, rng)
+    }
+}
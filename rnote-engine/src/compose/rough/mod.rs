@@ -1,9 +1,11 @@
+mod fillers;
 pub mod roughoptions;
 mod roughshapes;
 
+use rand::Rng;
 use svg::node::element::{self, path};
 
-use roughoptions::RoughOptions;
+use roughoptions::{FillStyle, RoughOptions};
 
 use super::{curves, shapes};
 use crate::compose;
@@ -43,83 +45,32 @@ pub fn cubic_bezier(cubbez: curves::CubicBezier, options: &RoughOptions) -> elem
     options.apply_to_line(element::Path::new().set("d", path::Data::from(commands)))
 }
 
-/// Generating a rectangle
+/// Generating a rectangle, with rounded corners when it has a corner radius
 pub fn rectangle(rectangle: shapes::Rectangle, options: &RoughOptions) -> element::Group {
     let mut rng = compose::new_rng_default_pcg64(options.seed);
 
-    let mut commands = Vec::new();
     // Applying the transform at the end
     let top_left = -rectangle.cuboid.half_extents;
     let bottom_right = rectangle.cuboid.half_extents;
+    let corner_radius = rectangle
+        .corner_radius
+        .clamp(0.0, rectangle.max_corner_radius());
 
-    if !options.disable_multistroke {
-        commands.append(&mut roughshapes::doubleline(
-            top_left,
-            na::vector![bottom_right[0], top_left[1]],
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::doubleline(
-            na::vector![bottom_right[0], top_left[1]],
-            bottom_right,
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::doubleline(
-            bottom_right,
-            na::vector![top_left[0], bottom_right[1]],
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::doubleline(
-            na::vector![top_left[0], bottom_right[1]],
-            top_left,
-            options,
-            &mut rng,
-        ));
+    let (commands, fill_points) = if corner_radius > 0.0 {
+        rounded_rectangle_outline(top_left, bottom_right, corner_radius, options, &mut rng)
     } else {
-        commands.append(&mut roughshapes::line(
+        let corners = vec![
             top_left,
             na::vector![bottom_right[0], top_left[1]],
-            true,
-            false,
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::line(
-            na::vector![bottom_right[0], top_left[1]],
-            bottom_right,
-            true,
-            false,
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::line(
             bottom_right,
             na::vector![top_left[0], bottom_right[1]],
-            true,
-            false,
-            options,
-            &mut rng,
-        ));
-        commands.append(&mut roughshapes::line(
-            na::vector![top_left[0], bottom_right[1]],
-            top_left,
-            true,
-            false,
-            options,
-            &mut rng,
-        ));
-    }
+        ];
+
+        (linear_path(&corners, true, options, &mut rng), corners)
+    };
 
     let rect = options.apply_to_rect(element::Path::new().set("d", path::Data::from(commands)));
 
-    let fill_points = vec![
-        na::vector![top_left[0], top_left[1]],
-        na::vector![bottom_right[0], top_left[1]],
-        na::vector![bottom_right[0], bottom_right[1]],
-        na::vector![top_left[0], bottom_right[1]],
-    ];
     let fill_polygon = fill_polygon(fill_points, options);
 
     let transform_string = rectangle.transform.to_svg_transform_attr_str();
@@ -130,14 +81,96 @@ pub fn rectangle(rectangle: shapes::Rectangle, options: &RoughOptions) -> elemen
         .add(rect)
 }
 
-/// Generating a fill polygon
+/// The commands for the outline of a rectangle with rounded corners, and the points of the polygon to fill it.
+/// The edges are shortened by the radius and connected by quarter arcs, clockwise from the top edge
+fn rounded_rectangle_outline<R>(
+    top_left: na::Vector2<f64>,
+    bottom_right: na::Vector2<f64>,
+    corner_radius: f64,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> (Vec<path::Command>, Vec<na::Vector2<f64>>)
+where
+    R: rand::Rng + ?Sized,
+{
+    // The number of points approximating each corner arc of the fill polygon
+    const FILL_CORNER_STEPS: u32 = 8;
+    let radius = corner_radius;
+
+    // Each edge is followed by the arc around the center of the next corner, starting at the given angle
+    let edges_w_corners = [
+        (
+            na::vector![top_left[0] + radius, top_left[1]],
+            na::vector![bottom_right[0] - radius, top_left[1]],
+            na::vector![bottom_right[0] - radius, top_left[1] + radius],
+            1.5 * std::f64::consts::PI,
+        ),
+        (
+            na::vector![bottom_right[0], top_left[1] + radius],
+            na::vector![bottom_right[0], bottom_right[1] - radius],
+            na::vector![bottom_right[0] - radius, bottom_right[1] - radius],
+            0.0,
+        ),
+        (
+            na::vector![bottom_right[0] - radius, bottom_right[1]],
+            na::vector![top_left[0] + radius, bottom_right[1]],
+            na::vector![top_left[0] + radius, bottom_right[1] - radius],
+            std::f64::consts::FRAC_PI_2,
+        ),
+        (
+            na::vector![top_left[0], bottom_right[1] - radius],
+            na::vector![top_left[0], top_left[1] + radius],
+            na::vector![top_left[0] + radius, top_left[1] + radius],
+            std::f64::consts::PI,
+        ),
+    ];
+
+    let mut commands = Vec::new();
+    let mut fill_points = Vec::new();
+
+    for (edge_start, edge_end, corner_center, arc_start) in edges_w_corners {
+        // The edges vanish when the radius is half of the side length
+        if edge_start != edge_end {
+            commands.append(&mut segment_line(edge_start, edge_end, options, rng));
+        }
+        commands.append(&mut roughshapes::arc(
+            corner_center,
+            radius,
+            radius,
+            arc_start,
+            arc_start + std::f64::consts::FRAC_PI_2,
+            options,
+            rng,
+        ));
+
+        fill_points.extend((0..=FILL_CORNER_STEPS).map(|i| {
+            let angle = arc_start
+                + std::f64::consts::FRAC_PI_2 * f64::from(i) / f64::from(FILL_CORNER_STEPS);
+            corner_center + na::vector![angle.cos(), angle.sin()] * radius
+        }));
+    }
+
+    (commands, fill_points)
+}
+
+/// Generating a fill polygon, filled according to the fill style
 pub fn fill_polygon(coords: Vec<na::Vector2<f64>>, options: &RoughOptions) -> element::Path {
     let mut rng = compose::new_rng_default_pcg64(options.seed);
 
-    let mut commands = Vec::new();
-    commands.append(&mut roughshapes::fill_polygon(coords, options, &mut rng));
+    match options.fill_style {
+        FillStyle::Solid => {
+            let commands = roughshapes::fill_polygon(coords, options, &mut rng);
+
+            options.apply_to_fill_polygon_solid(
+                element::Path::new().set("d", path::Data::from(commands)),
+            )
+        }
+        _ => {
+            let commands = fillers::pattern_fill_polygons(vec![coords], options, &mut rng);
 
-    options.apply_to_fill_polygon_solid(element::Path::new().set("d", path::Data::from(commands)))
+            options.apply_to_fill_sketch(element::Path::new().set("d", path::Data::from(commands)))
+        }
+    }
 }
 
 /// Generating a ellipse
@@ -164,3 +197,372 @@ pub fn ellipse(ellipse: shapes::Ellipse, options: &RoughOptions) -> element::Gro
         .add(fill_polygon)
         .add(ellipse)
 }
+
+/// Generating a arrow
+pub fn arrow(arrow: shapes::Arrow, options: &RoughOptions) -> element::Group {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let mut commands = linear_path(
+        &[arrow.line.start, arrow.line.end],
+        false,
+        options,
+        &mut rng,
+    );
+    let mut group = element::Group::new();
+
+    for (head, head_points, tip) in [
+        (
+            arrow.start_head,
+            arrow.start_head_points(),
+            arrow.line.start,
+        ),
+        (arrow.end_head, arrow.end_head_points(), arrow.line.end),
+    ] {
+        let [first, second] = if let Some(head_points) = head_points {
+            head_points
+        } else {
+            continue;
+        };
+
+        match head {
+            shapes::ArrowHead::None => {}
+            shapes::ArrowHead::Open => {
+                commands.append(&mut linear_path(
+                    &[first, tip, second],
+                    false,
+                    options,
+                    &mut rng,
+                ));
+            }
+            shapes::ArrowHead::Filled => {
+                // The head is filled solid with the stroke color
+                let mut head_options = options.clone();
+                head_options.fill_color = options.stroke_color;
+                head_options.fill_style = FillStyle::Solid;
+                head_options.seed = Some(rng.gen::<u64>());
+
+                group = group.add(fill_polygon(vec![tip, first, second], &head_options));
+                commands.append(&mut linear_path(
+                    &[tip, first, second],
+                    true,
+                    options,
+                    &mut rng,
+                ));
+            }
+        }
+    }
+
+    group.add(options.apply_to_line(element::Path::new().set("d", path::Data::from(commands))))
+}
+
+/// Generating a polyline
+pub fn polyline(polyline: &shapes::Polyline, options: &RoughOptions) -> element::Path {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let commands = linear_path(&polyline.points, false, options, &mut rng);
+
+    options.apply_to_line(element::Path::new().set("d", path::Data::from(commands)))
+}
+
+/// Generating a polygon
+pub fn polygon(polygon: &shapes::Polygon, options: &RoughOptions) -> element::Group {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let commands = linear_path(&polygon.points, true, options, &mut rng);
+    let outline = options.apply_to_rect(element::Path::new().set("d", path::Data::from(commands)));
+
+    let fill_polygon = fill_polygon(polygon.points.clone(), options);
+
+    element::Group::new().add(fill_polygon).add(outline)
+}
+
+/// Generating a arc
+pub fn arc(arc: shapes::Arc, options: &RoughOptions) -> element::Path {
+    let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+    let (start, stop) = arc.ordered_angles();
+    let commands = roughshapes::arc(
+        na::vector![0.0, 0.0],
+        arc.radius,
+        arc.radius,
+        start,
+        stop,
+        options,
+        &mut rng,
+    );
+
+    options
+        .apply_to_line(element::Path::new().set("d", path::Data::from(commands)))
+        .set("transform", arc.transform.to_svg_transform_attr_str())
+}
+
+/// The commands for lines connecting the points, optionally closed back to the first point
+fn linear_path<R>(
+    points: &[na::Vector2<f64>],
+    close: bool,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: rand::Rng + ?Sized,
+{
+    let mut commands = Vec::new();
+
+    for segment in points.windows(2) {
+        commands.append(&mut segment_line(segment[0], segment[1], options, rng));
+    }
+    if close && points.len() > 2 {
+        commands.append(&mut segment_line(
+            points[points.len() - 1],
+            points[0],
+            options,
+            rng,
+        ));
+    }
+
+    commands
+}
+
+fn segment_line<R>(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: rand::Rng + ?Sized,
+{
+    if !options.disable_multistroke {
+        roughshapes::doubleline(start, end, options, rng)
+    } else {
+        roughshapes::line(start, end, true, false, options, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::color::Color;
+    use std::path::PathBuf;
+
+    const SEED: u64 = 42;
+    const FILL_STYLES: [FillStyle; 7] = [
+        FillStyle::Hachure,
+        FillStyle::Zigzag,
+        FillStyle::ZigzagLine,
+        FillStyle::Crosshatch,
+        FillStyle::Dots,
+        FillStyle::Sunburst,
+        FillStyle::Dashed,
+    ];
+
+    /// Compares the generated path data with the stored snapshot.
+    /// Snapshots are only recorded or updated when the `UPDATE_SNAPSHOTS` environment variable is set, a missing snapshot fails
+    fn assert_snapshot(name: &str, actual: &str) {
+        let snapshot_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/compose/rough/snapshots")
+            .join(format!("{}.snap", name));
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(snapshot_path.parent().unwrap()).unwrap();
+            std::fs::write(&snapshot_path, actual).unwrap();
+            return;
+        }
+
+        let expected = std::fs::read_to_string(&snapshot_path).unwrap_or_else(|e| {
+            panic!(
+                "reading snapshot `{}` failed with Err {}. Run with UPDATE_SNAPSHOTS=1 to record it",
+                name, e
+            )
+        });
+        assert_eq!(
+            expected, actual,
+            "snapshot `{}` differs. Rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+            name
+        );
+    }
+
+    fn fill_options(fill_style: FillStyle) -> RoughOptions {
+        RoughOptions {
+            seed: Some(SEED),
+            fill_color: Some(Color::BLACK),
+            fill_style,
+            // A wide gap keeps the snapshots small
+            hachure_gap: 8.0,
+            ..RoughOptions::default()
+        }
+    }
+
+    fn square(size: f64) -> Vec<na::Vector2<f64>> {
+        vec![
+            na::vector![0.0, 0.0],
+            na::vector![size, 0.0],
+            na::vector![size, size],
+            na::vector![0.0, size],
+        ]
+    }
+
+    /// A concave polygon, where the scan lines cross more than two edges
+    fn concave_polygon() -> Vec<na::Vector2<f64>> {
+        vec![
+            na::vector![0.0, 0.0],
+            na::vector![60.0, 0.0],
+            na::vector![60.0, 60.0],
+            na::vector![30.0, 20.0],
+            na::vector![0.0, 60.0],
+        ]
+    }
+
+    #[test]
+    fn hachure_lines_of_square() {
+        // With an angle of -90° the scan lines are not rotated
+        let options = RoughOptions {
+            hachure_angle: -90.0,
+            hachure_gap: 4.0,
+            ..RoughOptions::default()
+        };
+
+        let lines = fillers::polygon_hachure_lines(&[square(100.0)], &options);
+
+        assert_eq!(lines.len(), 25);
+        for (i, [start, end]) in lines.iter().enumerate() {
+            let y = i as f64 * 4.0;
+            assert!((start - na::vector![0.0, y]).magnitude() < 1e-9);
+            assert!((end - na::vector![100.0, y]).magnitude() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn hachure_lines_of_concave_polygon() {
+        let options = RoughOptions {
+            hachure_angle: -90.0,
+            hachure_gap: 10.0,
+            ..RoughOptions::default()
+        };
+
+        let lines = fillers::polygon_hachure_lines(&[concave_polygon()], &options);
+
+        // Below the notch the scan lines are split in two
+        let split_rows = lines.iter().filter(|[start, _]| start[1] >= 20.0).count();
+        let full_rows = lines.iter().filter(|[start, _]| start[1] < 20.0).count();
+        assert_eq!(full_rows, 2);
+        assert_eq!(split_rows, 8);
+    }
+
+    #[test]
+    fn zero_gaps_and_offsets_are_clamped() {
+        for fill_style in FILL_STYLES {
+            let options = RoughOptions {
+                hachure_gap: 0.0,
+                zigzag_offset: 0.0,
+                dash_offset: 0.0,
+                dash_gap: 0.0,
+                ..fill_options(fill_style)
+            };
+
+            // Would never finish without the clamping
+            let data = fill_path_data(square(10.0), &options);
+
+            assert!(data.starts_with('M'), "fill style {:?}", fill_style);
+        }
+    }
+
+    /// The path data of the pattern fill, generated with the seeded rng
+    fn fill_path_data(polygon: Vec<na::Vector2<f64>>, options: &RoughOptions) -> String {
+        let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+        let commands = fillers::pattern_fill_polygons(vec![polygon], options, &mut rng);
+
+        svg::node::Value::from(path::Data::from(commands)).to_string()
+    }
+
+    #[test]
+    fn fill_is_deterministic_with_seed() {
+        for fill_style in FILL_STYLES {
+            let options = fill_options(fill_style);
+
+            let first = fill_path_data(concave_polygon(), &options);
+            let second = fill_path_data(concave_polygon(), &options);
+
+            assert_eq!(first, second, "fill style {:?}", fill_style);
+            assert!(first.starts_with('M'), "fill style {:?}", fill_style);
+        }
+    }
+
+    #[test]
+    fn fill_styles_snapshots() {
+        for fill_style in FILL_STYLES {
+            let options = fill_options(fill_style);
+            let name = format!("{:?}", fill_style).to_lowercase();
+
+            assert_snapshot(
+                &format!("square_{}", name),
+                &fill_path_data(square(60.0), &options),
+            );
+            assert_snapshot(
+                &format!("concave_polygon_{}", name),
+                &fill_path_data(concave_polygon(), &options),
+            );
+        }
+    }
+
+    #[test]
+    fn ellipse_fill_snapshot() {
+        let options = fill_options(FillStyle::Hachure);
+        let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+        let ellipse_result =
+            roughshapes::ellipse(na::vector![0.0, 0.0], 40.0, 20.0, &options, &mut rng);
+
+        assert_snapshot(
+            "ellipse_outline",
+            &svg::node::Value::from(path::Data::from(ellipse_result.commands)).to_string(),
+        );
+        assert_snapshot(
+            "ellipse_hachure",
+            &fill_path_data(ellipse_result.estimated_points, &options),
+        );
+    }
+
+    #[test]
+    fn rounded_rectangle_snapshot() {
+        let options = fill_options(FillStyle::Hachure);
+        let mut rng = compose::new_rng_default_pcg64(options.seed);
+
+        let (commands, fill_points) = rounded_rectangle_outline(
+            na::vector![-30.0, -20.0],
+            na::vector![30.0, 20.0],
+            10.0,
+            &options,
+            &mut rng,
+        );
+
+        assert_snapshot(
+            "rounded_rectangle_outline",
+            &svg::node::Value::from(path::Data::from(commands)).to_string(),
+        );
+        assert_snapshot(
+            "rounded_rectangle_hachure",
+            &fill_path_data(fill_points, &options),
+        );
+    }
+
+    #[test]
+    fn rectangle_corner_radius_is_clamped() {
+        let options = fill_options(FillStyle::Hachure);
+        let rectangle_w_radius = |corner_radius| shapes::Rectangle {
+            cuboid: p2d::shape::Cuboid::new(na::vector![30.0, 20.0]),
+            corner_radius,
+            ..shapes::Rectangle::default()
+        };
+
+        assert_eq!(
+            rectangle(rectangle_w_radius(100.0), &options).to_string(),
+            rectangle(rectangle_w_radius(20.0), &options).to_string()
+        );
+        assert_ne!(
+            rectangle(rectangle_w_radius(10.0), &options).to_string(),
+            rectangle(rectangle_w_radius(0.0), &options).to_string()
+        );
+    }
+}
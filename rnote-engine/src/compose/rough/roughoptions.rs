@@ -1,9 +1,10 @@
 use crate::compose::color::Color;
+use gtk4::glib;
 use serde::{Deserialize, Serialize};
 use svg::node::element;
 
 /// The rough options
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "rough_options")]
 pub struct RoughOptions {
     /// limits the maximum offset the randomness is allowed to create.
@@ -99,9 +100,9 @@ impl Default for RoughOptions {
             stroke_width: Self::STROKE_WIDTH_DEFAULT,
             fill_color: None,
             fill_style: FillStyle::Hachure,
-            fill_weight: 0.5,
-            hachure_angle: -41.0,
-            hachure_gap: 4.0,
+            fill_weight: Self::FILL_WEIGHT_DEFAULT,
+            hachure_angle: Self::HACHURE_ANGLE_DEFAULT,
+            hachure_gap: Self::HACHURE_GAP_DEFAULT,
             curve_stepcount: Self::CURVESTEPCOUNT_DEFAULT,
             curve_fitting: 0.95,
             curve_tightness: 0.0,
@@ -145,6 +146,24 @@ impl RoughOptions {
     pub const CURVESTEPCOUNT_MAX: f64 = 1000.0;
     /// Curve stepcount default
     pub const CURVESTEPCOUNT_DEFAULT: f64 = 9.0;
+    /// Hachure angle min (in degrees)
+    pub const HACHURE_ANGLE_MIN: f64 = -180.0;
+    /// Hachure angle max (in degrees)
+    pub const HACHURE_ANGLE_MAX: f64 = 180.0;
+    /// Hachure angle default (in degrees)
+    pub const HACHURE_ANGLE_DEFAULT: f64 = -41.0;
+    /// Hachure gap min
+    pub const HACHURE_GAP_MIN: f64 = 0.1;
+    /// Hachure gap max
+    pub const HACHURE_GAP_MAX: f64 = 100.0;
+    /// Hachure gap default
+    pub const HACHURE_GAP_DEFAULT: f64 = 4.0;
+    /// Fill weight min
+    pub const FILL_WEIGHT_MIN: f64 = 0.1;
+    /// Fill weight max
+    pub const FILL_WEIGHT_MAX: f64 = 100.0;
+    /// Fill weight default
+    pub const FILL_WEIGHT_DEFAULT: f64 = 0.5;
 
     pub(super) fn apply_to_line(&self, mut path: element::Path) -> element::Path {
         path = if let Some(stroke) = self.stroke_color {
@@ -184,6 +203,32 @@ impl RoughOptions {
         path
     }
 
+    pub(super) fn apply_to_fill_sketch(&self, mut path: element::Path) -> element::Path {
+        // the sketched fill lines are drawn with the fill color
+        path = if let Some(fill) = self.fill_color {
+            path.set("stroke", fill.to_css_color())
+        } else {
+            path.set("stroke", "none")
+        };
+        path = path.set("stroke-width", super::fillers::fill_weight(self));
+
+        path = path.set("fill", "none");
+
+        path = path.set(
+            "stroke-dasharray",
+            self.fill_line_dash
+                .iter()
+                .map(|&no| {
+                    format! {"{}", no}
+                })
+                .collect::<Vec<String>>()
+                .join(" "),
+        );
+        path = path.set("stroke-dashoffset", self.fill_line_dash_offset);
+
+        path
+    }
+
     pub(super) fn apply_to_rect(&self, mut rect: element::Path) -> element::Path {
         rect = if let Some(stroke) = self.stroke_color {
             rect.set("stroke", stroke.to_css_color())
@@ -276,6 +321,36 @@ impl RoughOptions {
         self.curve_stepcount = curve_stepcount;
     }
 
+    /// Returns the hachure angle (in degrees)
+    pub fn hachure_angle(&self) -> f64 {
+        self.hachure_angle
+    }
+
+    /// Sets the hachure angle (in degrees)
+    pub fn set_hachure_angle(&mut self, hachure_angle: f64) {
+        self.hachure_angle = hachure_angle.clamp(Self::HACHURE_ANGLE_MIN, Self::HACHURE_ANGLE_MAX);
+    }
+
+    /// Returns the hachure gap
+    pub fn hachure_gap(&self) -> f64 {
+        self.hachure_gap
+    }
+
+    /// Sets the hachure gap
+    pub fn set_hachure_gap(&mut self, hachure_gap: f64) {
+        self.hachure_gap = hachure_gap.clamp(Self::HACHURE_GAP_MIN, Self::HACHURE_GAP_MAX);
+    }
+
+    /// Returns the fill weight
+    pub fn fill_weight(&self) -> f64 {
+        self.fill_weight
+    }
+
+    /// Sets the fill weight
+    pub fn set_fill_weight(&mut self, fill_weight: f64) {
+        self.fill_weight = fill_weight.clamp(Self::FILL_WEIGHT_MIN, Self::FILL_WEIGHT_MAX);
+    }
+
     /// Returns multistroke
     pub fn multistroke(&self) -> bool {
         !self.disable_multistroke
@@ -298,23 +373,33 @@ impl RoughOptions {
 }
 
 /// available Fill styles
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "RoughFillStyle")]
 pub enum FillStyle {
     /// Solid
+    #[enum_value(name = "Solid", nick = "solid")]
     Solid,
     /// Hachure
+    #[enum_value(name = "Hachure", nick = "hachure")]
     Hachure,
     /// Zigzag
+    #[enum_value(name = "Zigzag", nick = "zigzag")]
     Zigzag,
     /// Zigzagline
+    #[enum_value(name = "ZigzagLine", nick = "zigzag-line")]
     ZigzagLine,
     /// Crosshatch
+    #[enum_value(name = "Crosshatch", nick = "crosshatch")]
     Crosshatch,
     /// Dots
+    #[enum_value(name = "Dots", nick = "dots")]
     Dots,
     /// Sunburst
+    #[enum_value(name = "Sunburst", nick = "sunburst")]
     Sunburst,
     /// Dashed
+    #[enum_value(name = "Dashed", nick = "dashed")]
     Dashed,
 }
 
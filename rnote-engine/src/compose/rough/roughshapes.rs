@@ -359,6 +359,112 @@ where
     }
 }
 
+/// Generating a arc. The angles are in rad, stop must be larger than start
+pub(super) fn arc<R>(
+    center: na::Vector2<f64>,
+    radius_x: f64,
+    radius_y: f64,
+    start: f64,
+    stop: f64,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let mut commands = Vec::new();
+
+    let radius_x = radius_x + offset_opt(radius_x * 0.01, options, rng, None);
+    let radius_y = radius_y + offset_opt(radius_y * 0.01, options, rng, None);
+
+    let (start, stop) = if stop - start > std::f64::consts::PI * 2.0 {
+        (0.0, std::f64::consts::PI * 2.0)
+    } else {
+        (start, stop)
+    };
+
+    let ellipse_increment = std::f64::consts::PI * 2.0 / options.curve_stepcount;
+    let arc_increment = (ellipse_increment / 2.0).min((stop - start) / 2.0);
+    if arc_increment <= 0.0 {
+        return commands;
+    }
+
+    commands.append(&mut compute_arc(
+        arc_increment,
+        center,
+        radius_x,
+        radius_y,
+        start,
+        stop,
+        1.0,
+        options,
+        rng,
+    ));
+
+    if !options.disable_multistroke {
+        commands.append(&mut compute_arc(
+            arc_increment,
+            center,
+            radius_x,
+            radius_y,
+            start,
+            stop,
+            1.5,
+            options,
+            rng,
+        ));
+    }
+
+    commands
+}
+
+#[allow(clippy::too_many_arguments)]
+fn compute_arc<R>(
+    increment: f64,
+    center: na::Vector2<f64>,
+    radius_x: f64,
+    radius_y: f64,
+    start: f64,
+    stop: f64,
+    offset: f64,
+    options: &RoughOptions,
+    rng: &mut R,
+) -> Vec<path::Command>
+where
+    R: Rng + ?Sized,
+{
+    let rad_offset = start + offset_opt(0.1, options, rng, None);
+    let mut points = Vec::new();
+
+    points.push(na::vector![
+        offset_opt(offset, options, rng, None)
+            + center[0]
+            + 0.9 * radius_x * (rad_offset - increment).cos(),
+        offset_opt(offset, options, rng, None)
+            + center[1]
+            + 0.9 * radius_y * (rad_offset - increment).sin()
+    ]);
+
+    let mut angle = rad_offset;
+    while angle <= stop {
+        points.push(na::vector![
+            offset_opt(offset, options, rng, None) + center[0] + radius_x * angle.cos(),
+            offset_opt(offset, options, rng, None) + center[1] + radius_y * angle.sin()
+        ]);
+
+        angle += increment;
+    }
+
+    let stop_point = na::vector![
+        center[0] + radius_x * stop.cos(),
+        center[1] + radius_y * stop.sin()
+    ];
+    points.push(stop_point);
+    points.push(stop_point);
+
+    curve(points, None, options, rng)
+}
+
 pub(super) fn curve<R>(
     points: Vec<na::Vector2<f64>>,
     close_point: Option<na::Vector2<f64>>,
@@ -1,7 +1,9 @@
+use gtk4::glib;
 use p2d::bounding_volume::AABB;
 use serde::{Deserialize, Serialize};
 
-use super::transformable::{Transform, Transformable};
+use super::curves;
+use super::transformable::{shear_point, MirrorAxis, Transform, Transformable};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "rectangle")]
@@ -10,6 +12,9 @@ pub struct Rectangle {
     pub cuboid: p2d::shape::Cuboid,
     #[serde(rename = "transform")]
     pub transform: Transform,
+    /// The radius of the rounded corners, in the coordinates of the cuboid
+    #[serde(rename = "corner_radius")]
+    pub corner_radius: f64,
 }
 
 impl Default for Rectangle {
@@ -17,6 +22,7 @@ impl Default for Rectangle {
         Self {
             cuboid: p2d::shape::Cuboid::new(na::Vector2::zeros()),
             transform: Transform::default(),
+            corner_radius: 0.0,
         }
     }
 }
@@ -32,6 +38,76 @@ impl Rectangle {
 
         AABB::from_half_extents(center, half_extents)
     }
+
+    /// The corners, in the order top left, top right, bottom right, bottom left (before the transform)
+    pub fn corners(&self) -> [na::Vector2<f64>; 4] {
+        let half_extents = self.cuboid.half_extents;
+
+        [
+            na::vector![-half_extents[0], -half_extents[1]],
+            na::vector![half_extents[0], -half_extents[1]],
+            na::vector![half_extents[0], half_extents[1]],
+            na::vector![-half_extents[0], half_extents[1]],
+        ]
+        .map(|corner| self.transform.transform_point(corner.into()).coords)
+    }
+
+    /// Moves the corner with the given index (see corners()) to the position, while the opposite corner stays in place.
+    /// The transform is not scaled, only the cuboid changes.
+    /// Returns the new index of the moved corner, which changes when it is moved past the opposite edges
+    pub fn move_corner(&mut self, index: usize, pos: na::Vector2<f64>) -> usize {
+        let half_extents = self.cuboid.half_extents;
+        let opposite = match index % 4 {
+            0 => half_extents,
+            1 => na::vector![-half_extents[0], half_extents[1]],
+            2 => -half_extents,
+            _ => na::vector![half_extents[0], -half_extents[1]],
+        };
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return index,
+        };
+
+        self.cuboid.half_extents = ((local_pos - opposite) / 2.0).abs();
+        self.transform.transform =
+            self.transform.transform * na::Translation2::from((local_pos + opposite) / 2.0);
+        self.corner_radius = self.corner_radius.min(self.max_corner_radius());
+
+        match (local_pos[0] < opposite[0], local_pos[1] < opposite[1]) {
+            (true, true) => 0,
+            (false, true) => 1,
+            (false, false) => 2,
+            (true, false) => 3,
+        }
+    }
+
+    /// The upper limit of the corner radius
+    pub fn max_corner_radius(&self) -> f64 {
+        self.cuboid.half_extents.min()
+    }
+
+    /// The position of the handle for the corner radius, on the top edge
+    pub fn corner_radius_handle(&self) -> na::Vector2<f64> {
+        let half_extents = self.cuboid.half_extents;
+
+        self.transform
+            .transform_point(na::point![
+                -half_extents[0] + self.corner_radius,
+                -half_extents[1]
+            ])
+            .coords
+    }
+
+    /// Sets the corner radius from the position of its handle
+    pub fn move_corner_radius_handle(&mut self, pos: na::Vector2<f64>) {
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return,
+        };
+
+        self.corner_radius =
+            (local_pos[0] + self.cuboid.half_extents[0]).clamp(0.0, self.max_corner_radius());
+    }
 }
 
 impl Transformable for Rectangle {
@@ -46,6 +122,14 @@ impl Transformable for Rectangle {
     fn scale(&mut self, scale: na::Vector2<f64>) {
         self.transform.append_scale_mut(scale);
     }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.transform.append_mirror_wrt_point_mut(axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform.append_shear_wrt_point_mut(shear, center);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,17 +164,612 @@ impl Transformable for Ellipse {
     fn scale(&mut self, scale: na::Vector2<f64>) {
         self.transform.append_scale_mut(scale);
     }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.transform.append_mirror_wrt_point_mut(axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform.append_shear_wrt_point_mut(shear, center);
+    }
 }
 
 impl Ellipse {
     pub fn global_aabb(&self) -> AABB {
         let center = self.transform.transform * na::point![0.0, 0.0];
-        // using a vector to ignore the translation
-        let half_extents = na::Vector2::from_homogeneous(
-            self.transform.transform.into_inner().abs() * self.radii.to_homogeneous(),
-        )
-        .unwrap();
+        let matrix = self.transform.transform.matrix();
+        // The extremes of the transformed ellipse, which is exact for any linear part of the transform (e.g. when it is rotated or sheared)
+        let half_extents = na::vector![
+            (matrix[(0, 0)] * self.radii[0]).hypot(matrix[(0, 1)] * self.radii[1]),
+            (matrix[(1, 0)] * self.radii[0]).hypot(matrix[(1, 1)] * self.radii[1])
+        ];
 
         AABB::from_half_extents(center, half_extents)
     }
+
+    /// The handles for the radii, at the ends of the first and the second semi-axis
+    pub fn radii_handles(&self) -> [na::Vector2<f64>; 2] {
+        [
+            na::vector![self.radii[0], 0.0],
+            na::vector![0.0, self.radii[1]],
+        ]
+        .map(|handle| self.transform.transform_point(handle.into()).coords)
+    }
+
+    /// Sets the radius of the semi-axis with the given index (see radii_handles()) from the position of its handle
+    pub fn move_radius_handle(&mut self, index: usize, pos: na::Vector2<f64>) {
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return,
+        };
+
+        if index == 0 {
+            self.radii[0] = local_pos[0].abs();
+        } else {
+            self.radii[1] = local_pos[1].abs();
+        }
+    }
+}
+
+/// The head at an end of an arrow
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "ArrowHead")]
+#[serde(rename = "arrow_head")]
+pub enum ArrowHead {
+    #[enum_value(name = "None", nick = "none")]
+    #[serde(rename = "none")]
+    None,
+    #[enum_value(name = "Open", nick = "open")]
+    #[serde(rename = "open")]
+    Open,
+    #[enum_value(name = "Filled", nick = "filled")]
+    #[serde(rename = "filled")]
+    Filled,
+}
+
+impl Default for ArrowHead {
+    fn default() -> Self {
+        Self::Open
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "arrow")]
+pub struct Arrow {
+    /// The line of the arrow, the end head is at line.end
+    #[serde(rename = "line")]
+    pub line: curves::Line,
+    #[serde(rename = "start_head")]
+    pub start_head: ArrowHead,
+    #[serde(rename = "end_head")]
+    pub end_head: ArrowHead,
+    /// The length of the heads
+    #[serde(rename = "head_length")]
+    pub head_length: f64,
+    /// The angle (in rad) between the line and the sides of the heads
+    #[serde(rename = "head_angle")]
+    pub head_angle: f64,
+}
+
+impl Default for Arrow {
+    fn default() -> Self {
+        Self {
+            line: curves::Line::default(),
+            start_head: ArrowHead::None,
+            end_head: ArrowHead::Open,
+            head_length: Self::HEAD_LENGTH_DEFAULT,
+            head_angle: Self::HEAD_ANGLE_DEFAULT,
+        }
+    }
+}
+
+impl Transformable for Arrow {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.line.translate(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.line.rotate(angle, center);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.line.scale(scale);
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.line.mirror(axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.line.shear(shear, center);
+    }
+}
+
+impl Arrow {
+    /// The default head length
+    pub const HEAD_LENGTH_DEFAULT: f64 = 12.0;
+    /// The min head length
+    pub const HEAD_LENGTH_MIN: f64 = 1.0;
+    /// The max head length
+    pub const HEAD_LENGTH_MAX: f64 = 500.0;
+    /// The default head angle
+    pub const HEAD_ANGLE_DEFAULT: f64 = std::f64::consts::FRAC_PI_6;
+
+    /// The outer points of the head at the start. None if the arrow has no head there or its line has no length
+    pub fn start_head_points(&self) -> Option<[na::Vector2<f64>; 2]> {
+        if self.start_head == ArrowHead::None {
+            return None;
+        }
+        self.head_points(self.line.start, self.line.end)
+    }
+
+    /// The outer points of the head at the end. None if the arrow has no head there or its line has no length
+    pub fn end_head_points(&self) -> Option<[na::Vector2<f64>; 2]> {
+        if self.end_head == ArrowHead::None {
+            return None;
+        }
+        self.head_points(self.line.end, self.line.start)
+    }
+
+    fn head_points(
+        &self,
+        tip: na::Vector2<f64>,
+        towards: na::Vector2<f64>,
+    ) -> Option<[na::Vector2<f64>; 2]> {
+        let direction = towards - tip;
+        if direction.magnitude() == 0.0 {
+            return None;
+        }
+        let back = direction.normalize() * self.head_length;
+
+        Some([
+            tip + na::Rotation2::new(self.head_angle) * back,
+            tip + na::Rotation2::new(-self.head_angle) * back,
+        ])
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        let points = [self.line.start, self.line.end]
+            .into_iter()
+            .chain(self.start_head_points().into_iter().flatten())
+            .chain(self.end_head_points().into_iter().flatten())
+            .map(na::Point2::from)
+            .collect::<Vec<na::Point2<f64>>>();
+
+        AABB::from_points(&points)
+    }
+}
+
+/// A open path of connected lines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "polyline")]
+pub struct Polyline {
+    #[serde(rename = "points")]
+    pub points: Vec<na::Vector2<f64>>,
+}
+
+impl Transformable for Polyline {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        transform_points_translate(&mut self.points, offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        transform_points_rotate(&mut self.points, angle, center);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        transform_points_scale(&mut self.points, scale);
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        transform_points_mirror(&mut self.points, axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        transform_points_shear(&mut self.points, shear, center);
+    }
+}
+
+impl Polyline {
+    pub fn global_aabb(&self) -> AABB {
+        points_aabb(&self.points)
+    }
+}
+
+/// A closed path of connected lines
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "polygon")]
+pub struct Polygon {
+    /// The vertices. The last one is connected back to the first
+    #[serde(rename = "points")]
+    pub points: Vec<na::Vector2<f64>>,
+}
+
+impl Transformable for Polygon {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        transform_points_translate(&mut self.points, offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        transform_points_rotate(&mut self.points, angle, center);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        transform_points_scale(&mut self.points, scale);
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        transform_points_mirror(&mut self.points, axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        transform_points_shear(&mut self.points, shear, center);
+    }
+}
+
+impl Polygon {
+    pub fn global_aabb(&self) -> AABB {
+        points_aabb(&self.points)
+    }
+}
+
+/// A circular arc around the origin of its transform. The angles are in rad, going clockwise from the x axis (in the coordinate space with y pointing down)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "arc")]
+pub struct Arc {
+    #[serde(rename = "radius")]
+    pub radius: f64,
+    #[serde(rename = "start_angle")]
+    pub start_angle: f64,
+    /// The angle the arc sweeps over, starting at start_angle. Negative values sweep counterclockwise
+    #[serde(rename = "sweep_angle")]
+    pub sweep_angle: f64,
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+}
+
+impl Default for Arc {
+    fn default() -> Self {
+        Self {
+            radius: 0.0,
+            start_angle: 0.0,
+            sweep_angle: 0.0,
+            transform: Transform::default(),
+        }
+    }
+}
+
+impl Transformable for Arc {
+    fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: nalgebra::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center)
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform.append_scale_mut(scale);
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.transform.append_mirror_wrt_point_mut(axis, center);
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform.append_shear_wrt_point_mut(shear, center);
+    }
+}
+
+impl Arc {
+    /// The point at the angle, in local coordinates
+    pub fn local_point_at(&self, angle: f64) -> na::Vector2<f64> {
+        na::vector![angle.cos(), angle.sin()] * self.radius
+    }
+
+    /// The start and end angle, with end >= start
+    pub fn ordered_angles(&self) -> (f64, f64) {
+        if self.sweep_angle < 0.0 {
+            (self.start_angle + self.sweep_angle, self.start_angle)
+        } else {
+            (self.start_angle, self.start_angle + self.sweep_angle)
+        }
+    }
+
+    pub fn global_aabb(&self) -> AABB {
+        let (start, end) = self.ordered_angles();
+        let matrix = self.transform.transform.matrix();
+
+        // The extremes of the transformed circle in x and y direction. Only the ones which are on the arc are considered
+        let extreme_angles = [
+            matrix[(0, 1)].atan2(matrix[(0, 0)]),
+            matrix[(1, 1)].atan2(matrix[(1, 0)]),
+        ]
+        .into_iter()
+        .flat_map(|angle| [angle, angle + std::f64::consts::PI])
+        .filter_map(|angle| {
+            // Moving the angle into the range of the arc
+            let angle = start + (angle - start).rem_euclid(2.0 * std::f64::consts::PI);
+            if angle <= end {
+                Some(angle)
+            } else {
+                None
+            }
+        });
+
+        let points = [start, end]
+            .into_iter()
+            .chain(extreme_angles)
+            .map(|angle| {
+                self.transform
+                    .transform_point(na::Point2::from(self.local_point_at(angle)))
+            })
+            .collect::<Vec<na::Point2<f64>>>();
+
+        AABB::from_points(&points)
+    }
+}
+
+fn points_aabb(points: &[na::Vector2<f64>]) -> AABB {
+    AABB::from_points(
+        &points
+            .iter()
+            .map(|&point| na::Point2::from(point))
+            .collect::<Vec<na::Point2<f64>>>(),
+    )
+}
+
+fn transform_points_translate(points: &mut [na::Vector2<f64>], offset: na::Vector2<f64>) {
+    points.iter_mut().for_each(|point| *point += offset);
+}
+
+fn transform_points_rotate(points: &mut [na::Vector2<f64>], angle: f64, center: na::Point2<f64>) {
+    let mut isometry = na::Isometry2::identity();
+    isometry.append_rotation_wrt_point_mut(&na::UnitComplex::new(angle), &center);
+
+    points
+        .iter_mut()
+        .for_each(|point| *point = (isometry * na::Point2::from(*point)).coords);
+}
+
+/// Scales the points relative to the center of their bounds
+fn transform_points_scale(points: &mut [na::Vector2<f64>], scale: na::Vector2<f64>) {
+    if points.is_empty() {
+        return;
+    }
+    let center = points_aabb(points).center().coords;
+
+    points
+        .iter_mut()
+        .for_each(|point| *point = center + (*point - center).component_mul(&scale));
+}
+
+fn transform_points_mirror(
+    points: &mut [na::Vector2<f64>],
+    axis: MirrorAxis,
+    center: na::Point2<f64>,
+) {
+    points
+        .iter_mut()
+        .for_each(|point| *point = axis.mirror_point(*point, center));
+}
+
+fn transform_points_shear(
+    points: &mut [na::Vector2<f64>],
+    shear: na::Vector2<f64>,
+    center: na::Point2<f64>,
+) {
+    points
+        .iter_mut()
+        .for_each(|point| *point = shear_point(*point, shear, center));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle() -> Rectangle {
+        Rectangle {
+            cuboid: p2d::shape::Cuboid::new(na::vector![10.0, 5.0]),
+            transform: Transform::new_w_isometry(na::Isometry2::new(na::vector![100.0, 50.0], 0.0)),
+            corner_radius: 0.0,
+        }
+    }
+
+    fn assert_corners_eq(corners: [na::Vector2<f64>; 4], expected: [na::Vector2<f64>; 4]) {
+        for (corner, expected) in corners.iter().zip(expected.iter()) {
+            assert!(
+                (corner - expected).magnitude() < 1e-9,
+                "corner {} != {}",
+                corner,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn move_corner_keeps_index_inside_opposite_edges() {
+        let mut rectangle = rectangle();
+
+        let index = rectangle.move_corner(2, na::vector![120.0, 60.0]);
+
+        assert_eq!(index, 2);
+        assert_corners_eq(
+            rectangle.corners(),
+            [
+                na::vector![90.0, 45.0],
+                na::vector![120.0, 45.0],
+                na::vector![120.0, 60.0],
+                na::vector![90.0, 60.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn move_corner_past_opposite_edges() {
+        let mut rectangle = rectangle();
+
+        // Past the left edge only, the bottom right corner becomes the bottom left corner
+        let index = rectangle.move_corner(2, na::vector![80.0, 60.0]);
+        assert_eq!(index, 3);
+        assert_corners_eq(
+            rectangle.corners(),
+            [
+                na::vector![80.0, 45.0],
+                na::vector![90.0, 45.0],
+                na::vector![90.0, 60.0],
+                na::vector![80.0, 60.0],
+            ],
+        );
+
+        // Continuing the drag with the new index past the top edge keeps the same anchor
+        let index = rectangle.move_corner(index, na::vector![70.0, 30.0]);
+        assert_eq!(index, 0);
+        assert_corners_eq(
+            rectangle.corners(),
+            [
+                na::vector![70.0, 30.0],
+                na::vector![90.0, 30.0],
+                na::vector![90.0, 45.0],
+                na::vector![70.0, 45.0],
+            ],
+        );
+
+        // And back to where the drag started
+        let index = rectangle.move_corner(index, na::vector![110.0, 55.0]);
+        assert_eq!(index, 2);
+        assert_corners_eq(
+            rectangle.corners(),
+            [
+                na::vector![90.0, 45.0],
+                na::vector![110.0, 45.0],
+                na::vector![110.0, 55.0],
+                na::vector![90.0, 55.0],
+            ],
+        );
+    }
+
+    fn assert_aabb_eq(aabb: AABB, mins: na::Vector2<f64>, maxs: na::Vector2<f64>) {
+        assert!(
+            (aabb.mins.coords - mins).magnitude() < 1e-3
+                && (aabb.maxs.coords - maxs).magnitude() < 1e-3,
+            "aabb {:?} != mins: {}, maxs: {}",
+            aabb,
+            mins,
+            maxs
+        );
+    }
+
+    #[test]
+    fn shear_point_wrt_center() {
+        let sheared = shear_point(
+            na::vector![20.0, 20.0],
+            na::vector![0.5, 0.0],
+            na::point![10.0, 10.0],
+        );
+
+        assert!((sheared - na::vector![25.0, 20.0]).magnitude() < 1e-9);
+    }
+
+    #[test]
+    fn shear_rectangle() {
+        // Sheared around its center, the center stays in place
+        let mut centered = rectangle();
+        centered.shear(na::vector![0.5, 0.0], na::point![100.0, 50.0]);
+
+        assert_eq!(
+            centered.transform.to_svg_transform_attr_str(),
+            "matrix(1.000 0.000 0.500 1.000 100.000 50.000)"
+        );
+        assert_aabb_eq(
+            centered.global_aabb(),
+            na::vector![87.5, 45.0],
+            na::vector![112.5, 55.0],
+        );
+
+        // Sheared around the origin, the center moves along
+        let mut at_origin = rectangle();
+        at_origin.shear(na::vector![0.5, 0.0], na::point![0.0, 0.0]);
+
+        assert_eq!(
+            at_origin.transform.to_svg_transform_attr_str(),
+            "matrix(1.000 0.000 0.500 1.000 125.000 50.000)"
+        );
+        assert_aabb_eq(
+            at_origin.global_aabb(),
+            na::vector![112.5, 45.0],
+            na::vector![137.5, 55.0],
+        );
+    }
+
+    #[test]
+    fn shear_ellipse() {
+        let mut ellipse = Ellipse {
+            radii: na::vector![10.0, 5.0],
+            transform: Transform::default(),
+        };
+        ellipse.shear(na::vector![0.0, 0.5], na::point![0.0, 0.0]);
+
+        assert_eq!(
+            ellipse.transform.to_svg_transform_attr_str(),
+            "matrix(1.000 0.500 0.000 1.000 0.000 0.000)"
+        );
+        // The points are (10 cos(t), 5 cos(t) + 5 sin(t)), so the extreme in y direction is 5 * sqrt(2)
+        let half_height = 5.0 * std::f64::consts::SQRT_2;
+        assert_aabb_eq(
+            ellipse.global_aabb(),
+            na::vector![-10.0, -half_height],
+            na::vector![10.0, half_height],
+        );
+    }
+
+    #[test]
+    fn shear_arc() {
+        // The quarter from (10, 0) to (0, 10)
+        let mut arc = Arc {
+            radius: 10.0,
+            start_angle: 0.0,
+            sweep_angle: std::f64::consts::FRAC_PI_2,
+            transform: Transform::default(),
+        };
+        arc.shear(na::vector![0.5, 0.0], na::point![0.0, 0.0]);
+
+        assert_eq!(
+            arc.transform.to_svg_transform_attr_str(),
+            "matrix(1.000 0.000 0.500 1.000 0.000 0.000)"
+        );
+        // The points are (10 cos(t) + 5 sin(t), 10 sin(t)), the extreme in x direction is at tan(t) = 0.5 and lies on the arc
+        assert_aabb_eq(
+            arc.global_aabb(),
+            na::vector![5.0, 0.0],
+            na::vector![125.0_f64.sqrt(), 10.0],
+        );
+    }
+
+    #[test]
+    fn shear_polygon() {
+        let mut polygon = Polygon {
+            points: vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![0.0, 10.0],
+            ],
+        };
+        polygon.shear(na::vector![0.5, 0.0], na::point![0.0, 0.0]);
+
+        assert_eq!(
+            polygon.points,
+            vec![
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 0.0],
+                na::vector![5.0, 10.0],
+            ]
+        );
+        assert_aabb_eq(
+            polygon.global_aabb(),
+            na::vector![0.0, 0.0],
+            na::vector![10.0, 10.0],
+        );
+    }
 }
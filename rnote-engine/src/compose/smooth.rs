@@ -1,10 +1,111 @@
 use super::geometry::Vector2Helpers;
 use super::{color::Color, curves, shapes};
 
+use gtk4::glib;
 use serde::{Deserialize, Serialize};
 use svg::node::element::{self, path};
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// The shape at the ends of lines and dashes
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "LineCap")]
+#[serde(rename = "line_cap")]
+pub enum LineCap {
+    #[enum_value(name = "Round", nick = "round")]
+    #[serde(rename = "round")]
+    Round,
+    /// Like butt, but extended by half of the width
+    #[enum_value(name = "Square", nick = "square")]
+    #[serde(rename = "square")]
+    Square,
+    /// Ends exactly at the end of the line
+    #[enum_value(name = "Butt", nick = "butt")]
+    #[serde(rename = "butt")]
+    Butt,
+}
+
+impl Default for LineCap {
+    /// Butt is the default in SVG, so documents from before the line cap was configurable keep their look
+    fn default() -> Self {
+        Self::Butt
+    }
+}
+
+impl LineCap {
+    pub fn as_svg_attr_str(&self) -> &'static str {
+        match self {
+            Self::Round => "round",
+            Self::Square => "square",
+            Self::Butt => "butt",
+        }
+    }
+
+    /// How far the cap extends beyond the end of a line with the given width
+    pub fn extension(&self, width: f64) -> f64 {
+        match self {
+            Self::Round | Self::Square => width * 0.5,
+            Self::Butt => 0.0,
+        }
+    }
+}
+
+/// The dash pattern of lines. The lengths of the dashes and gaps are relative to the width
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "DashPattern")]
+#[serde(rename = "dash_pattern")]
+pub enum DashPattern {
+    #[enum_value(name = "Solid", nick = "solid")]
+    #[serde(rename = "solid")]
+    Solid,
+    #[enum_value(name = "Dashed", nick = "dashed")]
+    #[serde(rename = "dashed")]
+    Dashed,
+    #[enum_value(name = "Dotted", nick = "dotted")]
+    #[serde(rename = "dotted")]
+    Dotted,
+    #[enum_value(name = "DashDotted", nick = "dash-dotted")]
+    #[serde(rename = "dash_dotted")]
+    DashDotted,
+}
+
+impl Default for DashPattern {
+    fn default() -> Self {
+        Self::Solid
+    }
+}
+
+impl DashPattern {
+    /// The lengths of the visible dashes and gaps, relative to the width. Empty for solid lines
+    fn visible_lengths(&self) -> &'static [f64] {
+        match self {
+            Self::Solid => &[],
+            Self::Dashed => &[3.0, 2.0],
+            Self::Dotted => &[1.0, 1.5],
+            Self::DashDotted => &[3.0, 1.5, 1.0, 1.5],
+        }
+    }
+
+    /// The dash array for the given width and line cap, as expected by the svg `stroke-dasharray` attribute.
+    /// The caps extend the dashes into the gaps, so the dashes are shortened and the gaps are lengthened by the extent of the caps. Empty for solid lines
+    pub fn dash_array(&self, width: f64, line_cap: LineCap) -> Vec<f64> {
+        let cap_extension = 2.0 * line_cap.extension(width);
+
+        self.visible_lengths()
+            .iter()
+            .enumerate()
+            .map(|(i, &length)| {
+                if i % 2 == 0 {
+                    (length * width - cap_extension).max(0.0)
+                } else {
+                    length * width + cap_extension
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "smoothoptions")]
 pub struct SmoothOptions {
     /// An optional seed to generate reproducable strokes
@@ -16,6 +117,16 @@ pub struct SmoothOptions {
     pub stroke_color: Option<Color>,
     #[serde(rename = "fill_color")]
     pub fill_color: Option<Color>,
+    /// How much faster strokes get thinner, between 0.0 (disabled) and 1.0
+    #[serde(rename = "velocity_thinning")]
+    pub velocity_thinning: f64,
+    #[serde(rename = "line_cap")]
+    pub line_cap: LineCap,
+    #[serde(rename = "dash_pattern")]
+    pub dash_pattern: DashPattern,
+    /// The offset of the dash pattern
+    #[serde(rename = "dash_offset")]
+    pub dash_offset: f64,
 }
 
 impl Default for SmoothOptions {
@@ -25,6 +136,10 @@ impl Default for SmoothOptions {
             width: Self::WIDTH_DEFAULT,
             stroke_color: Some(Self::COLOR_DEFAULT),
             fill_color: None,
+            velocity_thinning: 0.0,
+            line_cap: LineCap::default(),
+            dash_pattern: DashPattern::default(),
+            dash_offset: 0.0,
         }
     }
 }
@@ -43,6 +158,35 @@ impl SmoothOptions {
         b: 0.0,
         a: 1.0,
     };
+    /// The min dash offset
+    pub const DASH_OFFSET_MIN: f64 = 0.0;
+    /// The max dash offset
+    pub const DASH_OFFSET_MAX: f64 = 1000.0;
+    /// The velocity in coordinate units per second at which the width is thinned by half of the velocity thinning
+    pub const VELOCITY_THINNING_HALF: f64 = 1000.0;
+
+    /// The factor the width is multiplied with for the given velocity. Is 1.0 if velocity thinning is disabled or the velocity is not available
+    pub fn velocity_width_factor(&self, velocity: Option<f64>) -> f64 {
+        match velocity {
+            Some(velocity) if self.velocity_thinning > 0.0 => {
+                let velocity = velocity.max(0.0);
+
+                1.0 - self.velocity_thinning.clamp(0.0, 1.0) * velocity
+                    / (velocity + Self::VELOCITY_THINNING_HALF)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// The dash array for the svg `stroke-dasharray` attribute. Empty for solid lines
+    pub fn dash_array(&self) -> Vec<f64> {
+        self.dash_pattern.dash_array(self.width, self.line_cap)
+    }
+
+    /// Wether the lines are dashed
+    pub fn is_dashed(&self) -> bool {
+        self.dash_pattern != DashPattern::Solid
+    }
 }
 
 pub fn compose_line(
@@ -532,6 +676,236 @@ pub fn compose_cubbez_variable_width(
     commands
 }
 
+/// Composes the outline of connected lines with variable widths, each given with its start and end width.
+/// The start and the end of the lines get the given caps, the joints between the lines are round.
+pub fn compose_lines_variable_width(
+    lines: &[(curves::Line, f64, f64)],
+    start_cap: LineCap,
+    end_cap: LineCap,
+) -> Vec<path::Command> {
+    // Lines without length have no direction
+    let lines = lines
+        .iter()
+        .filter(|(line, _, _)| line.end != line.start)
+        .collect::<Vec<_>>();
+    let last = lines.len().saturating_sub(1);
+
+    lines
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, &(line, width_start, width_end))| {
+            compose_piece_variable_width(
+                line.start,
+                line.end,
+                line.end - line.start,
+                width_start,
+                width_end,
+                if i == 0 { start_cap } else { LineCap::Round },
+                if i == last { end_cap } else { LineCap::Round },
+            )
+        })
+        .collect()
+}
+
+/// Composes the dashed outline of connected lines with variable widths, each given with its start and end width.
+/// The dash pattern continues from the start length, so consecutive parts of the same path can be composed separately.
+/// The ends of the dashes get the line cap of the options, the joints between the lines are round.
+pub fn compose_lines_variable_width_dashed(
+    lines: &[(curves::Line, f64, f64)],
+    start_length: f64,
+    options: &SmoothOptions,
+) -> Vec<path::Command> {
+    let total_length: f64 = lines
+        .iter()
+        .map(|(line, _, _)| (line.end - line.start).magnitude())
+        .sum();
+    let mut commands = Vec::new();
+
+    for (dash_start, dash_end) in dash_intervals(
+        &options.dash_array(),
+        options.dash_offset,
+        start_length,
+        start_length + total_length,
+    ) {
+        let mut line_start_length = start_length;
+
+        for &(line, width_start, width_end) in lines.iter() {
+            let line_length = (line.end - line.start).magnitude();
+            let line_end_length = line_start_length + line_length;
+
+            let piece_start = dash_start.max(line_start_length);
+            let piece_end = dash_end.min(line_end_length);
+
+            // Only dots are allowed to have no length. Dots on a joint are only composed once, with the line that starts there
+            let is_piece = if dash_start == dash_end {
+                piece_start == piece_end
+                    && piece_start >= line_start_length
+                    && piece_start < line_end_length
+            } else {
+                piece_start < piece_end
+            };
+
+            if line_length > 0.0 && is_piece {
+                let t_start = (piece_start - line_start_length) / line_length;
+                let t_end = (piece_end - line_start_length) / line_length;
+
+                let start_cap = if piece_start == dash_start {
+                    options.line_cap
+                } else {
+                    LineCap::Round
+                };
+                let end_cap = if piece_end == dash_end {
+                    options.line_cap
+                } else {
+                    LineCap::Round
+                };
+
+                commands.append(&mut compose_piece_variable_width(
+                    line.start.lerp(&line.end, t_start),
+                    line.start.lerp(&line.end, t_end),
+                    line.end - line.start,
+                    width_start + (width_end - width_start) * t_start,
+                    width_start + (width_end - width_start) * t_end,
+                    start_cap,
+                    end_cap,
+                ));
+            }
+
+            line_start_length = line_end_length;
+        }
+    }
+
+    commands
+}
+
+/// The dashes of the pattern that overlap the path between the start and the end length, as (start, end) lengths along the path.
+/// The dashes are not clipped to the range. Returns one dash spanning everything if the pattern is empty
+fn dash_intervals(
+    dash_array: &[f64],
+    dash_offset: f64,
+    start_length: f64,
+    end_length: f64,
+) -> Vec<(f64, f64)> {
+    let period: f64 = dash_array.iter().sum();
+    if dash_array.is_empty() || period <= 0.0 {
+        return vec![(f64::NEG_INFINITY, f64::INFINITY)];
+    }
+
+    let mut intervals = Vec::new();
+    // The pattern position at a length along the path is (length + dash_offset), so this is where the period containing the start length begins
+    let mut pos = start_length - (start_length + dash_offset).rem_euclid(period);
+
+    while pos <= end_length {
+        for (i, &length) in dash_array.iter().enumerate() {
+            if i % 2 == 0 && pos <= end_length && pos + length >= start_length {
+                intervals.push((pos, pos + length));
+            }
+            pos += length;
+        }
+    }
+
+    intervals
+}
+
+/// Composes the closed outline of a straight piece with variable width and the given caps.
+/// The direction is passed separately, so that pieces without length can be composed as dots
+fn compose_piece_variable_width(
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+    direction: na::Vector2<f64>,
+    width_start: f64,
+    width_end: f64,
+    start_cap: LineCap,
+    end_cap: LineCap,
+) -> Vec<path::Command> {
+    let direction_unit = direction.normalize();
+    let direction_unit_norm = direction.unit_norm();
+    let (start_offset_dist, end_offset_dist) = (width_start / 2.0, width_end / 2.0);
+
+    let start_left = start + direction_unit_norm * start_offset_dist;
+    let start_right = start - direction_unit_norm * start_offset_dist;
+    let end_left = end + direction_unit_norm * end_offset_dist;
+    let end_right = end - direction_unit_norm * end_offset_dist;
+
+    let mut commands = vec![
+        path::Command::Move(
+            path::Position::Absolute,
+            path::Parameters::from((start_left[0], start_left[1])),
+        ),
+        path::Command::Line(
+            path::Position::Absolute,
+            path::Parameters::from((end_left[0], end_left[1])),
+        ),
+    ];
+
+    match end_cap {
+        LineCap::Round => {
+            commands.push(path::Command::EllipticalArc(
+                path::Position::Absolute,
+                path::Parameters::from((
+                    end_offset_dist,
+                    end_offset_dist,
+                    0.0,
+                    0.0,
+                    0.0,
+                    end_right[0],
+                    end_right[1],
+                )),
+            ));
+        }
+        LineCap::Square => {
+            let extension = direction_unit * end_offset_dist;
+            for point in [end_left + extension, end_right + extension, end_right] {
+                commands.push(path::Command::Line(
+                    path::Position::Absolute,
+                    path::Parameters::from((point[0], point[1])),
+                ));
+            }
+        }
+        LineCap::Butt => {
+            commands.push(path::Command::Line(
+                path::Position::Absolute,
+                path::Parameters::from((end_right[0], end_right[1])),
+            ));
+        }
+    }
+
+    commands.push(path::Command::Line(
+        path::Position::Absolute,
+        path::Parameters::from((start_right[0], start_right[1])),
+    ));
+
+    match start_cap {
+        LineCap::Round => {
+            commands.push(path::Command::EllipticalArc(
+                path::Position::Absolute,
+                path::Parameters::from((
+                    start_offset_dist,
+                    start_offset_dist,
+                    0.0,
+                    0.0,
+                    0.0,
+                    start_left[0],
+                    start_left[1],
+                )),
+            ));
+        }
+        LineCap::Square => {
+            let extension = direction_unit * start_offset_dist;
+            for point in [start_right - extension, start_left - extension] {
+                commands.push(path::Command::Line(
+                    path::Position::Absolute,
+                    path::Parameters::from((point[0], point[1])),
+                ));
+            }
+        }
+        LineCap::Butt => {}
+    }
+    commands.push(path::Command::Close);
+
+    commands
+}
+
 pub fn compose_rectangle(
     rectangle: shapes::Rectangle,
     options: &SmoothOptions,
@@ -551,7 +925,7 @@ pub fn compose_rectangle(
 
     let transform_string = rectangle.transform.to_svg_transform_attr_str();
 
-    svg::node::element::Rectangle::new()
+    let mut rect = svg::node::element::Rectangle::new()
         .set("transform", transform_string)
         .set("x", mins[0])
         .set("y", mins[1])
@@ -559,8 +933,15 @@ pub fn compose_rectangle(
         .set("height", maxs[1] - mins[1])
         .set("stroke", color)
         .set("stroke-width", options.width)
-        .set("fill", fill)
-        .into()
+        .set("fill", fill);
+    if rectangle.corner_radius > 0.0 {
+        rect = rect
+            .set("rx", rectangle.corner_radius)
+            .set("ry", rectangle.corner_radius);
+    }
+    apply_stroke_line_style(&mut rect, options);
+
+    rect.into()
 }
 
 pub fn compose_ellipse(ellipse: shapes::Ellipse, options: &SmoothOptions) -> element::Element {
@@ -577,7 +958,7 @@ pub fn compose_ellipse(ellipse: shapes::Ellipse, options: &SmoothOptions) -> ele
 
     let transform_string = ellipse.transform.to_svg_transform_attr_str();
 
-    svg::node::element::Ellipse::new()
+    let mut ellipse = svg::node::element::Ellipse::new()
         .set("transform", transform_string)
         .set("cx", 0_f64)
         .set("cy", 0_f64)
@@ -585,6 +966,191 @@ pub fn compose_ellipse(ellipse: shapes::Ellipse, options: &SmoothOptions) -> ele
         .set("ry", ellipse.radii[1])
         .set("stroke", color)
         .set("stroke-width", options.width)
-        .set("fill", fill)
-        .into()
+        .set("fill", fill);
+    apply_stroke_line_style(&mut ellipse, options);
+
+    ellipse.into()
+}
+
+pub fn compose_arrow(arrow: shapes::Arrow, options: &SmoothOptions) -> element::Element {
+    let color = css_color_or_none(options.stroke_color);
+
+    let commands = compose_line(arrow.line, true, options);
+    // The heads are never dashed
+    let mut open_heads_commands = Vec::new();
+    let mut filled_heads = element::Group::new();
+
+    for (head, head_points, tip) in [
+        (
+            arrow.start_head,
+            arrow.start_head_points(),
+            arrow.line.start,
+        ),
+        (arrow.end_head, arrow.end_head_points(), arrow.line.end),
+    ] {
+        let [first, second] = if let Some(head_points) = head_points {
+            head_points
+        } else {
+            continue;
+        };
+
+        match head {
+            shapes::ArrowHead::None => {}
+            shapes::ArrowHead::Open => {
+                open_heads_commands.push(path::Command::Move(
+                    path::Position::Absolute,
+                    path::Parameters::from((first[0], first[1])),
+                ));
+                open_heads_commands.push(path::Command::Line(
+                    path::Position::Absolute,
+                    path::Parameters::from((tip[0], tip[1])),
+                ));
+                open_heads_commands.push(path::Command::Line(
+                    path::Position::Absolute,
+                    path::Parameters::from((second[0], second[1])),
+                ));
+            }
+            shapes::ArrowHead::Filled => {
+                filled_heads = filled_heads.add(
+                    element::Polygon::new()
+                        .set("points", svg_points_str(&[tip, first, second]))
+                        .set("stroke", color.clone())
+                        .set("stroke-width", options.width)
+                        .set("stroke-linejoin", "round")
+                        .set("fill", color.clone()),
+                );
+            }
+        }
+    }
+
+    let mut line_path = element::Path::new()
+        .set("d", path::Data::from(commands))
+        .set("stroke", color.clone())
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("fill", "none");
+    apply_stroke_line_style(&mut line_path, options);
+
+    let mut group = element::Group::new().add(line_path);
+    if !open_heads_commands.is_empty() {
+        let mut heads_path = element::Path::new()
+            .set("d", path::Data::from(open_heads_commands))
+            .set("stroke", color)
+            .set("stroke-width", options.width)
+            .set("stroke-linejoin", "round")
+            .set("fill", "none");
+        if options.line_cap != LineCap::default() {
+            heads_path.assign("stroke-linecap", options.line_cap.as_svg_attr_str());
+        }
+        group = group.add(heads_path);
+    }
+
+    group.add(filled_heads).into()
+}
+
+pub fn compose_polyline(polyline: &shapes::Polyline, options: &SmoothOptions) -> element::Element {
+    let mut polyline = element::Polyline::new()
+        .set("points", svg_points_str(&polyline.points))
+        .set("stroke", css_color_or_none(options.stroke_color))
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("fill", "none");
+    apply_stroke_line_style(&mut polyline, options);
+
+    polyline.into()
+}
+
+pub fn compose_polygon(polygon: &shapes::Polygon, options: &SmoothOptions) -> element::Element {
+    let mut polygon = element::Polygon::new()
+        .set("points", svg_points_str(&polygon.points))
+        .set("stroke", css_color_or_none(options.stroke_color))
+        .set("stroke-width", options.width)
+        .set("stroke-linejoin", "round")
+        .set("fill", css_color_or_none(options.fill_color));
+    apply_stroke_line_style(&mut polygon, options);
+
+    polygon.into()
+}
+
+pub fn compose_arc(arc: shapes::Arc, options: &SmoothOptions) -> element::Element {
+    let (start_angle, end_angle) = arc.ordered_angles();
+    // A full circle can't be expressed with a single svg arc, so it is split into two halves
+    let mid_angle = (start_angle + end_angle) / 2.0;
+
+    let start = arc.local_point_at(start_angle);
+    let mut commands = vec![path::Command::Move(
+        path::Position::Absolute,
+        path::Parameters::from((start[0], start[1])),
+    )];
+
+    for angle in [mid_angle, end_angle] {
+        let point = arc.local_point_at(angle);
+
+        commands.push(path::Command::EllipticalArc(
+            path::Position::Absolute,
+            path::Parameters::from((arc.radius, arc.radius, 0.0, 0.0, 1.0, point[0], point[1])),
+        ));
+    }
+
+    let mut path = element::Path::new()
+        .set("transform", arc.transform.to_svg_transform_attr_str())
+        .set("d", path::Data::from(commands))
+        .set("stroke", css_color_or_none(options.stroke_color))
+        .set("stroke-width", options.width)
+        .set("fill", "none");
+    apply_stroke_line_style(&mut path, options);
+
+    path.into()
+}
+
+pub fn compose_cubic_bezier(
+    cubbez: curves::CubicBezier,
+    options: &SmoothOptions,
+) -> element::Element {
+    let mut path = element::Path::new()
+        .set("d", path::Data::from(compose_cubbez(cubbez, true, options)))
+        .set("stroke", css_color_or_none(options.stroke_color))
+        .set("stroke-width", options.width)
+        .set("fill", "none");
+    apply_stroke_line_style(&mut path, options);
+
+    path.into()
+}
+
+/// Applies the line cap and the dash pattern of the options to the stroke of the node
+pub fn apply_stroke_line_style<T: svg::Node>(node: &mut T, options: &SmoothOptions) {
+    // Only set when it differs from the SVG default, to not change the output for existing documents
+    if options.line_cap != LineCap::default() {
+        node.assign("stroke-linecap", options.line_cap.as_svg_attr_str());
+    }
+
+    if options.is_dashed() {
+        node.assign(
+            "stroke-dasharray",
+            options
+                .dash_array()
+                .iter()
+                .map(|&no| format!("{}", no))
+                .collect::<Vec<String>>()
+                .join(" "),
+        );
+        node.assign("stroke-dashoffset", options.dash_offset);
+    }
+}
+
+fn css_color_or_none(color: Option<Color>) -> String {
+    if let Some(color) = color {
+        color.to_css_color()
+    } else {
+        String::from("none")
+    }
+}
+
+/// The points formatted for the svg `points` attribute of polylines and polygons
+fn svg_points_str(points: &[na::Vector2<f64>]) -> String {
+    points
+        .iter()
+        .map(|point| format!("{:.3},{:.3}", point[0], point[1]))
+        .collect::<Vec<String>>()
+        .join(" ")
 }
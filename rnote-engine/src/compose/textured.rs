@@ -84,7 +84,7 @@ impl TexturedDotsDistribution {
 
 /// The Options of how a textured shape should look
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "textured_options")]
 pub struct TexturedOptions {
     /// An optional seed to generate reproducable strokes
@@ -105,6 +105,9 @@ pub struct TexturedOptions {
     /// the distribution type
     #[serde(rename = "distribution")]
     pub distribution: TexturedDotsDistribution,
+    /// Wether tilting the stylus widens the stroke and spreads the dots, like shading with the side of a pencil
+    #[serde(rename = "tilt_shading")]
+    pub tilt_shading: bool,
 }
 
 impl Default for TexturedOptions {
@@ -116,6 +119,7 @@ impl Default for TexturedOptions {
             stroke_color: Some(Self::COLOR_DEFAULT),
             radii: Self::RADII_DEFAULT,
             distribution: TexturedDotsDistribution::default(),
+            tilt_shading: false,
         }
     }
 }
@@ -134,6 +138,19 @@ impl TexturedOptions {
     pub const DENSITY_DEFAULT: f64 = 5.0;
     /// Radii default
     pub const RADII_DEFAULT: na::Vector2<f64> = na::vector![2.0, 0.3];
+    /// The factor the width is widened by when the stylus lies flat on the surface
+    pub const TILT_SHADING_WIDTH_FACTOR_MAX: f64 = 3.0;
+
+    /// The factor the width is widened and the density is reduced by for the given tilt amount (see `InputData::tilt_amount()`).
+    /// Is 1.0 if tilt shading is disabled or the tilt is not available
+    pub fn tilt_shading_factor(&self, tilt_amount: Option<f64>) -> f64 {
+        match tilt_amount {
+            Some(tilt_amount) if self.tilt_shading => {
+                1.0 + tilt_amount.clamp(0.0, 1.0) * (Self::TILT_SHADING_WIDTH_FACTOR_MAX - 1.0)
+            }
+            _ => 1.0,
+        }
+    }
 }
 
 pub fn compose_line(line: curves::Line, width: f64, options: &TexturedOptions) -> Element {
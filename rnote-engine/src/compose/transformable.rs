@@ -8,6 +8,38 @@ pub trait Transformable {
     fn rotate(&mut self, angle: f64, center: na::Point2<f64>);
     /// scales the stroke by the desired scale
     fn scale(&mut self, scale: na::Vector2<f64>);
+    /// mirrors the stroke along the axis going through the center
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>);
+    /// shears the stroke relative to the center. The x component of shear shifts x proportional to y, the y component shifts y proportional to x
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>);
+}
+
+/// The axis along which is mirrored
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum MirrorAxis {
+    /// Mirrors horizontally, which swaps left and right
+    Horizontal,
+    /// Mirrors vertically, which swaps top and bottom
+    Vertical,
+}
+
+impl MirrorAxis {
+    /// The scale which reflects across the axis
+    pub fn reflection_scale(self) -> na::Vector2<f64> {
+        match self {
+            Self::Horizontal => na::vector![-1.0, 1.0],
+            Self::Vertical => na::vector![1.0, -1.0],
+        }
+    }
+
+    /// Mirrors the point along the axis going through the center
+    pub fn mirror_point(
+        self,
+        point: na::Vector2<f64>,
+        center: na::Point2<f64>,
+    ) -> na::Vector2<f64> {
+        center.coords + (point - center.coords).component_mul(&self.reflection_scale())
+    }
 }
 
 /// To be used as state in a stroke to help implement the StrokeBehaviour trait
@@ -64,6 +96,31 @@ impl Transform {
         self.transform = na::Translation2::from(translation) * self.transform;
     }
 
+    pub fn append_scale_wrt_point_mut(&mut self, scale: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform = na::Translation2::from(-center.coords) * self.transform;
+
+        self.transform = na::try_convert(
+            na::Scale2::<f64>::from(scale).to_homogeneous() * self.transform.to_homogeneous(),
+        )
+        .unwrap();
+
+        self.transform = na::Translation2::from(center.coords) * self.transform;
+    }
+
+    pub fn append_shear_wrt_point_mut(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform = na::Translation2::from(-center.coords) * self.transform;
+
+        self.transform =
+            na::try_convert(shear_matrix(shear).to_homogeneous() * self.transform.to_homogeneous())
+                .unwrap();
+
+        self.transform = na::Translation2::from(center.coords) * self.transform;
+    }
+
+    pub fn append_mirror_wrt_point_mut(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.append_scale_wrt_point_mut(axis.reflection_scale(), center);
+    }
+
     pub fn to_svg_transform_attr_str(&self) -> String {
         let matrix = self.transform;
 
@@ -78,3 +135,18 @@ impl Transform {
         )
     }
 }
+
+/// The linear map which shears with the shear factors
+pub fn shear_matrix(shear: na::Vector2<f64>) -> na::Matrix2<f64> {
+    na::matrix![1.0, shear[0];
+                shear[1], 1.0]
+}
+
+/// Shears the point relative to the center
+pub fn shear_point(
+    point: na::Vector2<f64>,
+    shear: na::Vector2<f64>,
+    center: na::Point2<f64>,
+) -> na::Vector2<f64> {
+    center.coords + shear_matrix(shear) * (point - center.coords)
+}
@@ -1,12 +1,15 @@
 use std::collections::VecDeque;
 
-use crate::compose::smooth::SmoothOptions;
+use crate::compose::calligraphy::CalligraphyOptions;
+use crate::compose::pressurecurve::PressureCurve;
+use crate::compose::smooth::{LineCap, SmoothOptions};
 use crate::compose::textured::TexturedOptions;
 use crate::render::Renderer;
 use crate::sheet::Sheet;
-use crate::strokes::brushstroke::BrushStroke;
+use crate::strokes::brushstroke::{self, BrushStroke};
 use crate::strokes::element::Element;
 use crate::strokes::inputdata::InputData;
+use crate::strokes::shaperecognizer::ShapeRecognizer;
 use crate::strokes::strokestyle::StrokeStyle;
 use crate::strokesstate::StrokeKey;
 use crate::utils;
@@ -15,8 +18,10 @@ use gtk4::glib;
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use super::penbehaviour::PenBehaviour;
+use super::stabilizer::Stabilizer;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
 #[repr(u32)]
@@ -32,6 +37,12 @@ pub enum BrushStyle {
     #[enum_value(name = "Textured", nick = "textured")]
     #[serde(rename = "textured")]
     Textured,
+    #[enum_value(name = "Highlighter", nick = "highlighter")]
+    #[serde(rename = "highlighter")]
+    Highlighter,
+    #[enum_value(name = "Calligraphy", nick = "calligraphy")]
+    #[serde(rename = "calligraphy")]
+    Calligraphy,
 }
 
 impl Default for BrushStyle {
@@ -45,22 +56,49 @@ impl Default for BrushStyle {
 pub struct Brush {
     #[serde(rename = "style")]
     pub style: BrushStyle,
-    #[serde(rename = "smooth_options")]
+    #[serde(
+        rename = "smooth_options",
+        deserialize_with = "brushstroke::deserialize_smooth_options_w_round_caps"
+    )]
     pub smooth_options: SmoothOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    #[serde(rename = "calligraphy_options")]
+    pub calligraphy_options: CalligraphyOptions,
+    /// The pressure curve for new strokes
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
+    #[serde(rename = "stabilizer")]
+    pub stabilizer: Stabilizer,
+    /// Wether a finished stroke is replaced by the shape it was drawn as, when the pen was held still before lifting it
+    #[serde(rename = "recognize_shapes")]
+    pub recognize_shapes: bool,
+    #[serde(rename = "shape_recognizer")]
+    pub shape_recognizer: ShapeRecognizer,
 
     #[serde(skip)]
     pub current_stroke: Option<StrokeKey>,
+    /// The time and position of the last movement of the current stroke
+    #[serde(skip)]
+    last_movement: Option<(Instant, na::Vector2<f64>)>,
 }
 
 impl Default for Brush {
     fn default() -> Self {
         Self {
             style: BrushStyle::default(),
-            smooth_options: SmoothOptions::default(),
+            smooth_options: SmoothOptions {
+                line_cap: LineCap::Round,
+                ..SmoothOptions::default()
+            },
             textured_options: TexturedOptions::default(),
+            calligraphy_options: CalligraphyOptions::default(),
+            pressure_curve: PressureCurve::default(),
+            stabilizer: Stabilizer::default(),
+            recognize_shapes: false,
+            shape_recognizer: ShapeRecognizer::default(),
             current_stroke: None,
+            last_movement: None,
         }
     }
 }
@@ -75,9 +113,13 @@ impl PenBehaviour for Brush {
         _renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_stroke = None;
+        self.last_movement = None;
+        self.stabilizer.reset();
         let filter_bounds = sheet.bounds().loosened(utils::INPUT_OVERSHOOT);
 
         utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
+        self.update_last_movement(&data_entries);
+        let data_entries = self.stabilizer.stabilize(data_entries);
 
         let elements_iter = data_entries
             .into_iter()
@@ -106,8 +148,9 @@ impl PenBehaviour for Brush {
             let filter_bounds = sheet.bounds().loosened(utils::INPUT_OVERSHOOT);
 
             utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
+            self.update_last_movement(&data_entries);
 
-            for inputdata in data_entries {
+            for inputdata in self.stabilizer.stabilize(data_entries) {
                 sheet.strokes_state.add_to_brushstroke(
                     current_stroke_key,
                     Element::new(inputdata),
@@ -127,14 +170,67 @@ impl PenBehaviour for Brush {
         renderer: Arc<RwLock<Renderer>>,
     ) {
         let current_stroke_key = self.current_stroke.take();
+        // The stabilized input lags behind, so the stroke is finished up to where the input ended
+        let finishing_entries = self.stabilizer.finish();
+
         if let Some(current_stroke_key) = current_stroke_key {
+            for inputdata in finishing_entries {
+                sheet.strokes_state.add_to_brushstroke(
+                    current_stroke_key,
+                    Element::new(inputdata),
+                    renderer.clone(),
+                    zoom,
+                );
+            }
+
             sheet
                 .strokes_state
                 .update_geometry_for_stroke(current_stroke_key);
 
             sheet
                 .strokes_state
-                .regenerate_rendering_for_stroke_threaded(current_stroke_key, renderer, zoom);
+                .regenerate_rendering_for_stroke_threaded(
+                    current_stroke_key,
+                    Arc::clone(&renderer),
+                    zoom,
+                );
+
+            if self.recognize_shapes && self.held_still() {
+                sheet.strokes_state.beautify_strokes(
+                    &[current_stroke_key],
+                    &self.shape_recognizer,
+                    renderer,
+                    zoom,
+                );
+            }
         }
+        self.last_movement = None;
+    }
+}
+
+impl Brush {
+    /// The duration the pen must be held still at the end of a stroke for its shape to be recognized
+    pub const HOLD_STILL_DURATION: Duration = Duration::from_millis(500);
+    /// Movements below this distance are not regarded as movements while holding still
+    pub const HOLD_STILL_TOLERANCE: f64 = 2.0;
+
+    fn update_last_movement(&mut self, data_entries: &VecDeque<InputData>) {
+        for inputdata in data_entries.iter() {
+            let pos = inputdata.pos();
+
+            let moved = self.last_movement.map_or(true, |(_, last_pos)| {
+                (pos - last_pos).magnitude() >= Self::HOLD_STILL_TOLERANCE
+            });
+
+            if moved {
+                self.last_movement = Some((Instant::now(), pos));
+            }
+        }
+    }
+
+    fn held_still(&self) -> bool {
+        self.last_movement.map_or(false, |(last_movement, _)| {
+            last_movement.elapsed() >= Self::HOLD_STILL_DURATION
+        })
     }
 }
@@ -7,17 +7,67 @@ use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
 
-use gtk4::{graphene, gsk, Snapshot};
-use p2d::bounding_volume::AABB;
+use gtk4::{glib, graphene, gsk, Snapshot};
+use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 
 use super::penbehaviour::PenBehaviour;
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "EraserMode")]
+#[serde(rename = "eraser_mode")]
+pub enum EraserMode {
+    /// Trashes the whole strokes which collide with the eraser
+    #[enum_value(name = "TrashCollidingStrokes", nick = "trash-colliding-strokes")]
+    #[serde(rename = "trash_colliding_strokes")]
+    TrashCollidingStrokes,
+    /// Removes only the parts of brush strokes which are under the eraser, splitting them apart. Other strokes are trashed whole
+    #[enum_value(name = "SplitCollidingStrokes", nick = "split-colliding-strokes")]
+    #[serde(rename = "split_colliding_strokes")]
+    SplitCollidingStrokes,
+}
+
+impl Default for EraserMode {
+    fn default() -> Self {
+        Self::TrashCollidingStrokes
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "EraserShape")]
+#[serde(rename = "eraser_shape")]
+pub enum EraserShape {
+    #[enum_value(name = "Square", nick = "square")]
+    #[serde(rename = "square")]
+    Square,
+    #[enum_value(name = "Circle", nick = "circle")]
+    #[serde(rename = "circle")]
+    Circle,
+}
+
+impl Default for EraserShape {
+    fn default() -> Self {
+        Self::Square
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "eraser")]
 pub struct Eraser {
     #[serde(rename = "width")]
     pub width: f64,
+    #[serde(rename = "mode")]
+    pub mode: EraserMode,
+    #[serde(rename = "shape")]
+    pub shape: EraserShape,
+    /// Scales the size of the tip with the pressure, with the width being the size at full pressure
+    #[serde(rename = "pressure_sensitive")]
+    pub pressure_sensitive: bool,
+    /// Wether vector and bitmap images are trashed as well
+    #[serde(rename = "erase_images")]
+    pub erase_images: bool,
     #[serde(skip)]
     pub current_input: Option<InputData>,
 }
@@ -26,6 +76,10 @@ impl Default for Eraser {
     fn default() -> Self {
         Self {
             width: Self::WIDTH_DEFAULT,
+            mode: EraserMode::default(),
+            shape: EraserShape::default(),
+            pressure_sensitive: false,
+            erase_images: false,
             current_input: None,
         }
     }
@@ -48,12 +102,21 @@ impl PenBehaviour for Eraser {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         viewport: Option<AABB>,
-        _zoom: f64,
-        _renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_input = data_entries.pop_back();
 
-        sheet.strokes_state.trash_colliding_strokes(&self, viewport);
+        match self.mode {
+            EraserMode::TrashCollidingStrokes => {
+                sheet.strokes_state.trash_colliding_strokes(&self, viewport);
+            }
+            EraserMode::SplitCollidingStrokes => {
+                sheet
+                    .strokes_state
+                    .split_colliding_strokes(&self, viewport, renderer, zoom);
+            }
+        }
     }
 
     fn end(
@@ -79,19 +142,30 @@ impl PenBehaviour for Eraser {
             let border_color = Self::OUTLINE_COLOR_DEFAULT.to_gdk();
             let border_width = 2.0;
 
+            let corner_radius = match self.shape {
+                EraserShape::Square => graphene::Size::zero(),
+                EraserShape::Circle => graphene::Size::new(
+                    (bounds.extents()[0] / 2.0) as f32,
+                    (bounds.extents()[1] / 2.0) as f32,
+                ),
+            };
+            let tip_rect = gsk::RoundedRect::new(
+                bounds.to_graphene_rect(),
+                corner_radius,
+                corner_radius,
+                corner_radius,
+                corner_radius,
+            );
+
+            snapshot.push_rounded_clip(&tip_rect);
             snapshot.append_color(
                 &Self::FILL_COLOR_DEFAULT.to_gdk(),
                 &bounds.to_graphene_rect(),
             );
+            snapshot.pop();
 
             snapshot.append_border(
-                &gsk::RoundedRect::new(
-                    bounds.to_graphene_rect(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                    graphene::Size::zero(),
-                ),
+                &tip_rect,
                 &[border_width, border_width, border_width, border_width],
                 &[border_color, border_color, border_color, border_color],
             );
@@ -120,25 +194,139 @@ impl Eraser {
     pub fn new(width: f64) -> Self {
         Self {
             width,
-            current_input: None,
+            ..Self::default()
+        }
+    }
+
+    /// The current size of the tip in sheet coordinates, scaled by the pressure if the eraser is pressure sensitive
+    pub fn tip_size(&self) -> Option<f64> {
+        self.current_input.map(|current_input| {
+            if self.pressure_sensitive {
+                (self.width * current_input.pressure()).max(Self::WIDTH_MIN)
+            } else {
+                self.width
+            }
+        })
+    }
+
+    /// The bounds of the tip in sheet coordinates
+    pub fn eraser_bounds(&self) -> Option<AABB> {
+        let current_input = self.current_input?;
+        let half_size = self.tip_size()? / 2.0;
+
+        Some(AABB::new(
+            na::Point2::from(current_input.pos() - na::vector![half_size, half_size]),
+            na::Point2::from(current_input.pos() + na::vector![half_size, half_size]),
+        ))
+    }
+
+    /// Wether the tip collides with the bounds, tested against the real shape of the tip
+    pub fn tip_intersects_bounds(&self, bounds: AABB) -> bool {
+        let (current_input, tip_size) =
+            if let (Some(current_input), Some(tip_size)) = (self.current_input, self.tip_size()) {
+                (current_input, tip_size)
+            } else {
+                return false;
+            };
+
+        match self.shape {
+            EraserShape::Square => self
+                .eraser_bounds()
+                .map_or(false, |eraser_bounds| eraser_bounds.intersects(&bounds)),
+            EraserShape::Circle => {
+                let center = current_input.pos();
+                let closest = center.sup(&bounds.mins.coords).inf(&bounds.maxs.coords);
+
+                (closest - center).magnitude() <= tip_size / 2.0
+            }
         }
     }
 
+    /// Wether the point lies on the tip, with the tip grown by the margin
+    pub fn tip_contains_point(&self, point: na::Vector2<f64>, margin: f64) -> bool {
+        let (current_input, tip_size) =
+            if let (Some(current_input), Some(tip_size)) = (self.current_input, self.tip_size()) {
+                (current_input, tip_size)
+            } else {
+                return false;
+            };
+
+        match self.shape {
+            EraserShape::Square => self.eraser_bounds().map_or(false, |eraser_bounds| {
+                eraser_bounds
+                    .loosened(margin)
+                    .contains_local_point(&na::Point2::from(point))
+            }),
+            EraserShape::Circle => {
+                (point - current_input.pos()).magnitude() <= tip_size / 2.0 + margin
+            }
+        }
+    }
+
+    /// The bounds of the tip, scaled by the zoom
     pub fn gen_bounds(&self, zoom: f64) -> Option<AABB> {
-        self.current_input.map_or_else(
-            || None,
-            |current_input| {
-                Some(AABB::new(
-                    na::point![
-                        ((current_input.pos()[0]) - self.width / 2.0) * zoom,
-                        ((current_input.pos()[1]) - self.width / 2.0) * zoom
-                    ],
-                    na::point![
-                        ((current_input.pos()[0]) + self.width / 2.0) * zoom,
-                        ((current_input.pos()[1]) + self.width / 2.0) * zoom
-                    ],
-                ))
-            },
+        self.eraser_bounds()
+            .map(|eraser_bounds| eraser_bounds.scale(na::Vector2::from_element(zoom)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eraser_at(shape: EraserShape, pos: na::Vector2<f64>, pressure: f64) -> Eraser {
+        Eraser {
+            width: 10.0,
+            shape,
+            current_input: Some(InputData::new(pos, pressure)),
+            ..Eraser::default()
+        }
+    }
+
+    /// A box which extends away from the center of the tip, with its closest corner at the given position
+    fn box_from_corner(corner: na::Vector2<f64>) -> AABB {
+        AABB::new(
+            na::Point2::from(corner),
+            na::Point2::from(corner + na::vector![10.0, 10.0]),
         )
     }
+
+    #[test]
+    fn circle_tip_touches_box_corner_on_its_boundary() {
+        let eraser = eraser_at(EraserShape::Circle, na::vector![0.0, 0.0], 1.0);
+
+        // The corner is exactly one radius away
+        assert!(eraser.tip_intersects_bounds(box_from_corner(na::vector![3.0, 4.0])));
+        assert!(!eraser.tip_intersects_bounds(box_from_corner(na::vector![3.0, 4.01])));
+        // The corner of the square tip reaches further
+        assert!(eraser_at(EraserShape::Square, na::vector![0.0, 0.0], 1.0)
+            .tip_intersects_bounds(box_from_corner(na::vector![3.0, 4.01])));
+
+        assert!(eraser.tip_contains_point(na::vector![3.0, 4.0], 0.0));
+        assert!(!eraser.tip_contains_point(na::vector![3.0, 4.01], 0.0));
+        assert!(eraser.tip_contains_point(na::vector![3.0, 4.01], 0.1));
+    }
+
+    #[test]
+    fn pressure_sensitive_tip_size_is_clamped() {
+        let mut eraser = eraser_at(EraserShape::Circle, na::vector![0.0, 0.0], 0.0);
+        assert_eq!(eraser.tip_size(), Some(10.0));
+
+        eraser.pressure_sensitive = true;
+        assert_eq!(eraser.tip_size(), Some(Eraser::WIDTH_MIN));
+        assert!(eraser.tip_contains_point(na::vector![Eraser::WIDTH_MIN / 2.0, 0.0], 0.0));
+        assert!(!eraser.tip_contains_point(na::vector![Eraser::WIDTH_MIN, 0.0], 0.0));
+        assert!(eraser
+            .tip_intersects_bounds(box_from_corner(na::vector![Eraser::WIDTH_MIN / 2.0, 0.0])));
+
+        eraser.current_input = Some(InputData::new(na::vector![0.0, 0.0], 0.05));
+        assert_eq!(eraser.tip_size(), Some(Eraser::WIDTH_MIN));
+
+        eraser.current_input = Some(InputData::new(na::vector![0.0, 0.0], 0.5));
+        assert_eq!(eraser.tip_size(), Some(5.0));
+
+        eraser.current_input = None;
+        assert_eq!(eraser.tip_size(), None);
+        assert!(!eraser.tip_contains_point(na::vector![0.0, 0.0], 0.0));
+    }
 }
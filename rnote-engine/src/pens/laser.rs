@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use crate::compose;
+use crate::compose::color::Color;
+use crate::render::{self, Renderer};
+use crate::sheet::Sheet;
+use crate::strokes::inputdata::InputData;
+
+use anyhow::Context;
+use gtk4::Snapshot;
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Serialize};
+
+use super::penbehaviour::PenBehaviour;
+
+#[derive(Debug, Clone, Copy)]
+struct LaserPoint {
+    pos: na::Vector2<f64>,
+    time: Instant,
+}
+
+/// A laser pointer for presenting. It draws glowing trails which fade out after some time.
+/// The trails are only state of the pen, they are never added to the sheet.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename = "laser")]
+pub struct Laser {
+    #[serde(rename = "width")]
+    pub width: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    /// The time in seconds until the trails are faded out
+    #[serde(rename = "fade_time")]
+    pub fade_time: f64,
+
+    #[serde(skip)]
+    trails: Vec<VecDeque<LaserPoint>>,
+    /// Wether the last trail is currently drawn
+    #[serde(skip)]
+    drawing: bool,
+}
+
+impl Default for Laser {
+    fn default() -> Self {
+        Self {
+            width: Self::WIDTH_DEFAULT,
+            color: Self::COLOR_DEFAULT,
+            fade_time: Self::FADE_TIME_DEFAULT,
+            trails: vec![],
+            drawing: false,
+        }
+    }
+}
+
+impl PenBehaviour for Laser {
+    fn begin(
+        &mut self,
+        data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+        self.trails.push(VecDeque::new());
+        self.drawing = true;
+
+        self.add_to_trail(data_entries);
+    }
+
+    fn motion(
+        &mut self,
+        data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+        self.add_to_trail(data_entries);
+    }
+
+    fn end(
+        &mut self,
+        data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+        self.add_to_trail(data_entries);
+
+        self.drawing = false;
+    }
+
+    fn draw(
+        &self,
+        snapshot: &Snapshot,
+        _sheet: &Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let now = Instant::now();
+        let glow_width = self.width * Self::GLOW_WIDTH_FACTOR;
+
+        let mut bounds: Option<AABB> = None;
+        let mut group = svg::node::element::Group::new();
+
+        for trail in self.trails.iter() {
+            for point in trail.iter() {
+                let point_bounds = AABB::from_half_extents(
+                    na::Point2::from(point.pos),
+                    na::Vector2::repeat(glow_width * 0.5),
+                );
+                bounds = Some(bounds.map_or(point_bounds, |bounds| bounds.merged(&point_bounds)));
+            }
+
+            if let (1, Some(point)) = (trail.len(), trail.front()) {
+                let opacity = self.opacity(point, now);
+
+                for (width, color) in self.layers(opacity) {
+                    let dot = svg::node::element::Circle::new()
+                        .set("cx", point.pos[0])
+                        .set("cy", point.pos[1])
+                        .set("r", width * 0.5)
+                        .set("stroke", "none")
+                        .set("fill", color.to_css_color());
+
+                    group = group.add(dot);
+                }
+                continue;
+            }
+
+            // The layers are added in separate passes, so the cores of the segments are always drawn above the glow
+            for layer in 0..Self::N_LAYERS {
+                for (start, end) in trail.iter().zip(trail.iter().skip(1)) {
+                    let opacity = self.opacity(end, now);
+                    let (width, color) = self.layers(opacity)[layer];
+
+                    let line = svg::node::element::Line::new()
+                        .set("x1", start.pos[0])
+                        .set("y1", start.pos[1])
+                        .set("x2", end.pos[0])
+                        .set("y2", end.pos[1])
+                        .set("stroke", color.to_css_color())
+                        .set("stroke-width", width)
+                        .set("stroke-linecap", "round")
+                        .set("fill", "none");
+
+                    group = group.add(line);
+                }
+            }
+        }
+
+        let bounds = match bounds {
+            Some(bounds) => bounds,
+            None => return Ok(()),
+        };
+
+        let svg_data = compose::svg_node_to_string(&group)?;
+        let svg = render::Svg { svg_data, bounds };
+
+        let images = renderer
+            .read()
+            .unwrap()
+            .gen_images(zoom, vec![svg], bounds)?;
+        if let Some(rendernode) = render::images_to_rendernode(&images, zoom)
+            .context("images_to_rendernode() failed in laser .draw()")?
+        {
+            snapshot.append_node(&rendernode);
+        }
+
+        Ok(())
+    }
+}
+
+impl Laser {
+    /// The default width
+    pub const WIDTH_DEFAULT: f64 = 4.0;
+    /// The min width
+    pub const WIDTH_MIN: f64 = 1.0;
+    /// The max width
+    pub const WIDTH_MAX: f64 = 50.0;
+    /// The default color
+    pub const COLOR_DEFAULT: Color = Color {
+        r: 1.0,
+        g: 0.1,
+        b: 0.1,
+        a: 1.0,
+    };
+    /// The default fade time in seconds
+    pub const FADE_TIME_DEFAULT: f64 = 1.5;
+    /// The min fade time in seconds
+    pub const FADE_TIME_MIN: f64 = 0.2;
+    /// The max fade time in seconds
+    pub const FADE_TIME_MAX: f64 = 10.0;
+    /// The width of the glow relative to the width of the trail
+    pub const GLOW_WIDTH_FACTOR: f64 = 3.0;
+    /// The count of the layers of a trail, from the glow to the core
+    const N_LAYERS: usize = 3;
+
+    /// Wether there are trails which are not faded out yet, and the animation needs to continue
+    pub fn is_animating(&self) -> bool {
+        self.drawing || !self.trails.is_empty()
+    }
+
+    /// Removes the parts of the trails which are faded out. Needs to be called repeatedly while animating.
+    /// Returns wether the animation should continue
+    pub fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let fade_time = self.fade_time();
+
+        for trail in self.trails.iter_mut() {
+            while let Some(point) = trail.front() {
+                if now.duration_since(point.time).as_secs_f64() < fade_time {
+                    break;
+                }
+                trail.pop_front();
+            }
+        }
+
+        // The trail which is currently drawn is kept, even when it is faded out while the pen is held still
+        let current_trail = if self.drawing {
+            self.trails.pop()
+        } else {
+            None
+        };
+        self.trails.retain(|trail| !trail.is_empty());
+        self.trails.extend(current_trail);
+
+        self.is_animating()
+    }
+
+    fn fade_time(&self) -> f64 {
+        self.fade_time
+            .clamp(Self::FADE_TIME_MIN, Self::FADE_TIME_MAX)
+    }
+
+    fn add_to_trail(&mut self, data_entries: VecDeque<InputData>) {
+        let now = Instant::now();
+
+        if let Some(trail) = self.trails.last_mut() {
+            trail.extend(data_entries.into_iter().map(|inputdata| LaserPoint {
+                pos: inputdata.pos(),
+                time: now,
+            }));
+        }
+    }
+
+    /// The opacity of the trail at the point, fading out linearly with its age
+    fn opacity(&self, point: &LaserPoint, now: Instant) -> f64 {
+        let age = now.duration_since(point.time).as_secs_f64();
+
+        (1.0 - age / self.fade_time()).clamp(0.0, 1.0)
+    }
+
+    /// The widths and colors of the layers of the trail, from the glow to the core
+    fn layers(&self, opacity: f64) -> [(f64, Color); Self::N_LAYERS] {
+        let with_alpha = |color: Color, alpha: f64| Color {
+            a: color.a * alpha,
+            ..color
+        };
+
+        [
+            (
+                self.width * Self::GLOW_WIDTH_FACTOR,
+                with_alpha(self.color, 0.25 * opacity),
+            ),
+            (self.width, with_alpha(self.color, opacity)),
+            (self.width * 0.4, with_alpha(Color::WHITE, 0.8 * opacity)),
+        ]
+    }
+}
@@ -3,21 +3,27 @@ pub mod shortcuts;
 
 pub mod brush;
 pub mod eraser;
+pub mod laser;
 pub mod selector;
 pub mod shaper;
+pub mod stabilizer;
 pub mod tools;
+pub mod typewriter;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, RwLock};
 
+use crate::compose::pressurecurve::PressureCurve;
 use crate::render::Renderer;
 use crate::sheet::Sheet;
 use crate::strokes::inputdata::InputData;
 use crate::surfaceflags::SurfaceFlags;
 
+use self::laser::Laser;
 use self::penbehaviour::PenBehaviour;
 use self::shortcuts::{ShortcutAction, ShortcutKey, Shortcuts};
 use self::tools::Tools;
+use self::typewriter::{KeyboardKey, Typewriter};
 use self::{brush::Brush, eraser::Eraser, selector::Selector, shaper::Shaper};
 use gtk4::{glib, glib::prelude::*, Snapshot};
 use num_derive::FromPrimitive;
@@ -57,6 +63,12 @@ pub enum PenStyle {
     #[enum_value(name = "ToolsStyle", nick = "tools_style")]
     #[serde(rename = "tools_style")]
     ToolsStyle,
+    #[enum_value(name = "TypewriterStyle", nick = "typewriter_style")]
+    #[serde(rename = "typewriter_style")]
+    TypewriterStyle,
+    #[enum_value(name = "LaserStyle", nick = "laser_style")]
+    #[serde(rename = "laser_style")]
+    LaserStyle,
 }
 
 impl Default for PenStyle {
@@ -92,6 +104,8 @@ impl PenStyle {
             PenStyle::EraserStyle => String::from("Eraser style"),
             PenStyle::SelectorStyle => String::from("Selector style"),
             PenStyle::ToolsStyle => String::from("Tools style"),
+            PenStyle::TypewriterStyle => String::from("Typewriter style"),
+            PenStyle::LaserStyle => String::from("Laser style"),
         }
     }
     pub fn icon_name(self) -> String {
@@ -101,8 +115,15 @@ impl PenStyle {
             Self::EraserStyle => String::from("pen-eraser-symbolic"),
             Self::SelectorStyle => String::from("pen-selector-symbolic"),
             Self::ToolsStyle => String::from("pen-tools-symbolic"),
+            Self::TypewriterStyle => String::from("pen-typewriter-symbolic"),
+            Self::LaserStyle => String::from("pen-laser-symbolic"),
         }
     }
+
+    /// Wether the pen changes the sheet. Pens which don't leave the history and the unsaved changes untouched
+    pub fn changes_sheet(self) -> bool {
+        !matches!(self, Self::LaserStyle)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -134,6 +155,7 @@ pub enum PenEvent {
     ChangeStyle(PenStyle),
     ChangeStyleOverride(Option<PenStyle>),
     PressedShortcutkey(ShortcutKey),
+    KeyPressed(KeyboardKey),
 }
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
@@ -154,6 +176,13 @@ pub struct Pens {
     pub selector: Selector,
     #[serde(rename = "tools")]
     pub tools: Tools,
+    #[serde(rename = "typewriter")]
+    pub typewriter: Typewriter,
+    #[serde(rename = "laser")]
+    pub laser: Laser,
+    /// The default pressure curves of the input devices, by their device name
+    #[serde(rename = "device_pressure_curves")]
+    device_pressure_curves: HashMap<String, PressureCurve>,
 
     #[serde(skip)]
     pen_shown: bool,
@@ -161,6 +190,8 @@ pub struct Pens {
     state: PenState,
     #[serde(skip)]
     style_override: Option<PenStyle>,
+    #[serde(skip)]
+    input_device: Option<String>,
 }
 
 impl Pens {
@@ -184,6 +215,50 @@ impl Pens {
         self.style_override.unwrap_or(self.style)
     }
 
+    /// The name of the device the last input came from
+    pub fn input_device(&self) -> Option<&str> {
+        self.input_device.as_deref()
+    }
+
+    /// Sets the device the input comes from. When it changed and there is a default pressure curve for the device, it is loaded into the brush.
+    /// Returns true if the pressure curve of the brush was changed
+    pub fn set_input_device(&mut self, device: Option<String>) -> bool {
+        if self.input_device == device {
+            return false;
+        }
+        self.input_device = device;
+
+        match self
+            .input_device
+            .as_ref()
+            .and_then(|device| self.device_pressure_curves.get(device))
+        {
+            Some(&pressure_curve) if pressure_curve != self.brush.pressure_curve => {
+                self.brush.pressure_curve = pressure_curve;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Saves the pressure curve of the brush as the default for the current input device.
+    /// Returns false if the device of the input is not known yet
+    pub fn save_pressure_curve_for_input_device(&mut self) -> bool {
+        if let Some(device) = self.input_device.clone() {
+            self.device_pressure_curves
+                .insert(device, self.brush.pressure_curve);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes the default pressure curve of the current input device
+    pub fn remove_pressure_curve_for_input_device(&mut self) -> Option<PressureCurve> {
+        let device = self.input_device.as_ref()?;
+        self.device_pressure_curves.remove(device)
+    }
+
     pub fn register_new_shortcut(&mut self, key: ShortcutKey, action: ShortcutAction) {
         self.shortcuts.insert(key, action);
     }
@@ -229,12 +304,13 @@ impl Pens {
                     self.handle_shortcut_key(shortcut_key, &mut surface_flags);
                 }
 
-                self.pen_begin(data_entries, sheet, viewport, zoom, renderer);
+                self.pen_begin(data_entries, sheet, viewport, zoom, renderer.clone());
 
                 self.state = PenState::Down;
                 self.pen_shown = true;
 
                 surface_flags.redraw = true;
+                surface_flags.animate = self.laser.is_animating();
             }
             (
                 PenState::Down,
@@ -257,7 +333,7 @@ impl Pens {
                     shortcut_key: _,
                 },
             ) => {
-                self.pen_motion(data_entries, sheet, viewport, zoom, renderer);
+                self.pen_motion(data_entries, sheet, viewport, zoom, renderer.clone());
 
                 surface_flags.redraw = true;
             }
@@ -275,39 +351,51 @@ impl Pens {
                     shortcut_key: _,
                 },
             ) => {
-                // We deselect the selection here, before updating it when the current style is the selector
-                let all_strokes = sheet.strokes_state.keys_sorted_chrono();
-                sheet.strokes_state.set_selected_keys(&all_strokes, false);
+                let changes_sheet = self.style_w_override().changes_sheet();
+
+                // The selector updates the selection itself, depending on its selection operation
+                if changes_sheet && self.style_w_override() != PenStyle::SelectorStyle {
+                    // We deselect the selection here, before the stroke is finished
+                    let all_strokes = sheet.strokes_state.keys_sorted_chrono();
+                    sheet.strokes_state.set_selected_keys(&all_strokes, false);
+                }
 
-                self.pen_end(data_entries, sheet, viewport, zoom, renderer);
+                self.pen_end(data_entries, sheet, viewport, zoom, renderer.clone());
 
                 self.state = PenState::Up;
                 self.pen_shown = false;
 
-                // Disable the style override after finishing the stroke
+                // Disable the style override and the selection operation override after finishing the stroke
                 if self.style_override.take().is_some() {
                     surface_flags.pen_changed = true;
                 }
+                self.selector.operation_override = None;
 
                 surface_flags.redraw = true;
-                surface_flags.resize = true;
-                surface_flags.sheet_changed = true;
-                surface_flags.selection_changed = true;
+                if changes_sheet {
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                    surface_flags.selection_changed = true;
+                }
             }
             (PenState::Down, PenEvent::ChangeStyle(new_style)) => {
                 if self.style != new_style {
+                    let changes_sheet = self.style_w_override().changes_sheet();
+
                     // before changing the style, the current stroke is finished
-                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer);
+                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer.clone());
 
                     self.state = PenState::Up;
                     self.pen_shown = false;
                     self.style = new_style;
 
                     surface_flags.redraw = true;
-                    surface_flags.resize = true;
                     surface_flags.pen_changed = true;
-                    surface_flags.sheet_changed = true;
-                    surface_flags.selection_changed = true;
+                    if changes_sheet {
+                        surface_flags.resize = true;
+                        surface_flags.sheet_changed = true;
+                        surface_flags.selection_changed = true;
+                    }
                 }
             }
             (PenState::Up, PenEvent::ChangeStyle(new_style)) => {
@@ -321,18 +409,22 @@ impl Pens {
             }
             (PenState::Down, PenEvent::ChangeStyleOverride(new_style_override)) => {
                 if self.style_override != new_style_override {
+                    let changes_sheet = self.style_w_override().changes_sheet();
+
                     // before changing the style override, the current stroke is finished
-                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer);
+                    self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer.clone());
 
                     self.pen_shown = false;
                     self.state = PenState::Up;
                     self.style_override = new_style_override;
 
                     surface_flags.redraw = true;
-                    surface_flags.resize = true;
                     surface_flags.pen_changed = true;
-                    surface_flags.sheet_changed = true;
-                    surface_flags.selection_changed = true;
+                    if changes_sheet {
+                        surface_flags.resize = true;
+                        surface_flags.sheet_changed = true;
+                        surface_flags.selection_changed = true;
+                    }
                 }
             }
             (PenState::Up, PenEvent::ChangeStyleOverride(new_style_override)) => {
@@ -344,13 +436,49 @@ impl Pens {
                 }
             }
             (PenState::Down, PenEvent::PressedShortcutkey(shortcut_key)) => {
-                self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer);
+                self.pen_end(VecDeque::new(), sheet, viewport, zoom, renderer.clone());
 
                 self.handle_shortcut_key(shortcut_key, &mut surface_flags);
             }
             (PenState::Up, PenEvent::PressedShortcutkey(shortcut_key)) => {
                 self.handle_shortcut_key(shortcut_key, &mut surface_flags);
             }
+            (_, PenEvent::KeyPressed(keyboard_key)) => {
+                if self.style_w_override() == PenStyle::TypewriterStyle
+                    && self
+                        .typewriter
+                        .handle_key(keyboard_key, sheet, zoom, renderer.clone())
+                {
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+                if self.style_w_override() == PenStyle::ShaperStyle
+                    && self.shaper.is_constructing()
+                    && matches!(keyboard_key, KeyboardKey::Escape | KeyboardKey::Linefeed)
+                {
+                    self.shaper
+                        .finish_construction(sheet, zoom, renderer.clone());
+
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
+
+                surface_flags.redraw = true;
+            }
+        }
+
+        // Text which is edited is finished when switching to another pen
+        if self.style_w_override() != PenStyle::TypewriterStyle && self.typewriter.is_active() {
+            self.typewriter.finish_editing(sheet);
+
+            surface_flags.redraw = true;
+        }
+        // Same for shapes which are constructed over several strokes
+        if self.style_w_override() != PenStyle::ShaperStyle && self.shaper.is_constructing() {
+            self.shaper.finish_construction(sheet, zoom, renderer);
+
+            surface_flags.redraw = true;
+            surface_flags.sheet_changed = true;
         }
 
         surface_flags
@@ -364,6 +492,12 @@ impl Pens {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) -> Result<(), anyhow::Error> {
+        // The trails of the laser fade out independently of the current pen
+        if self.laser.is_animating() {
+            self.laser
+                .draw(snapshot, sheet, viewport, zoom, renderer.clone())?;
+        }
+
         if self.pen_shown {
             match self.style_w_override() {
                 PenStyle::BrushStyle => self.brush.draw(snapshot, sheet, viewport, zoom, renderer),
@@ -377,7 +511,20 @@ impl Pens {
                     .selector
                     .draw(snapshot, sheet, viewport, zoom, renderer),
                 PenStyle::ToolsStyle => self.tools.draw(snapshot, sheet, viewport, zoom, renderer),
+                PenStyle::TypewriterStyle => self
+                    .typewriter
+                    .draw(snapshot, sheet, viewport, zoom, renderer),
+                // Already drawn above
+                PenStyle::LaserStyle => Ok(()),
             }
+        } else if self.style_w_override() == PenStyle::TypewriterStyle {
+            // The cursor of the typewriter stays visible while typing
+            self.typewriter
+                .draw(snapshot, sheet, viewport, zoom, renderer)
+        } else if self.style_w_override() == PenStyle::ShaperStyle && self.shaper.is_constructing()
+        {
+            // The handles of a shape under construction stay visible between strokes
+            self.shaper.draw(snapshot, sheet, viewport, zoom, renderer)
         } else {
             Ok(())
         }
@@ -390,6 +537,10 @@ impl Pens {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) {
+        if self.style_w_override().changes_sheet() {
+            sheet.strokes_state.history_mut().begin_group();
+        }
+
         match self.style_w_override() {
             PenStyle::BrushStyle => {
                 self.brush
@@ -411,6 +562,14 @@ impl Pens {
                 self.tools
                     .begin(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .begin(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::LaserStyle => {
+                self.laser
+                    .begin(data_entries, sheet, viewport, zoom, renderer);
+            }
         }
     }
 
@@ -443,6 +602,14 @@ impl Pens {
                 self.tools
                     .motion(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .motion(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::LaserStyle => {
+                self.laser
+                    .motion(data_entries, sheet, viewport, zoom, renderer);
+            }
         }
     }
 
@@ -475,9 +642,26 @@ impl Pens {
                 self.tools
                     .end(data_entries, sheet, viewport, zoom, renderer);
             }
+            PenStyle::TypewriterStyle => {
+                self.typewriter
+                    .end(data_entries, sheet, viewport, zoom, renderer);
+            }
+            PenStyle::LaserStyle => {
+                self.laser
+                    .end(data_entries, sheet, viewport, zoom, renderer);
+            }
+        }
+
+        if self.style_w_override().changes_sheet() {
+            sheet.strokes_state.history_mut().end_group();
         }
     }
 
+    /// Advances the animations of the pens. Returns wether they should continue
+    pub fn tick_animations(&mut self) -> bool {
+        self.laser.tick()
+    }
+
     fn handle_shortcut_key(&mut self, shortcut_key: ShortcutKey, surface_flags: &mut SurfaceFlags) {
         if let Some(&action) = self.shortcuts.get(&shortcut_key) {
             match action {
@@ -492,6 +676,15 @@ impl Pens {
                         surface_flags.pen_changed = true;
                     }
                 }
+                ShortcutAction::ChangeSelectionOperation { operation } => {
+                    // The next selection with the selector is combined with the current selection
+                    if self.style_w_override() != PenStyle::SelectorStyle {
+                        self.style_override = Some(PenStyle::SelectorStyle);
+                    }
+                    self.selector.operation_override = Some(operation);
+
+                    surface_flags.pen_changed = true;
+                }
             }
         }
     }
@@ -24,6 +24,40 @@ pub enum SelectorStyle {
     #[serde(rename = "rectangle")]
     #[enum_value(name = "Rectangle", nick = "rectangle")]
     Rectangle,
+    /// Selects all strokes which are touched by the polygon, not only the ones which are fully contained
+    #[serde(rename = "touch")]
+    #[enum_value(name = "Touch", nick = "touch")]
+    Touch,
+    /// Selects the single topmost stroke at the tapped position
+    #[serde(rename = "single")]
+    #[enum_value(name = "Single", nick = "single")]
+    Single,
+}
+
+/// How the strokes matched by the selector are combined with the current selection
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "SelectionOperation")]
+#[serde(rename = "selection_operation")]
+pub enum SelectionOperation {
+    /// The matched strokes replace the current selection
+    #[enum_value(name = "Replace", nick = "replace")]
+    #[serde(rename = "replace")]
+    Replace,
+    /// The matched strokes are added to the current selection
+    #[enum_value(name = "Add", nick = "add")]
+    #[serde(rename = "add")]
+    Add,
+    /// The matched strokes are removed from the current selection
+    #[enum_value(name = "Subtract", nick = "subtract")]
+    #[serde(rename = "subtract")]
+    Subtract,
+}
+
+impl Default for SelectionOperation {
+    fn default() -> Self {
+        Self::Replace
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -33,6 +67,9 @@ pub struct Selector {
     pub style: SelectorStyle,
     #[serde(skip)]
     pub path: Vec<InputData>,
+    /// Overrides the selection operation for the next selection. Set by a pressed modifier key or stylus button
+    #[serde(skip)]
+    pub operation_override: Option<SelectionOperation>,
 }
 
 impl Default for Selector {
@@ -40,6 +77,7 @@ impl Default for Selector {
         Self {
             style: SelectorStyle::Polygon,
             path: vec![],
+            operation_override: None,
         }
     }
 }
@@ -76,7 +114,7 @@ impl PenBehaviour for Selector {
             let style = self.style;
 
             match style {
-                SelectorStyle::Polygon => {
+                SelectorStyle::Polygon | SelectorStyle::Touch => {
                     self.path.push(inputdata);
                 }
                 SelectorStyle::Rectangle => {
@@ -87,6 +125,11 @@ impl PenBehaviour for Selector {
                         self.path.insert(1, inputdata);
                     }
                 }
+                SelectorStyle::Single => {
+                    // Only the last position is tapped
+                    self.path.clear();
+                    self.path.push(inputdata);
+                }
             }
         }
     }
@@ -121,12 +164,17 @@ impl PenBehaviour for Selector {
         zoom: f64,
         renderer: Arc<RwLock<Renderer>>,
     ) -> Result<(), anyhow::Error> {
+        // A tap does not need any feedback
+        if let SelectorStyle::Single = self.style {
+            return Ok(());
+        }
+
         if let Some(bounds) = self.gen_bounds() {
             let mut data = element::path::Data::new();
             let offset = na::vector![0.0, 0.0];
 
             match self.style {
-                SelectorStyle::Polygon => {
+                SelectorStyle::Polygon | SelectorStyle::Touch => {
                     for (i, element) in self.path.iter().enumerate() {
                         if i == 0 {
                             data = data.move_to((
@@ -152,6 +200,7 @@ impl PenBehaviour for Selector {
                             data.line_to((first.pos()[0] + offset[0], last.pos()[1] + offset[1]));
                     }
                 }
+                SelectorStyle::Single => {}
             }
             data = data.close();
 
@@ -198,6 +247,13 @@ impl Selector {
         b: 0.7,
         a: 0.15,
     };
+    /// The distance around the tapped position, in which strokes are matched when selecting a single stroke
+    pub const SINGLE_SELECTION_TOLERANCE: f64 = 4.0;
+
+    /// The selection operation which is applied for the current selection
+    pub fn operation(&self) -> SelectionOperation {
+        self.operation_override.unwrap_or_default()
+    }
 
     pub fn gen_bounds(&self) -> Option<AABB> {
         // Making sure bounds are always outside of coord + width
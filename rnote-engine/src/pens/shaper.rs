@@ -1,7 +1,12 @@
+use crate::compose::color::Color;
+use crate::compose::curves;
+use crate::compose::geometry::AABBHelpers;
+use crate::compose::shapes::{self, ArrowHead};
 use crate::render::Renderer;
 use crate::strokes::inputdata::InputData;
+use crate::strokes::shapestroke::Shape;
 use crate::utils;
-use gtk4::glib;
+use gtk4::{glib, graphene, gsk, Snapshot};
 use p2d::bounding_volume::{BoundingVolume, AABB};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -18,7 +23,7 @@ use crate::strokesstate::StrokeKey;
 
 use super::penbehaviour::PenBehaviour;
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, glib::Enum)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
 #[serde(rename = "shaperstyle")]
 #[enum_type(name = "ShaperStyle")]
 pub enum ShaperStyle {
@@ -31,6 +36,21 @@ pub enum ShaperStyle {
     #[serde(rename = "ellipse")]
     #[enum_value(name = "Ellipse", nick = "ellipse")]
     Ellipse,
+    #[serde(rename = "arrow")]
+    #[enum_value(name = "Arrow", nick = "arrow")]
+    Arrow,
+    #[serde(rename = "polyline")]
+    #[enum_value(name = "Polyline", nick = "polyline")]
+    Polyline,
+    #[serde(rename = "polygon")]
+    #[enum_value(name = "Polygon", nick = "polygon")]
+    Polygon,
+    #[serde(rename = "arc")]
+    #[enum_value(name = "Arc", nick = "arc")]
+    Arc,
+    #[serde(rename = "cubic_bezier")]
+    #[enum_value(name = "CubicBezier", nick = "cubic_bezier")]
+    CubicBezier,
 }
 
 impl Default for ShaperStyle {
@@ -39,7 +59,14 @@ impl Default for ShaperStyle {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, glib::Enum)]
+impl ShaperStyle {
+    /// If shapes of this style can be filled
+    pub fn fillable(self) -> bool {
+        matches!(self, Self::Rectangle | Self::Ellipse | Self::Polygon)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
 #[enum_type(name = "ShaperDrawStyle")]
 #[serde(rename = "shaper_drawstyle")]
 pub enum ShaperDrawStyle {
@@ -62,6 +89,36 @@ impl ShaperDrawStyle {
     pub const ROUGH_MARGIN: f64 = 20.0;
 }
 
+/// A point of a cubic bezier curve which can be dragged after the curve was drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BezierHandle {
+    Start,
+    Cp1,
+    Cp2,
+    End,
+}
+
+/// A shape which is constructed over several strokes of the pen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShaperConstruction {
+    None,
+    /// A polyline or polygon, which gets a new vertex with every stroke
+    Path {
+        stroke_key: StrokeKey,
+    },
+    /// A cubic bezier curve, whose handles can be dragged until it is finished
+    CubicBezier {
+        stroke_key: StrokeKey,
+        dragged: Option<BezierHandle>,
+    },
+}
+
+impl Default for ShaperConstruction {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "shaper")]
 pub struct Shaper {
@@ -73,7 +130,15 @@ pub struct Shaper {
     pub smooth_options: SmoothOptions,
     #[serde(rename = "rough_options")]
     pub rough_options: RoughOptions,
+    #[serde(rename = "arrow_start_head")]
+    pub arrow_start_head: ArrowHead,
+    #[serde(rename = "arrow_end_head")]
+    pub arrow_end_head: ArrowHead,
+    #[serde(rename = "arrow_head_length")]
+    pub arrow_head_length: f64,
 
+    #[serde(skip)]
+    construction: ShaperConstruction,
     #[serde(skip)]
     pub current_stroke: Option<StrokeKey>,
     #[serde(skip)]
@@ -89,6 +154,10 @@ impl Default for Shaper {
             drawstyle: ShaperDrawStyle::default(),
             smooth_options: SmoothOptions::default(),
             rough_options: RoughOptions::default(),
+            arrow_start_head: ArrowHead::None,
+            arrow_end_head: ArrowHead::Open,
+            arrow_head_length: shapes::Arrow::HEAD_LENGTH_DEFAULT,
+            construction: ShaperConstruction::default(),
             current_stroke: None,
             rect_start: na::vector![0.0, 0.0],
             rect_current: na::vector![0.0, 0.0],
@@ -102,8 +171,8 @@ impl PenBehaviour for Shaper {
         mut data_entries: VecDeque<InputData>,
         sheet: &mut Sheet,
         _viewport: Option<AABB>,
-        _zoom: f64,
-        _renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
     ) {
         self.current_stroke = None;
 
@@ -112,14 +181,24 @@ impl PenBehaviour for Shaper {
         utils::filter_mapped_inputdata(filter_bounds, &mut data_entries);
 
         if let Some(inputdata) = data_entries.pop_back() {
+            if self.continue_construction(inputdata.pos(), sheet, zoom, renderer) {
+                return;
+            }
+
             let element = Element::new(inputdata);
 
             let shapestroke = StrokeStyle::ShapeStroke(ShapeStroke::new(element, self));
             self.rect_start = element.inputdata.pos();
             self.rect_current = element.inputdata.pos();
 
-            let current_stroke_key = Some(sheet.strokes_state.insert_stroke(shapestroke));
-            self.current_stroke = current_stroke_key;
+            let current_stroke_key = sheet.strokes_state.insert_stroke(shapestroke);
+            self.current_stroke = Some(current_stroke_key);
+
+            if matches!(self.style, ShaperStyle::Polyline | ShaperStyle::Polygon) {
+                self.construction = ShaperConstruction::Path {
+                    stroke_key: current_stroke_key,
+                };
+            }
         }
     }
 
@@ -172,6 +251,292 @@ impl PenBehaviour for Shaper {
                     zoom,
                 );
             }
+
+            match self.construction {
+                ShaperConstruction::CubicBezier {
+                    ref mut dragged, ..
+                } => {
+                    *dragged = None;
+                }
+                ShaperConstruction::None if self.style == ShaperStyle::CubicBezier => {
+                    self.construction = ShaperConstruction::CubicBezier {
+                        stroke_key: current_stroke_key,
+                        dragged: None,
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let stroke_key = match self.construction {
+            ShaperConstruction::None => return Ok(()),
+            ShaperConstruction::Path { stroke_key }
+            | ShaperConstruction::CubicBezier { stroke_key, .. } => stroke_key,
+        };
+        let shapestroke = if let Some(shapestroke) = sheet.strokes_state.shape_stroke(stroke_key) {
+            shapestroke
+        } else {
+            return Ok(());
+        };
+
+        match shapestroke.shape {
+            Shape::Polyline(shapes::Polyline { ref points })
+            | Shape::Polygon(shapes::Polygon { ref points }) => {
+                for &vertex in points.iter() {
+                    Self::draw_handle(snapshot, vertex, zoom);
+                }
+            }
+            Shape::CubicBezier(cubbez) => {
+                Self::draw_handle_line(snapshot, cubbez.start, cubbez.cp1, zoom);
+                Self::draw_handle_line(snapshot, cubbez.end, cubbez.cp2, zoom);
+
+                for handle in [cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end] {
+                    Self::draw_handle(snapshot, handle, zoom);
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl Shaper {
+    /// The radius around the center of an arc, in which the pen sets the start angle
+    pub const ARC_START_RADIUS: f64 = 2.0;
+    /// The radius of the vertex and control point handles, in surface coordinates
+    pub const HANDLE_RADIUS: f64 = 6.0;
+    pub const HANDLE_BORDER_WIDTH: f32 = 1.5;
+    pub const HANDLE_LINE_WIDTH: f64 = 1.0;
+    pub const HANDLE_COLOR: Color = Color {
+        r: 0.1,
+        g: 0.3,
+        b: 0.9,
+        a: 0.9,
+    };
+    pub const HANDLE_FILL_COLOR: Color = Color {
+        r: 1.0,
+        g: 1.0,
+        b: 1.0,
+        a: 0.9,
+    };
+    /// Vertices closer than this to their predecessor are removed when a path is finished
+    pub const VERTEX_TOLERANCE: f64 = 0.5;
+
+    /// If a shape is currently constructed over several strokes
+    pub fn is_constructing(&self) -> bool {
+        self.construction != ShaperConstruction::None
+    }
+
+    /// The handle of the cubic bezier curve under construction which is currently dragged
+    pub fn dragged_bezier_handle(&self) -> Option<BezierHandle> {
+        if let ShaperConstruction::CubicBezier { dragged, .. } = self.construction {
+            dragged
+        } else {
+            None
         }
     }
+
+    /// Finishes the shape under construction. Paths with too few vertices are trashed
+    pub fn finish_construction(
+        &mut self,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) {
+        if let ShaperConstruction::Path { stroke_key } = self.construction {
+            if sheet.strokes_state.trashed(stroke_key) == Some(false) {
+                let valid = sheet
+                    .strokes_state
+                    .modify_shape_stroke(stroke_key, renderer, zoom, |shapestroke| {
+                        shapestroke.dedup_vertices(Self::VERTEX_TOLERANCE)
+                    })
+                    .unwrap_or(false);
+
+                if !valid {
+                    sheet.strokes_state.set_trashed(stroke_key, true);
+                }
+            }
+        }
+
+        self.construction = ShaperConstruction::None;
+    }
+
+    /// Continues the shape under construction with a new stroke starting at the position.
+    /// Returns true if the stroke was consumed by the construction and no new shape should be started
+    fn continue_construction(
+        &mut self,
+        pos: na::Vector2<f64>,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        let handle_radius = Self::HANDLE_RADIUS / zoom;
+
+        match self.construction {
+            ShaperConstruction::None => false,
+            // The stroke might have been removed in the meantime, e.g. by undoing
+            ShaperConstruction::Path { stroke_key }
+            | ShaperConstruction::CubicBezier { stroke_key, .. }
+                if sheet.strokes_state.trashed(stroke_key) != Some(false) =>
+            {
+                self.construction = ShaperConstruction::None;
+                false
+            }
+            // The style was switched in the meantime
+            ShaperConstruction::Path { .. }
+                if !matches!(self.style, ShaperStyle::Polyline | ShaperStyle::Polygon) =>
+            {
+                self.finish_construction(sheet, zoom, renderer);
+                false
+            }
+            ShaperConstruction::CubicBezier { .. } if self.style != ShaperStyle::CubicBezier => {
+                self.finish_construction(sheet, zoom, renderer);
+                false
+            }
+            ShaperConstruction::Path { stroke_key } => {
+                let is_polygon = matches!(
+                    sheet
+                        .strokes_state
+                        .shape_stroke(stroke_key)
+                        .map(|shapestroke| &shapestroke.shape),
+                    Some(Shape::Polygon(_))
+                );
+                let vertices = sheet
+                    .strokes_state
+                    .shape_stroke(stroke_key)
+                    .and_then(|shapestroke| shapestroke.vertices())
+                    .map(|vertices| vertices.to_vec())
+                    .unwrap_or_default();
+
+                // Tapping the last vertex again, or the first vertex of a polygon, finishes the path
+                let closes = is_polygon
+                    && vertices.len() >= 3
+                    && (vertices[0] - pos).magnitude() < handle_radius;
+                let ends = vertices
+                    .last()
+                    .map_or(true, |&last| (last - pos).magnitude() < handle_radius);
+
+                if closes || ends {
+                    self.finish_construction(sheet, zoom, renderer);
+                } else {
+                    sheet.strokes_state.modify_shape_stroke(
+                        stroke_key,
+                        renderer,
+                        zoom,
+                        |shapestroke| shapestroke.add_vertex(pos),
+                    );
+                    self.current_stroke = Some(stroke_key);
+                }
+                true
+            }
+            ShaperConstruction::CubicBezier { stroke_key, .. } => {
+                let handle = match sheet
+                    .strokes_state
+                    .shape_stroke(stroke_key)
+                    .map(|shapestroke| &shapestroke.shape)
+                {
+                    Some(Shape::CubicBezier(cubbez)) => {
+                        Self::bezier_handle_at(cubbez, pos, handle_radius)
+                    }
+                    _ => None,
+                };
+
+                if let Some(handle) = handle {
+                    sheet.strokes_state.record_strokes(&[stroke_key]);
+
+                    self.construction = ShaperConstruction::CubicBezier {
+                        stroke_key,
+                        dragged: Some(handle),
+                    };
+                    self.current_stroke = Some(stroke_key);
+                    true
+                } else {
+                    // Starting somewhere else finishes the curve and draws a new one
+                    self.finish_construction(sheet, zoom, renderer);
+                    false
+                }
+            }
+        }
+    }
+
+    fn bezier_handle_at(
+        cubbez: &curves::CubicBezier,
+        pos: na::Vector2<f64>,
+        radius: f64,
+    ) -> Option<BezierHandle> {
+        // Control points are preferred, as they can lie on top of the end points
+        [
+            (BezierHandle::Cp1, cubbez.cp1),
+            (BezierHandle::Cp2, cubbez.cp2),
+            (BezierHandle::Start, cubbez.start),
+            (BezierHandle::End, cubbez.end),
+        ]
+        .into_iter()
+        .find(|(_, point)| (point - pos).magnitude() < radius)
+        .map(|(handle, _)| handle)
+    }
+
+    fn draw_handle(snapshot: &Snapshot, pos: na::Vector2<f64>, zoom: f64) {
+        let bounds = AABB::from_half_extents(
+            na::Point2::from(pos * zoom),
+            na::Vector2::from_element(Self::HANDLE_RADIUS),
+        );
+        let corner_radius =
+            graphene::Size::new(Self::HANDLE_RADIUS as f32, Self::HANDLE_RADIUS as f32);
+        let handle_rect = gsk::RoundedRect::new(
+            bounds.to_graphene_rect(),
+            corner_radius,
+            corner_radius,
+            corner_radius,
+            corner_radius,
+        );
+        let handle_color = Self::HANDLE_COLOR.to_gdk();
+
+        snapshot.push_rounded_clip(&handle_rect);
+        snapshot.append_color(
+            &Self::HANDLE_FILL_COLOR.to_gdk(),
+            &bounds.to_graphene_rect(),
+        );
+        snapshot.pop();
+
+        snapshot.append_border(
+            &handle_rect,
+            &[Self::HANDLE_BORDER_WIDTH; 4],
+            &[handle_color; 4],
+        );
+    }
+
+    fn draw_handle_line(
+        snapshot: &Snapshot,
+        start: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+        zoom: f64,
+    ) {
+        let line_vec = (end - start) * zoom;
+        let start = start * zoom;
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(start[0] as f32, start[1] as f32));
+        snapshot.rotate(line_vec[1].atan2(line_vec[0]).to_degrees() as f32);
+        snapshot.append_color(
+            &Self::HANDLE_COLOR.to_gdk(),
+            &graphene::Rect::new(
+                0.0,
+                -(Self::HANDLE_LINE_WIDTH / 2.0) as f32,
+                line_vec.magnitude() as f32,
+                Self::HANDLE_LINE_WIDTH as f32,
+            ),
+        );
+        snapshot.restore();
+    }
 }
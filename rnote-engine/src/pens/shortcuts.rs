@@ -3,11 +3,19 @@ use std::ops::{Deref, DerefMut};
 
 use serde::{Deserialize, Serialize};
 
+use super::selector::SelectionOperation;
 use super::PenStyle;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ShortcutAction {
-    ChangePenStyle { style: PenStyle, permanent: bool },
+    ChangePenStyle {
+        style: PenStyle,
+        permanent: bool,
+    },
+    /// Switches to the selector for the next selection, which is combined with the current selection by the operation
+    ChangeSelectionOperation {
+        operation: SelectionOperation,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
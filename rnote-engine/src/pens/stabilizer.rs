@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+
+use crate::strokes::inputdata::InputData;
+
+use gtk4::glib;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "StabilizerMode")]
+#[serde(rename = "stabilizer_mode")]
+pub enum StabilizerMode {
+    #[enum_value(name = "Off", nick = "off")]
+    #[serde(rename = "off")]
+    Off,
+    /// Averages the position and pressure over a window of the last inputs
+    #[enum_value(name = "MovingAverage", nick = "moving-average")]
+    #[serde(rename = "moving_average")]
+    MovingAverage,
+    /// The stroke is pulled behind the input on a string, and only follows when the string is tight
+    #[enum_value(name = "PulledString", nick = "pulled-string")]
+    #[serde(rename = "pulled_string")]
+    PulledString,
+    /// Exponentially smoothes the position and pressure
+    #[enum_value(name = "Exponential", nick = "exponential")]
+    #[serde(rename = "exponential")]
+    Exponential,
+}
+
+impl Default for StabilizerMode {
+    fn default() -> Self {
+        Self::Off
+    }
+}
+
+/// Smoothes the input of the brush before it is added to the stroke
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "stabilizer")]
+pub struct Stabilizer {
+    #[serde(rename = "mode")]
+    pub mode: StabilizerMode,
+    /// The strength, between 0.0 and 1.0
+    #[serde(rename = "strength")]
+    pub strength: f64,
+
+    /// The last unmodified inputs
+    #[serde(skip)]
+    window: VecDeque<InputData>,
+    /// The last stabilized input
+    #[serde(skip)]
+    current: Option<InputData>,
+}
+
+impl Default for Stabilizer {
+    fn default() -> Self {
+        Self {
+            mode: StabilizerMode::default(),
+            strength: Self::STRENGTH_DEFAULT,
+            window: VecDeque::new(),
+            current: None,
+        }
+    }
+}
+
+impl Stabilizer {
+    /// The default strength
+    pub const STRENGTH_DEFAULT: f64 = 0.5;
+    /// The window size of the moving average at full strength
+    pub const WINDOW_SIZE_MAX: usize = 16;
+    /// The length of the string at full strength
+    pub const STRING_LENGTH_MAX: f64 = 30.0;
+    /// The smoothing factor of the exponential smoothing at full strength. Must be > 0.0, else the smoothed input never moves
+    pub const EXP_FACTOR_MIN: f64 = 0.05;
+    /// The distance to the last input when finishing the exponential smoothing
+    const FINISH_TOLERANCE: f64 = 0.5;
+    /// The maximum count of inputs that are generated when finishing the exponential smoothing
+    const FINISH_STEPS_MAX: usize = 32;
+
+    fn strength(&self) -> f64 {
+        self.strength.clamp(0.0, 1.0)
+    }
+
+    fn window_size(&self) -> usize {
+        1 + (self.strength() * (Self::WINDOW_SIZE_MAX - 1) as f64).round() as usize
+    }
+
+    fn string_length(&self) -> f64 {
+        self.strength() * Self::STRING_LENGTH_MAX
+    }
+
+    fn exp_factor(&self) -> f64 {
+        1.0 - self.strength() * (1.0 - Self::EXP_FACTOR_MIN)
+    }
+
+    /// Resets the state. Needs to be called before a new stroke is started
+    pub fn reset(&mut self) {
+        self.window.clear();
+        self.current = None;
+    }
+
+    /// Stabilizes the inputs. Depending on the mode, fewer inputs than given may be returned
+    pub fn stabilize(&mut self, data_entries: VecDeque<InputData>) -> VecDeque<InputData> {
+        data_entries
+            .into_iter()
+            .filter_map(|inputdata| self.stabilize_single(inputdata))
+            .collect()
+    }
+
+    fn stabilize_single(&mut self, inputdata: InputData) -> Option<InputData> {
+        self.window.push_back(inputdata);
+        while self.window.len() > self.window_size().max(1) {
+            self.window.pop_front();
+        }
+
+        let stabilized = match (self.mode, self.current) {
+            (StabilizerMode::Off, _) | (_, None) => Some(inputdata),
+            (StabilizerMode::MovingAverage, Some(_)) => Some(Self::average(&self.window)),
+            (StabilizerMode::PulledString, Some(current)) => {
+                let string_length = self.string_length();
+                let delta = inputdata.pos() - current.pos();
+                let dist = delta.magnitude();
+
+                if dist > string_length {
+                    // The string is tight and pulls the stroke behind the input
+                    let mut pulled = inputdata;
+                    pulled.set_pos(current.pos() + delta * ((dist - string_length) / dist));
+                    Some(pulled)
+                } else {
+                    None
+                }
+            }
+            (StabilizerMode::Exponential, Some(current)) => {
+                Some(Self::smooth_exp(current, inputdata, self.exp_factor()))
+            }
+        };
+
+        if let Some(stabilized) = stabilized {
+            self.current = Some(stabilized);
+        }
+        stabilized
+    }
+
+    /// Finishes the stabilization, returning the inputs that lead from the stabilized position to the last unmodified input, so the stroke ends where the input ended.
+    /// Resets the state
+    pub fn finish(&mut self) -> VecDeque<InputData> {
+        let mut data_entries = VecDeque::new();
+
+        if let (Some(mut current), Some(&last)) = (self.current, self.window.back()) {
+            match self.mode {
+                StabilizerMode::Off => {}
+                StabilizerMode::MovingAverage => {
+                    // Shrinking the window until only the last input is left
+                    while self.window.len() > 1 {
+                        self.window.pop_front();
+                        data_entries.push_back(Self::average(&self.window));
+                    }
+                }
+                StabilizerMode::PulledString => {
+                    if !Self::is_at(current, last) {
+                        data_entries.push_back(last);
+                    }
+                }
+                StabilizerMode::Exponential => {
+                    for _ in 0..Self::FINISH_STEPS_MAX {
+                        if (last.pos() - current.pos()).magnitude() < Self::FINISH_TOLERANCE {
+                            break;
+                        }
+                        current = Self::smooth_exp(current, last, self.exp_factor());
+                        data_entries.push_back(current);
+                    }
+                    if !Self::is_at(current, last) {
+                        data_entries.push_back(last);
+                    }
+                }
+            }
+        }
+
+        self.reset();
+        data_entries
+    }
+
+    /// Wether the stabilized input has reached the position and pressure of the unmodified input
+    fn is_at(stabilized: InputData, inputdata: InputData) -> bool {
+        stabilized.pos() == inputdata.pos() && stabilized.pressure() == inputdata.pressure()
+    }
+
+    /// Averages position and pressure. The other data like tilt and timestamp is taken from the latest input
+    fn average(window: &VecDeque<InputData>) -> InputData {
+        let n = window.len().max(1) as f64;
+        let (pos_sum, pressure_sum) = window.iter().fold(
+            (na::Vector2::<f64>::zeros(), 0.0),
+            |(pos_sum, pressure_sum), inputdata| {
+                (
+                    pos_sum + inputdata.pos(),
+                    pressure_sum + inputdata.pressure(),
+                )
+            },
+        );
+
+        let mut averaged = window.back().copied().unwrap_or_default();
+        averaged.set_pos(pos_sum / n);
+        averaged.set_pressure(pressure_sum / n);
+        averaged
+    }
+
+    /// Smoothes position and pressure. The other data like tilt and timestamp is taken from the next input
+    fn smooth_exp(current: InputData, next: InputData, factor: f64) -> InputData {
+        let mut smoothed = next;
+        smoothed.set_pos(current.pos() + (next.pos() - current.pos()) * factor);
+        smoothed.set_pressure(current.pressure() + (next.pressure() - current.pressure()) * factor);
+        smoothed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [StabilizerMode; 4] = [
+        StabilizerMode::Off,
+        StabilizerMode::MovingAverage,
+        StabilizerMode::PulledString,
+        StabilizerMode::Exponential,
+    ];
+
+    /// A straight line of inputs with a rising pressure
+    fn line_inputs() -> VecDeque<InputData> {
+        (0..20)
+            .map(|i| {
+                InputData::new(
+                    na::vector![f64::from(i) * 5.0, 10.0],
+                    f64::from(i) / 25.0 + 0.1,
+                )
+            })
+            .collect()
+    }
+
+    fn stabilizer(mode: StabilizerMode, strength: f64) -> Stabilizer {
+        Stabilizer {
+            mode,
+            strength,
+            ..Stabilizer::default()
+        }
+    }
+
+    #[test]
+    fn every_mode_ends_at_last_input() {
+        let inputs = line_inputs();
+        let last = *inputs.back().unwrap();
+
+        for mode in MODES {
+            for strength in [0.0, 0.5, 1.0] {
+                let mut stabilizer = stabilizer(mode, strength);
+                let mut outputs = stabilizer.stabilize(inputs.clone());
+                outputs.extend(stabilizer.finish());
+
+                let n = outputs.len();
+                assert!(
+                    Stabilizer::is_at(outputs[n - 1], last),
+                    "{:?} with strength {} ends at {:?}",
+                    mode,
+                    strength,
+                    outputs[n - 1]
+                );
+                // The last input is not repeated
+                assert!(
+                    !Stabilizer::is_at(outputs[n - 2], last),
+                    "{:?} with strength {} repeats the last input",
+                    mode,
+                    strength
+                );
+                assert!(stabilizer.finish().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn finishing_at_last_input_adds_nothing() {
+        for mode in MODES {
+            let mut stabilizer = stabilizer(mode, 1.0);
+            let outputs = stabilizer.stabilize(line_inputs().into_iter().take(1).collect());
+
+            assert_eq!(outputs.len(), 1);
+            assert!(stabilizer.finish().is_empty(), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn off_passes_inputs_through() {
+        let inputs = line_inputs();
+        let mut stabilizer = stabilizer(StabilizerMode::Off, 1.0);
+
+        let outputs = stabilizer.stabilize(inputs.clone());
+        assert_eq!(outputs.len(), inputs.len());
+        assert!(outputs
+            .iter()
+            .zip(inputs.iter())
+            .all(|(&output, &input)| Stabilizer::is_at(output, input)));
+        assert!(stabilizer.finish().is_empty());
+    }
+
+    #[test]
+    fn pulled_string_trails_by_string_length() {
+        let mut stabilizer = stabilizer(StabilizerMode::PulledString, 0.5);
+        let string_length = stabilizer.string_length();
+
+        let outputs = stabilizer.stabilize(line_inputs());
+        // The first input starts the stroke, the next ones only pull it once the string is tight
+        let pulled = outputs.back().unwrap();
+        assert!((95.0 - pulled.pos()[0] - string_length).abs() < 1e-9);
+        assert!(outputs.len() < line_inputs().len());
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::render::Renderer;
+use crate::sheet::Sheet;
+use crate::strokes::inputdata::InputData;
+use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokes::textstroke::{TextStroke, TextStyle};
+use crate::strokesstate::StrokeKey;
+
+use gtk4::{graphene, gsk, Snapshot};
+use p2d::bounding_volume::{BoundingVolume, AABB};
+use serde::{Deserialize, Serialize};
+
+use super::penbehaviour::PenBehaviour;
+
+/// A key which was pressed on the keyboard, mapped from the toolkit key events
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardKey {
+    Unicode(char),
+    BackSpace,
+    Delete,
+    Linefeed,
+    HorizontalTab,
+    NavLeft,
+    NavRight,
+    NavUp,
+    NavDown,
+    Home,
+    End,
+    Escape,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TypewriterState {
+    Idle,
+    /// A position was chosen, the text stroke is created when the first character is typed
+    Placed {
+        pos: na::Vector2<f64>,
+    },
+    /// The text stroke is being edited. The cursor is the char index between characters
+    Editing {
+        stroke_key: StrokeKey,
+        cursor: usize,
+    },
+}
+
+impl Default for TypewriterState {
+    fn default() -> Self {
+        Self::Idle
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "typewriter")]
+pub struct Typewriter {
+    #[serde(rename = "text_style")]
+    pub text_style: TextStyle,
+
+    #[serde(skip)]
+    state: TypewriterState,
+    /// Counts the runs of typing. A run ends when the cursor is moved or the editing is finished. Its insertions and deletions are each merged
+    #[serde(skip)]
+    typing_run: u32,
+}
+
+impl Default for Typewriter {
+    fn default() -> Self {
+        Self {
+            text_style: TextStyle::default(),
+            state: TypewriterState::default(),
+            typing_run: 0,
+        }
+    }
+}
+
+impl PenBehaviour for Typewriter {
+    fn begin(
+        &mut self,
+        mut data_entries: VecDeque<InputData>,
+        sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+        self.finish_editing(sheet);
+
+        if let Some(inputdata) = data_entries.pop_back() {
+            let pos = inputdata.pos();
+
+            self.state = if let Some(stroke_key) = sheet.strokes_state.text_stroke_key_at_pos(pos) {
+                let cursor = sheet
+                    .strokes_state
+                    .text_stroke(stroke_key)
+                    .map_or(0, |textstroke| textstroke.cursor_at_pos(pos));
+
+                TypewriterState::Editing { stroke_key, cursor }
+            } else {
+                TypewriterState::Placed { pos }
+            };
+        }
+    }
+
+    fn motion(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn end(
+        &mut self,
+        _data_entries: VecDeque<InputData>,
+        _sheet: &mut Sheet,
+        _viewport: Option<AABB>,
+        _zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) {
+    }
+
+    fn draw(
+        &self,
+        snapshot: &Snapshot,
+        sheet: &Sheet,
+        _viewport: Option<AABB>,
+        zoom: f64,
+        _renderer: Arc<RwLock<Renderer>>,
+    ) -> Result<(), anyhow::Error> {
+        let (cursor_top, cursor_bottom) = match self.state {
+            TypewriterState::Idle => return Ok(()),
+            TypewriterState::Placed { pos } => (
+                na::Point2::from(pos),
+                na::Point2::from(pos + na::vector![0.0, self.text_style.line_height()]),
+            ),
+            TypewriterState::Editing { stroke_key, cursor } => {
+                let textstroke =
+                    if let Some(textstroke) = sheet.strokes_state.text_stroke(stroke_key) {
+                        textstroke
+                    } else {
+                        return Ok(());
+                    };
+
+                let bounds = textstroke
+                    .bounds
+                    .scale(na::Vector2::from_element(zoom))
+                    .loosened(Self::OUTLINE_MARGIN);
+                let outline_color = Self::OUTLINE_COLOR.to_gdk();
+                snapshot.append_border(
+                    &gsk::RoundedRect::from_rect(bounds.to_graphene_rect(), 0.0),
+                    &[Self::OUTLINE_WIDTH; 4],
+                    &[outline_color; 4],
+                );
+
+                textstroke.gen_cursor_line(cursor.min(textstroke.len_chars()))
+            }
+        };
+
+        // The cursor follows the rotation of the text
+        let cursor_vec = (cursor_bottom - cursor_top) * zoom;
+        let cursor_top = cursor_top.coords * zoom;
+
+        snapshot.save();
+        snapshot.translate(&graphene::Point::new(
+            cursor_top[0] as f32,
+            cursor_top[1] as f32,
+        ));
+        snapshot.rotate(cursor_vec[1].atan2(cursor_vec[0]).to_degrees() as f32 - 90.0);
+        snapshot.append_color(
+            &Self::CURSOR_COLOR.to_gdk(),
+            &graphene::Rect::new(
+                -(Self::CURSOR_WIDTH / 2.0) as f32,
+                0.0,
+                Self::CURSOR_WIDTH as f32,
+                cursor_vec.magnitude() as f32,
+            ),
+        );
+        snapshot.restore();
+
+        Ok(())
+    }
+}
+
+impl Typewriter {
+    pub const CURSOR_COLOR: Color = Color {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    pub const CURSOR_WIDTH: f64 = 2.0;
+    pub const OUTLINE_COLOR: Color = Color {
+        r: 0.6,
+        g: 0.6,
+        b: 0.6,
+        a: 0.8,
+    };
+    pub const OUTLINE_WIDTH: f32 = 1.0;
+    pub const OUTLINE_MARGIN: f64 = 4.0;
+    /// The text inserted for a horizontal tab
+    pub const TAB_TEXT: &'static str = "    ";
+
+    /// Wether the typewriter is currently placed or editing a text stroke
+    pub fn is_active(&self) -> bool {
+        self.state != TypewriterState::Idle
+    }
+
+    /// Handles a pressed key. Returns true if the sheet was changed
+    pub fn handle_key(
+        &mut self,
+        key: KeyboardKey,
+        sheet: &mut Sheet,
+        zoom: f64,
+        renderer: Arc<RwLock<Renderer>>,
+    ) -> bool {
+        match self.state {
+            TypewriterState::Idle => false,
+            TypewriterState::Placed { pos } => {
+                let text = match key {
+                    KeyboardKey::Unicode(c) => c.to_string(),
+                    KeyboardKey::Linefeed => String::from("\n"),
+                    KeyboardKey::HorizontalTab => String::from(Self::TAB_TEXT),
+                    KeyboardKey::Escape => {
+                        self.state = TypewriterState::Idle;
+                        return false;
+                    }
+                    _ => return false,
+                };
+
+                let textstroke = TextStroke::new(text, pos, self.text_style.clone());
+                let cursor = textstroke.len_chars();
+
+                let stroke_key = sheet
+                    .strokes_state
+                    .insert_stroke(StrokeStyle::TextStroke(textstroke));
+                sheet
+                    .strokes_state
+                    .regenerate_rendering_for_stroke_threaded(stroke_key, renderer, zoom);
+
+                self.state = TypewriterState::Editing { stroke_key, cursor };
+                true
+            }
+            TypewriterState::Editing { stroke_key, cursor } => {
+                // The stroke might have been trashed or changed in the meantime, e.g. through undo
+                let len_chars = match sheet.strokes_state.text_stroke(stroke_key) {
+                    Some(textstroke)
+                        if !sheet.strokes_state.trashed(stroke_key).unwrap_or(true) =>
+                    {
+                        textstroke.len_chars()
+                    }
+                    _ => {
+                        self.state = TypewriterState::Idle;
+                        return false;
+                    }
+                };
+                let cursor = cursor.min(len_chars);
+                let insert_merge_id = format!("typewriter-insert-{}", self.typing_run);
+                let delete_merge_id = format!("typewriter-delete-{}", self.typing_run);
+
+                let (new_cursor, changed) = match key {
+                    KeyboardKey::Unicode(c) => (
+                        sheet.strokes_state.modify_text_stroke(
+                            stroke_key,
+                            insert_merge_id,
+                            renderer,
+                            zoom,
+                            |textstroke| textstroke.insert_text_at_cursor(&c.to_string(), cursor),
+                        ),
+                        true,
+                    ),
+                    KeyboardKey::Linefeed => (
+                        sheet.strokes_state.modify_text_stroke(
+                            stroke_key,
+                            insert_merge_id,
+                            renderer,
+                            zoom,
+                            |textstroke| textstroke.insert_text_at_cursor("\n", cursor),
+                        ),
+                        true,
+                    ),
+                    KeyboardKey::HorizontalTab => (
+                        sheet.strokes_state.modify_text_stroke(
+                            stroke_key,
+                            insert_merge_id,
+                            renderer,
+                            zoom,
+                            |textstroke| textstroke.insert_text_at_cursor(Self::TAB_TEXT, cursor),
+                        ),
+                        true,
+                    ),
+                    KeyboardKey::BackSpace if cursor > 0 => (
+                        sheet.strokes_state.modify_text_stroke(
+                            stroke_key,
+                            delete_merge_id,
+                            renderer,
+                            zoom,
+                            |textstroke| textstroke.remove_char_before_cursor(cursor),
+                        ),
+                        true,
+                    ),
+                    KeyboardKey::Delete if cursor < len_chars => (
+                        sheet.strokes_state.modify_text_stroke(
+                            stroke_key,
+                            delete_merge_id,
+                            renderer,
+                            zoom,
+                            |textstroke| textstroke.remove_char_after_cursor(cursor),
+                        ),
+                        true,
+                    ),
+                    KeyboardKey::NavLeft => (Some(cursor.saturating_sub(1)), false),
+                    KeyboardKey::NavRight => (Some((cursor + 1).min(len_chars)), false),
+                    KeyboardKey::NavUp | KeyboardKey::NavDown => (
+                        sheet
+                            .strokes_state
+                            .text_stroke(stroke_key)
+                            .map(|textstroke| {
+                                textstroke
+                                    .cursor_on_adjacent_line(cursor, key == KeyboardKey::NavDown)
+                            }),
+                        false,
+                    ),
+                    KeyboardKey::Home | KeyboardKey::End => (
+                        sheet
+                            .strokes_state
+                            .text_stroke(stroke_key)
+                            .map(|textstroke| {
+                                let (line_start, line_end) = textstroke.cursor_line_bounds(cursor);
+
+                                if key == KeyboardKey::Home {
+                                    line_start
+                                } else {
+                                    line_end
+                                }
+                            }),
+                        false,
+                    ),
+                    KeyboardKey::Escape => {
+                        self.finish_editing(sheet);
+                        return true;
+                    }
+                    _ => (Some(cursor), false),
+                };
+
+                // Moving the cursor ends the typing run
+                if matches!(
+                    key,
+                    KeyboardKey::NavLeft
+                        | KeyboardKey::NavRight
+                        | KeyboardKey::NavUp
+                        | KeyboardKey::NavDown
+                        | KeyboardKey::Home
+                        | KeyboardKey::End
+                ) {
+                    self.typing_run = self.typing_run.wrapping_add(1);
+                }
+
+                self.state = TypewriterState::Editing {
+                    stroke_key,
+                    cursor: new_cursor.unwrap_or(cursor),
+                };
+
+                changed
+            }
+        }
+    }
+
+    /// Finishes placing or editing text. A text stroke which was emptied gets trashed
+    pub fn finish_editing(&mut self, sheet: &mut Sheet) {
+        if let TypewriterState::Editing { stroke_key, .. } = self.state {
+            let emptied = sheet
+                .strokes_state
+                .text_stroke(stroke_key)
+                .map_or(false, |textstroke| textstroke.text.is_empty());
+            let trashed = sheet.strokes_state.trashed(stroke_key).unwrap_or(true);
+
+            if emptied && !trashed {
+                sheet.strokes_state.set_trashed(stroke_key, true);
+            }
+        }
+
+        self.state = TypewriterState::Idle;
+        self.typing_run = self.typing_run.wrapping_add(1);
+    }
+}
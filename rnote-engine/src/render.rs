@@ -2,7 +2,7 @@ use std::ops::Deref;
 
 use anyhow::Context;
 use gtk4::{gdk, gio, glib, gsk, prelude::*, Native, Snapshot, Widget};
-use p2d::bounding_volume::AABB;
+use p2d::bounding_volume::{BoundingVolume, AABB};
 
 use crate::compose;
 use crate::compose::geometry::AABBHelpers;
@@ -468,6 +468,7 @@ pub fn rendernode_to_texture(
     Ok(None)
 }
 
+/// Draws the svgs as one document, so that blend modes (e.g. of highlighter strokes) apply to the svgs drawn before them
 pub fn draw_svgs_to_cairo_context(
     zoom: f64,
     svgs: &[Svg],
@@ -477,40 +478,47 @@ pub fn draw_svgs_to_cairo_context(
     bounds.ensure_valid();
     assert_bounds(bounds)?;
 
-    for svg in svgs {
-        let svg_data = compose::wrap_svg_root(
-            svg.svg_data.as_str(),
-            Some(svg.bounds),
-            Some(svg.bounds),
-            false,
-        );
+    let svgs_bounds = match svgs
+        .iter()
+        .map(|svg| svg.bounds)
+        .reduce(|acc, next| acc.merged(&next))
+    {
+        Some(svgs_bounds) => svgs_bounds,
+        None => return Ok(()),
+    };
+
+    let svg_data = svgs
+        .iter()
+        .map(|svg| svg.svg_data.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n");
+    let svg_data = compose::wrap_svg_root(&svg_data, Some(svgs_bounds), Some(svgs_bounds), false);
+
+    cx.scale(zoom, zoom);
+
+    let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from(svg_data.as_bytes()));
+
+    let handle = librsvg::Loader::new()
+        .read_stream::<gio::MemoryInputStream, gio::File, gio::Cancellable>(&stream, None, None)
+        .context("read stream to librsvg Loader failed")?;
+    let renderer = librsvg::CairoRenderer::new(&handle);
+    renderer
+        .render_document(
+            &cx,
+            &cairo::Rectangle {
+                x: svgs_bounds.mins[0],
+                y: svgs_bounds.mins[1],
+                width: svgs_bounds.extents()[0],
+                height: svgs_bounds.extents()[1],
+            },
+        )
+        .map_err(|e| {
+            anyhow::Error::msg(format!(
+                "librsvg render_document() failed in draw_svgs_to_cairo_context() with Err {}",
+                e
+            ))
+        })?;
 
-        cx.scale(zoom, zoom);
-        //cx.translate(-svg.bounds.mins[0], -svg.bounds.mins[1]);
-
-        let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from(svg_data.as_bytes()));
-
-        let handle = librsvg::Loader::new()
-            .read_stream::<gio::MemoryInputStream, gio::File, gio::Cancellable>(&stream, None, None)
-            .context("read stream to librsvg Loader failed")?;
-        let renderer = librsvg::CairoRenderer::new(&handle);
-        renderer
-            .render_document(
-                &cx,
-                &cairo::Rectangle {
-                    x: svg.bounds.mins[0],
-                    y: svg.bounds.mins[1],
-                    width: svg.bounds.extents()[0],
-                    height: svg.bounds.extents()[1],
-                },
-            )
-            .map_err(|e| {
-                anyhow::Error::msg(format!(
-                    "librsvg render_document() failed in draw_svgs_to_cairo_context() with Err {}",
-                    e
-                ))
-            })?;
-    }
     Ok(())
 }
 
@@ -8,7 +8,8 @@ use crate::compose::geometry::AABBHelpers;
 use crate::compose::transformable::Transformable;
 use crate::render::{self, Renderer};
 use crate::strokes::strokestyle::StrokeStyle;
-use crate::strokesstate::StrokesState;
+use crate::strokesstate::history::HistoryEntry;
+use crate::strokesstate::{StrokeKey, StrokesState};
 use crate::utils;
 use anyhow::Context;
 use futures::channel::oneshot;
@@ -215,6 +216,126 @@ impl Sheet {
         self.height = new_bounds.extents()[1];
     }
 
+    /// Records the current sheet bounds in the history. Call it before explicitly resizing the sheet.
+    pub fn record_bounds(&mut self) {
+        let bounds = self.bounds();
+
+        self.strokes_state
+            .history_mut()
+            .record(HistoryEntry::SheetBounds { bounds });
+    }
+
+    /// Records the current format in the history. Call it before changing the format.
+    /// The sheet bounds are recorded as well, as changing the format resizes the sheet.
+    pub fn record_format(&mut self) {
+        let entries = vec![
+            HistoryEntry::Format {
+                format: self.format,
+            },
+            HistoryEntry::SheetBounds {
+                bounds: self.bounds(),
+            },
+        ];
+
+        self.strokes_state
+            .history_mut()
+            .record(HistoryEntry::Group { entries });
+    }
+
+    /// Records the current background in the history. Call it before changing the background.
+    pub fn record_background(&mut self) {
+        let background = self.background.clone();
+
+        self.strokes_state
+            .history_mut()
+            .record(HistoryEntry::Background { background });
+    }
+
+    /// Undoes the latest step in the history. Returns false if there was nothing to undo.
+    /// The background and the rendering of the strokes need to be regenerated after calling this.
+    pub fn undo(&mut self) -> bool {
+        if let Some(entry) = self.strokes_state.history_mut().pop_undo() {
+            let reverting_entry = self.apply_history_entry(entry);
+            self.strokes_state.history_mut().push_redo(reverting_entry);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Redoes the latest undone step in the history. Returns false if there was nothing to redo.
+    /// The background and the rendering of the strokes need to be regenerated after calling this.
+    pub fn redo(&mut self) -> bool {
+        if let Some(entry) = self.strokes_state.history_mut().pop_redo() {
+            let reverting_entry = self.apply_history_entry(entry);
+            self.strokes_state.history_mut().push_undo(reverting_entry);
+
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies the history entry, returns the entry which reverts it
+    fn apply_history_entry(&mut self, entry: HistoryEntry) -> HistoryEntry {
+        match entry {
+            HistoryEntry::TrashState { states } => HistoryEntry::TrashState {
+                states: self.strokes_state.swap_trash_state(states),
+            },
+            HistoryEntry::Strokes { strokes } => HistoryEntry::Strokes {
+                strokes: self.strokes_state.swap_strokes(strokes),
+            },
+            HistoryEntry::Groups { groups, group_ids } => {
+                let (groups, group_ids) = self.strokes_state.swap_groups(groups, group_ids);
+
+                HistoryEntry::Groups { groups, group_ids }
+            }
+            HistoryEntry::Layers {
+                layers,
+                current_layer,
+                layer_ids,
+            } => {
+                let (layers, current_layer, layer_ids) =
+                    self.strokes_state
+                        .swap_layers(layers, current_layer, layer_ids);
+
+                HistoryEntry::Layers {
+                    layers,
+                    current_layer,
+                    layer_ids,
+                }
+            }
+            HistoryEntry::ZOrder { z_orders } => HistoryEntry::ZOrder {
+                z_orders: self.strokes_state.swap_z_order(z_orders),
+            },
+            HistoryEntry::SheetBounds { bounds } => {
+                let replaced = self.bounds();
+
+                self.x = bounds.mins[0];
+                self.y = bounds.mins[1];
+                self.width = bounds.extents()[0];
+                self.height = bounds.extents()[1];
+
+                HistoryEntry::SheetBounds { bounds: replaced }
+            }
+            HistoryEntry::Format { format } => HistoryEntry::Format {
+                format: std::mem::replace(&mut self.format, format),
+            },
+            HistoryEntry::Background { background } => HistoryEntry::Background {
+                background: std::mem::replace(&mut self.background, background),
+            },
+            HistoryEntry::Group { entries } => HistoryEntry::Group {
+                // Applied in reverse order. The reverting entries are then in the order they need to be applied again.
+                entries: entries
+                    .into_iter()
+                    .rev()
+                    .map(|entry| self.apply_history_entry(entry))
+                    .collect(),
+            },
+        }
+    }
+
     // a new sheet should always be imported with this method, as to not replace the threadpool, channel handlers, ..
     pub fn import_sheet(&mut self, sheet: Self) {
         self.x = sheet.x;
@@ -297,7 +418,22 @@ impl Sheet {
         let mut offset = na::Vector2::<f64>::zeros();
 
         for (_page_i, page) in xopp_file.xopp_root.pages.into_iter().enumerate() {
-            for layers in page.layers.into_iter() {
+            for (layer_i, layers) in page.layers.into_iter().enumerate() {
+                // The xopp layers of all pages with the same index are mapped to one layer
+                let layer_name = layers
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("Layer {}", layer_i + 1));
+                let layer_id = if let Some(layer) = sheet.strokes_state.layers().get(layer_i) {
+                    layer.id
+                } else {
+                    sheet.strokes_state.add_layer(layer_name.clone())
+                };
+                if layers.name.is_some() {
+                    sheet.strokes_state.set_layer_name(layer_id, layer_name);
+                }
+                sheet.strokes_state.set_current_layer(layer_id);
+
                 // import strokes
                 for new_xoppstroke in layers.strokes.into_iter() {
                     match StrokeStyle::from_xoppstroke(new_xoppstroke, offset) {
@@ -313,6 +449,21 @@ impl Sheet {
                     }
                 }
 
+                // import texts
+                for new_xopptext in layers.texts.into_iter() {
+                    match StrokeStyle::from_xopptext(new_xopptext, offset) {
+                        Ok(new_text) => {
+                            sheet.strokes_state.insert_stroke(new_text);
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "from_xopptext() failed in open_from_xopp_bytes() with Err {}",
+                                e
+                            );
+                        }
+                    }
+                }
+
                 // import images
                 for new_xoppimage in layers.images.into_iter() {
                     match StrokeStyle::from_xoppimage(new_xoppimage, offset) {
@@ -333,6 +484,11 @@ impl Sheet {
             offset[1] += page.height;
         }
 
+        // New strokes are inserted into the top layer
+        if let Some(top_layer) = sheet.strokes_state.layers().last().map(|layer| layer.id) {
+            sheet.strokes_state.set_current_layer(top_layer);
+        }
+
         sheet.background = background;
         sheet.format = format;
 
@@ -392,62 +548,80 @@ impl Sheet {
             .pages_bounds_containing_content()
             .iter()
             .map(|&page_bounds| {
-                let page_keys = self.strokes_state.keys_intersecting_bounds(page_bounds);
-
-                let strokes = self.strokes_state.clone_strokes_for_keys(&page_keys);
-
-                // Translate strokes to to page mins and convert to XoppStrokStyle
-                let xopp_strokestyles = strokes
-                    .into_iter()
-                    .filter_map(|mut stroke| {
-                        stroke.translate(-page_bounds.mins.coords);
-
-                        stroke.into_xopp(current_dpi, Arc::clone(&renderer))
-                    })
-                    .collect::<Vec<xoppformat::XoppStrokeStyle>>();
-
-                // Extract the strokes
-                let xopp_strokes = xopp_strokestyles
-                    .iter()
-                    .filter_map(|stroke| {
-                        if let xoppformat::XoppStrokeStyle::XoppStroke(xoppstroke) = stroke {
-                            Some(xoppstroke.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<xoppformat::XoppStroke>>();
-
-                // Extract the texts
-                let xopp_texts = xopp_strokestyles
+                // Hidden layers are exported as well
+                let page_keys = self
+                    .strokes_state
+                    .keys_intersecting_bounds_all_layers(page_bounds);
+
+                // One xopp layer for every layer, so the layers are kept when the file is opened again
+                let layers = self
+                    .strokes_state
+                    .layers()
                     .iter()
-                    .filter_map(|stroke| {
-                        if let xoppformat::XoppStrokeStyle::XoppText(xopptext) = stroke {
-                            Some(xopptext.clone())
-                        } else {
-                            None
+                    .map(|layer| {
+                        let layer_keys = page_keys
+                            .iter()
+                            .copied()
+                            .filter(|&key| self.strokes_state.layer_of(key) == layer.id)
+                            .collect::<Vec<StrokeKey>>();
+
+                        let strokes = self.strokes_state.clone_strokes_for_keys(&layer_keys);
+
+                        // Translate strokes to to page mins and convert to XoppStrokStyle
+                        let xopp_strokestyles = strokes
+                            .into_iter()
+                            .filter_map(|mut stroke| {
+                                stroke.translate(-page_bounds.mins.coords);
+
+                                stroke.into_xopp(current_dpi, Arc::clone(&renderer))
+                            })
+                            .collect::<Vec<xoppformat::XoppStrokeStyle>>();
+
+                        // Extract the strokes
+                        let xopp_strokes = xopp_strokestyles
+                            .iter()
+                            .filter_map(|stroke| {
+                                if let xoppformat::XoppStrokeStyle::XoppStroke(xoppstroke) = stroke
+                                {
+                                    Some(xoppstroke.clone())
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect::<Vec<xoppformat::XoppStroke>>();
+
+                        // Extract the texts
+                        let xopp_texts = xopp_strokestyles
+                            .iter()
+                            .filter_map(|stroke| {
+                                if let xoppformat::XoppStrokeStyle::XoppText(xopptext) = stroke {
+                                    Some(xopptext.clone())
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect::<Vec<xoppformat::XoppText>>();
+
+                        // Extract the images
+                        let xopp_images = xopp_strokestyles
+                            .iter()
+                            .filter_map(|stroke| {
+                                if let xoppformat::XoppStrokeStyle::XoppImage(xoppstroke) = stroke {
+                                    Some(xoppstroke.clone())
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect::<Vec<xoppformat::XoppImage>>();
+
+                        xoppformat::XoppLayer {
+                            name: Some(layer.name.clone()),
+                            strokes: xopp_strokes,
+                            texts: xopp_texts,
+                            images: xopp_images,
                         }
                     })
-                    .collect::<Vec<xoppformat::XoppText>>();
-
-                // Extract the images
-                let xopp_images = xopp_strokestyles
-                    .iter()
-                    .filter_map(|stroke| {
-                        if let xoppformat::XoppStrokeStyle::XoppImage(xoppstroke) = stroke {
-                            Some(xoppstroke.clone())
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<xoppformat::XoppImage>>();
-
-                let layer = xoppformat::XoppLayer {
-                    name: None,
-                    strokes: xopp_strokes,
-                    texts: xopp_texts,
-                    images: xopp_images,
-                };
+                    .collect::<Vec<xoppformat::XoppLayer>>();
 
                 let page_dimensions = utils::convert_coord_dpi(
                     page_bounds.extents(),
@@ -459,7 +633,7 @@ impl Sheet {
                     width: page_dimensions[0],
                     height: page_dimensions[1],
                     background: background.clone(),
-                    layers: vec![layer],
+                    layers,
                 }
             })
             .collect::<Vec<xoppformat::XoppPage>>();
@@ -559,3 +733,156 @@ impl Sheet {
         oneshot_receiver
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::color::Color;
+    use crate::strokes::brushstroke::BrushStroke;
+
+    /// Checks that undoing the change restores the state from before and redoing it restores the changed state
+    fn assert_undo_redo_round_trip<T, S, C>(sheet: &mut Sheet, state: S, change: C)
+    where
+        T: PartialEq + std::fmt::Debug,
+        S: Fn(&Sheet) -> T,
+        C: FnOnce(&mut Sheet),
+    {
+        let before = state(sheet);
+        change(sheet);
+        let after = state(sheet);
+        assert_ne!(before, after);
+
+        assert!(sheet.undo());
+        assert_eq!(state(sheet), before);
+
+        assert!(sheet.redo());
+        assert_eq!(state(sheet), after);
+    }
+
+    fn sheet_w_strokes() -> (Sheet, Vec<StrokeKey>) {
+        let mut sheet = Sheet::default();
+        let keys = (0..2)
+            .map(|_| {
+                sheet
+                    .strokes_state
+                    .insert_stroke(StrokeStyle::BrushStroke(BrushStroke::default()))
+            })
+            .collect::<Vec<StrokeKey>>();
+        keys.iter()
+            .for_each(|&key| sheet.strokes_state.set_selected(key, true));
+
+        (sheet, keys)
+    }
+
+    #[test]
+    fn trash_state_round_trip() {
+        let (mut sheet, keys) = sheet_w_strokes();
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| sheet.strokes_state.trashed(keys[0]),
+            |sheet| sheet.strokes_state.set_trashed(keys[0], true),
+        );
+    }
+
+    #[test]
+    fn strokes_round_trip() {
+        let (mut sheet, keys) = sheet_w_strokes();
+        let renderer = Arc::new(RwLock::new(Renderer::default()));
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| {
+                sheet
+                    .strokes_state
+                    .brush_stroke(keys[0])
+                    .map(|brushstroke| brushstroke.style.width())
+            },
+            |sheet| {
+                sheet
+                    .strokes_state
+                    .modify_brush_stroke(keys[0], renderer, 1.0, |brushstroke| {
+                        brushstroke.style.set_width(42.0);
+                        Some(())
+                    });
+            },
+        );
+    }
+
+    #[test]
+    fn groups_round_trip() {
+        let (mut sheet, keys) = sheet_w_strokes();
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| sheet.strokes_state.group_of(keys[0]),
+            |sheet| {
+                sheet.strokes_state.group_selection();
+            },
+        );
+    }
+
+    #[test]
+    fn layers_round_trip() {
+        let (mut sheet, keys) = sheet_w_strokes();
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| {
+                (
+                    sheet.strokes_state.layers().len(),
+                    sheet.strokes_state.current_layer(),
+                    sheet.strokes_state.layer_of(keys[0]),
+                )
+            },
+            |sheet| {
+                sheet.strokes_state.history_mut().begin_group();
+                let layer_id = sheet.strokes_state.add_layer(String::from("Top"));
+                sheet.strokes_state.set_current_layer(layer_id);
+                sheet.strokes_state.move_selection_to_layer(layer_id);
+                sheet.strokes_state.history_mut().end_group();
+            },
+        );
+    }
+
+    #[test]
+    fn z_order_round_trip() {
+        let (mut sheet, keys) = sheet_w_strokes();
+        sheet.strokes_state.set_selected(keys[1], false);
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| sheet.strokes_state.keys_sorted_z(),
+            |sheet| sheet.strokes_state.selection_to_front(),
+        );
+    }
+
+    #[test]
+    fn format_and_bounds_round_trip() {
+        let mut sheet = Sheet::default();
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| (sheet.format.dpi, sheet.bounds()),
+            |sheet| {
+                sheet.record_format();
+                sheet.format.dpi = 300.0;
+                sheet.height *= 2.0;
+            },
+        );
+    }
+
+    #[test]
+    fn background_round_trip() {
+        let mut sheet = Sheet::default();
+
+        assert_undo_redo_round_trip(
+            &mut sheet,
+            |sheet| sheet.background.color,
+            |sheet| {
+                sheet.record_background();
+                sheet.background.color = Color::new(1.0, 0.0, 0.0, 1.0);
+            },
+        );
+    }
+}
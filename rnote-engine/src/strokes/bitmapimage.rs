@@ -4,7 +4,7 @@ use std::sync::{Arc, RwLock};
 use crate::compose;
 use crate::compose::geometry::AABBHelpers;
 use crate::compose::shapes;
-use crate::compose::transformable::{Transform, Transformable};
+use crate::compose::transformable::{MirrorAxis, Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::render::{self, Renderer};
 
@@ -127,6 +127,16 @@ impl Transformable for BitmapImage {
         self.rectangle.scale(scale);
         self.update_geometry();
     }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.rectangle.mirror(axis, center);
+        self.update_geometry();
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.rectangle.shear(shear, center);
+        self.update_geometry();
+    }
 }
 
 impl BitmapImage {
@@ -161,6 +171,7 @@ impl BitmapImage {
                 pos + intrinsic_size / 2.0,
                 0.0,
             )),
+            corner_radius: 0.0,
         };
 
         let mut bitmapimage = Self {
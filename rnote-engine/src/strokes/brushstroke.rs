@@ -1,11 +1,15 @@
-use crate::compose::geometry::AABBHelpers;
-use crate::compose::smooth::SmoothOptions;
+use crate::compose::calligraphy::CalligraphyOptions;
+use crate::compose::color::Color;
+use crate::compose::geometry::{self, AABBHelpers};
+use crate::compose::pressurecurve::PressureCurve;
+use crate::compose::smooth::{LineCap, SmoothOptions};
 use crate::compose::textured::TexturedOptions;
-use crate::compose::transformable::Transformable;
-use crate::compose::{self, curves, smooth, textured};
+use crate::compose::transformable::{shear_point, MirrorAxis, Transformable};
+use crate::compose::{self, calligraphy, curves, smooth, textured};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::brush::Brush;
 use crate::pens::brush::BrushStyle;
+use crate::pens::eraser::Eraser;
 use crate::strokes::inputdata::InputData;
 use crate::{render, utils};
 
@@ -14,20 +18,29 @@ use rand::{Rng, SeedableRng};
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use svg::node::element::path;
+use svg::Node;
 
 use super::element::Element;
+use super::shaperecognizer::ShapeRecognizer;
+use super::shapestroke::{ShapeDrawStyle, ShapeStroke};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "brushstroke_style")]
 pub enum BrushStrokeStyle {
     #[serde(rename = "marker")]
     Marker {
-        #[serde(rename = "options")]
+        #[serde(
+            rename = "options",
+            deserialize_with = "deserialize_smooth_options_w_round_caps"
+        )]
         options: SmoothOptions,
     },
     #[serde(rename = "solid")]
     Solid {
-        #[serde(rename = "options")]
+        #[serde(
+            rename = "options",
+            deserialize_with = "deserialize_smooth_options_w_round_caps"
+        )]
         options: SmoothOptions,
     },
     #[serde(rename = "textured")]
@@ -35,6 +48,123 @@ pub enum BrushStrokeStyle {
         #[serde(rename = "options")]
         options: TexturedOptions,
     },
+    /// A flat chisel tip with a constant width, which multiplies its color with the content below it
+    #[serde(rename = "highlighter")]
+    Highlighter {
+        #[serde(rename = "options")]
+        options: SmoothOptions,
+    },
+    /// A flat nib, which draws wider or thinner depending on the direction of the stroke
+    #[serde(rename = "calligraphy")]
+    Calligraphy {
+        #[serde(rename = "options")]
+        options: CalligraphyOptions,
+    },
+}
+
+/// Marker and solid strokes were always drawn with round caps before the line cap was configurable,
+/// so options without a line cap keep them.
+pub(crate) fn deserialize_smooth_options_w_round_caps<'de, D>(
+    deserializer: D,
+) -> Result<SmoothOptions, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    let has_line_cap = value.get("line_cap").is_some();
+
+    let mut options = SmoothOptions::deserialize(value).map_err(serde::de::Error::custom)?;
+    if !has_line_cap {
+        options.line_cap = LineCap::Round;
+    }
+
+    Ok(options)
+}
+
+impl BrushStrokeStyle {
+    /// The style with the current style and options of the brush
+    pub fn from_brush(brush: &Brush, seed: Option<u64>) -> Self {
+        match brush.style {
+            BrushStyle::Marker => {
+                let mut options = brush.smooth_options;
+                options.seed = seed;
+
+                Self::Marker { options }
+            }
+            BrushStyle::Solid => {
+                let mut options = brush.smooth_options;
+                options.seed = seed;
+
+                Self::Solid { options }
+            }
+            BrushStyle::Textured => {
+                let mut options = brush.textured_options;
+                options.seed = seed;
+
+                Self::Textured { options }
+            }
+            BrushStyle::Highlighter => {
+                let mut options = brush.smooth_options;
+                options.seed = seed;
+
+                Self::Highlighter { options }
+            }
+            BrushStyle::Calligraphy => Self::Calligraphy {
+                options: brush.calligraphy_options,
+            },
+        }
+    }
+
+    /// The seed of the options. Calligraphy strokes have no seed
+    pub fn seed(&self) -> Option<u64> {
+        match self {
+            Self::Marker { options } => options.seed,
+            Self::Solid { options } => options.seed,
+            Self::Textured { options } => options.seed,
+            Self::Highlighter { options } => options.seed,
+            Self::Calligraphy { .. } => None,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        match self {
+            Self::Marker { options } => options.width,
+            Self::Solid { options } => options.width,
+            Self::Textured { options } => options.width,
+            Self::Highlighter { options } => options.width,
+            Self::Calligraphy { options } => options.width,
+        }
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        match self {
+            Self::Marker { options } => options.width = width,
+            Self::Solid { options } => options.width = width,
+            Self::Textured { options } => options.width = width,
+            Self::Highlighter { options } => options.width = width,
+            Self::Calligraphy { options } => options.width = width,
+        }
+    }
+
+    pub fn stroke_color(&self) -> Option<Color> {
+        match self {
+            Self::Marker { options } => options.stroke_color,
+            Self::Solid { options } => options.stroke_color,
+            Self::Textured { options } => options.stroke_color,
+            Self::Highlighter { options } => options.stroke_color,
+            Self::Calligraphy { options } => options.stroke_color,
+        }
+    }
+
+    pub fn set_stroke_color(&mut self, color: Option<Color>) {
+        match self {
+            Self::Marker { options } => options.stroke_color = color,
+            Self::Solid { options } => options.stroke_color = color,
+            Self::Textured { options } => options.stroke_color = color,
+            Self::Highlighter { options } => options.stroke_color = color,
+            Self::Calligraphy { options } => options.stroke_color = color,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,10 +174,16 @@ pub struct BrushStroke {
     pub elements: Vec<Element>,
     #[serde(rename = "style")]
     pub style: BrushStrokeStyle,
+    /// Maps the pressures of the elements to the widths and opacities the stroke is drawn with
+    #[serde(rename = "pressure_curve")]
+    pub pressure_curve: PressureCurve,
     #[serde(rename = "bounds")]
     pub bounds: AABB,
     #[serde(skip)]
     pub hitboxes: Vec<AABB>,
+    /// The length of the stroke before the last elements window, where the dash pattern of that window continues from
+    #[serde(skip)]
+    pub dash_start_length: f64,
 }
 
 impl Default for BrushStroke {
@@ -80,12 +216,6 @@ impl DrawBehaviour for BrushStroke {
                     .filter_map(|(((first, second), third), forth)| {
                         let mut bounds = AABB::new_invalid();
 
-                        let width = match &self.style {
-                            BrushStrokeStyle::Marker { options } => options.width,
-                            BrushStrokeStyle::Solid { options } => options.width,
-                            BrushStrokeStyle::Textured { options } => options.width,
-                        };
-
                         if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
                             first.inputdata.pos(),
                             second.inputdata.pos(),
@@ -106,7 +236,7 @@ impl DrawBehaviour for BrushStroke {
                             return None;
                         }
 
-                        bounds.loosen(width + 1.0);
+                        bounds.loosen(self.bounds_margin());
 
                         Some(bounds)
                     })
@@ -127,6 +257,12 @@ impl DrawBehaviour for BrushStroke {
             BrushStrokeStyle::Textured { options } => {
                 self.gen_svgs_textured(options, offset, svg_root)
             }
+            BrushStrokeStyle::Highlighter { options } => {
+                self.gen_svgs_highlighter(options, offset, svg_root)
+            }
+            BrushStrokeStyle::Calligraphy { options } => {
+                self.gen_svgs_calligraphy(options, offset, svg_root)
+            }
         }
     }
 }
@@ -159,34 +295,47 @@ impl Transformable for BrushStroke {
         });
         self.update_geometry();
     }
+    fn mirror(&mut self, axis: MirrorAxis, center: nalgebra::Point2<f64>) {
+        self.elements.iter_mut().for_each(|element| {
+            element
+                .inputdata
+                .set_pos(axis.mirror_point(element.inputdata.pos(), center));
+            // The tilt is mirrored as well, for nibs following the tilt
+            element.inputdata.set_tilt(
+                element
+                    .inputdata
+                    .tilt()
+                    .map(|tilt| tilt.component_mul(&axis.reflection_scale())),
+            );
+        });
+        if let BrushStrokeStyle::Calligraphy { options } = &mut self.style {
+            options.mirror(axis);
+        }
+        self.update_geometry();
+    }
+    fn shear(&mut self, shear: nalgebra::Vector2<f64>, center: nalgebra::Point2<f64>) {
+        self.elements.iter_mut().for_each(|element| {
+            element
+                .inputdata
+                .set_pos(shear_point(element.inputdata.pos(), shear, center));
+        });
+        self.update_geometry();
+    }
 }
 
 impl BrushStroke {
     pub const HITBOX_DEFAULT: f64 = 10.0;
+    /// The number of splits of the bezier curve approximation of solid and dashed marker strokes
+    const DASHED_N_SPLITS: i32 = 5;
+    /// The default tolerance when simplifying the stroke before editing its nodes
+    pub const SIMPLIFY_TOLERANCE_DEFAULT: f64 = 1.0;
+    /// The step when increasing or decreasing the pressure of a node
+    pub const NODE_PRESSURE_STEP: f64 = 0.1;
 
     pub fn new(element: Element, brush: &Brush) -> Self {
         let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
 
-        let style = match brush.style {
-            BrushStyle::Marker => {
-                let mut options = brush.smooth_options;
-                options.seed = seed;
-
-                BrushStrokeStyle::Marker { options }
-            }
-            BrushStyle::Solid => {
-                let mut options = brush.smooth_options;
-                options.seed = seed;
-
-                BrushStrokeStyle::Solid { options }
-            }
-            BrushStyle::Textured => {
-                let mut options = brush.textured_options;
-                options.seed = seed;
-
-                BrushStrokeStyle::Textured { options }
-            }
-        };
+        let style = BrushStrokeStyle::from_brush(brush, seed);
         let elements = Vec::with_capacity(4);
         let bounds = AABB::new(
             na::point![element.inputdata.pos()[0], element.inputdata.pos()[1]],
@@ -197,8 +346,10 @@ impl BrushStroke {
         let mut brushstroke = Self {
             elements,
             style,
+            pressure_curve: brush.pressure_curve,
             bounds,
             hitboxes: hitbox,
+            dash_start_length: 0.0,
         };
 
         // Pushing with push_elem() instead filling vector, because bounds are getting updated there too
@@ -225,9 +376,33 @@ impl BrushStroke {
         }
     }
 
+    /// Changes the style to the current style and options of the brush. The seed of the stroke is kept if the style has one,
+    /// and the width and color of the stroke are kept as well
+    pub fn change_style(&mut self, brush: &Brush) {
+        let seed = self
+            .style
+            .seed()
+            .or_else(|| Some(rand_pcg::Pcg64::from_entropy().gen()));
+        let width = self.style.width();
+        let stroke_color = self.style.stroke_color();
+
+        self.style = BrushStrokeStyle::from_brush(brush, seed);
+        self.style.set_width(width);
+        self.style.set_stroke_color(stroke_color);
+        self.update_geometry();
+    }
+
     pub fn push_elem(&mut self, element: Element) {
         self.elements.push(element);
 
+        // The window before the new last window is now part of the start length
+        let n_elements = self.elements.len();
+        if n_elements >= 5 {
+            let window = &self.elements[n_elements - 5..n_elements - 1];
+            self.dash_start_length +=
+                Self::window_dash_length((&window[0], &window[1], &window[2], &window[3]));
+        }
+
         self.update_bounds_to_last_elem();
     }
 
@@ -239,11 +414,212 @@ impl BrushStroke {
         element
     }
 
+    /// The margin of the bounds around the elements, which does not scale with the stroke
+    pub fn bounds_margin(&self) -> f64 {
+        self.style.width() + 1.0
+    }
+
     pub fn update_geometry(&mut self) {
         if let Some(new_bounds) = self.gen_bounds() {
             self.set_bounds(new_bounds);
         }
         self.hitboxes = self.gen_hitboxes();
+        self.dash_start_length = self
+            .elements
+            .windows(4)
+            .take(self.elements.len().saturating_sub(4))
+            .map(|window| {
+                Self::window_dash_length((&window[0], &window[1], &window[2], &window[3]))
+            })
+            .sum();
+    }
+
+    /// Recognizes the shape the stroke was drawn as. The shape stroke keeps the width and the color of the stroke
+    pub fn recognize_shape(&self, recognizer: &ShapeRecognizer) -> Option<ShapeStroke> {
+        let (width, stroke_color) = match self.style {
+            BrushStrokeStyle::Marker { options } => (options.width, options.stroke_color),
+            BrushStrokeStyle::Solid { options } => (options.width, options.stroke_color),
+            BrushStrokeStyle::Textured { options } => (options.width, options.stroke_color),
+            BrushStrokeStyle::Highlighter { options } => (options.width, options.stroke_color),
+            BrushStrokeStyle::Calligraphy { options } => (options.width, options.stroke_color),
+        };
+        let path = self
+            .elements
+            .iter()
+            .map(|element| element.inputdata.pos())
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let shape = recognizer.recognize(&path)?;
+        let options = SmoothOptions {
+            width,
+            stroke_color,
+            fill_color: None,
+            ..SmoothOptions::default()
+        };
+
+        Some(ShapeStroke::new_w_shape(
+            shape,
+            ShapeDrawStyle::Smooth { options },
+        ))
+    }
+
+    /// Removes the elements which collide with the eraser tip and splits the stroke apart where they were removed.
+    /// Returns None if no element collides. The returned strokes have the same style, pieces which are too short to be drawn are dropped
+    pub fn split_colliding_elements(&self, eraser: &Eraser) -> Option<Vec<Self>> {
+        let width = match self.style {
+            BrushStrokeStyle::Marker { options } => options.width,
+            BrushStrokeStyle::Solid { options } => options.width,
+            BrushStrokeStyle::Textured { options } => options.width,
+            BrushStrokeStyle::Highlighter { options } => options.width,
+            BrushStrokeStyle::Calligraphy { options } => options.width,
+        };
+
+        if !eraser.tip_intersects_bounds(self.bounds) {
+            return None;
+        }
+
+        let mut collided = false;
+        let mut pieces = vec![];
+        let mut current_elements = vec![];
+
+        for element in self.elements.iter() {
+            if eraser.tip_contains_point(element.inputdata.pos(), width / 2.0) {
+                collided = true;
+
+                if !current_elements.is_empty() {
+                    pieces.push(std::mem::take(&mut current_elements));
+                }
+            } else {
+                current_elements.push(*element);
+            }
+        }
+        if !current_elements.is_empty() {
+            pieces.push(current_elements);
+        }
+
+        if !collided {
+            return None;
+        }
+
+        Some(
+            pieces
+                .into_iter()
+                // The svgs are generated for windows of four elements, anything shorter would not be drawn
+                .filter(|elements| elements.len() >= 4)
+                .map(|elements| {
+                    let mut brushstroke = Self {
+                        elements,
+                        style: self.style.clone(),
+                        pressure_curve: self.pressure_curve,
+                        bounds: self.bounds,
+                        hitboxes: vec![],
+                        dash_start_length: 0.0,
+                    };
+                    brushstroke.update_geometry();
+
+                    brushstroke
+                })
+                .collect(),
+        )
+    }
+
+    /// The positions of the elements, which are the nodes that can be edited
+    pub fn nodes(&self) -> Vec<na::Vector2<f64>> {
+        self.elements
+            .iter()
+            .map(|element| element.inputdata.pos())
+            .collect()
+    }
+
+    /// Removes the elements which deviate less than the tolerance from the simplified path. Returns true if any element was removed
+    pub fn simplify(&mut self, tolerance: f64) -> bool {
+        let kept = geometry::simplify_polyline(&self.nodes(), tolerance);
+        if kept.len() == self.elements.len() {
+            return false;
+        }
+
+        self.elements = kept.into_iter().map(|i| self.elements[i]).collect();
+        self.update_geometry();
+        true
+    }
+
+    /// Moves the node with the given index to the position. Returns false if there is no node with the index
+    pub fn move_node(&mut self, index: usize, pos: na::Vector2<f64>) -> bool {
+        if let Some(element) = self.elements.get_mut(index) {
+            element.inputdata.set_pos(pos);
+            self.update_geometry();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sets the pressure of the node with the given index, clamped between 0.0 and 1.0. Returns false if there is no node with the index
+    pub fn set_node_pressure(&mut self, index: usize, pressure: f64) -> bool {
+        if let Some(element) = self.elements.get_mut(index) {
+            element.inputdata.set_pressure(pressure.clamp(0.0, 1.0));
+            self.update_geometry();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes the node with the given index. The last remaining node can't be removed
+    pub fn remove_node(&mut self, index: usize) -> Option<Element> {
+        if self.elements.len() <= 1 || index >= self.elements.len() {
+            return None;
+        }
+
+        let element = self.elements.remove(index);
+        self.update_geometry();
+        Some(element)
+    }
+
+    /// Splits the stroke at the node with the given index, which then is the end of this stroke and the start of the returned stroke.
+    /// Returns None if the node is at either end of the stroke
+    pub fn split_at_node(&mut self, index: usize) -> Option<Self> {
+        if index == 0 || index + 1 >= self.elements.len() {
+            return None;
+        }
+
+        let mut split_off = Self {
+            elements: self.elements.split_off(index),
+            style: self.style.clone(),
+            pressure_curve: self.pressure_curve,
+            bounds: self.bounds,
+            hitboxes: vec![],
+            dash_start_length: 0.0,
+        };
+        self.elements.push(split_off.elements[0]);
+
+        self.update_geometry();
+        split_off.update_geometry();
+        Some(split_off)
+    }
+
+    /// Joins the other stroke to the end of this stroke. The other stroke is reversed if its end is closer to the end of this stroke than its start,
+    /// so that the nearest ends are connected
+    pub fn join(&mut self, other: &Self) {
+        let (end, other_start, other_end) = match (
+            self.elements.last(),
+            other.elements.first(),
+            other.elements.last(),
+        ) {
+            (Some(end), Some(other_start), Some(other_end)) => (
+                end.inputdata.pos(),
+                other_start.inputdata.pos(),
+                other_end.inputdata.pos(),
+            ),
+            _ => return,
+        };
+
+        if (other_end - end).magnitude() < (other_start - end).magnitude() {
+            self.elements.extend(other.elements.iter().rev());
+        } else {
+            self.elements.extend(other.elements.iter());
+        }
+        self.update_geometry();
     }
 
     fn update_bounds_to_last_elem(&mut self) {
@@ -252,6 +628,8 @@ impl BrushStroke {
                 BrushStrokeStyle::Marker { options } => options.width,
                 BrushStrokeStyle::Solid { options } => options.width,
                 BrushStrokeStyle::Textured { options } => options.width,
+                BrushStrokeStyle::Highlighter { options } => options.width,
+                BrushStrokeStyle::Calligraphy { options } => options.width,
             };
 
             self.bounds.merge(&AABB::new(
@@ -281,6 +659,8 @@ impl BrushStroke {
             BrushStrokeStyle::Marker { options } => options.width,
             BrushStrokeStyle::Solid { options } => options.width,
             BrushStrokeStyle::Textured { options } => options.width,
+            BrushStrokeStyle::Highlighter { options } => options.width,
+            BrushStrokeStyle::Calligraphy { options } => options.width,
         };
 
         let first = first.inputdata.pos();
@@ -311,6 +691,54 @@ impl BrushStroke {
         }
     }
 
+    /// The length of the lines which the dash pattern is composed along in the given elements window.
+    /// The stroke between the second and the third element is generated, approximated the same way as when composing it.
+    pub fn window_dash_length(elements: (&Element, &Element, &Element, &Element)) -> f64 {
+        if let Some(cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            curves::approx_cubbez_with_lines(cubbez, Self::DASHED_N_SPLITS)
+                .iter()
+                .map(|line| (line.end - line.start).magnitude())
+                .sum()
+        } else if let Some(line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            (line.end - line.start).magnitude()
+        } else {
+            0.0
+        }
+    }
+
+    /// The caps at the start and the end of the window of elements with the index. Only the ends of the stroke get the line cap, the joints are round
+    fn window_caps(options: &SmoothOptions, index: usize, n_windows: usize) -> (LineCap, LineCap) {
+        (
+            if index == 0 {
+                options.line_cap
+            } else {
+                LineCap::Round
+            },
+            if index + 1 == n_windows {
+                options.line_cap
+            } else {
+                LineCap::Round
+            },
+        )
+    }
+
+    /// The caps of the last window of elements while the stroke is drawn. Its end is a joint, as the stroke continues
+    fn last_elems_caps(&self, options: &SmoothOptions) -> (LineCap, LineCap) {
+        let n_windows = self.elements.len().saturating_sub(3);
+
+        (
+            Self::window_caps(options, n_windows.saturating_sub(1), n_windows).0,
+            LineCap::Round,
+        )
+    }
+
     pub fn gen_svg_for_elems(
         &self,
         elements: (&Element, &Element, &Element, &Element),
@@ -327,7 +755,13 @@ impl BrushStroke {
                 options.seed = seed;
 
                 Ok(Self::gen_svg_elem_marker(
-                    &options, elements, offset, svg_root,
+                    &options,
+                    &self.pressure_curve,
+                    elements,
+                    self.dash_start_length,
+                    self.last_elems_caps(&options),
+                    offset,
+                    svg_root,
                 ))
             }
             BrushStrokeStyle::Solid { mut options } => {
@@ -339,7 +773,13 @@ impl BrushStroke {
                 options.seed = seed;
 
                 Ok(Self::gen_svg_elem_solid(
-                    &options, elements, offset, svg_root,
+                    &options,
+                    &self.pressure_curve,
+                    elements,
+                    self.dash_start_length,
+                    self.last_elems_caps(&options),
+                    offset,
+                    svg_root,
                 ))
             }
             BrushStrokeStyle::Textured { mut options } => {
@@ -351,15 +791,37 @@ impl BrushStroke {
                 options.seed = seed;
 
                 Ok(Self::gen_svg_elem_textured(
-                    &options, elements, offset, svg_root,
+                    &options,
+                    &self.pressure_curve,
+                    elements,
+                    offset,
+                    svg_root,
                 ))
             }
+            BrushStrokeStyle::Highlighter { options } => Ok(Self::gen_svg_highlighter(
+                &options,
+                &[elements.0, elements.1, elements.2, elements.3],
+                offset,
+                svg_root,
+            )),
+            BrushStrokeStyle::Calligraphy { options } => Ok(Self::gen_svg_elem_calligraphy(
+                &options,
+                &self.pressure_curve,
+                elements,
+                offset,
+                svg_root,
+            )),
         }
     }
 
+    /// The start length is the length of the stroke before the elements, where the dash pattern continues from.
+    /// The caps are the caps at the start and the end of the elements, which are only the line cap at the ends of the stroke
     pub fn gen_svg_elem_marker(
         options: &SmoothOptions,
+        pressure_curve: &PressureCurve,
         elements: (&Element, &Element, &Element, &Element),
+        start_length: f64,
+        caps: (LineCap, LineCap),
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Option<render::Svg> {
@@ -385,7 +847,22 @@ impl BrushStroke {
             bounds.take_point(na::Point2::from(cubbez.cp2));
             bounds.take_point(na::Point2::from(cubbez.end));
 
-            commands.append(&mut smooth::compose_cubbez(cubbez, true, options));
+            let lines = curves::approx_cubbez_with_lines(cubbez, Self::DASHED_N_SPLITS)
+                .into_iter()
+                .map(|line| (line, width, width))
+                .collect::<Vec<(curves::Line, f64, f64)>>();
+
+            if options.is_dashed() {
+                commands.append(&mut smooth::compose_lines_variable_width_dashed(
+                    &lines,
+                    start_length,
+                    options,
+                ));
+            } else {
+                commands.append(&mut smooth::compose_lines_variable_width(
+                    &lines, caps.0, caps.1,
+                ));
+            }
         } else if let Some(mut line) =
             curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
         {
@@ -395,7 +872,19 @@ impl BrushStroke {
             bounds.take_point(na::Point2::from(line.start));
             bounds.take_point(na::Point2::from(line.end));
 
-            commands.append(&mut smooth::compose_line(line, true, options));
+            if options.is_dashed() {
+                commands.append(&mut smooth::compose_lines_variable_width_dashed(
+                    &[(line, width, width)],
+                    start_length,
+                    options,
+                ));
+            } else {
+                commands.append(&mut smooth::compose_lines_variable_width(
+                    &[(line, width, width)],
+                    caps.0,
+                    caps.1,
+                ));
+            }
         } else {
             return None;
         }
@@ -406,14 +895,19 @@ impl BrushStroke {
             .stroke_color
             .map_or(String::from(""), |color| color.to_css_color());
 
-        let path = svg::node::element::Path::new()
-            .set("stroke", color)
-            .set("stroke-width", width)
-            .set("stroke-linejoin", "round")
-            .set("stroke-linecap", "round")
-            .set("fill", "none")
+        let opacity = pressure_curve
+            .opacity((elements.1.inputdata.pressure() + elements.2.inputdata.pressure()) * 0.5);
+
+        // Composed as filled outlines, so that dashes continue across the elements and the caps differ between the ends of the stroke and the joints
+        let mut path = svg::node::element::Path::new()
+            .set("stroke", "none")
+            .set("fill", color)
             .set("d", path::Data::from(commands));
 
+        if opacity < 1.0 {
+            path = path.set("fill-opacity", opacity);
+        }
+
         let mut svg_data = compose::svg_node_to_string(&path)
             .map_err(|e| {
                 anyhow::anyhow!(
@@ -435,31 +929,155 @@ impl BrushStroke {
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut start_length = 0.0;
+        let n_windows = self.elements.len().saturating_sub(3);
+
         let svgs: Vec<render::Svg> = self
             .elements
             .iter()
             .zip(self.elements.iter().skip(1))
             .zip(self.elements.iter().skip(2))
             .zip(self.elements.iter().skip(3))
-            .filter_map(|(((first, second), third), forth)| {
-                Self::gen_svg_elem_marker(&options, (first, second, third, forth), offset, svg_root)
+            .enumerate()
+            .filter_map(|(i, (((first, second), third), forth))| {
+                let svg = Self::gen_svg_elem_marker(
+                    &options,
+                    &self.pressure_curve,
+                    (first, second, third, forth),
+                    start_length,
+                    Self::window_caps(&options, i, n_windows),
+                    offset,
+                    svg_root,
+                );
+                if options.is_dashed() {
+                    start_length += Self::window_dash_length((first, second, third, forth));
+                }
+
+                svg
             })
             .collect();
 
         Ok(svgs)
     }
 
+    /// Generates one path through all elements. Overlapping parts of the stroke therefore don't darken each other,
+    /// and the multiply blend mode lets the content below the stroke shine through.
+    pub fn gen_svg_highlighter(
+        options: &SmoothOptions,
+        elements: &[&Element],
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        let mut commands = Vec::new();
+        let width = options.width;
+
+        let mut bounds = AABB::new_invalid();
+
+        for window in elements.windows(4) {
+            let move_start = commands.is_empty();
+
+            if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+                window[0].inputdata.pos(),
+                window[1].inputdata.pos(),
+                window[2].inputdata.pos(),
+                window[3].inputdata.pos(),
+            ) {
+                cubbez.start += offset;
+                cubbez.cp1 += offset;
+                cubbez.cp2 += offset;
+                cubbez.end += offset;
+
+                bounds.take_point(na::Point2::from(cubbez.start));
+                bounds.take_point(na::Point2::from(cubbez.cp1));
+                bounds.take_point(na::Point2::from(cubbez.cp2));
+                bounds.take_point(na::Point2::from(cubbez.end));
+
+                commands.append(&mut smooth::compose_cubbez(cubbez, move_start, options));
+            } else if let Some(mut line) =
+                curves::gen_line(window[1].inputdata.pos(), window[2].inputdata.pos())
+            {
+                line.start += offset;
+                line.end += offset;
+
+                bounds.take_point(na::Point2::from(line.start));
+                bounds.take_point(na::Point2::from(line.end));
+
+                commands.append(&mut smooth::compose_line(line, move_start, options));
+            }
+        }
+
+        if commands.is_empty() {
+            return None;
+        }
+
+        bounds.loosen(width + 1.0);
+
+        let color = options
+            .stroke_color
+            .map_or(String::from(""), |color| color.to_css_color());
+
+        // The square caps and bevel joins give the stroke the look of a flat chisel tip
+        let path = svg::node::element::Path::new()
+            .set("stroke", color)
+            .set("stroke-width", width)
+            .set("stroke-linejoin", "bevel")
+            .set("stroke-linecap", "square")
+            .set("fill", "none")
+            .set("style", "mix-blend-mode:multiply")
+            .set("d", path::Data::from(commands));
+
+        let mut svg_data = compose::svg_node_to_string(&path)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_highlighter() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_highlighter(
+        &self,
+        options: SmoothOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let elements = self.elements.iter().collect::<Vec<&Element>>();
+
+        Ok(
+            Self::gen_svg_highlighter(&options, &elements, offset, svg_root)
+                .into_iter()
+                .collect(),
+        )
+    }
+
+    /// The start length is the length of the stroke before the elements, where the dash pattern continues from.
+    /// The caps are the caps at the start and the end of the elements, which are only the line cap at the ends of the stroke
     pub fn gen_svg_elem_solid(
         options: &SmoothOptions,
+        pressure_curve: &PressureCurve,
         elements: (&Element, &Element, &Element, &Element),
+        start_length: f64,
+        caps: (LineCap, LineCap),
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Option<render::Svg> {
         let mut commands = Vec::new();
 
         let width = options.width;
-        let start_width = elements.1.inputdata.pressure() * options.width;
-        let end_width = elements.2.inputdata.pressure() * options.width;
+        let start_width = pressure_curve.apply(elements.1.inputdata.pressure())
+            * options
+                .velocity_width_factor(elements.1.inputdata.velocity_from(&elements.0.inputdata))
+            * options.width;
+        let end_width = pressure_curve.apply(elements.2.inputdata.pressure())
+            * options
+                .velocity_width_factor(elements.2.inputdata.velocity_from(&elements.1.inputdata))
+            * options.width;
 
         let mut bounds = AABB::new_invalid();
 
@@ -480,26 +1098,35 @@ impl BrushStroke {
             bounds.take_point(na::Point2::from(cubbez.cp2));
             bounds.take_point(na::Point2::from(cubbez.end));
 
-            let n_splits = 5;
-            // Number of splits for the bezier curve approximation
-            let lines = curves::approx_cubbez_with_lines(cubbez, n_splits);
+            let lines = curves::approx_cubbez_with_lines(cubbez, Self::DASHED_N_SPLITS);
             let n_lines = lines.len() as i32;
 
-            for (i, line) in lines.iter().enumerate() {
-                // splitted line start / end widths are a linear interpolation between the start and end width / n splits.
-                // Not mathematically correct, TODO to carry the t of the splits through approx_offsetted_cubbez_with_lines_w_subdivion()
-                let line_start_width = start_width
-                    + (end_width - start_width) * (f64::from(i as i32) / f64::from(n_lines));
-                let line_end_width = start_width
-                    + (end_width - start_width) * (f64::from(i as i32 + 1) / f64::from(n_lines));
-
-                commands.append(&mut smooth::compose_line_variable_width(
-                    *line,
-                    line_start_width,
-                    line_end_width,
-                    true,
+            // splitted line start / end widths are a linear interpolation between the start and end width / n splits.
+            // Not mathematically correct, TODO to carry the t of the splits through approx_offsetted_cubbez_with_lines_w_subdivion()
+            let lines = lines
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let line_start_width = start_width
+                        + (end_width - start_width) * (f64::from(i as i32) / f64::from(n_lines));
+                    let line_end_width = start_width
+                        + (end_width - start_width)
+                            * (f64::from(i as i32 + 1) / f64::from(n_lines));
+
+                    (line, line_start_width, line_end_width)
+                })
+                .collect::<Vec<(curves::Line, f64, f64)>>();
+
+            if options.is_dashed() {
+                commands.append(&mut smooth::compose_lines_variable_width_dashed(
+                    &lines,
+                    start_length,
                     options,
                 ));
+            } else {
+                commands.append(&mut smooth::compose_lines_variable_width(
+                    &lines, caps.0, caps.1,
+                ));
             }
         } else if let Some(mut line) =
             curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
@@ -510,13 +1137,19 @@ impl BrushStroke {
             bounds.take_point(na::Point2::from(line.start));
             bounds.take_point(na::Point2::from(line.end));
 
-            commands.append(&mut smooth::compose_line_variable_width(
-                line,
-                start_width,
-                end_width,
-                true,
-                options,
-            ));
+            if options.is_dashed() {
+                commands.append(&mut smooth::compose_lines_variable_width_dashed(
+                    &[(line, start_width, end_width)],
+                    start_length,
+                    options,
+                ));
+            } else {
+                commands.append(&mut smooth::compose_lines_variable_width(
+                    &[(line, start_width, end_width)],
+                    caps.0,
+                    caps.1,
+                ));
+            }
         } else {
             return None;
         }
@@ -527,13 +1160,19 @@ impl BrushStroke {
             .stroke_color
             .map_or(String::from(""), |color| color.to_css_color());
 
-        let path = svg::node::element::Path::new()
+        let mut path = svg::node::element::Path::new()
             .set("stroke", "none")
             //.set("stroke", self.brush.color.to_css_color())
             //.set("stroke-width", 1.0)
             .set("fill", fill)
             .set("d", path::Data::from(commands));
 
+        let opacity = pressure_curve
+            .opacity((elements.1.inputdata.pressure() + elements.2.inputdata.pressure()) * 0.5);
+        if opacity < 1.0 {
+            path = path.set("fill-opacity", opacity);
+        }
+
         let mut svg_data = compose::svg_node_to_string(&path)
             .map_err(|e| {
                 anyhow::anyhow!(
@@ -555,14 +1194,31 @@ impl BrushStroke {
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut start_length = 0.0;
+        let n_windows = self.elements.len().saturating_sub(3);
+
         let svgs: Vec<render::Svg> = self
             .elements
             .iter()
             .zip(self.elements.iter().skip(1))
             .zip(self.elements.iter().skip(2))
             .zip(self.elements.iter().skip(3))
-            .filter_map(|(((first, second), third), forth)| {
-                Self::gen_svg_elem_solid(&options, (first, second, third, forth), offset, svg_root)
+            .enumerate()
+            .filter_map(|(i, (((first, second), third), forth))| {
+                let svg = Self::gen_svg_elem_solid(
+                    &options,
+                    &self.pressure_curve,
+                    (first, second, third, forth),
+                    start_length,
+                    Self::window_caps(&options, i, n_windows),
+                    offset,
+                    svg_root,
+                );
+                if options.is_dashed() {
+                    start_length += Self::window_dash_length((first, second, third, forth));
+                }
+
+                svg
             })
             .collect();
 
@@ -571,19 +1227,32 @@ impl BrushStroke {
 
     pub fn gen_svg_elem_textured(
         options: &TexturedOptions,
+        pressure_curve: &PressureCurve,
         elements: (&Element, &Element, &Element, &Element),
         offset: na::Vector2<f64>,
         svg_root: bool,
     ) -> Option<render::Svg> {
-        let width = options.width;
-        let start_width = elements.1.inputdata.pressure() * options.width;
-        let end_width = elements.2.inputdata.pressure() * options.width;
+        let mut options = *options;
+        // Tilting the stylus widens the stroke, while the same amount of dots get spread over the larger area
+        let tilt_shading_factor = options.tilt_shading_factor(
+            elements
+                .1
+                .inputdata
+                .tilt_amount()
+                .zip(elements.2.inputdata.tilt_amount())
+                .map(|(start_tilt, end_tilt)| (start_tilt + end_tilt) * 0.5),
+        );
+        options.density /= tilt_shading_factor;
+
+        let width = options.width * tilt_shading_factor;
+        let start_width = pressure_curve.apply(elements.1.inputdata.pressure()) * width;
+        let end_width = pressure_curve.apply(elements.2.inputdata.pressure()) * width;
         let mid_width = (start_width + end_width) * 0.5;
 
         let mut bounds = AABB::new_invalid();
 
         // Configure the textured Configuration
-        let element = if let Some(mut line) =
+        let mut element = if let Some(mut line) =
             curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
         {
             line.start += offset;
@@ -597,6 +1266,12 @@ impl BrushStroke {
             return None;
         };
 
+        let opacity = pressure_curve
+            .opacity((elements.1.inputdata.pressure() + elements.2.inputdata.pressure()) * 0.5);
+        if opacity < 1.0 {
+            element.assign("opacity", opacity);
+        }
+
         bounds.loosen(width + 1.0);
 
         let mut svg_data = compose::svg_node_to_string(&element)
@@ -635,6 +1310,114 @@ impl BrushStroke {
 
                 Self::gen_svg_elem_textured(
                     &options,
+                    &self.pressure_curve,
+                    (first, second, third, forth),
+                    offset,
+                    svg_root,
+                )
+            })
+            .collect();
+
+        Ok(svgs)
+    }
+
+    pub fn gen_svg_elem_calligraphy(
+        options: &CalligraphyOptions,
+        pressure_curve: &PressureCurve,
+        elements: (&Element, &Element, &Element, &Element),
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Option<render::Svg> {
+        let width = options.width;
+        let start_nib = options.nib_direction(elements.1.inputdata.azimuth())
+            * pressure_curve.apply(elements.1.inputdata.pressure())
+            * width;
+        let end_nib = options.nib_direction(elements.2.inputdata.azimuth())
+            * pressure_curve.apply(elements.2.inputdata.pressure())
+            * width;
+
+        let mut bounds = AABB::new_invalid();
+
+        let commands = if let Some(mut cubbez) = curves::gen_cubbez_w_catmull_rom(
+            elements.0.inputdata.pos(),
+            elements.1.inputdata.pos(),
+            elements.2.inputdata.pos(),
+            elements.3.inputdata.pos(),
+        ) {
+            cubbez.start += offset;
+            cubbez.cp1 += offset;
+            cubbez.cp2 += offset;
+            cubbez.end += offset;
+
+            // Bounds are definitely inside the polygon of the control points. (Could be improved with the second derivative of the bezier curve)
+            bounds.take_point(na::Point2::from(cubbez.start));
+            bounds.take_point(na::Point2::from(cubbez.cp1));
+            bounds.take_point(na::Point2::from(cubbez.cp2));
+            bounds.take_point(na::Point2::from(cubbez.end));
+
+            calligraphy::compose_cubbez_w_nib(cubbez, start_nib, end_nib, options)
+        } else if let Some(mut line) =
+            curves::gen_line(elements.1.inputdata.pos(), elements.2.inputdata.pos())
+        {
+            line.start += offset;
+            line.end += offset;
+
+            bounds.take_point(na::Point2::from(line.start));
+            bounds.take_point(na::Point2::from(line.end));
+
+            calligraphy::compose_line_w_nib(line, start_nib, end_nib, options)
+        } else {
+            return None;
+        };
+
+        bounds.loosen(width + 1.0);
+
+        let fill = options
+            .stroke_color
+            .map_or(String::from(""), |color| color.to_css_color());
+
+        let mut path = svg::node::element::Path::new()
+            .set("stroke", "none")
+            .set("fill", fill)
+            .set("d", path::Data::from(commands));
+
+        let opacity = pressure_curve
+            .opacity((elements.1.inputdata.pressure() + elements.2.inputdata.pressure()) * 0.5);
+        if opacity < 1.0 {
+            path = path.set("fill-opacity", opacity);
+        }
+
+        let mut svg_data = compose::svg_node_to_string(&path)
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "node_to_string() failed in gen_svg_elem_calligraphy() of brushstroke with Err `{}`",
+                    e
+                )
+            })
+            .ok()?;
+
+        if svg_root {
+            svg_data = compose::wrap_svg_root(&svg_data, Some(bounds), Some(bounds), true);
+        }
+        Some(render::Svg { svg_data, bounds })
+    }
+
+    pub fn gen_svgs_calligraphy(
+        &self,
+        options: CalligraphyOptions,
+        offset: na::Vector2<f64>,
+        svg_root: bool,
+    ) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let svgs: Vec<render::Svg> = self
+            .elements
+            .iter()
+            .zip(self.elements.iter().skip(1))
+            .zip(self.elements.iter().skip(2))
+            .zip(self.elements.iter().skip(3))
+            .filter_map(|(((first, second), third), forth)| {
+                Self::gen_svg_elem_calligraphy(
+                    &options,
+                    &self.pressure_curve,
                     (first, second, third, forth),
                     offset,
                     svg_root,
@@ -645,3 +1428,205 @@ impl BrushStroke {
         Ok(svgs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn calligraphy_stroke(nib_angle: f64, tilt: Option<na::Vector2<f64>>) -> BrushStroke {
+        let mut brush = Brush::default();
+        brush.style = BrushStyle::Calligraphy;
+        brush.calligraphy_options.nib_angle = nib_angle;
+        brush.calligraphy_options.follow_tilt = tilt.is_some();
+
+        BrushStroke::new_w_elements(
+            [
+                na::vector![0.0, 0.0],
+                na::vector![10.0, 5.0],
+                na::vector![20.0, 0.0],
+            ]
+            .into_iter()
+            .map(|pos| {
+                let mut inputdata = InputData::new(pos, 0.5);
+                inputdata.set_tilt(tilt);
+                Element::new(inputdata)
+            }),
+            &brush,
+        )
+        .unwrap()
+    }
+
+    fn nib(brushstroke: &BrushStroke) -> na::Vector2<f64> {
+        match &brushstroke.style {
+            BrushStrokeStyle::Calligraphy { options } => {
+                options.nib_direction(brushstroke.elements[0].inputdata.azimuth())
+            }
+            _ => panic!("brushstroke is not a calligraphy stroke"),
+        }
+    }
+
+    #[test]
+    fn only_stroke_ends_get_line_cap() {
+        let options = SmoothOptions {
+            line_cap: LineCap::Butt,
+            ..SmoothOptions::default()
+        };
+
+        assert_eq!(
+            (0..3)
+                .map(|i| BrushStroke::window_caps(&options, i, 3))
+                .collect::<Vec<(LineCap, LineCap)>>(),
+            vec![
+                (LineCap::Butt, LineCap::Round),
+                (LineCap::Round, LineCap::Round),
+                (LineCap::Round, LineCap::Butt)
+            ]
+        );
+        assert_eq!(
+            BrushStroke::window_caps(&options, 0, 1),
+            (LineCap::Butt, LineCap::Butt)
+        );
+    }
+
+    #[test]
+    fn marker_ends_have_line_cap() {
+        let elements =
+            [0.0, 10.0, 20.0, 30.0].map(|x| Element::new(InputData::new(na::vector![x, 0.0], 0.5)));
+        let elements = (&elements[0], &elements[1], &elements[2], &elements[3]);
+        let marker_svg_data = |line_cap| {
+            let options = SmoothOptions {
+                line_cap,
+                ..SmoothOptions::default()
+            };
+
+            BrushStroke::gen_svg_elem_marker(
+                &options,
+                &PressureCurve::default(),
+                elements,
+                0.0,
+                (line_cap, line_cap),
+                na::vector![0.0, 0.0],
+                false,
+            )
+            .unwrap()
+            .svg_data
+        };
+
+        // Round caps are composed with arcs
+        assert!(marker_svg_data(LineCap::Round).contains('A'));
+        assert!(!marker_svg_data(LineCap::Butt).contains('A'));
+    }
+
+    /// The nibs lie on the same line, regardless of the direction they point to
+    fn assert_nibs_parallel(nib: na::Vector2<f64>, expected: na::Vector2<f64>) {
+        assert!(
+            nib.perp(&expected).abs() < 1e-9,
+            "nib {:?} is not parallel to {:?}",
+            nib,
+            expected
+        );
+    }
+
+    #[test]
+    fn mirror_reflects_nib_angle() {
+        let center = na::point![10.0, 0.0];
+
+        for axis in [MirrorAxis::Horizontal, MirrorAxis::Vertical] {
+            let mut brushstroke = calligraphy_stroke(PI / 6.0, None);
+            let expected = nib(&brushstroke).component_mul(&axis.reflection_scale());
+
+            brushstroke.mirror(axis, center);
+            assert_nibs_parallel(nib(&brushstroke), expected);
+
+            // Mirroring twice restores the nib
+            brushstroke.mirror(axis, center);
+            assert_nibs_parallel(
+                nib(&brushstroke),
+                na::vector![(PI / 6.0).cos(), (PI / 6.0).sin()],
+            );
+        }
+    }
+
+    #[test]
+    fn mirror_reflects_nib_following_tilt() {
+        let center = na::point![10.0, 0.0];
+
+        for axis in [MirrorAxis::Horizontal, MirrorAxis::Vertical] {
+            let mut brushstroke = calligraphy_stroke(0.0, Some(na::vector![30.0, 10.0]));
+            let expected = nib(&brushstroke).component_mul(&axis.reflection_scale());
+
+            brushstroke.mirror(axis, center);
+            assert_nibs_parallel(nib(&brushstroke), expected);
+        }
+    }
+
+    fn brushstroke_w_nodes(nodes: &[na::Vector2<f64>]) -> BrushStroke {
+        BrushStroke::new_w_elements(
+            nodes
+                .iter()
+                .map(|&pos| Element::new(InputData::new(pos, 0.5))),
+            &Brush::default(),
+        )
+        .unwrap()
+    }
+
+    fn nodes_along_x(xs: &[f64]) -> Vec<na::Vector2<f64>> {
+        xs.iter().map(|&x| na::vector![x, 0.0]).collect()
+    }
+
+    #[test]
+    fn split_at_node_at_ends() {
+        let nodes = nodes_along_x(&[0.0, 10.0, 20.0, 30.0]);
+        let mut brushstroke = brushstroke_w_nodes(&nodes);
+
+        assert!(brushstroke.split_at_node(0).is_none());
+        assert!(brushstroke.split_at_node(3).is_none());
+        assert!(brushstroke.split_at_node(4).is_none());
+        assert_eq!(brushstroke.nodes(), nodes);
+    }
+
+    #[test]
+    fn split_at_node_in_middle() {
+        let mut brushstroke = brushstroke_w_nodes(&nodes_along_x(&[0.0, 10.0, 20.0, 30.0, 40.0]));
+
+        let split_off = brushstroke.split_at_node(2).unwrap();
+        // The node at the split is part of both strokes
+        assert_eq!(brushstroke.nodes(), nodes_along_x(&[0.0, 10.0, 20.0]));
+        assert_eq!(split_off.nodes(), nodes_along_x(&[20.0, 30.0, 40.0]));
+    }
+
+    #[test]
+    fn join_connects_nearest_ends() {
+        let mut brushstroke = brushstroke_w_nodes(&nodes_along_x(&[0.0, 10.0]));
+
+        brushstroke.join(&brushstroke_w_nodes(&nodes_along_x(&[20.0, 30.0])));
+        assert_eq!(brushstroke.nodes(), nodes_along_x(&[0.0, 10.0, 20.0, 30.0]));
+
+        // The end of the reversed stroke is nearest to the end, so it is joined reversed
+        brushstroke.join(&brushstroke_w_nodes(&nodes_along_x(&[60.0, 50.0, 40.0])));
+        assert_eq!(
+            brushstroke.nodes(),
+            nodes_along_x(&[0.0, 10.0, 20.0, 30.0, 40.0, 50.0, 60.0])
+        );
+    }
+
+    #[test]
+    fn remove_node() {
+        let mut brushstroke = brushstroke_w_nodes(&nodes_along_x(&[0.0, 10.0, 20.0]));
+
+        assert!(brushstroke.remove_node(3).is_none());
+        assert_eq!(
+            brushstroke
+                .remove_node(1)
+                .map(|element| element.inputdata.pos()),
+            Some(na::vector![10.0, 0.0])
+        );
+        assert_eq!(brushstroke.nodes(), nodes_along_x(&[0.0, 20.0]));
+
+        assert!(brushstroke.remove_node(1).is_some());
+        // The last remaining node can't be removed
+        assert!(brushstroke.remove_node(0).is_none());
+        assert_eq!(brushstroke.nodes(), nodes_along_x(&[0.0]));
+    }
+}
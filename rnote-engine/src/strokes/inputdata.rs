@@ -5,6 +5,14 @@ use serde::{Deserialize, Serialize};
 pub struct InputData {
     pos: na::Vector2<f64>,
     pressure: f64,
+    /// The tilt of the stylus in x and y direction in degrees, between -90.0 and 90.0
+    tilt: Option<na::Vector2<f64>>,
+    /// The rotation of the stylus around its own axis in radians, between 0.0 and 2 * PI
+    rotation: Option<f64>,
+    /// The distance of the stylus to the surface, between 0.0 and 1.0
+    distance: Option<f64>,
+    /// A timestamp in seconds of a monotonic clock. Only the differences between timestamps are meaningful
+    timestamp: Option<f64>,
 }
 
 impl Default for InputData {
@@ -12,12 +20,18 @@ impl Default for InputData {
         Self {
             pos: na::vector![0.0, 0.0],
             pressure: Self::PRESSURE_DEFAULT,
+            tilt: None,
+            rotation: None,
+            distance: None,
+            timestamp: None,
         }
     }
 }
 
 impl InputData {
     pub const PRESSURE_DEFAULT: f64 = 0.5;
+    /// The maximum tilt in degrees
+    pub const TILT_MAX: f64 = 90.0;
 
     pub fn new(pos: na::Vector2<f64>, pressure: f64) -> Self {
         let mut inputdata = Self::default();
@@ -42,4 +56,66 @@ impl InputData {
     pub fn set_pressure(&mut self, pressure: f64) {
         self.pressure = pressure.clamp(0.0, 1.0);
     }
+
+    pub fn tilt(&self) -> Option<na::Vector2<f64>> {
+        self.tilt
+    }
+
+    pub fn set_tilt(&mut self, tilt: Option<na::Vector2<f64>>) {
+        self.tilt = tilt.map(|tilt| {
+            na::vector![
+                tilt[0].clamp(-Self::TILT_MAX, Self::TILT_MAX),
+                tilt[1].clamp(-Self::TILT_MAX, Self::TILT_MAX)
+            ]
+        });
+    }
+
+    /// How much the stylus is tilted away from the upright position, between 0.0 (upright) and 1.0 (flat on the surface)
+    pub fn tilt_amount(&self) -> Option<f64> {
+        self.tilt
+            .map(|tilt| (tilt.magnitude() / Self::TILT_MAX).clamp(0.0, 1.0))
+    }
+
+    /// The direction the stylus is tilted to in radians, measured from the x axis. None if the stylus is upright
+    pub fn azimuth(&self) -> Option<f64> {
+        self.tilt
+            .filter(|tilt| tilt.magnitude() > 0.0)
+            .map(|tilt| tilt[1].atan2(tilt[0]))
+    }
+
+    pub fn rotation(&self) -> Option<f64> {
+        self.rotation
+    }
+
+    pub fn set_rotation(&mut self, rotation: Option<f64>) {
+        self.rotation = rotation.map(|rotation| rotation.rem_euclid(std::f64::consts::TAU));
+    }
+
+    pub fn distance(&self) -> Option<f64> {
+        self.distance
+    }
+
+    pub fn set_distance(&mut self, distance: Option<f64>) {
+        self.distance = distance.map(|distance| distance.clamp(0.0, 1.0));
+    }
+
+    pub fn timestamp(&self) -> Option<f64> {
+        self.timestamp
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: Option<f64>) {
+        self.timestamp = timestamp;
+    }
+
+    /// The velocity in coordinate units per second when moving from the previous input to this one.
+    /// None if one of them has no timestamp or no time has passed between them
+    pub fn velocity_from(&self, prev: &Self) -> Option<f64> {
+        let delta_t = self.timestamp? - prev.timestamp?;
+
+        if delta_t > 0.0 {
+            Some((self.pos - prev.pos).magnitude() / delta_t)
+        } else {
+            None
+        }
+    }
 }
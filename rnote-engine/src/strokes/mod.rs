@@ -2,6 +2,8 @@ pub mod bitmapimage;
 pub mod brushstroke;
 pub mod element;
 pub mod inputdata;
+pub mod shaperecognizer;
 pub mod shapestroke;
 pub mod strokestyle;
+pub mod textstroke;
 pub mod vectorimage;
@@ -0,0 +1,612 @@
+use crate::compose::transformable::Transform;
+use crate::compose::{curves, geometry, shapes};
+
+use super::shapestroke::Shape;
+
+use serde::{Deserialize, Serialize};
+
+/// Recognizes shapes in freehand drawn paths.
+/// The errors of the fitted shapes are relative to their size, so the thresholds don't depend on how large the path was drawn.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, rename = "shape_recognizer")]
+pub struct ShapeRecognizer {
+    /// The max. distance of the path to the fitted line, relative to the line length
+    #[serde(rename = "line_threshold")]
+    pub line_threshold: f64,
+    /// The max. mean distance of the path to the fitted rectangle, relative to its smaller half extent
+    #[serde(rename = "rectangle_threshold")]
+    pub rectangle_threshold: f64,
+    /// The max. mean deviation of the path from the fitted ellipse, relative to its radii
+    #[serde(rename = "ellipse_threshold")]
+    pub ellipse_threshold: f64,
+    /// The max. mean distance of the path to the fitted polygon, relative to the smaller half extent of its bounds
+    #[serde(rename = "polygon_threshold")]
+    pub polygon_threshold: f64,
+    /// The max. size of the arrow head, relative to the length of the arrow line
+    #[serde(rename = "arrow_head_threshold")]
+    pub arrow_head_threshold: f64,
+    /// The max. gap between the start and the end of the path for it to be regarded as closed, relative to the path length
+    #[serde(rename = "closed_threshold")]
+    pub closed_threshold: f64,
+    /// Paths with a smaller diagonal of their bounds are not recognized
+    #[serde(rename = "min_size")]
+    pub min_size: f64,
+}
+
+impl Default for ShapeRecognizer {
+    fn default() -> Self {
+        Self {
+            line_threshold: 0.06,
+            rectangle_threshold: 0.08,
+            ellipse_threshold: 0.1,
+            polygon_threshold: 0.06,
+            arrow_head_threshold: 0.5,
+            closed_threshold: 0.15,
+            min_size: 10.0,
+        }
+    }
+}
+
+impl ShapeRecognizer {
+    /// The number of points the path is resampled to before fitting
+    const RESAMPLE_POINTS: usize = 64;
+    /// The angle step (in rad) when searching for the orientation of closed shapes
+    const ANGLE_STEP: f64 = std::f64::consts::PI / 180.0;
+    /// The max. number of vertices of recognized polygons. Paths which need more are rather ellipses or drawn freehand
+    const POLYGON_MAX_VERTICES: usize = 8;
+    /// Corners of polygons and arrows which turn by a smaller angle (in rad) are regarded as the jitter of the drawn path
+    const MIN_CORNER_ANGLE: f64 = std::f64::consts::PI / 9.0;
+
+    /// Recognizes the best matching shape for the path. Returns None if no shape fits well enough
+    pub fn recognize(&self, path: &[na::Vector2<f64>]) -> Option<Shape> {
+        if path.len() < 2 {
+            return None;
+        }
+
+        let path_length = path_length(path);
+        let first = path[0];
+        let last = path[path.len() - 1];
+
+        let (mins, maxs) = path.iter().fold(
+            (na::Vector2::repeat(f64::MAX), na::Vector2::repeat(f64::MIN)),
+            |(mins, maxs), point| (mins.inf(point), maxs.sup(point)),
+        );
+        if (maxs - mins).magnitude() < self.min_size {
+            return None;
+        }
+
+        let points = resample(path, Self::RESAMPLE_POINTS);
+
+        if (last - first).magnitude() > self.closed_threshold * path_length {
+            return self
+                .recognize_line(&points, first, last)
+                .or_else(|| self.recognize_arrow(&points));
+        }
+
+        let (angle, center, half_extents) = min_area_frame(&points, Self::ANGLE_STEP);
+        if half_extents.min() <= 0.0 {
+            return None;
+        }
+        let isometry = na::Isometry2::new(center, angle);
+        let local_points = points
+            .iter()
+            .map(|point| {
+                isometry
+                    .inverse_transform_point(&na::Point2::from(*point))
+                    .coords
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let rectangle_error = rectangle_error(&local_points, half_extents);
+        let ellipse_error = ellipse_error(&local_points, half_extents);
+
+        // The errors are compared relative to their thresholds, to pick the shape which fits best
+        let mut candidates = vec![];
+
+        let rectangle_fits = rectangle_error <= self.rectangle_threshold;
+        if rectangle_fits {
+            candidates.push((
+                rectangle_error / self.rectangle_threshold,
+                Shape::Rectangle(shapes::Rectangle {
+                    cuboid: p2d::shape::Cuboid::new(half_extents),
+                    transform: Transform::new_w_isometry(isometry),
+                    corner_radius: 0.0,
+                }),
+            ));
+        }
+        if ellipse_error <= self.ellipse_threshold {
+            candidates.push((
+                ellipse_error / self.ellipse_threshold,
+                Shape::Ellipse(shapes::Ellipse {
+                    radii: half_extents,
+                    transform: Transform::new_w_isometry(isometry),
+                }),
+            ));
+        }
+        if let Some((vertices, polygon_error)) = self.fit_polygon(&points, half_extents.min()) {
+            // Four vertices which fit a rectangle are meant as one, even when the free vertices fit the path slightly better
+            if !(rectangle_fits && vertices.len() == 4) {
+                candidates.push((
+                    polygon_error / self.polygon_threshold,
+                    Shape::Polygon(shapes::Polygon { points: vertices }),
+                ));
+            }
+        }
+
+        candidates
+            .into_iter()
+            .min_by(|(first, _), (second, _)| {
+                first
+                    .partial_cmp(second)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map(|(_, shape)| shape)
+    }
+
+    fn recognize_line(
+        &self,
+        points: &[na::Vector2<f64>],
+        start: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+    ) -> Option<Shape> {
+        let line_length = (end - start).magnitude();
+        if line_length <= 0.0 {
+            return None;
+        }
+        let direction = (end - start) / line_length;
+
+        let max_distance = points
+            .iter()
+            .map(|point| {
+                let offset = point - start;
+                (offset - direction * offset.dot(&direction)).magnitude()
+            })
+            .fold(0.0, f64::max);
+
+        if max_distance / line_length <= self.line_threshold {
+            Some(Shape::Line(curves::Line { start, end }))
+        } else {
+            None
+        }
+    }
+
+    /// Fits a polygon to the points of a closed path.
+    /// Returns the vertices and the mean distance of the points to the polygon outline, relative to the given size
+    fn fit_polygon(
+        &self,
+        points: &[na::Vector2<f64>],
+        size: f64,
+    ) -> Option<(Vec<na::Vector2<f64>>, f64)> {
+        let mut closed_points = points.to_vec();
+        closed_points.push(points[0]);
+
+        let mut vertices =
+            geometry::simplify_polyline(&closed_points, self.polygon_threshold * size)
+                .into_iter()
+                .map(|i| closed_points[i])
+                .collect::<Vec<na::Vector2<f64>>>();
+        // The last vertex is the first one closing the path
+        vertices.pop();
+        remove_flat_corners(&mut vertices, true, Self::MIN_CORNER_ANGLE);
+
+        if vertices.len() < 3 || vertices.len() > Self::POLYGON_MAX_VERTICES {
+            return None;
+        }
+
+        let total_distance = points
+            .iter()
+            .map(|&point| {
+                (0..vertices.len())
+                    .map(|i| {
+                        geometry::dist_to_segment(
+                            point,
+                            vertices[i],
+                            vertices[(i + 1) % vertices.len()],
+                        )
+                    })
+                    .fold(f64::MAX, f64::min)
+            })
+            .sum::<f64>();
+        let polygon_error = total_distance / points.len() as f64 / size;
+
+        if polygon_error <= self.polygon_threshold {
+            Some((vertices, polygon_error))
+        } else {
+            None
+        }
+    }
+
+    /// Recognizes an arrow which is drawn as its line followed by the head at its end, with sides on both sides of the line.
+    fn recognize_arrow(&self, points: &[na::Vector2<f64>]) -> Option<Shape> {
+        let (mins, maxs) = points.iter().fold(
+            (na::Vector2::repeat(f64::MAX), na::Vector2::repeat(f64::MIN)),
+            |(mins, maxs), point| (mins.inf(point), maxs.sup(point)),
+        );
+
+        let mut vertices =
+            geometry::simplify_polyline(points, self.line_threshold * (maxs - mins).magnitude())
+                .into_iter()
+                .map(|i| points[i])
+                .collect::<Vec<na::Vector2<f64>>>();
+        remove_flat_corners(&mut vertices, false, Self::MIN_CORNER_ANGLE);
+
+        // The line and at least two sides of the head
+        if vertices.len() < 4 {
+            return None;
+        }
+        let start = vertices[0];
+        let tip = vertices[1];
+        let line_length = (tip - start).magnitude();
+        if line_length <= 0.0 {
+            return None;
+        }
+        let direction = (tip - start) / line_length;
+
+        let head_offsets = vertices[2..]
+            .iter()
+            .map(|vertex| vertex - tip)
+            .collect::<Vec<na::Vector2<f64>>>();
+        let head_size = head_offsets
+            .iter()
+            .map(|offset| offset.magnitude())
+            .fold(0.0, f64::max);
+        if head_size > self.arrow_head_threshold * line_length {
+            return None;
+        }
+
+        // The outer points of the sides. Vertices close to the tip are where the head was drawn back to it
+        let sides = head_offsets
+            .iter()
+            .filter(|offset| offset.magnitude() > 0.5 * head_size && offset.dot(&direction) < 0.0)
+            .collect::<Vec<&na::Vector2<f64>>>();
+        let on_left = sides.iter().any(|offset| direction.perp(offset) > 0.0);
+        let on_right = sides.iter().any(|offset| direction.perp(offset) < 0.0);
+        if !on_left || !on_right {
+            return None;
+        }
+
+        let head_length =
+            sides.iter().map(|offset| offset.magnitude()).sum::<f64>() / sides.len() as f64;
+        let head_angle = sides
+            .iter()
+            .map(|offset| offset.angle(&(-direction)))
+            .sum::<f64>()
+            / sides.len() as f64;
+
+        Some(Shape::Arrow(shapes::Arrow {
+            line: curves::Line { start, end: tip },
+            start_head: shapes::ArrowHead::None,
+            end_head: shapes::ArrowHead::Open,
+            head_length: head_length.clamp(
+                shapes::Arrow::HEAD_LENGTH_MIN,
+                shapes::Arrow::HEAD_LENGTH_MAX,
+            ),
+            head_angle,
+        }))
+    }
+}
+
+fn path_length(path: &[na::Vector2<f64>]) -> f64 {
+    path.windows(2)
+        .map(|segment| (segment[1] - segment[0]).magnitude())
+        .sum()
+}
+
+/// Resamples the path to points with equal distances along it, so that the fitting does not depend on the drawing speed
+fn resample(path: &[na::Vector2<f64>], n_points: usize) -> Vec<na::Vector2<f64>> {
+    let path_length = path_length(path);
+    if path_length <= 0.0 || n_points < 2 {
+        return path.to_vec();
+    }
+    let step = path_length / (n_points - 1) as f64;
+
+    let mut points = vec![path[0]];
+    let mut distance_to_next = step;
+
+    for segment in path.windows(2) {
+        let mut start = segment[0];
+        let mut segment_length = (segment[1] - start).magnitude();
+
+        while segment_length >= distance_to_next && points.len() < n_points {
+            let point = start + (segment[1] - start) * (distance_to_next / segment_length);
+            points.push(point);
+
+            start = point;
+            segment_length -= distance_to_next;
+            distance_to_next = step;
+        }
+        distance_to_next -= segment_length;
+    }
+
+    if points.len() < n_points {
+        points.push(path[path.len() - 1]);
+    }
+
+    points
+}
+
+/// Searches the orientation in which the bounds of the points have the smallest area.
+/// Returns the angle, the center and the half extents of the bounds in that orientation
+fn min_area_frame(
+    points: &[na::Vector2<f64>],
+    angle_step: f64,
+) -> (f64, na::Vector2<f64>, na::Vector2<f64>) {
+    let n_steps = (std::f64::consts::FRAC_PI_2 / angle_step).ceil() as usize;
+
+    (0..n_steps)
+        .map(|i| {
+            let angle = i as f64 * angle_step;
+            let rotation = na::Rotation2::new(-angle);
+
+            let (mins, maxs) = points.iter().fold(
+                (na::Vector2::repeat(f64::MAX), na::Vector2::repeat(f64::MIN)),
+                |(mins, maxs), point| {
+                    let rotated = rotation * point;
+                    (mins.inf(&rotated), maxs.sup(&rotated))
+                },
+            );
+
+            let center = rotation.inverse() * ((mins + maxs) / 2.0);
+            (angle, center, (maxs - mins) / 2.0)
+        })
+        .min_by(|(_, _, first), (_, _, second)| {
+            (first[0] * first[1])
+                .partial_cmp(&(second[0] * second[1]))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or((0.0, na::Vector2::zeros(), na::Vector2::zeros()))
+}
+
+/// The mean distance of the points to the outline of the rectangle, relative to its smaller half extent
+fn rectangle_error(local_points: &[na::Vector2<f64>], half_extents: na::Vector2<f64>) -> f64 {
+    let total_distance = local_points
+        .iter()
+        .map(|point| {
+            let outside = point.abs() - half_extents;
+
+            if outside[0] <= 0.0 && outside[1] <= 0.0 {
+                -outside.max()
+            } else {
+                outside.sup(&na::Vector2::zeros()).magnitude()
+            }
+        })
+        .sum::<f64>();
+
+    total_distance / local_points.len() as f64 / half_extents.min()
+}
+
+/// The mean deviation of the points from the outline of the ellipse, relative to its radii
+fn ellipse_error(local_points: &[na::Vector2<f64>], radii: na::Vector2<f64>) -> f64 {
+    let total_deviation = local_points
+        .iter()
+        .map(|point| (point.component_div(&radii).magnitude() - 1.0).abs())
+        .sum::<f64>();
+
+    total_deviation / local_points.len() as f64
+}
+
+/// Removes the corners which turn by less than the min. angle, starting with the flattest one.
+/// The ends of open paths are always kept
+fn remove_flat_corners(vertices: &mut Vec<na::Vector2<f64>>, closed: bool, min_angle: f64) {
+    loop {
+        let n_vertices = vertices.len();
+        if n_vertices < 3 {
+            return;
+        }
+        let corners = if closed {
+            0..n_vertices
+        } else {
+            1..n_vertices - 1
+        };
+
+        let flattest = corners
+            .map(|i| {
+                let prev = vertices[(i + n_vertices - 1) % n_vertices];
+                let next = vertices[(i + 1) % n_vertices];
+                let incoming = vertices[i] - prev;
+                let outgoing = next - vertices[i];
+
+                let angle = if incoming.magnitude() > 0.0 && outgoing.magnitude() > 0.0 {
+                    incoming.angle(&outgoing)
+                } else {
+                    0.0
+                };
+                (i, angle)
+            })
+            .min_by(|(_, first), (_, second)| {
+                first
+                    .partial_cmp(second)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+
+        match flattest {
+            Some((i, angle)) if angle < min_angle => {
+                vertices.remove(i);
+            }
+            _ => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A path through the vertices with points every few units, like they are sampled when drawing
+    fn path_through(vertices: &[na::Vector2<f64>]) -> Vec<na::Vector2<f64>> {
+        let mut path = vec![vertices[0]];
+
+        for segment in vertices.windows(2) {
+            let n_steps = ((segment[1] - segment[0]).magnitude() / 2.0)
+                .ceil()
+                .max(1.0) as usize;
+            path.extend(
+                (1..=n_steps).map(|i| segment[0].lerp(&segment[1], i as f64 / n_steps as f64)),
+            );
+        }
+
+        path
+    }
+
+    fn assert_vector_eq(vector: na::Vector2<f64>, expected: na::Vector2<f64>, tolerance: f64) {
+        assert!(
+            (vector - expected).magnitude() < tolerance,
+            "{} != {}",
+            vector,
+            expected
+        );
+    }
+
+    #[test]
+    fn recognize_line() {
+        let path = (0..=50)
+            .map(|i| {
+                let x = i as f64 * 4.0;
+                na::vector![x, 100.0 + (x / 10.0).sin()]
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        match ShapeRecognizer::default().recognize(&path) {
+            Some(Shape::Line(line)) => {
+                assert_vector_eq(line.start, path[0], 1e-9);
+                assert_vector_eq(line.end, path[path.len() - 1], 1e-9);
+            }
+            shape => panic!("expected a line, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn recognize_rectangle() {
+        let path = path_through(&[
+            na::vector![0.0, 0.0],
+            na::vector![200.0, 0.0],
+            na::vector![200.0, 100.0],
+            na::vector![0.0, 100.0],
+            na::vector![0.0, 0.0],
+        ]);
+
+        match ShapeRecognizer::default().recognize(&path) {
+            Some(Shape::Rectangle(rectangle)) => {
+                let half_extents =
+                    if rectangle.cuboid.half_extents[0] > rectangle.cuboid.half_extents[1] {
+                        rectangle.cuboid.half_extents
+                    } else {
+                        rectangle.cuboid.half_extents.yx()
+                    };
+                assert_vector_eq(half_extents, na::vector![100.0, 50.0], 2.0);
+                assert_vector_eq(
+                    rectangle
+                        .transform
+                        .transform_point(na::Point2::origin())
+                        .coords,
+                    na::vector![100.0, 50.0],
+                    2.0,
+                );
+            }
+            shape => panic!("expected a rectangle, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn recognize_ellipse() {
+        let path = (0..=100)
+            .map(|i| {
+                let angle = i as f64 / 100.0 * std::f64::consts::TAU;
+                na::vector![100.0 + 80.0 * angle.cos(), 100.0 + 40.0 * angle.sin()]
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        match ShapeRecognizer::default().recognize(&path) {
+            Some(Shape::Ellipse(ellipse)) => {
+                let radii = if ellipse.radii[0] > ellipse.radii[1] {
+                    ellipse.radii
+                } else {
+                    ellipse.radii.yx()
+                };
+                assert_vector_eq(radii, na::vector![80.0, 40.0], 2.0);
+                assert_vector_eq(
+                    ellipse
+                        .transform
+                        .transform_point(na::Point2::origin())
+                        .coords,
+                    na::vector![100.0, 100.0],
+                    2.0,
+                );
+            }
+            shape => panic!("expected an ellipse, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn recognize_polygon() {
+        let corners = [
+            na::vector![0.0, 0.0],
+            na::vector![200.0, 20.0],
+            na::vector![80.0, 150.0],
+        ];
+        // Starting in the middle of an edge, the start is not a corner of the polygon
+        let path = path_through(&[
+            na::vector![100.0, 10.0],
+            corners[1],
+            corners[2],
+            corners[0],
+            na::vector![100.0, 10.0],
+        ]);
+
+        match ShapeRecognizer::default().recognize(&path) {
+            Some(Shape::Polygon(polygon)) => {
+                assert_eq!(polygon.points.len(), 3);
+                for corner in corners {
+                    assert!(
+                        polygon
+                            .points
+                            .iter()
+                            .any(|point| (point - corner).magnitude() < 5.0),
+                        "no vertex at corner {}, vertices: {:?}",
+                        corner,
+                        polygon.points
+                    );
+                }
+            }
+            shape => panic!("expected a polygon, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn recognize_arrow() {
+        let start = na::vector![0.0, 100.0];
+        let tip = na::vector![200.0, 100.0];
+        let path = path_through(&[
+            start,
+            tip,
+            na::vector![180.0, 85.0],
+            tip,
+            na::vector![180.0, 115.0],
+        ]);
+
+        match ShapeRecognizer::default().recognize(&path) {
+            Some(Shape::Arrow(arrow)) => {
+                assert_vector_eq(arrow.line.start, start, 1e-9);
+                assert_vector_eq(arrow.line.end, tip, 5.0);
+                assert_eq!(arrow.start_head, shapes::ArrowHead::None);
+                assert_eq!(arrow.end_head, shapes::ArrowHead::Open);
+                assert!((arrow.head_length - 25.0).abs() < 3.0);
+                assert!((arrow.head_angle - (15.0_f64 / 20.0).atan()).abs() < 0.15);
+            }
+            shape => panic!("expected an arrow, got {:?}", shape),
+        }
+    }
+
+    #[test]
+    fn wave_is_not_recognized() {
+        let path = (0..=100)
+            .map(|i| {
+                let x = i as f64 * 2.0;
+                na::vector![x, 100.0 + 30.0 * (x / 20.0).sin()]
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let shape = ShapeRecognizer::default().recognize(&path);
+        assert!(shape.is_none(), "expected no shape, got {:?}", shape);
+    }
+}
@@ -1,11 +1,12 @@
 use crate::compose;
+use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
 use crate::compose::rough::roughoptions::RoughOptions;
 use crate::compose::smooth::SmoothOptions;
-use crate::compose::transformable::{Transform, Transformable};
+use crate::compose::transformable::{MirrorAxis, Transform, Transformable};
 use crate::compose::{curves, rough, shapes};
 use crate::drawbehaviour::DrawBehaviour;
-use crate::pens::shaper::ShaperDrawStyle;
+use crate::pens::shaper::{BezierHandle, ShaperDrawStyle};
 use crate::render;
 use crate::strokes::element::Element;
 use crate::strokes::inputdata::InputData;
@@ -24,9 +25,19 @@ pub enum Shape {
     Rectangle(shapes::Rectangle),
     #[serde(rename = "ellipse")]
     Ellipse(shapes::Ellipse),
+    #[serde(rename = "arrow")]
+    Arrow(shapes::Arrow),
+    #[serde(rename = "polyline")]
+    Polyline(shapes::Polyline),
+    #[serde(rename = "polygon")]
+    Polygon(shapes::Polygon),
+    #[serde(rename = "arc")]
+    Arc(shapes::Arc),
+    #[serde(rename = "cubic_bezier")]
+    CubicBezier(curves::CubicBezier),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename = "shape_drawstyle")]
 pub enum ShapeDrawStyle {
     #[serde(rename = "smooth")]
@@ -41,6 +52,107 @@ pub enum ShapeDrawStyle {
     },
 }
 
+impl ShapeDrawStyle {
+    /// The draw style with the current draw style and options of the shaper
+    pub fn from_shaper(shaper: &Shaper, seed: Option<u64>) -> Self {
+        match shaper.drawstyle {
+            ShaperDrawStyle::Smooth => {
+                let mut options = shaper.smooth_options;
+                options.seed = seed;
+
+                Self::Smooth { options }
+            }
+            ShaperDrawStyle::Rough => {
+                let mut options = shaper.rough_options.clone();
+                options.seed = seed;
+
+                Self::Rough { options }
+            }
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        match self {
+            Self::Smooth { options } => options.width,
+            Self::Rough { options } => options.stroke_width,
+        }
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        match self {
+            Self::Smooth { options } => options.width = width,
+            Self::Rough { options } => options.stroke_width = width,
+        }
+    }
+
+    pub fn stroke_color(&self) -> Option<Color> {
+        match self {
+            Self::Smooth { options } => options.stroke_color,
+            Self::Rough { options } => options.stroke_color,
+        }
+    }
+
+    pub fn set_stroke_color(&mut self, color: Option<Color>) {
+        match self {
+            Self::Smooth { options } => options.stroke_color = color,
+            Self::Rough { options } => options.stroke_color = color,
+        }
+    }
+
+    pub fn fill_color(&self) -> Option<Color> {
+        match self {
+            Self::Smooth { options } => options.fill_color,
+            Self::Rough { options } => options.fill_color,
+        }
+    }
+
+    pub fn set_fill_color(&mut self, color: Option<Color>) {
+        match self {
+            Self::Smooth { options } => options.fill_color = color,
+            Self::Rough { options } => options.fill_color = color,
+        }
+    }
+
+    /// The other draw style (smooth <-> rough), keeping the seed, the colors and the width.
+    /// The remaining options are taken from the previous draw style if it is the other style, else they are the defaults
+    pub fn switched(&self, previous: Option<&Self>) -> Self {
+        match self {
+            Self::Smooth { options } => {
+                let previous_options = match previous {
+                    Some(Self::Rough { options }) => options.clone(),
+                    _ => RoughOptions::default(),
+                };
+
+                Self::Rough {
+                    options: RoughOptions {
+                        seed: options.seed,
+                        stroke_color: options.stroke_color,
+                        stroke_width: options.width,
+                        fill_color: options.fill_color,
+                        ..previous_options
+                    },
+                }
+            }
+            Self::Rough { options } => {
+                let previous_options = match previous {
+                    Some(Self::Smooth { options }) => *options,
+                    _ => SmoothOptions::default(),
+                };
+
+                Self::Smooth {
+                    options: SmoothOptions {
+                        seed: options.seed,
+                        width: options.stroke_width,
+                        stroke_color: options.stroke_color,
+                        fill_color: options.fill_color,
+                        ..previous_options
+                    },
+                }
+            }
+        }
+    }
+}
+
 impl Transformable for Shape {
     fn translate(&mut self, offset: nalgebra::Vector2<f64>) {
         match self {
@@ -53,6 +165,21 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.translate(offset);
             }
+            Self::Arrow(arrow) => {
+                arrow.translate(offset);
+            }
+            Self::Polyline(polyline) => {
+                polyline.translate(offset);
+            }
+            Self::Polygon(polygon) => {
+                polygon.translate(offset);
+            }
+            Self::Arc(arc) => {
+                arc.translate(offset);
+            }
+            Self::CubicBezier(cubbez) => {
+                cubbez.translate(offset);
+            }
         }
     }
 
@@ -67,6 +194,21 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.rotate(angle, center);
             }
+            Self::Arrow(arrow) => {
+                arrow.rotate(angle, center);
+            }
+            Self::Polyline(polyline) => {
+                polyline.rotate(angle, center);
+            }
+            Self::Polygon(polygon) => {
+                polygon.rotate(angle, center);
+            }
+            Self::Arc(arc) => {
+                arc.rotate(angle, center);
+            }
+            Self::CubicBezier(cubbez) => {
+                cubbez.rotate(angle, center);
+            }
         }
     }
 
@@ -81,6 +223,79 @@ impl Transformable for Shape {
             Self::Ellipse(ellipse) => {
                 ellipse.scale(scale);
             }
+            Self::Arrow(arrow) => {
+                arrow.scale(scale);
+            }
+            Self::Polyline(polyline) => {
+                polyline.scale(scale);
+            }
+            Self::Polygon(polygon) => {
+                polygon.scale(scale);
+            }
+            Self::Arc(arc) => {
+                arc.scale(scale);
+            }
+            Self::CubicBezier(cubbez) => {
+                cubbez.scale(scale);
+            }
+        }
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: nalgebra::Point2<f64>) {
+        match self {
+            Self::Line(line) => {
+                line.mirror(axis, center);
+            }
+            Self::Rectangle(rectangle) => {
+                rectangle.mirror(axis, center);
+            }
+            Self::Ellipse(ellipse) => {
+                ellipse.mirror(axis, center);
+            }
+            Self::Arrow(arrow) => {
+                arrow.mirror(axis, center);
+            }
+            Self::Polyline(polyline) => {
+                polyline.mirror(axis, center);
+            }
+            Self::Polygon(polygon) => {
+                polygon.mirror(axis, center);
+            }
+            Self::Arc(arc) => {
+                arc.mirror(axis, center);
+            }
+            Self::CubicBezier(cubbez) => {
+                cubbez.mirror(axis, center);
+            }
+        }
+    }
+
+    fn shear(&mut self, shear: nalgebra::Vector2<f64>, center: nalgebra::Point2<f64>) {
+        match self {
+            Self::Line(line) => {
+                line.shear(shear, center);
+            }
+            Self::Rectangle(rectangle) => {
+                rectangle.shear(shear, center);
+            }
+            Self::Ellipse(ellipse) => {
+                ellipse.shear(shear, center);
+            }
+            Self::Arrow(arrow) => {
+                arrow.shear(shear, center);
+            }
+            Self::Polyline(polyline) => {
+                polyline.shear(shear, center);
+            }
+            Self::Polygon(polygon) => {
+                polygon.shear(shear, center);
+            }
+            Self::Arc(arc) => {
+                arc.shear(shear, center);
+            }
+            Self::CubicBezier(cubbez) => {
+                cubbez.shear(shear, center);
+            }
         }
     }
 }
@@ -91,8 +306,75 @@ impl Shape {
             Self::Line(line) => line.global_aabb(),
             Self::Rectangle(rectangle) => rectangle.global_aabb(),
             Self::Ellipse(ellipse) => ellipse.global_aabb(),
+            Self::Arrow(arrow) => arrow.global_aabb(),
+            Self::Polyline(polyline) => polyline.global_aabb(),
+            Self::Polygon(polygon) => polygon.global_aabb(),
+            Self::Arc(arc) => arc.global_aabb(),
+            Self::CubicBezier(cubbez) => cubbez.global_aabb(),
+        }
+    }
+
+    /// The handles to edit the geometry of the shape, as positions on the sheet.
+    /// Rectangles have their corners followed by the handle for the corner radius
+    pub fn handles(&self) -> Vec<na::Vector2<f64>> {
+        match self {
+            Self::Line(line) => vec![line.start, line.end],
+            Self::Rectangle(rectangle) => {
+                let mut handles = rectangle.corners().to_vec();
+                handles.push(rectangle.corner_radius_handle());
+                handles
+            }
+            Self::Ellipse(ellipse) => ellipse.radii_handles().to_vec(),
+            Self::Arrow(arrow) => vec![arrow.line.start, arrow.line.end],
+            Self::Polyline(shapes::Polyline { points })
+            | Self::Polygon(shapes::Polygon { points }) => points.clone(),
+            Self::Arc(_) => vec![],
+            Self::CubicBezier(cubbez) => vec![cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end],
         }
     }
+
+    /// Moves the handle with the given index (see handles()) to the position by changing the geometry directly,
+    /// so that the stroke width is not distorted. Returns the new index of the moved handle
+    pub fn move_handle(&mut self, index: usize, pos: na::Vector2<f64>) -> usize {
+        match self {
+            Self::Line(line) => match index {
+                0 => line.start = pos,
+                1 => line.end = pos,
+                _ => {}
+            },
+            Self::Rectangle(rectangle) => match index {
+                0..=3 => return rectangle.move_corner(index, pos),
+                4 => rectangle.move_corner_radius_handle(pos),
+                _ => {}
+            },
+            Self::Ellipse(ellipse) => {
+                if index < 2 {
+                    ellipse.move_radius_handle(index, pos);
+                }
+            }
+            Self::Arrow(arrow) => match index {
+                0 => arrow.line.start = pos,
+                1 => arrow.line.end = pos,
+                _ => {}
+            },
+            Self::Polyline(shapes::Polyline { points })
+            | Self::Polygon(shapes::Polygon { points }) => {
+                if let Some(point) = points.get_mut(index) {
+                    *point = pos;
+                }
+            }
+            Self::Arc(_) => {}
+            Self::CubicBezier(cubbez) => match index {
+                0 => cubbez.start = pos,
+                1 => cubbez.cp1 = pos,
+                2 => cubbez.cp2 = pos,
+                3 => cubbez.end = pos,
+                _ => {}
+            },
+        }
+
+        index
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -104,6 +386,9 @@ pub struct ShapeStroke {
     pub shape: Shape,
     #[serde(rename = "drawstyle")]
     pub drawstyle: ShapeDrawStyle,
+    /// The draw style before it was last switched, so that switching back restores its options
+    #[serde(rename = "previous_drawstyle")]
+    pub previous_drawstyle: Option<ShapeDrawStyle>,
     #[serde(rename = "bounds")]
     pub bounds: AABB,
 }
@@ -124,24 +409,7 @@ impl DrawBehaviour for ShapeStroke {
     }
 
     fn gen_bounds(&self) -> Option<AABB> {
-        match &self.drawstyle {
-            ShapeDrawStyle::Smooth { options } => {
-                let width = options.width;
-                Some(
-                    self.shape
-                        .bounds()
-                        .loosened(width * 0.5 + ShaperDrawStyle::SMOOTH_MARGIN),
-                )
-            }
-            ShapeDrawStyle::Rough { options } => {
-                let width = options.stroke_width();
-                Some(
-                    self.shape
-                        .bounds()
-                        .loosened(width * 0.5 + ShaperDrawStyle::ROUGH_MARGIN),
-                )
-            }
-        }
+        Some(self.shape.bounds().loosened(self.bounds_margin()))
     }
 
     fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
@@ -166,15 +434,17 @@ impl DrawBehaviour for ShapeStroke {
                             String::from("none")
                         };
 
-                        svg::node::element::Line::new()
+                        let mut line = svg::node::element::Line::new()
                             .set("x1", line.start[0])
                             .set("y1", line.start[1])
                             .set("x2", line.end[0])
                             .set("y2", line.end[1])
                             .set("stroke", color)
                             .set("stroke-width", options.width)
-                            .set("fill", fill)
-                            .into()
+                            .set("fill", fill);
+                        compose::smooth::apply_stroke_line_style(&mut line, options);
+
+                        line.into()
                     }
                     ShapeDrawStyle::Rough { options } => svg::node::element::Group::new()
                         .add(rough::line(line, options))
@@ -205,6 +475,67 @@ impl DrawBehaviour for ShapeStroke {
                     ShapeDrawStyle::Rough { options } => rough::ellipse(ellipse, options).into(),
                 }
             }
+            Shape::Arrow(ref arrow) => {
+                let mut arrow = *arrow;
+                arrow.translate(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => {
+                        compose::smooth::compose_arrow(arrow, options)
+                    }
+                    ShapeDrawStyle::Rough { options } => rough::arrow(arrow, options).into(),
+                }
+            }
+            Shape::Polyline(ref polyline) => {
+                let mut polyline = polyline.clone();
+                polyline.translate(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => {
+                        compose::smooth::compose_polyline(&polyline, options)
+                    }
+                    ShapeDrawStyle::Rough { options } => svg::node::element::Group::new()
+                        .add(rough::polyline(&polyline, options))
+                        .into(),
+                }
+            }
+            Shape::Polygon(ref polygon) => {
+                let mut polygon = polygon.clone();
+                polygon.translate(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => {
+                        compose::smooth::compose_polygon(&polygon, options)
+                    }
+                    ShapeDrawStyle::Rough { options } => rough::polygon(&polygon, options).into(),
+                }
+            }
+            Shape::Arc(ref arc) => {
+                let mut arc = arc.clone();
+                arc.transform.append_translation_mut(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => {
+                        compose::smooth::compose_arc(arc, options)
+                    }
+                    ShapeDrawStyle::Rough { options } => svg::node::element::Group::new()
+                        .add(rough::arc(arc, options))
+                        .into(),
+                }
+            }
+            Shape::CubicBezier(ref cubbez) => {
+                let mut cubbez = *cubbez;
+                cubbez.translate(offset);
+
+                match &self.drawstyle {
+                    ShapeDrawStyle::Smooth { options } => {
+                        compose::smooth::compose_cubic_bezier(cubbez, options)
+                    }
+                    ShapeDrawStyle::Rough { options } => svg::node::element::Group::new()
+                        .add(rough::cubic_bezier(cubbez, options))
+                        .into(),
+                }
+            }
         };
 
         let svg_data = compose::svg_node_to_string(&element).map_err(|e| {
@@ -235,6 +566,14 @@ impl Transformable for ShapeStroke {
         self.shape.scale(scale);
         self.update_geometry();
     }
+    fn mirror(&mut self, axis: MirrorAxis, center: nalgebra::Point2<f64>) {
+        self.shape.mirror(axis, center);
+        self.update_geometry();
+    }
+    fn shear(&mut self, shear: nalgebra::Vector2<f64>, center: nalgebra::Point2<f64>) {
+        self.shape.shear(shear, center);
+        self.update_geometry();
+    }
 }
 
 impl ShapeStroke {
@@ -252,6 +591,7 @@ impl ShapeStroke {
                     element.inputdata.pos(),
                     0.0,
                 )),
+                corner_radius: 0.0,
             }),
             ShaperStyle::Ellipse => Shape::Ellipse(shapes::Ellipse {
                 radii: na::vector![0.0, 0.0],
@@ -260,26 +600,44 @@ impl ShapeStroke {
                     0.0,
                 )),
             }),
+            ShaperStyle::Arrow => Shape::Arrow(shapes::Arrow {
+                line: curves::Line {
+                    start: element.inputdata.pos(),
+                    end: element.inputdata.pos(),
+                },
+                start_head: shaper.arrow_start_head,
+                end_head: shaper.arrow_end_head,
+                head_length: shaper.arrow_head_length,
+                ..shapes::Arrow::default()
+            }),
+            // The first vertex stays in place, the second follows the pen
+            ShaperStyle::Polyline => Shape::Polyline(shapes::Polyline {
+                points: vec![element.inputdata.pos(), element.inputdata.pos()],
+            }),
+            ShaperStyle::Polygon => Shape::Polygon(shapes::Polygon {
+                points: vec![element.inputdata.pos(), element.inputdata.pos()],
+            }),
+            ShaperStyle::Arc => Shape::Arc(shapes::Arc {
+                transform: Transform::new_w_isometry(na::Isometry2::<f64>::new(
+                    element.inputdata.pos(),
+                    0.0,
+                )),
+                ..shapes::Arc::default()
+            }),
+            ShaperStyle::CubicBezier => Shape::CubicBezier(curves::CubicBezier {
+                start: element.inputdata.pos(),
+                cp1: element.inputdata.pos(),
+                cp2: element.inputdata.pos(),
+                end: element.inputdata.pos(),
+            }),
         };
         let bounds = shape.bounds();
-        let drawstyle = match shaper.drawstyle {
-            ShaperDrawStyle::Smooth => {
-                let mut options = shaper.smooth_options;
-                options.seed = seed;
-
-                ShapeDrawStyle::Smooth { options }
-            }
-            ShaperDrawStyle::Rough => {
-                let mut options = shaper.rough_options.clone();
-                options.seed = seed;
-
-                ShapeDrawStyle::Rough { options }
-            }
-        };
+        let drawstyle = ShapeDrawStyle::from_shaper(shaper, seed);
 
         let mut shapestroke = Self {
             shape,
             drawstyle,
+            previous_drawstyle: None,
             bounds,
             seed,
         };
@@ -291,6 +649,24 @@ impl ShapeStroke {
         shapestroke
     }
 
+    pub fn new_w_shape(shape: Shape, mut drawstyle: ShapeDrawStyle) -> Self {
+        let seed = Some(rand_pcg::Pcg64::from_entropy().gen());
+        match drawstyle {
+            ShapeDrawStyle::Smooth { ref mut options } => options.seed = seed,
+            ShapeDrawStyle::Rough { ref mut options } => options.seed = seed,
+        }
+
+        let mut shapestroke = Self {
+            seed,
+            bounds: shape.bounds(),
+            shape,
+            drawstyle,
+        };
+        shapestroke.update_geometry();
+
+        shapestroke
+    }
+
     pub fn update_shape(&mut self, shaper: &mut Shaper, element: Element) {
         match self.shape {
             Shape::Line(ref mut line) => {
@@ -316,9 +692,144 @@ impl ShapeStroke {
 
                 ellipse.radii = diff.abs();
             }
+            Shape::Arrow(ref mut arrow) => {
+                arrow.line.end = element.inputdata.pos();
+            }
+            Shape::Polyline(shapes::Polyline { ref mut points })
+            | Shape::Polygon(shapes::Polygon { ref mut points }) => {
+                if let Some(last) = points.last_mut() {
+                    *last = element.inputdata.pos();
+                }
+            }
+            Shape::Arc(ref mut arc) => {
+                let center = arc.transform.transform_point(na::point![0.0, 0.0]);
+                let diff = element.inputdata.pos() - center.coords;
+                let angle = diff[1].atan2(diff[0]);
+
+                // The arc starts where the pen first moves away from the center, and then follows its angle around it
+                if arc.radius < Shaper::ARC_START_RADIUS {
+                    arc.start_angle = angle;
+                    arc.sweep_angle = 0.0;
+                } else {
+                    let delta = (angle - (arc.start_angle + arc.sweep_angle)
+                        + std::f64::consts::PI)
+                        .rem_euclid(2.0 * std::f64::consts::PI)
+                        - std::f64::consts::PI;
+
+                    arc.sweep_angle = (arc.sweep_angle + delta)
+                        .clamp(-2.0 * std::f64::consts::PI, 2.0 * std::f64::consts::PI);
+                }
+                arc.radius = diff.magnitude();
+            }
+            Shape::CubicBezier(ref mut cubbez) => {
+                let pos = element.inputdata.pos();
+
+                match shaper.dragged_bezier_handle() {
+                    Some(BezierHandle::Start) => cubbez.start = pos,
+                    Some(BezierHandle::Cp1) => cubbez.cp1 = pos,
+                    Some(BezierHandle::Cp2) => cubbez.cp2 = pos,
+                    Some(BezierHandle::End) => cubbez.end = pos,
+                    // While drawing the curve, it is a straight line. Its control points can be dragged afterwards
+                    None => {
+                        cubbez.end = pos;
+                        cubbez.cp1 = cubbez.start + (pos - cubbez.start) / 3.0;
+                        cubbez.cp2 = cubbez.start + (pos - cubbez.start) * 2.0 / 3.0;
+                    }
+                }
+            }
+        }
+
+        self.update_geometry();
+    }
+
+    /// Changes the draw style to the current draw style and options of the shaper, keeping the seed, the width and the colors of the stroke
+    pub fn change_drawstyle(&mut self, shaper: &Shaper) {
+        let width = self.drawstyle.width();
+        let stroke_color = self.drawstyle.stroke_color();
+        let fill_color = self.drawstyle.fill_color();
+
+        self.drawstyle = ShapeDrawStyle::from_shaper(shaper, self.seed);
+        self.drawstyle.set_width(width);
+        self.drawstyle.set_stroke_color(stroke_color);
+        self.drawstyle.set_fill_color(fill_color);
+        self.update_geometry();
+    }
+
+    /// The margin of the bounds around the shape, which does not scale with the stroke
+    pub fn bounds_margin(&self) -> f64 {
+        match &self.drawstyle {
+            ShapeDrawStyle::Smooth { options } => {
+                options.width * 0.5 + ShaperDrawStyle::SMOOTH_MARGIN
+            }
+            ShapeDrawStyle::Rough { options } => {
+                options.stroke_width() * 0.5 + ShaperDrawStyle::ROUGH_MARGIN
+            }
+        }
+    }
+
+    /// Switches between the smooth and the rough draw style, restoring the options the other style had before
+    pub fn switch_drawstyle(&mut self) {
+        let switched = self.drawstyle.switched(self.previous_drawstyle.as_ref());
+        self.previous_drawstyle = Some(std::mem::replace(&mut self.drawstyle, switched));
+        self.update_geometry();
+    }
+
+    /// The handles to edit the shape, see Shape::handles()
+    pub fn handles(&self) -> Vec<na::Vector2<f64>> {
+        self.shape.handles()
+    }
+
+    /// Moves a handle of the shape, see Shape::move_handle()
+    pub fn move_handle(&mut self, index: usize, pos: na::Vector2<f64>) -> usize {
+        let index = self.shape.move_handle(index, pos);
+        self.update_geometry();
+        index
+    }
+
+    /// Adds a vertex to a polyline or polygon. Returns false if the shape has no vertices
+    pub fn add_vertex(&mut self, vertex: na::Vector2<f64>) -> bool {
+        match self.shape {
+            Shape::Polyline(shapes::Polyline { ref mut points })
+            | Shape::Polygon(shapes::Polygon { ref mut points }) => {
+                points.push(vertex);
+                self.update_geometry();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The vertices of a polyline or polygon
+    pub fn vertices(&self) -> Option<&[na::Vector2<f64>]> {
+        match self.shape {
+            Shape::Polyline(shapes::Polyline { ref points })
+            | Shape::Polygon(shapes::Polygon { ref points }) => Some(points),
+            _ => None,
         }
+    }
 
+    /// Removes vertices of a polyline or polygon which are closer than the tolerance to their predecessor.
+    /// Returns true if the shape still has enough vertices to be drawn
+    pub fn dedup_vertices(&mut self, tolerance: f64) -> bool {
+        let valid = match self.shape {
+            Shape::Polyline(shapes::Polyline { ref mut points }) => {
+                points.dedup_by(|point, prev| (*point - *prev).magnitude() < tolerance);
+                points.len() >= 2
+            }
+            Shape::Polygon(shapes::Polygon { ref mut points }) => {
+                points.dedup_by(|point, prev| (*point - *prev).magnitude() < tolerance);
+                if points.len() > 1
+                    && (points[points.len() - 1] - points[0]).magnitude() < tolerance
+                {
+                    points.pop();
+                }
+                points.len() >= 3
+            }
+            _ => true,
+        };
         self.update_geometry();
+
+        valid
     }
 
     pub fn update_geometry(&mut self) {
@@ -327,3 +838,56 @@ impl ShapeStroke {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::rough::roughoptions::FillStyle;
+    use crate::compose::smooth::{DashPattern, LineCap};
+
+    #[test]
+    fn switching_drawstyle_back_restores_options() {
+        let smooth = ShapeDrawStyle::Smooth {
+            options: SmoothOptions {
+                seed: Some(42),
+                width: 3.0,
+                line_cap: LineCap::Square,
+                dash_pattern: DashPattern::Dotted,
+                ..SmoothOptions::default()
+            },
+        };
+
+        let rough = match smooth.switched(None) {
+            ShapeDrawStyle::Rough { options } => options,
+            ShapeDrawStyle::Smooth { .. } => panic!("switching smooth did not result in rough"),
+        };
+        assert_eq!(rough.seed, Some(42));
+        assert_eq!(rough.stroke_width, 3.0);
+        assert_eq!(rough.fill_style, RoughOptions::default().fill_style);
+
+        // Changing the options of the rough style and switching back
+        let rough = ShapeDrawStyle::Rough {
+            options: RoughOptions {
+                stroke_width: 5.0,
+                fill_style: FillStyle::Dots,
+                ..rough
+            },
+        };
+        let smooth_again = match rough.switched(Some(&smooth)) {
+            ShapeDrawStyle::Smooth { options } => options,
+            ShapeDrawStyle::Rough { .. } => panic!("switching rough did not result in smooth"),
+        };
+        assert_eq!(smooth_again.width, 5.0);
+        assert_eq!(smooth_again.line_cap, LineCap::Square);
+        assert_eq!(smooth_again.dash_pattern, DashPattern::Dotted);
+
+        // And once more to rough
+        let smooth_again = ShapeDrawStyle::Smooth {
+            options: smooth_again,
+        };
+        match smooth_again.switched(Some(&rough)) {
+            ShapeDrawStyle::Rough { options } => assert_eq!(options.fill_style, FillStyle::Dots),
+            ShapeDrawStyle::Smooth { .. } => panic!("switching smooth did not result in rough"),
+        }
+    }
+}
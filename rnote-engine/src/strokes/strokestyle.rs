@@ -2,12 +2,13 @@ use super::bitmapimage::{self, BitmapImage};
 use super::brushstroke::{BrushStroke, BrushStrokeStyle};
 use super::inputdata::InputData;
 use super::shapestroke::ShapeStroke;
+use super::textstroke::{TextStroke, TextStyle};
 use super::vectorimage::VectorImage;
 use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
 use crate::compose::shapes;
 use crate::compose::smooth::SmoothOptions;
-use crate::compose::transformable::{Transform, Transformable};
+use crate::compose::transformable::{MirrorAxis, Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::brush::{Brush, BrushStyle};
 use crate::render::{self, Renderer};
@@ -16,6 +17,7 @@ use crate::utils;
 
 use std::sync::{Arc, RwLock};
 
+use chrono::{TimeZone, Utc};
 use p2d::bounding_volume::AABB;
 use rnote_fileformats::xoppformat::{self, XoppColor};
 use serde::{Deserialize, Serialize};
@@ -31,6 +33,8 @@ pub enum StrokeStyle {
     VectorImage(VectorImage),
     #[serde(rename = "bitmapimage")]
     BitmapImage(BitmapImage),
+    #[serde(rename = "textstroke")]
+    TextStroke(TextStroke),
 }
 
 impl Default for StrokeStyle {
@@ -46,6 +50,7 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.bounds(),
             Self::VectorImage(vectorimage) => vectorimage.bounds(),
             Self::BitmapImage(bitmapimage) => bitmapimage.bounds(),
+            Self::TextStroke(textstroke) => textstroke.bounds(),
         }
     }
 
@@ -55,6 +60,7 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.set_bounds(bounds),
             Self::VectorImage(vectorimage) => vectorimage.set_bounds(bounds),
             Self::BitmapImage(bitmapimage) => bitmapimage.set_bounds(bounds),
+            Self::TextStroke(textstroke) => textstroke.set_bounds(bounds),
         }
     }
 
@@ -64,6 +70,7 @@ impl DrawBehaviour for StrokeStyle {
             Self::ShapeStroke(shapestroke) => shapestroke.gen_svgs(offset),
             Self::VectorImage(vectorimage) => vectorimage.gen_svgs(offset),
             Self::BitmapImage(bitmapimage) => bitmapimage.gen_svgs(offset),
+            Self::TextStroke(textstroke) => textstroke.gen_svgs(offset),
         }
     }
 }
@@ -83,6 +90,9 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.translate(offset);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.translate(offset);
+            }
         }
     }
 
@@ -100,6 +110,9 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.rotate(angle, center);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.rotate(angle, center);
+            }
         }
     }
 
@@ -117,11 +130,63 @@ impl Transformable for StrokeStyle {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.scale(scale);
             }
+            Self::TextStroke(textstroke) => {
+                textstroke.scale(scale);
+            }
+        }
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                brushstroke.mirror(axis, center);
+            }
+            Self::ShapeStroke(shapestroke) => {
+                shapestroke.mirror(axis, center);
+            }
+            Self::VectorImage(vectorimage) => {
+                vectorimage.mirror(axis, center);
+            }
+            Self::BitmapImage(bitmapimage) => {
+                bitmapimage.mirror(axis, center);
+            }
+            Self::TextStroke(textstroke) => {
+                textstroke.mirror(axis, center);
+            }
+        }
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        match self {
+            Self::BrushStroke(brushstroke) => {
+                brushstroke.shear(shear, center);
+            }
+            Self::ShapeStroke(shapestroke) => {
+                shapestroke.shear(shear, center);
+            }
+            Self::VectorImage(vectorimage) => {
+                vectorimage.shear(shear, center);
+            }
+            Self::BitmapImage(bitmapimage) => {
+                bitmapimage.shear(shear, center);
+            }
+            Self::TextStroke(textstroke) => {
+                textstroke.shear(shear, center);
+            }
         }
     }
 }
 
 impl StrokeStyle {
+    /// The margin of the bounds around the geometry of the stroke (e.g. for its width), which does not scale when the stroke is scaled
+    pub fn bounds_margin(&self) -> f64 {
+        match self {
+            Self::BrushStroke(brushstroke) => brushstroke.bounds_margin(),
+            Self::ShapeStroke(shapestroke) => shapestroke.bounds_margin(),
+            Self::VectorImage(_) | Self::BitmapImage(_) | Self::TextStroke(_) => 0.0,
+        }
+    }
+
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -136,12 +201,23 @@ impl StrokeStyle {
             smooth_options.width = width;
         }
 
+        let style = match stroke.tool {
+            xoppformat::XoppTool::Highlighter => BrushStyle::Highlighter,
+            xoppformat::XoppTool::Pen | xoppformat::XoppTool::Eraser => BrushStyle::Solid,
+        };
+
         let brush = Brush {
-            style: BrushStyle::Solid,
+            style,
             smooth_options,
             ..Brush::default()
         };
 
+        // The time when the stroke was started, if available
+        let timestamp = stroke
+            .timestamp
+            .and_then(|timestamp| i64::try_from(timestamp).ok())
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single());
+
         let elements = stroke.coords.into_iter().map(|mut coords| {
             coords[0] += offset[0];
             coords[1] += offset[1];
@@ -151,7 +227,11 @@ impl StrokeStyle {
                 .map(|&width| width / smooth_options.width)
                 .unwrap_or(InputData::PRESSURE_DEFAULT);
 
-            Element::new(InputData::new(coords, pressure))
+            let mut element = Element::new(InputData::new(coords, pressure));
+            if timestamp.is_some() {
+                element.timestamp = timestamp;
+            }
+            element
         });
 
         BrushStroke::new_w_elements(elements, &brush)
@@ -176,6 +256,7 @@ impl StrokeStyle {
         let rectangle = shapes::Rectangle {
             cuboid: p2d::shape::Cuboid::new(bounds.half_extents()),
             transform: Transform::new_w_isometry(na::Isometry2::new(bounds.center().coords, 0.0)),
+            corner_radius: 0.0,
         };
 
         let mut bitmapimage = BitmapImage {
@@ -191,6 +272,24 @@ impl StrokeStyle {
         Ok(StrokeStyle::BitmapImage(bitmapimage))
     }
 
+    pub fn from_xopptext(
+        text: xoppformat::XoppText,
+        offset: na::Vector2<f64>,
+    ) -> Result<Self, anyhow::Error> {
+        let text_style = TextStyle {
+            font_family: text.font,
+            font_size: f64::from(text.size),
+            color: Color::from(text.color),
+            ..TextStyle::default()
+        };
+
+        Ok(StrokeStyle::TextStroke(TextStroke::new(
+            text.text,
+            na::vector![text.x, text.y] + offset,
+            text_style,
+        )))
+    }
+
     pub fn into_xopp(
         self,
         current_dpi: f64,
@@ -214,9 +313,18 @@ impl StrokeStyle {
                     BrushStrokeStyle::Textured { options } => {
                         (options.width, options.stroke_color?.into())
                     }
+                    BrushStrokeStyle::Highlighter { options } => {
+                        (options.width, options.stroke_color?.into())
+                    }
+                    BrushStrokeStyle::Calligraphy { options } => {
+                        (options.width, options.stroke_color?.into())
+                    }
                 };
 
-                let tool = xoppformat::XoppTool::Pen;
+                let tool = match brushstroke.style {
+                    BrushStrokeStyle::Highlighter { .. } => xoppformat::XoppTool::Highlighter,
+                    _ => xoppformat::XoppTool::Pen,
+                };
 
                 // The first width element is the absolute width of the stroke
                 let stroke_width =
@@ -224,13 +332,43 @@ impl StrokeStyle {
 
                 let mut width_vec = vec![stroke_width];
 
-                // the rest are pressures between 0.0 and 1.0
-                let mut pressures = brushstroke
-                    .elements
-                    .iter()
-                    .map(|element| stroke_width * element.inputdata.pressure())
-                    .collect::<Vec<f64>>();
-                width_vec.append(&mut pressures);
+                // the rest are pressures between 0.0 and 1.0. Highlighters have a constant width, so they have none
+                if !matches!(tool, xoppformat::XoppTool::Highlighter) {
+                    let mut pressures = match brushstroke.style {
+                        // Xopp has no nibs, so the width the nib draws in the direction of the stroke is used
+                        BrushStrokeStyle::Calligraphy { options } => brushstroke
+                            .elements
+                            .iter()
+                            .enumerate()
+                            .map(|(i, element)| {
+                                let prev = &brushstroke.elements[i.saturating_sub(1)];
+                                let next = &brushstroke.elements
+                                    [(i + 1).min(brushstroke.elements.len() - 1)];
+                                let nib = options.nib_direction(element.inputdata.azimuth())
+                                    * brushstroke
+                                        .pressure_curve
+                                        .apply(element.inputdata.pressure());
+
+                                stroke_width
+                                    * options.stroke_width(
+                                        nib,
+                                        next.inputdata.pos() - prev.inputdata.pos(),
+                                    )
+                            })
+                            .collect::<Vec<f64>>(),
+                        _ => brushstroke
+                            .elements
+                            .iter()
+                            .map(|element| {
+                                stroke_width
+                                    * brushstroke
+                                        .pressure_curve
+                                        .apply(element.inputdata.pressure())
+                            })
+                            .collect::<Vec<f64>>(),
+                    };
+                    width_vec.append(&mut pressures);
+                }
 
                 let coords = brushstroke
                     .elements
@@ -244,6 +382,13 @@ impl StrokeStyle {
                     })
                     .collect::<Vec<na::Vector2<f64>>>();
 
+                // The time when the stroke was started
+                let timestamp = brushstroke
+                    .elements
+                    .iter()
+                    .find_map(|element| element.timestamp)
+                    .and_then(|timestamp| u64::try_from(timestamp.timestamp()).ok());
+
                 Some(xoppformat::XoppStrokeStyle::XoppStroke(
                     xoppformat::XoppStroke {
                         tool,
@@ -251,7 +396,7 @@ impl StrokeStyle {
                         width: width_vec,
                         coords,
                         fill: None,
-                        timestamp: None,
+                        timestamp,
                         audio_filename: None,
                     },
                 ))
@@ -379,6 +524,28 @@ impl StrokeStyle {
                     },
                 ))
             }
+            StrokeStyle::TextStroke(textstroke) => {
+                // Xopp texts can't be rotated or skewed, so only the position and the vertical scale of the text is kept
+                let pos = textstroke.transform.transform_point(na::point![0.0, 0.0]);
+                let vertical_scale =
+                    (textstroke.transform.transform_point(na::point![0.0, 1.0]) - pos).magnitude();
+
+                Some(xoppformat::XoppStrokeStyle::XoppText(
+                    xoppformat::XoppText {
+                        font: textstroke.text_style.font_family,
+                        size: utils::convert_value_dpi(
+                            textstroke.text_style.font_size * vertical_scale,
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        )
+                        .round() as u32,
+                        x: utils::convert_value_dpi(pos[0], current_dpi, xoppformat::XoppFile::DPI),
+                        y: utils::convert_value_dpi(pos[1], current_dpi, xoppformat::XoppFile::DPI),
+                        color: textstroke.text_style.color.into(),
+                        text: textstroke.text,
+                    },
+                ))
+            }
         }
     }
 }
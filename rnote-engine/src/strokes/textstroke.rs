@@ -0,0 +1,560 @@
+use std::ops::Range;
+
+use crate::compose::color::Color;
+use crate::compose::geometry::AABBHelpers;
+use crate::compose::transformable::{MirrorAxis, Transform, Transformable};
+use crate::drawbehaviour::DrawBehaviour;
+use crate::render;
+
+use gtk4::{cairo, glib, pango};
+use p2d::bounding_volume::AABB;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, glib::Enum)]
+#[repr(u32)]
+#[enum_type(name = "TextAlignment")]
+#[serde(rename = "text_alignment")]
+pub enum TextAlignment {
+    #[enum_value(name = "Start", nick = "start")]
+    #[serde(rename = "start")]
+    Start,
+    #[enum_value(name = "Center", nick = "center")]
+    #[serde(rename = "center")]
+    Center,
+    #[enum_value(name = "End", nick = "end")]
+    #[serde(rename = "end")]
+    End,
+}
+
+impl Default for TextAlignment {
+    fn default() -> Self {
+        Self::Start
+    }
+}
+
+impl TextAlignment {
+    pub fn to_svg_text_anchor(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Center => "middle",
+            Self::End => "end",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "text_style")]
+pub struct TextStyle {
+    #[serde(rename = "font_family")]
+    pub font_family: String,
+    #[serde(rename = "font_size")]
+    pub font_size: f64,
+    #[serde(rename = "color")]
+    pub color: Color,
+    #[serde(rename = "alignment")]
+    pub alignment: TextAlignment,
+    /// The width at which the lines are wrapped. Lines are only broken at line breaks if None
+    #[serde(rename = "max_width")]
+    pub max_width: Option<f64>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_family: String::from(Self::FONT_FAMILY_DEFAULT),
+            font_size: Self::FONT_SIZE_DEFAULT,
+            color: Color::BLACK,
+            alignment: TextAlignment::default(),
+            max_width: None,
+        }
+    }
+}
+
+impl TextStyle {
+    pub const FONT_FAMILY_DEFAULT: &'static str = "monospace";
+    pub const FONT_SIZE_MIN: f64 = 1.0;
+    pub const FONT_SIZE_MAX: f64 = 500.0;
+    pub const FONT_SIZE_DEFAULT: f64 = 24.0;
+    pub const MAX_WIDTH_MIN: f64 = 10.0;
+    pub const MAX_WIDTH_MAX: f64 = 10000.0;
+    pub const MAX_WIDTH_DEFAULT: f64 = 600.0;
+    /// The estimated advance of a character relative to the font size. Used when the text can't be measured with the font
+    pub const CHAR_WIDTH_FACTOR: f64 = 0.6;
+    /// The height of a line relative to the font size
+    pub const LINE_HEIGHT_FACTOR: f64 = 1.25;
+
+    pub fn char_width(&self) -> f64 {
+        self.font_size * Self::CHAR_WIDTH_FACTOR
+    }
+
+    pub fn line_height(&self) -> f64 {
+        self.font_size * Self::LINE_HEIGHT_FACTOR
+    }
+
+    /// The x offsets of the cursor positions in the single line text, measured with the font.
+    /// Has one more entry than the text has characters, the last one is the width of the text
+    pub fn cursor_offsets(&self, text: &str) -> Vec<f64> {
+        self.measure_cursor_offsets(text).unwrap_or_else(|| {
+            (0..=text.chars().count())
+                .map(|i| i as f64 * self.char_width())
+                .collect()
+        })
+    }
+
+    /// Measures the text with a pango layout, which is what librsvg lays out the text with
+    fn measure_cursor_offsets(&self, text: &str) -> Option<Vec<f64>> {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, 1, 1).ok()?;
+        let cx = cairo::Context::new(&surface).ok()?;
+        let layout = pangocairo::create_layout(&cx)?;
+
+        let mut font_description = pango::FontDescription::new();
+        font_description.set_family(&self.font_family);
+        font_description.set_absolute_size(self.font_size * f64::from(pango::SCALE));
+        layout.set_font_description(Some(&font_description));
+        layout.set_text(text);
+
+        let scale = f64::from(pango::SCALE);
+        let mut offsets = text
+            .char_indices()
+            .map(|(byte_index, _)| f64::from(layout.index_to_pos(byte_index as i32).x()) / scale)
+            .collect::<Vec<f64>>();
+        offsets.push(f64::from(layout.size().0) / scale);
+
+        Some(offsets)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "textstroke")]
+pub struct TextStroke {
+    #[serde(rename = "text")]
+    pub text: String,
+    #[serde(rename = "text_style")]
+    pub text_style: TextStyle,
+    /// Transforms the text from its local coordinates, with the upper left corner of the first line as origin
+    #[serde(rename = "transform")]
+    pub transform: Transform,
+    #[serde(rename = "bounds")]
+    pub bounds: AABB,
+}
+
+impl Default for TextStroke {
+    fn default() -> Self {
+        Self::new(
+            String::default(),
+            na::Vector2::zeros(),
+            TextStyle::default(),
+        )
+    }
+}
+
+impl DrawBehaviour for TextStroke {
+    fn bounds(&self) -> AABB {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: AABB) {
+        self.bounds = bounds;
+    }
+
+    fn gen_bounds(&self) -> Option<AABB> {
+        let layout_size = self.layout_size();
+
+        let mut bounds = AABB::new_invalid();
+        [
+            na::point![0.0, 0.0],
+            na::point![layout_size[0], 0.0],
+            na::point![0.0, layout_size[1]],
+            na::point![layout_size[0], layout_size[1]],
+        ]
+        .iter()
+        .for_each(|&corner| {
+            bounds.take_point(self.transform.transform_point(corner));
+        });
+
+        Some(bounds)
+    }
+
+    fn gen_svgs(&self, offset: na::Vector2<f64>) -> Result<Vec<render::Svg>, anyhow::Error> {
+        let mut transform = self.transform;
+        transform.append_translation_mut(offset);
+
+        let chars = self.text.chars().collect::<Vec<char>>();
+        let anchor_x = match self.text_style.alignment {
+            TextAlignment::Start => 0.0,
+            TextAlignment::Center => self.layout_width() / 2.0,
+            TextAlignment::End => self.layout_width(),
+        };
+
+        let tspans = self
+            .gen_line_ranges()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, range)| !range.is_empty())
+            .map(|(i, range)| {
+                let line = chars[range].iter().collect::<String>();
+                // The baseline is approximated with the font size
+                let baseline = i as f64 * self.text_style.line_height() + self.text_style.font_size;
+
+                format!(
+                    "<tspan x=\"{:.3}\" y=\"{:.3}\">{}</tspan>",
+                    anchor_x,
+                    baseline,
+                    escape_xml_text(&line)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("");
+
+        let svg_data = format!(
+            "<text transform=\"{}\" font-family=\"{}\" font-size=\"{:.3}\" fill=\"{}\" text-anchor=\"{}\" xml:space=\"preserve\">{}</text>",
+            transform.to_svg_transform_attr_str(),
+            escape_xml_text(&self.text_style.font_family),
+            self.text_style.font_size,
+            self.text_style.color.to_css_color(),
+            self.text_style.alignment.to_svg_text_anchor(),
+            tspans
+        );
+
+        Ok(vec![render::Svg {
+            svg_data,
+            bounds: self.bounds.translate(offset),
+        }])
+    }
+}
+
+impl Transformable for TextStroke {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.transform.append_translation_mut(offset);
+        self.update_geometry();
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.transform.append_rotation_wrt_point_mut(angle, center);
+        self.update_geometry();
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.transform
+            .append_scale_wrt_point_mut(scale, self.bounds.center());
+        self.update_geometry();
+    }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.transform.append_mirror_wrt_point_mut(axis, center);
+        self.update_geometry();
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.transform.append_shear_wrt_point_mut(shear, center);
+        self.update_geometry();
+    }
+}
+
+impl TextStroke {
+    pub fn new(text: String, pos: na::Vector2<f64>, text_style: TextStyle) -> Self {
+        let mut textstroke = Self {
+            text,
+            text_style,
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos, 0.0)),
+            bounds: AABB::new_zero(),
+        };
+        textstroke.update_geometry();
+
+        textstroke
+    }
+
+    pub fn update_geometry(&mut self) {
+        if let Some(new_bounds) = self.gen_bounds() {
+            self.set_bounds(new_bounds);
+        }
+    }
+
+    /// The number of characters of the text, which is also the cursor position at the end of the text
+    pub fn len_chars(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// Splits the text into lines at line breaks and wraps them at the max width.
+    /// Returns the char ranges of the lines, the line breaks and the spaces where a line got wrapped are not part of them.
+    pub fn gen_line_ranges(&self) -> Vec<Range<usize>> {
+        let chars = self.text.chars().collect::<Vec<char>>();
+
+        let mut line_ranges = vec![];
+        let mut paragraph_start = 0;
+
+        for paragraph_end in chars
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &c)| if c == '\n' { Some(i) } else { None })
+            .chain(std::iter::once(chars.len()))
+        {
+            let mut line_start = paragraph_start;
+
+            if let Some(max_width) = self.text_style.max_width {
+                let offsets = self.text_style.cursor_offsets(
+                    &chars[paragraph_start..paragraph_end]
+                        .iter()
+                        .collect::<String>(),
+                );
+                let offset = |i: usize| offsets[i - paragraph_start];
+
+                // A single char which is wider than the max width stays on its own line
+                while paragraph_end - line_start > 1
+                    && offset(paragraph_end) - offset(line_start) > max_width
+                {
+                    // The last char which fits into the line, but at least one char per line
+                    let wrap_end = (line_start + 1..paragraph_end)
+                        .rev()
+                        .find(|&i| offset(i) - offset(line_start) <= max_width)
+                        .unwrap_or(line_start + 1);
+
+                    // Wrapping at the last space which fits into the line, else the word is broken apart
+                    if let Some(space) =
+                        (line_start + 1..=wrap_end).rev().find(|&i| chars[i] == ' ')
+                    {
+                        line_ranges.push(line_start..space);
+                        line_start = space + 1;
+                    } else {
+                        line_ranges.push(line_start..wrap_end);
+                        line_start = wrap_end;
+                    }
+                }
+            }
+
+            line_ranges.push(line_start..paragraph_end);
+            paragraph_start = paragraph_end + 1;
+        }
+
+        line_ranges
+    }
+
+    /// The x offsets of the cursor positions on the line with the char range, in local coordinates relative to the start of the line
+    fn line_cursor_offsets(&self, range: &Range<usize>) -> Vec<f64> {
+        self.text_style.cursor_offsets(
+            &self
+                .text
+                .chars()
+                .skip(range.start)
+                .take(range.len())
+                .collect::<String>(),
+        )
+    }
+
+    /// Where the line with the width starts in local coordinates, depending on the alignment
+    fn line_start_x(&self, line_width: f64) -> f64 {
+        match self.text_style.alignment {
+            TextAlignment::Start => 0.0,
+            TextAlignment::Center => (self.layout_width() - line_width) / 2.0,
+            TextAlignment::End => self.layout_width() - line_width,
+        }
+    }
+
+    /// The width of the layout in local coordinates. Is the max width if the text gets wrapped
+    pub fn layout_width(&self) -> f64 {
+        if let Some(max_width) = self.text_style.max_width {
+            return max_width;
+        }
+
+        self.gen_line_ranges()
+            .iter()
+            .filter_map(|range| self.line_cursor_offsets(range).last().copied())
+            .fold(self.text_style.char_width(), f64::max)
+    }
+
+    /// The size of the layout in local coordinates
+    pub fn layout_size(&self) -> na::Vector2<f64> {
+        let n_lines = self.gen_line_ranges().len();
+
+        na::vector![
+            self.layout_width(),
+            n_lines as f64 * self.text_style.line_height()
+        ]
+    }
+
+    /// The line index and the column of the cursor, which is a position between characters
+    pub fn cursor_line_col(&self, cursor: usize) -> (usize, usize) {
+        let line_ranges = self.gen_line_ranges();
+        let line = line_ranges
+            .iter()
+            .rposition(|range| range.start <= cursor)
+            .unwrap_or(0);
+        let col = cursor
+            .saturating_sub(line_ranges[line].start)
+            .min(line_ranges[line].len());
+
+        (line, col)
+    }
+
+    /// The cursor on the line above or below, keeping the column as far as possible
+    pub fn cursor_on_adjacent_line(&self, cursor: usize, below: bool) -> usize {
+        let line_ranges = self.gen_line_ranges();
+        let (line, col) = self.cursor_line_col(cursor);
+
+        let adjacent_line = if below {
+            if line + 1 >= line_ranges.len() {
+                return self.len_chars();
+            }
+            line + 1
+        } else {
+            if line == 0 {
+                return 0;
+            }
+            line - 1
+        };
+
+        let range = &line_ranges[adjacent_line];
+        range.start + col.min(range.len())
+    }
+
+    /// The start and end of the line the cursor is on, as cursor positions
+    pub fn cursor_line_bounds(&self, cursor: usize) -> (usize, usize) {
+        let line_ranges = self.gen_line_ranges();
+        let (line, _) = self.cursor_line_col(cursor);
+
+        (line_ranges[line].start, line_ranges[line].end)
+    }
+
+    /// The top and bottom point of the cursor, in the coordinate space of the sheet
+    pub fn gen_cursor_line(&self, cursor: usize) -> (na::Point2<f64>, na::Point2<f64>) {
+        let line_ranges = self.gen_line_ranges();
+        let (line, col) = self.cursor_line_col(cursor);
+
+        let offsets = self.line_cursor_offsets(&line_ranges[line]);
+        let line_width = offsets.last().copied().unwrap_or(0.0);
+        let x = self.line_start_x(line_width) + offsets[col.min(offsets.len() - 1)];
+        let y = line as f64 * self.text_style.line_height();
+
+        (
+            self.transform.transform_point(na::point![x, y]),
+            self.transform
+                .transform_point(na::point![x, y + self.text_style.line_height()]),
+        )
+    }
+
+    /// The cursor nearest to the position in the coordinate space of the sheet
+    pub fn cursor_at_pos(&self, pos: na::Vector2<f64>) -> usize {
+        let local_pos = match self.transform.transform.try_inverse() {
+            Some(inverse) => (inverse * na::Point2::from(pos)).coords,
+            None => return self.len_chars(),
+        };
+        let line_ranges = self.gen_line_ranges();
+
+        let line = ((local_pos[1] / self.text_style.line_height())
+            .floor()
+            .max(0.0) as usize)
+            .min(line_ranges.len() - 1);
+        let offsets = self.line_cursor_offsets(&line_ranges[line]);
+        let line_width = offsets.last().copied().unwrap_or(0.0);
+        let x = local_pos[0] - self.line_start_x(line_width);
+
+        let col = offsets
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                (*a - x)
+                    .abs()
+                    .partial_cmp(&(*b - x).abs())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .map_or(0, |(col, _)| col);
+
+        line_ranges[line].start + col
+    }
+
+    /// Inserts the text at the cursor, returns the new cursor
+    pub fn insert_text_at_cursor(&mut self, text: &str, cursor: usize) -> usize {
+        let byte_index = self.byte_index(cursor);
+        self.text.insert_str(byte_index, text);
+        self.update_geometry();
+
+        cursor + text.chars().count()
+    }
+
+    /// Removes the character before the cursor, returns the new cursor
+    pub fn remove_char_before_cursor(&mut self, cursor: usize) -> usize {
+        if cursor == 0 {
+            return cursor;
+        }
+
+        let byte_index = self.byte_index(cursor - 1);
+        self.text.remove(byte_index);
+        self.update_geometry();
+
+        cursor - 1
+    }
+
+    /// Removes the character after the cursor, returns the new cursor
+    pub fn remove_char_after_cursor(&mut self, cursor: usize) -> usize {
+        if cursor >= self.len_chars() {
+            return cursor;
+        }
+
+        let byte_index = self.byte_index(cursor);
+        self.text.remove(byte_index);
+        self.update_geometry();
+
+        cursor
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(byte_index, _)| byte_index)
+    }
+}
+
+/// Escapes the characters which have a special meaning in xml
+fn escape_xml_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_at_pos_hits_cursor_lines() {
+        for alignment in [
+            TextAlignment::Start,
+            TextAlignment::Center,
+            TextAlignment::End,
+        ] {
+            let mut textstroke = TextStroke::new(
+                String::from("Hello\nWide World\n\nabc"),
+                na::vector![100.0, 50.0],
+                TextStyle {
+                    alignment,
+                    ..TextStyle::default()
+                },
+            );
+            textstroke.rotate(0.3, na::point![0.0, 0.0]);
+
+            for cursor in 0..=textstroke.len_chars() {
+                // Placing the cursor on its own cursor line finds it again
+                let (top, bottom) = textstroke.gen_cursor_line(cursor);
+                let pos = top.coords.lerp(&bottom.coords, 0.5);
+
+                assert_eq!(textstroke.cursor_at_pos(pos), cursor, "{:?}", alignment);
+            }
+        }
+    }
+
+    #[test]
+    fn wrapping_chars_wider_than_max_width() {
+        let textstroke = TextStroke::new(
+            String::from("ab c\nd"),
+            na::vector![0.0, 0.0],
+            TextStyle {
+                max_width: Some(1.0),
+                ..TextStyle::default()
+            },
+        );
+
+        assert_eq!(textstroke.gen_line_ranges(), vec![0..1, 1..2, 3..4, 5..6]);
+    }
+}
@@ -3,7 +3,7 @@ use std::sync::{Arc, RwLock};
 use crate::compose;
 use crate::compose::geometry::AABBHelpers;
 use crate::compose::shapes;
-use crate::compose::transformable::{Transform, Transformable};
+use crate::compose::transformable::{MirrorAxis, Transform, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::render;
 use crate::render::Renderer;
@@ -101,6 +101,16 @@ impl Transformable for VectorImage {
         self.rectangle.scale(scale);
         self.update_geometry();
     }
+
+    fn mirror(&mut self, axis: MirrorAxis, center: na::Point2<f64>) {
+        self.rectangle.mirror(axis, center);
+        self.update_geometry();
+    }
+
+    fn shear(&mut self, shear: na::Vector2<f64>, center: na::Point2<f64>) {
+        self.rectangle.shear(shear, center);
+        self.update_geometry();
+    }
 }
 
 impl VectorImage {
@@ -135,6 +145,7 @@ impl VectorImage {
             shapes::Rectangle {
                 cuboid: p2d::shape::Cuboid::new(size / 2.0),
                 transform: Transform::new_w_isometry(na::Isometry2::new(pos + size / 2.0, 0.0)),
+                corner_radius: 0.0,
             }
         } else {
             shapes::Rectangle {
@@ -143,6 +154,7 @@ impl VectorImage {
                     pos + intrinsic_size / 2.0,
                     0.0,
                 )),
+                corner_radius: 0.0,
             }
         };
 
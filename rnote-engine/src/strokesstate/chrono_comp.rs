@@ -109,7 +109,7 @@ impl StrokesState {
         last_trashed_key.map(|(last_trashed_key, _i)| last_trashed_key)
     }
 
-    /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last
+    /// Returns the keys in chronological order, as in first: least recently modified, last: most recently modified
     pub fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
         let chrono_components = &self.chrono_components;
 
@@ -123,4 +123,13 @@ impl StrokesState {
         let (keys, _ts): (Vec<StrokeKey>, Vec<u32>) = sorted.into_iter().unzip();
         keys
     }
+
+    /// Sorts the keys in chronological order. Keys without a chrono component are put first
+    pub fn sort_keys_chrono(&self, keys: &mut [StrokeKey]) {
+        keys.sort_unstable_by_key(|&key| {
+            self.chrono_components
+                .get(key)
+                .map(|chrono_comp| chrono_comp.t)
+        });
+    }
 }
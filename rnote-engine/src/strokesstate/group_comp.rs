@@ -0,0 +1,461 @@
+use std::collections::{HashMap, HashSet};
+
+use super::history::HistoryEntry;
+use super::{StrokeKey, StrokesState};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default, rename = "group_component")]
+pub struct GroupComponent {
+    /// The id of the innermost group the stroke is part of
+    #[serde(rename = "group_id")]
+    pub group_id: Option<u32>,
+}
+
+impl Default for GroupComponent {
+    fn default() -> Self {
+        Self { group_id: None }
+    }
+}
+
+impl GroupComponent {
+    pub fn new(group_id: Option<u32>) -> Self {
+        Self { group_id }
+    }
+}
+
+/// A group of strokes. Groups can be nested, in which case the group has a parent group
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default, rename = "group")]
+pub struct Group {
+    #[serde(rename = "parent")]
+    pub parent: Option<u32>,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self { parent: None }
+    }
+}
+
+impl Group {
+    pub fn new(parent: Option<u32>) -> Self {
+        Self { parent }
+    }
+}
+
+/// Systems that are related to groups.
+impl StrokesState {
+    pub fn groups(&self) -> &HashMap<u32, Group> {
+        &self.groups
+    }
+
+    /// The innermost group of the stroke
+    pub fn group_of(&self, key: StrokeKey) -> Option<u32> {
+        self.group_components
+            .get(key)
+            .and_then(|group_comp| group_comp.group_id)
+    }
+
+    /// The outermost group which contains the given group
+    pub fn top_group(&self, group_id: u32) -> u32 {
+        let mut top_group = group_id;
+
+        // Bounded by the number of groups, to not loop forever on malformed parent relations
+        for _ in 0..self.groups.len() {
+            match self.groups.get(&top_group).and_then(|group| group.parent) {
+                Some(parent) => top_group = parent,
+                None => break,
+            }
+        }
+
+        top_group
+    }
+
+    /// The outermost group of the stroke
+    pub fn top_group_of(&self, key: StrokeKey) -> Option<u32> {
+        self.group_of(key).map(|group_id| self.top_group(group_id))
+    }
+
+    /// Returns true if the stroke is part of the group, directly or through nested groups
+    pub fn is_in_group(&self, key: StrokeKey, group_id: u32) -> bool {
+        let mut current = self.group_of(key);
+
+        for _ in 0..=self.groups.len() {
+            match current {
+                Some(current_id) if current_id == group_id => return true,
+                Some(current_id) => {
+                    current = self.groups.get(&current_id).and_then(|group| group.parent);
+                }
+                None => return false,
+            }
+        }
+
+        false
+    }
+
+    /// The keys of all strokes which are part of the group, including the ones in nested groups
+    pub fn keys_in_group(&self, group_id: u32) -> Vec<StrokeKey> {
+        self.group_components
+            .keys()
+            .filter(|&key| self.is_in_group(key, group_id))
+            .collect()
+    }
+
+    /// Extends the keys with all strokes which are in the same outermost groups
+    pub fn keys_with_groups(&self, keys: &[StrokeKey]) -> Vec<StrokeKey> {
+        let top_groups = keys
+            .iter()
+            .filter_map(|&key| self.top_group_of(key))
+            .collect::<HashSet<u32>>();
+
+        let mut keys_with_groups = keys.to_vec();
+        top_groups.into_iter().for_each(|group_id| {
+            keys_with_groups.extend(self.keys_in_group(group_id));
+        });
+        keys_with_groups.sort_unstable();
+        keys_with_groups.dedup();
+
+        keys_with_groups
+    }
+
+    /// Groups the selected strokes and groups. Returns the id of the new group, or None if there was not enough to group
+    pub fn group_selection(&mut self) -> Option<u32> {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        // The top groups and the strokes without a group are the items which end up in the new group
+        let top_groups = selection_keys
+            .iter()
+            .filter_map(|&key| self.top_group_of(key))
+            .collect::<HashSet<u32>>();
+        let ungrouped_keys = selection_keys
+            .iter()
+            .copied()
+            .filter(|&key| self.group_of(key).is_none())
+            .collect::<Vec<StrokeKey>>();
+
+        if top_groups.len() + ungrouped_keys.len() < 2 {
+            return None;
+        }
+
+        self.record_groups(&selection_keys);
+
+        let new_group = self.groups.keys().max().map_or(0, |max_id| max_id + 1);
+        self.groups.insert(new_group, Group::new(None));
+
+        top_groups.into_iter().for_each(|group_id| {
+            if let Some(group) = self.groups.get_mut(&group_id) {
+                group.parent = Some(new_group);
+            }
+        });
+        ungrouped_keys.into_iter().for_each(|key| {
+            self.group_components
+                .insert(key, GroupComponent::new(Some(new_group)));
+        });
+
+        Some(new_group)
+    }
+
+    /// Breaks apart the outermost groups of the selection. Nested groups are kept
+    pub fn ungroup_selection(&mut self) {
+        let selection_keys = self.selection_keys_as_rendered();
+        let top_groups = selection_keys
+            .iter()
+            .filter_map(|&key| self.top_group_of(key))
+            .collect::<HashSet<u32>>();
+
+        if top_groups.is_empty() {
+            return;
+        }
+
+        self.record_groups(&selection_keys);
+
+        self.groups.values_mut().for_each(|group| {
+            if let Some(parent) = group.parent {
+                if top_groups.contains(&parent) {
+                    group.parent = None;
+                }
+            }
+        });
+        self.group_components.values_mut().for_each(|group_comp| {
+            if let Some(group_id) = group_comp.group_id {
+                if top_groups.contains(&group_id) {
+                    group_comp.group_id = None;
+                }
+            }
+        });
+        top_groups.iter().for_each(|group_id| {
+            self.groups.remove(group_id);
+        });
+    }
+
+    /// Recreates the groups of the original strokes for their duplicates. The keys must be in the same order
+    pub fn duplicate_groups(&mut self, original_keys: &[StrokeKey], duplicate_keys: &[StrokeKey]) {
+        let mut group_ids = HashSet::new();
+        original_keys.iter().for_each(|&key| {
+            let mut current = self.group_of(key);
+
+            while let Some(group_id) = current {
+                if !group_ids.insert(group_id) {
+                    break;
+                }
+                current = self.groups.get(&group_id).and_then(|group| group.parent);
+            }
+        });
+
+        if group_ids.is_empty() {
+            return;
+        }
+
+        self.record_groups(duplicate_keys);
+
+        let mut next_id = self.groups.keys().max().map_or(0, |max_id| max_id + 1);
+        let new_ids = group_ids
+            .into_iter()
+            .map(|group_id| {
+                let new_id = next_id;
+                next_id += 1;
+                (group_id, new_id)
+            })
+            .collect::<HashMap<u32, u32>>();
+
+        new_ids.iter().for_each(|(group_id, &new_id)| {
+            let parent = self
+                .groups
+                .get(group_id)
+                .and_then(|group| group.parent)
+                .and_then(|parent| new_ids.get(&parent).copied());
+
+            self.groups.insert(new_id, Group::new(parent));
+        });
+
+        original_keys.iter().zip(duplicate_keys.iter()).for_each(
+            |(&original_key, &duplicate_key)| {
+                let group_id = self
+                    .group_of(original_key)
+                    .and_then(|group_id| new_ids.get(&group_id).copied());
+
+                self.group_components
+                    .insert(duplicate_key, GroupComponent::new(group_id));
+            },
+        );
+    }
+
+    /// Records the groups and the group ids of the strokes
+    pub fn record_groups(&mut self, keys: &[StrokeKey]) {
+        let group_ids = keys
+            .iter()
+            .map(|&key| (key, self.group_of(key)))
+            .collect::<Vec<(StrokeKey, Option<u32>)>>();
+
+        self.history.record(HistoryEntry::Groups {
+            groups: self.groups.clone(),
+            group_ids,
+        });
+    }
+
+    /// Swaps the groups and the group ids of the strokes, returns the replaced ones
+    pub fn swap_groups(
+        &mut self,
+        groups: HashMap<u32, Group>,
+        group_ids: Vec<(StrokeKey, Option<u32>)>,
+    ) -> (HashMap<u32, Group>, Vec<(StrokeKey, Option<u32>)>) {
+        let replaced_groups = std::mem::replace(&mut self.groups, groups);
+
+        let replaced_group_ids = group_ids
+            .into_iter()
+            .filter_map(|(key, group_id)| {
+                if let Some(group_comp) = self.group_components.get_mut(key) {
+                    let replaced = group_comp.group_id;
+                    group_comp.group_id = group_id;
+
+                    Some((key, replaced))
+                } else {
+                    log::debug!(
+                        "get group_comp in swap_groups() returned None for stroke with key {:?}",
+                        key
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        (replaced_groups, replaced_group_ids)
+    }
+
+    /// Makes sure every stroke has a group component and the groups only reference existing groups. Needed for sheets from older versions.
+    pub fn validate_groups(&mut self) {
+        let group_ids = self.groups.keys().copied().collect::<HashSet<u32>>();
+
+        self.groups.values_mut().for_each(|group| {
+            if let Some(parent) = group.parent {
+                if !group_ids.contains(&parent) {
+                    group.parent = None;
+                }
+            }
+        });
+
+        let keys = self.strokes.keys().collect::<Vec<StrokeKey>>();
+        keys.into_iter().for_each(|key| {
+            let group_id = self
+                .group_of(key)
+                .filter(|group_id| group_ids.contains(group_id));
+
+            self.group_components
+                .insert(key, GroupComponent::new(group_id));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::brushstroke::BrushStroke;
+    use crate::strokes::strokestyle::StrokeStyle;
+
+    fn strokes_state_w_strokes(n: usize) -> (StrokesState, Vec<StrokeKey>) {
+        let mut strokes_state = StrokesState::new();
+        let keys = (0..n)
+            .map(|_| strokes_state.insert_stroke(StrokeStyle::BrushStroke(BrushStroke::default())))
+            .collect::<Vec<StrokeKey>>();
+
+        (strokes_state, keys)
+    }
+
+    fn select_only(strokes_state: &mut StrokesState, keys: &[StrokeKey]) {
+        let all_keys = strokes_state.strokes.keys().collect::<Vec<StrokeKey>>();
+        strokes_state.set_selected_keys(&all_keys, false);
+        strokes_state.set_selected_keys(keys, true);
+    }
+
+    /// Groups the first two strokes, then that group together with the third stroke
+    fn nest_groups(strokes_state: &mut StrokesState, keys: &[StrokeKey]) -> (u32, u32) {
+        select_only(strokes_state, &keys[..2]);
+        let inner = strokes_state.group_selection().unwrap();
+        select_only(strokes_state, &keys[..3]);
+        let outer = strokes_state.group_selection().unwrap();
+
+        (inner, outer)
+    }
+
+    fn sorted(mut keys: Vec<StrokeKey>) -> Vec<StrokeKey> {
+        keys.sort_unstable();
+        keys
+    }
+
+    #[test]
+    fn nested_groups_are_ungrouped_from_the_outside() {
+        let (mut strokes_state, keys) = strokes_state_w_strokes(4);
+
+        select_only(&mut strokes_state, &keys[..1]);
+        assert_eq!(strokes_state.group_selection(), None);
+
+        let (inner, outer) = nest_groups(&mut strokes_state, &keys);
+        assert_ne!(inner, outer);
+        assert_eq!(strokes_state.groups()[&inner].parent, Some(outer));
+        assert_eq!(strokes_state.group_of(keys[0]), Some(inner));
+        assert_eq!(strokes_state.group_of(keys[2]), Some(outer));
+        assert_eq!(strokes_state.top_group_of(keys[1]), Some(outer));
+        assert_eq!(strokes_state.group_of(keys[3]), None);
+        assert_eq!(
+            sorted(strokes_state.keys_in_group(outer)),
+            sorted(keys[..3].to_vec())
+        );
+        assert_eq!(
+            sorted(strokes_state.keys_in_group(inner)),
+            sorted(keys[..2].to_vec())
+        );
+
+        select_only(&mut strokes_state, &keys[..1]);
+        strokes_state.ungroup_selection();
+        assert!(!strokes_state.groups().contains_key(&outer));
+        assert_eq!(strokes_state.groups()[&inner].parent, None);
+        assert_eq!(strokes_state.top_group_of(keys[0]), Some(inner));
+        assert_eq!(strokes_state.group_of(keys[2]), None);
+
+        strokes_state.ungroup_selection();
+        assert!(strokes_state.groups().is_empty());
+        assert!(keys
+            .iter()
+            .all(|&key| strokes_state.group_of(key).is_none()));
+    }
+
+    #[test]
+    fn keys_with_groups_expands_to_outermost_group() {
+        let (mut strokes_state, keys) = strokes_state_w_strokes(4);
+        nest_groups(&mut strokes_state, &keys);
+
+        let expected = sorted(keys[..3].to_vec());
+        for &key in keys[..3].iter() {
+            assert_eq!(strokes_state.keys_with_groups(&[key]), expected);
+        }
+        assert_eq!(strokes_state.keys_with_groups(&[keys[3]]), vec![keys[3]]);
+        assert_eq!(
+            strokes_state.keys_with_groups(&[keys[0], keys[3]]),
+            sorted(keys.clone())
+        );
+    }
+
+    #[test]
+    fn duplicates_get_new_groups() {
+        let (mut strokes_state, keys) = strokes_state_w_strokes(3);
+        let (inner, outer) = nest_groups(&mut strokes_state, &keys);
+
+        let duplicate_keys = keys
+            .iter()
+            .map(|_| strokes_state.insert_stroke(StrokeStyle::BrushStroke(BrushStroke::default())))
+            .collect::<Vec<StrokeKey>>();
+        strokes_state.duplicate_groups(&keys, &duplicate_keys);
+
+        let duplicate_inner = strokes_state.group_of(duplicate_keys[0]).unwrap();
+        let duplicate_outer = strokes_state.group_of(duplicate_keys[2]).unwrap();
+        assert!(![inner, outer].contains(&duplicate_inner));
+        assert!(![inner, outer].contains(&duplicate_outer));
+        assert_ne!(duplicate_inner, duplicate_outer);
+        assert_eq!(strokes_state.groups().len(), 4);
+        assert_eq!(
+            strokes_state.groups()[&duplicate_inner].parent,
+            Some(duplicate_outer)
+        );
+        assert_eq!(
+            strokes_state.group_of(duplicate_keys[1]),
+            Some(duplicate_inner)
+        );
+
+        // The originals are unchanged and the groups do not mix
+        assert_eq!(strokes_state.group_of(keys[0]), Some(inner));
+        assert_eq!(strokes_state.groups()[&inner].parent, Some(outer));
+        assert_eq!(
+            strokes_state.keys_with_groups(&duplicate_keys[..1]),
+            sorted(duplicate_keys.clone())
+        );
+        assert_eq!(
+            strokes_state.keys_with_groups(&keys[..1]),
+            sorted(keys.clone())
+        );
+    }
+
+    #[test]
+    fn validate_groups_drops_dangling_references() {
+        let (mut strokes_state, keys) = strokes_state_w_strokes(3);
+        strokes_state.groups.insert(0, Group::new(Some(7)));
+        strokes_state
+            .group_components
+            .insert(keys[0], GroupComponent::new(Some(0)));
+        strokes_state
+            .group_components
+            .insert(keys[1], GroupComponent::new(Some(5)));
+        strokes_state.group_components.remove(keys[2]);
+
+        strokes_state.validate_groups();
+
+        assert_eq!(strokes_state.groups()[&0].parent, None);
+        assert_eq!(strokes_state.top_group_of(keys[0]), Some(0));
+        assert_eq!(strokes_state.group_of(keys[1]), None);
+        assert_eq!(
+            strokes_state.group_components.get(keys[2]),
+            Some(&GroupComponent::default())
+        );
+    }
+}
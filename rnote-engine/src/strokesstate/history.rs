@@ -0,0 +1,502 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use super::group_comp::Group;
+use super::layer_comp::Layer;
+use super::{StrokeKey, StrokesState};
+use crate::sheet::background::Background;
+use crate::sheet::format::Format;
+use crate::strokes::strokestyle::StrokeStyle;
+
+use p2d::bounding_volume::AABB;
+
+/// A reversible entry of the history.
+/// Entries hold the state before the change. When they are applied they are swapped with the current state, so applying an entry returns the entry which reverts it again.
+#[derive(Debug, Clone)]
+pub enum HistoryEntry {
+    /// Strokes were inserted or trashed. Holds the trashed state of the strokes before. Inserted strokes are regarded as trashed before their insertion.
+    TrashState { states: Vec<(StrokeKey, bool)> },
+    /// Strokes were transformed or their style was changed. Holds the strokes as they were before.
+    Strokes {
+        strokes: Vec<(StrokeKey, StrokeStyle)>,
+    },
+    /// Strokes were grouped or ungrouped. Holds the groups and the group ids of the strokes before.
+    Groups {
+        groups: HashMap<u32, Group>,
+        group_ids: Vec<(StrokeKey, Option<u32>)>,
+    },
+    /// Layers were added, removed, reordered or changed, or strokes were moved to other layers. Holds the layers, the current layer and the layer ids of the strokes before.
+    Layers {
+        layers: Vec<Layer>,
+        current_layer: u32,
+        layer_ids: Vec<(StrokeKey, u32)>,
+    },
+    /// The z-order of strokes was changed. Holds the z-order of the strokes before.
+    ZOrder { z_orders: Vec<(StrokeKey, u32)> },
+    /// The sheet was resized. Holds the sheet bounds before.
+    SheetBounds { bounds: AABB },
+    /// The sheet format was changed. Holds the format before.
+    Format { format: Format },
+    /// The sheet background was changed. Holds the background before.
+    Background { background: Background },
+    /// Multiple entries which are undone / redone together in one step.
+    Group { entries: Vec<HistoryEntry> },
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    undo_stack: VecDeque<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    max_depth: usize,
+
+    /// The entries recorded while a group is open
+    group_entries: Vec<HistoryEntry>,
+    /// The keys of the strokes which are already recorded in the open group
+    group_recorded_strokes: HashSet<StrokeKey>,
+    /// Groups can be nested, only the outermost group is pushed as one entry
+    group_depth: usize,
+    /// The merge id of the latest entry on the undo stack, while it can still be continued by merged records
+    merge_id: Option<String>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(Self::MAX_DEPTH_DEFAULT)
+    }
+}
+
+impl History {
+    pub const MAX_DEPTH_DEFAULT: usize = 100;
+    pub const MAX_DEPTH_MIN: usize = 1;
+    pub const MAX_DEPTH_MAX: usize = 1000;
+
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            max_depth,
+
+            group_entries: vec![],
+            group_recorded_strokes: HashSet::new(),
+            group_depth: 0,
+            merge_id: None,
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Sets the maximum amount of undo steps. Older steps are dropped
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.truncate();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group_entries.clear();
+        self.group_recorded_strokes.clear();
+        self.group_depth = 0;
+        self.merge_id = None;
+    }
+
+    /// Opens a group. Every entry which is recorded until the group is ended is undone / redone in one step. (e.g. a eraser gesture or a selection drag)
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+    }
+
+    /// Ends the group. When the outermost group ends, its entries are pushed as one step
+    pub fn end_group(&mut self) {
+        if self.group_depth == 0 {
+            return;
+        }
+        self.group_depth -= 1;
+
+        if self.group_depth == 0 {
+            self.group_recorded_strokes.clear();
+
+            if !self.group_entries.is_empty() {
+                let entries = std::mem::take(&mut self.group_entries);
+                self.push_new(HistoryEntry::Group { entries });
+            }
+        }
+    }
+
+    /// Records a new entry. Clears the redo stack.
+    pub fn record(&mut self, entry: HistoryEntry) {
+        if self.group_depth > 0 {
+            match entry {
+                HistoryEntry::Strokes { strokes } => {
+                    // Only the first record of a stroke in a group is needed, as it holds the state before the group.
+                    let strokes = strokes
+                        .into_iter()
+                        .filter(|(key, _)| self.group_recorded_strokes.insert(*key))
+                        .collect::<Vec<(StrokeKey, StrokeStyle)>>();
+
+                    if !strokes.is_empty() {
+                        self.group_entries.push(HistoryEntry::Strokes { strokes });
+                    }
+                }
+                entry => {
+                    self.group_entries.push(entry);
+                }
+            }
+        } else {
+            self.push_new(entry);
+        }
+    }
+
+    /// Records a entry which continues the latest entry when that was recorded with the same merge id, so that a change made in many small steps
+    /// (e.g. while dragging a slider) is undone in one step. Only the state before the first step is kept then.
+    pub fn record_merged(&mut self, entry: HistoryEntry, merge_id: String) {
+        if self.group_depth == 0
+            && self.merge_id.as_ref() == Some(&merge_id)
+            && self.latest_entry_covers(&entry)
+        {
+            return;
+        }
+
+        self.record(entry);
+
+        if self.group_depth == 0 {
+            self.merge_id = Some(merge_id);
+        }
+    }
+
+    /// Ends merging, so that the next entry recorded with `record_merged()` starts a new step (e.g. when a new interaction with a slider begins)
+    pub fn end_merge(&mut self) {
+        self.merge_id = None;
+    }
+
+    pub fn pop_undo(&mut self) -> Option<HistoryEntry> {
+        self.merge_id = None;
+        self.undo_stack.pop_back()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<HistoryEntry> {
+        self.merge_id = None;
+        self.redo_stack.pop()
+    }
+
+    /// Pushes a entry which reverts a redo. Does not clear the redo stack.
+    pub fn push_undo(&mut self, entry: HistoryEntry) {
+        self.undo_stack.push_back(entry);
+        self.truncate();
+    }
+
+    /// Pushes a entry which reverts a undo.
+    pub fn push_redo(&mut self, entry: HistoryEntry) {
+        self.redo_stack.push(entry);
+    }
+
+    fn push_new(&mut self, entry: HistoryEntry) {
+        self.redo_stack.clear();
+        self.merge_id = None;
+        self.push_undo(entry);
+    }
+
+    /// Wether the latest entry on the undo stack already holds the state before the entry
+    fn latest_entry_covers(&self, entry: &HistoryEntry) -> bool {
+        match (self.undo_stack.back(), entry) {
+            (
+                Some(HistoryEntry::Strokes { strokes: recorded }),
+                HistoryEntry::Strokes { strokes },
+            ) => strokes
+                .iter()
+                .all(|(key, _)| recorded.iter().any(|(recorded_key, _)| recorded_key == key)),
+            (
+                Some(HistoryEntry::Layers {
+                    layer_ids: recorded,
+                    ..
+                }),
+                HistoryEntry::Layers { layer_ids, .. },
+            ) => layer_ids
+                .iter()
+                .all(|(key, _)| recorded.iter().any(|(recorded_key, _)| recorded_key == key)),
+            _ => false,
+        }
+    }
+
+    fn truncate(&mut self) {
+        while self.undo_stack.len() > self.max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+}
+
+/// Systems that are related to the history.
+impl StrokesState {
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    pub fn history_mut(&mut self) -> &mut History {
+        &mut self.history
+    }
+
+    /// Records the current trashed state of the strokes
+    pub fn record_trash_state(&mut self, keys: &[StrokeKey]) {
+        let states = keys
+            .iter()
+            .filter_map(|&key| Some((key, self.trash_components.get(key)?.trashed)))
+            .collect::<Vec<(StrokeKey, bool)>>();
+
+        if !states.is_empty() {
+            self.history.record(HistoryEntry::TrashState { states });
+        }
+    }
+
+    /// Records the current state of the strokes. Must be called before modifying them
+    pub fn record_strokes(&mut self, keys: &[StrokeKey]) {
+        let strokes = keys
+            .iter()
+            .filter_map(|&key| Some((key, self.strokes.get(key)?.clone())))
+            .collect::<Vec<(StrokeKey, StrokeStyle)>>();
+
+        if !strokes.is_empty() {
+            self.history.record(HistoryEntry::Strokes { strokes });
+        }
+    }
+
+    /// Swaps the trashed state of the strokes, returns the replaced state
+    pub fn swap_trash_state(&mut self, states: Vec<(StrokeKey, bool)>) -> Vec<(StrokeKey, bool)> {
+        states
+            .into_iter()
+            .filter_map(|(key, trashed)| {
+                if let Some(trash_comp) = self.trash_components.get_mut(key) {
+                    let replaced = trash_comp.trashed;
+                    trash_comp.trashed = trashed;
+
+                    Some((key, replaced))
+                } else {
+                    log::debug!(
+                        "get trash_comp in swap_trash_state() returned None for stroke with key {:?}",
+                        key
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Swaps the strokes, returns the replaced strokes. Rendering needs to be regenerated after calling this
+    pub fn swap_strokes(
+        &mut self,
+        strokes: Vec<(StrokeKey, StrokeStyle)>,
+    ) -> Vec<(StrokeKey, StrokeStyle)> {
+        strokes
+            .into_iter()
+            .filter_map(|(key, stroke)| {
+                if let Some(current) = self.strokes.get_mut(key) {
+                    let replaced = std::mem::replace(current, stroke);
+                    self.update_geometry_for_stroke(key);
+
+                    Some((key, replaced))
+                } else {
+                    log::debug!(
+                        "get stroke in swap_strokes() returned None for stroke with key {:?}",
+                        key
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::brushstroke::BrushStroke;
+    use slotmap::SlotMap;
+
+    fn stroke_keys(n: usize) -> Vec<StrokeKey> {
+        let mut slotmap = SlotMap::<StrokeKey, ()>::with_key();
+        (0..n).map(|_| slotmap.insert(())).collect()
+    }
+
+    fn strokes_entry(keys: &[StrokeKey]) -> HistoryEntry {
+        HistoryEntry::Strokes {
+            strokes: keys
+                .iter()
+                .map(|&key| (key, StrokeStyle::BrushStroke(BrushStroke::default())))
+                .collect(),
+        }
+    }
+
+    fn z_order_entry(key: StrokeKey, z: u32) -> HistoryEntry {
+        HistoryEntry::ZOrder {
+            z_orders: vec![(key, z)],
+        }
+    }
+
+    fn layers_entry(keys: &[StrokeKey]) -> HistoryEntry {
+        HistoryEntry::Layers {
+            layers: vec![],
+            current_layer: 0,
+            layer_ids: keys.iter().map(|&key| (key, 0)).collect(),
+        }
+    }
+
+    /// The keys of the entry, if it is a strokes entry
+    fn recorded_keys(entry: &HistoryEntry) -> Vec<StrokeKey> {
+        match entry {
+            HistoryEntry::Strokes { strokes } => strokes.iter().map(|(key, _)| *key).collect(),
+            entry => panic!("expected a strokes entry, got {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn nested_groups_are_one_step() {
+        let keys = stroke_keys(1);
+        let mut history = History::default();
+
+        history.begin_group();
+        history.record(z_order_entry(keys[0], 0));
+        history.begin_group();
+        history.record(z_order_entry(keys[0], 1));
+        history.end_group();
+        // Still recorded into the outer group
+        assert!(!history.can_undo());
+        history.record(z_order_entry(keys[0], 2));
+        history.end_group();
+
+        match history.pop_undo() {
+            Some(HistoryEntry::Group { entries }) => assert_eq!(entries.len(), 3),
+            entry => panic!("expected a group entry, got {:?}", entry),
+        }
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn group_records_strokes_once() {
+        let keys = stroke_keys(3);
+        let mut history = History::default();
+
+        history.begin_group();
+        history.record(strokes_entry(&keys[0..2]));
+        history.record(strokes_entry(&keys[1..3]));
+        history.record(strokes_entry(&keys[0..1]));
+        history.end_group();
+
+        match history.pop_undo() {
+            Some(HistoryEntry::Group { entries }) => {
+                assert_eq!(entries.len(), 2);
+                assert_eq!(recorded_keys(&entries[0]), keys[0..2]);
+                assert_eq!(recorded_keys(&entries[1]), keys[2..3]);
+            }
+            entry => panic!("expected a group entry, got {:?}", entry),
+        }
+
+        // A new group records the strokes again
+        history.begin_group();
+        history.record(strokes_entry(&keys[0..1]));
+        history.end_group();
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn empty_group_is_not_pushed() {
+        let mut history = History::default();
+
+        history.begin_group();
+        history.end_group();
+        // Ending without an open group is ignored
+        history.end_group();
+
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn merged_records_continue_latest_entry() {
+        let keys = stroke_keys(2);
+        let mut history = History::default();
+
+        for _ in 0..3 {
+            history.record_merged(strokes_entry(&keys[0..1]), String::from("a"));
+        }
+        assert_eq!(history.undo_stack.len(), 1);
+
+        // Not covered by the latest entry
+        history.record_merged(strokes_entry(&keys), String::from("a"));
+        assert_eq!(history.undo_stack.len(), 2);
+
+        // Another merge id
+        history.record_merged(strokes_entry(&keys), String::from("b"));
+        assert_eq!(history.undo_stack.len(), 3);
+
+        history.end_merge();
+        history.record_merged(strokes_entry(&keys), String::from("b"));
+        assert_eq!(history.undo_stack.len(), 4);
+
+        // Undoing ends the merge
+        history.pop_undo();
+        history.record_merged(strokes_entry(&keys), String::from("b"));
+        assert_eq!(history.undo_stack.len(), 4);
+    }
+
+    #[test]
+    fn latest_entry_covers_recorded_keys() {
+        let keys = stroke_keys(2);
+        let mut history = History::default();
+        assert!(!history.latest_entry_covers(&strokes_entry(&keys[0..1])));
+
+        history.record(strokes_entry(&keys[0..1]));
+        assert!(history.latest_entry_covers(&strokes_entry(&keys[0..1])));
+        assert!(!history.latest_entry_covers(&strokes_entry(&keys)));
+        assert!(!history.latest_entry_covers(&layers_entry(&keys[0..1])));
+
+        history.record(layers_entry(&keys));
+        assert!(history.latest_entry_covers(&layers_entry(&keys[1..2])));
+        assert!(!history.latest_entry_covers(&z_order_entry(keys[0], 0)));
+    }
+
+    #[test]
+    fn max_depth_drops_oldest_steps() {
+        let keys = stroke_keys(1);
+        let mut history = History::new(3);
+
+        for z in 0..5 {
+            history.record(z_order_entry(keys[0], z));
+        }
+        assert_eq!(history.undo_stack.len(), 3);
+        assert!(matches!(
+            history.undo_stack.front(),
+            Some(HistoryEntry::ZOrder { z_orders }) if z_orders[0].1 == 2
+        ));
+
+        history.set_max_depth(1);
+        assert_eq!(history.undo_stack.len(), 1);
+        assert!(matches!(
+            history.pop_undo(),
+            Some(HistoryEntry::ZOrder { z_orders }) if z_orders[0].1 == 4
+        ));
+    }
+
+    #[test]
+    fn new_record_clears_redo_stack() {
+        let keys = stroke_keys(1);
+        let mut history = History::default();
+
+        history.record(z_order_entry(keys[0], 0));
+        let entry = history.pop_undo().unwrap();
+        history.push_redo(entry);
+        assert!(history.can_redo());
+
+        // Reverting a redo keeps the redo stack
+        history.push_undo(z_order_entry(keys[0], 1));
+        assert!(history.can_redo());
+
+        history.record(z_order_entry(keys[0], 2));
+        assert!(!history.can_redo());
+    }
+}
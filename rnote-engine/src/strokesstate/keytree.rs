@@ -0,0 +1,233 @@
+use super::{StrokeKey, StrokesState};
+use crate::drawbehaviour::DrawBehaviour;
+
+use p2d::bounding_volume::AABB;
+use rstar::{RTree, RTreeObject};
+use slotmap::SecondaryMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KeyTreeObject {
+    key: StrokeKey,
+    envelope: rstar::AABB<[f64; 2]>,
+}
+
+impl RTreeObject for KeyTreeObject {
+    type Envelope = rstar::AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+impl KeyTreeObject {
+    fn new(key: StrokeKey, bounds: AABB) -> Self {
+        Self {
+            key,
+            envelope: envelope_from_bounds(bounds),
+        }
+    }
+}
+
+fn envelope_from_bounds(bounds: AABB) -> rstar::AABB<[f64; 2]> {
+    rstar::AABB::from_corners(
+        [bounds.mins[0], bounds.mins[1]],
+        [bounds.maxs[0], bounds.maxs[1]],
+    )
+}
+
+/// A R-tree of the stroke keys with the stroke bounds as envelopes, to query strokes spatially without iterating through all of them.
+#[derive(Clone, Default)]
+pub struct KeyTree {
+    tree: RTree<KeyTreeObject>,
+    /// The currently indexed objects, needed to find them again in the tree when they are updated or removed
+    objects: SecondaryMap<StrokeKey, KeyTreeObject>,
+}
+
+impl std::fmt::Debug for KeyTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyTree")
+            .field("size", &self.tree.size())
+            .finish()
+    }
+}
+
+impl KeyTree {
+    /// Inserts the key with the stroke bounds. Replaces the bounds if the key is already indexed
+    pub fn insert_with_bounds(&mut self, key: StrokeKey, bounds: AABB) {
+        let object = KeyTreeObject::new(key, bounds);
+
+        if let Some(old_object) = self.objects.insert(key, object) {
+            if old_object == object {
+                return;
+            }
+            self.tree.remove(&old_object);
+        }
+
+        self.tree.insert(object);
+    }
+
+    pub fn remove(&mut self, key: StrokeKey) {
+        if let Some(object) = self.objects.remove(key) {
+            self.tree.remove(&object);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tree = RTree::new();
+        self.objects.clear();
+    }
+
+    /// Rebuilds the tree from scratch, which is faster than inserting the keys one by one
+    pub fn rebuild_from_bounds(&mut self, keys_bounds: Vec<(StrokeKey, AABB)>) {
+        self.objects.clear();
+
+        let objects = keys_bounds
+            .into_iter()
+            .map(|(key, bounds)| {
+                let object = KeyTreeObject::new(key, bounds);
+                self.objects.insert(key, object);
+                object
+            })
+            .collect::<Vec<KeyTreeObject>>();
+
+        self.tree = RTree::bulk_load(objects);
+    }
+
+    /// The keys whose bounds intersect the given bounds. Unordered
+    pub fn keys_intersecting_bounds(&self, bounds: AABB) -> Vec<StrokeKey> {
+        self.tree
+            .locate_in_envelope_intersecting(&envelope_from_bounds(bounds))
+            .map(|object| object.key)
+            .collect()
+    }
+}
+
+/// Systems that are related to the spatial index of the strokes.
+impl StrokesState {
+    /// Updates the bounds of the stroke in the key tree. Must be called after the bounds of a stroke have changed
+    pub fn update_key_tree_for_stroke(&mut self, key: StrokeKey) {
+        if let Some(stroke) = self.strokes.get(key) {
+            self.key_tree.insert_with_bounds(key, stroke.bounds());
+        } else {
+            self.key_tree.remove(key);
+        }
+    }
+
+    pub fn update_key_tree_for_strokes(&mut self, keys: &[StrokeKey]) {
+        keys.iter().for_each(|&key| {
+            self.update_key_tree_for_stroke(key);
+        });
+    }
+
+    /// Rebuilds the key tree for all strokes. Needed after the strokes were imported
+    pub fn rebuild_key_tree(&mut self) {
+        let keys_bounds = self
+            .strokes
+            .iter()
+            .map(|(key, stroke)| (key, stroke.bounds()))
+            .collect::<Vec<(StrokeKey, AABB)>>();
+
+        self.key_tree.rebuild_from_bounds(keys_bounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::shapes;
+    use crate::compose::transformable::{Transform, Transformable};
+    use crate::strokes::shapestroke::{Shape, ShapeStroke};
+    use crate::strokes::strokestyle::StrokeStyle;
+    use crate::strokesstate::history::HistoryEntry;
+    use p2d::bounding_volume::BoundingVolume;
+
+    fn rectangle_stroke(mins: na::Vector2<f64>, maxs: na::Vector2<f64>) -> StrokeStyle {
+        let mut shapestroke = ShapeStroke {
+            shape: Shape::Rectangle(shapes::Rectangle {
+                cuboid: p2d::shape::Cuboid::new((maxs - mins) * 0.5),
+                transform: Transform::new_w_isometry(na::Isometry2::new((mins + maxs) * 0.5, 0.0)),
+                corner_radius: 0.0,
+            }),
+            ..ShapeStroke::default()
+        };
+        shapestroke.update_geometry();
+
+        StrokeStyle::ShapeStroke(shapestroke)
+    }
+
+    /// Asserts that the key tree finds the same keys as checking the bounds of every stroke
+    fn assert_matches_linear_scan(strokes_state: &StrokesState) {
+        let queries = [
+            AABB::new(na::point![0.0, 0.0], na::point![1000.0, 1000.0]),
+            AABB::new(na::point![0.0, 0.0], na::point![60.0, 60.0]),
+            AABB::new(na::point![90.0, 10.0], na::point![130.0, 45.0]),
+            AABB::new(na::point![240.0, 240.0], na::point![260.0, 260.0]),
+            AABB::new(na::point![500.0, 500.0], na::point![600.0, 600.0]),
+        ];
+
+        for query in queries {
+            let mut expected = strokes_state
+                .strokes
+                .iter()
+                .filter(|(_, stroke)| stroke.bounds().intersects(&query))
+                .map(|(key, _)| key)
+                .collect::<Vec<StrokeKey>>();
+            let mut actual = strokes_state.key_tree.keys_intersecting_bounds(query);
+            expected.sort_unstable();
+            actual.sort_unstable();
+
+            assert_eq!(actual, expected, "query {:?}", query);
+        }
+    }
+
+    #[test]
+    fn key_tree_matches_linear_scan() {
+        let mut strokes_state = StrokesState::new();
+        let keys = vec![
+            strokes_state.insert_stroke(rectangle_stroke(
+                na::vector![10.0, 20.0],
+                na::vector![110.0, 70.0],
+            )),
+            strokes_state.insert_stroke(rectangle_stroke(
+                na::vector![100.0, 30.0],
+                na::vector![150.0, 60.0],
+            )),
+            strokes_state.insert_stroke(rectangle_stroke(
+                na::vector![200.0, 200.0],
+                na::vector![300.0, 300.0],
+            )),
+        ];
+        strokes_state.history_mut().clear();
+        assert_matches_linear_scan(&strokes_state);
+
+        strokes_state.remove_stroke(keys[1]);
+        assert_matches_linear_scan(&strokes_state);
+
+        strokes_state.record_strokes(&[keys[2]]);
+        if let Some(stroke) = strokes_state.strokes.get_mut(keys[2]) {
+            stroke.translate(na::vector![300.0, 300.0]);
+        }
+        strokes_state.update_geometry_for_stroke(keys[2]);
+        assert_matches_linear_scan(&strokes_state);
+
+        // Undoing moves the stroke back
+        match strokes_state.history_mut().pop_undo() {
+            Some(HistoryEntry::Strokes { strokes }) => {
+                strokes_state.swap_strokes(strokes);
+            }
+            entry => panic!("expected a strokes entry, got {:?}", entry),
+        }
+        assert_matches_linear_scan(&strokes_state);
+        assert_eq!(
+            strokes_state.key_tree.keys_intersecting_bounds(AABB::new(
+                na::point![240.0, 240.0],
+                na::point![260.0, 260.0]
+            )),
+            vec![keys[2]]
+        );
+
+        strokes_state.key_tree.clear();
+        strokes_state.rebuild_key_tree();
+        assert_matches_linear_scan(&strokes_state);
+    }
+}
@@ -0,0 +1,486 @@
+use super::history::HistoryEntry;
+use super::{StrokeKey, StrokesState};
+use crate::render;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default, rename = "layer_component")]
+pub struct LayerComponent {
+    /// The id of the layer the stroke is on
+    #[serde(rename = "layer_id")]
+    pub layer_id: u32,
+}
+
+impl Default for LayerComponent {
+    fn default() -> Self {
+        Self {
+            layer_id: Layer::ID_DEFAULT,
+        }
+    }
+}
+
+impl LayerComponent {
+    pub fn new(layer_id: u32) -> Self {
+        Self { layer_id }
+    }
+}
+
+/// A layer of the sheet. The ordering of the layers is given by their position in the layer list of the strokes state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "layer")]
+pub struct Layer {
+    #[serde(rename = "id")]
+    pub id: u32,
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "visible")]
+    pub visible: bool,
+    /// Strokes on locked layers can't be selected or erased
+    #[serde(rename = "locked")]
+    pub locked: bool,
+    #[serde(rename = "opacity")]
+    pub opacity: f64,
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self {
+            id: Self::ID_DEFAULT,
+            name: String::from("Layer 1"),
+            visible: true,
+            locked: false,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl Layer {
+    pub const ID_DEFAULT: u32 = 0;
+
+    pub fn new(id: u32, name: String) -> Self {
+        Self {
+            id,
+            name,
+            ..Self::default()
+        }
+    }
+}
+
+pub fn default_layers() -> Vec<Layer> {
+    vec![Layer::default()]
+}
+
+/// Systems that are related to layers.
+impl StrokesState {
+    /// The layers, ordered from bottom to top
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn layer(&self, layer_id: u32) -> Option<&Layer> {
+        self.layers.iter().find(|layer| layer.id == layer_id)
+    }
+
+    fn layer_mut(&mut self, layer_id: u32) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|layer| layer.id == layer_id)
+    }
+
+    /// The position of the layer in the ordering, zero being the bottom layer
+    pub fn layer_position(&self, layer_id: u32) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.id == layer_id)
+    }
+
+    /// The layer new strokes are inserted into
+    pub fn current_layer(&self) -> u32 {
+        self.current_layer
+    }
+
+    pub fn set_current_layer(&mut self, layer_id: u32) {
+        if self.layer(layer_id).is_some() {
+            self.current_layer = layer_id;
+        } else {
+            log::debug!(
+                "layer() in set_current_layer() returned None for layer with id {}",
+                layer_id
+            );
+        }
+    }
+
+    /// Adds a new layer on top, returns its id
+    pub fn add_layer(&mut self, name: String) -> u32 {
+        self.record_layers(&[]);
+
+        let id = self
+            .layers
+            .iter()
+            .map(|layer| layer.id)
+            .max()
+            .map_or(Layer::ID_DEFAULT, |max_id| max_id + 1);
+
+        self.layers.push(Layer::new(id, name));
+
+        id
+    }
+
+    /// Removes the layer and trashes the strokes on it. The last layer can't be removed
+    pub fn remove_layer(&mut self, layer_id: u32) {
+        let position = if let Some(position) = self.layer_position(layer_id) {
+            position
+        } else {
+            log::debug!(
+                "layer_position() in remove_layer() returned None for layer with id {}",
+                layer_id
+            );
+            return;
+        };
+        if self.layers.len() <= 1 {
+            return;
+        }
+        let layer_keys = self.keys_on_layer(layer_id);
+
+        self.history.begin_group();
+        self.record_layers(&layer_keys);
+        self.record_trash_state(&layer_keys);
+        self.history.end_group();
+
+        self.layers.remove(position);
+        let fallback_layer = self.layers[position.saturating_sub(1)].id;
+        if self.current_layer == layer_id {
+            self.current_layer = fallback_layer;
+        }
+
+        // Moving the trashed strokes to an existing layer, so they are visible again when the trashing gets undone
+        layer_keys.iter().for_each(|&key| {
+            if let Some(trash_comp) = self.trash_components.get_mut(key) {
+                trash_comp.trashed = true;
+            }
+            if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                selection_comp.selected = false;
+            }
+            if let Some(layer_comp) = self.layer_components.get_mut(key) {
+                layer_comp.layer_id = fallback_layer;
+            }
+            if let Some(render_comp) = self.render_components.get_mut(key) {
+                render_comp.regenerate_flag = true;
+            }
+        });
+    }
+
+    pub fn set_layer_name(&mut self, layer_id: u32, name: String) {
+        if self
+            .layer(layer_id)
+            .map_or(true, |layer| layer.name == name)
+        {
+            return;
+        }
+        self.record_layers(&[]);
+
+        if let Some(layer) = self.layer_mut(layer_id) {
+            layer.name = name;
+        }
+    }
+
+    pub fn set_layer_visible(&mut self, layer_id: u32, visible: bool) {
+        if self
+            .layer(layer_id)
+            .map_or(true, |layer| layer.visible == visible)
+        {
+            return;
+        }
+        self.record_layers(&[]);
+
+        if let Some(layer) = self.layer_mut(layer_id) {
+            layer.visible = visible;
+        }
+
+        if !visible {
+            self.deselect_keys_on_layer(layer_id);
+        }
+    }
+
+    pub fn set_layer_locked(&mut self, layer_id: u32, locked: bool) {
+        if self
+            .layer(layer_id)
+            .map_or(true, |layer| layer.locked == locked)
+        {
+            return;
+        }
+        self.record_layers(&[]);
+
+        if let Some(layer) = self.layer_mut(layer_id) {
+            layer.locked = locked;
+        }
+
+        if locked {
+            self.deselect_keys_on_layer(layer_id);
+        }
+    }
+
+    /// Sets the opacity of the layer. Consecutive changes of the same layer are merged
+    pub fn set_layer_opacity(&mut self, layer_id: u32, opacity: f64) {
+        let opacity = opacity.clamp(0.0, 1.0);
+        if self
+            .layer(layer_id)
+            .map_or(true, |layer| layer.opacity == opacity)
+        {
+            return;
+        }
+        let entry = self.gen_layers_entry(&[]);
+        self.history
+            .record_merged(entry, format!("layer-opacity-{}", layer_id));
+
+        if let Some(layer) = self.layer_mut(layer_id) {
+            layer.opacity = opacity;
+        }
+        // The opacity is applied when rendering the strokes
+        self.keys_on_layer(layer_id)
+            .into_iter()
+            .for_each(|key| self.set_regenerate_flag(key, true));
+    }
+
+    /// Moves the layer one position up
+    pub fn raise_layer(&mut self, layer_id: u32) {
+        if let Some(position) = self.layer_position(layer_id) {
+            if position + 1 < self.layers.len() {
+                self.record_layers(&[]);
+                self.layers.swap(position, position + 1);
+            }
+        }
+    }
+
+    /// Moves the layer one position down
+    pub fn lower_layer(&mut self, layer_id: u32) {
+        if let Some(position) = self.layer_position(layer_id) {
+            if position > 0 {
+                self.record_layers(&[]);
+                self.layers.swap(position, position - 1);
+            }
+        }
+    }
+
+    /// The layer id of the stroke. Strokes without a layer component are on the bottom layer
+    pub fn layer_of(&self, key: StrokeKey) -> u32 {
+        if let Some(layer_comp) = self.layer_components.get(key) {
+            layer_comp.layer_id
+        } else {
+            log::debug!(
+                "get layer_comp in layer_of() returned None for stroke with key {:?}",
+                key
+            );
+            self.layers
+                .first()
+                .map_or(Layer::ID_DEFAULT, |layer| layer.id)
+        }
+    }
+
+    pub fn set_layer_of(&mut self, key: StrokeKey, layer_id: u32) {
+        self.set_layer_of_strokes(&[key], layer_id);
+    }
+
+    pub fn move_selection_to_layer(&mut self, layer_id: u32) {
+        let selection_keys = self.selection_keys_as_rendered();
+        self.set_layer_of_strokes(&selection_keys, layer_id);
+    }
+
+    /// Moves the strokes to the layer
+    fn set_layer_of_strokes(&mut self, keys: &[StrokeKey], layer_id: u32) {
+        if self.layer(layer_id).is_none() {
+            log::debug!(
+                "layer() in set_layer_of_strokes() returned None for layer with id {}",
+                layer_id
+            );
+            return;
+        }
+        if keys.is_empty() {
+            return;
+        }
+        self.record_layers(keys);
+
+        keys.iter().for_each(|&key| {
+            if let Some(layer_comp) = self.layer_components.get_mut(key) {
+                layer_comp.layer_id = layer_id;
+                self.set_regenerate_flag(key, true);
+            } else {
+                log::debug!(
+                    "get layer_comp in set_layer_of_strokes() returned None for stroke with key {:?}",
+                    key
+                );
+            }
+        });
+    }
+
+    /// Records the layers, the current layer and the layer ids of the strokes
+    pub fn record_layers(&mut self, keys: &[StrokeKey]) {
+        let entry = self.gen_layers_entry(keys);
+        self.history.record(entry);
+    }
+
+    /// Swaps the layers, the current layer and the layer ids of the strokes, returns the replaced ones
+    pub fn swap_layers(
+        &mut self,
+        layers: Vec<Layer>,
+        current_layer: u32,
+        layer_ids: Vec<(StrokeKey, u32)>,
+    ) -> (Vec<Layer>, u32, Vec<(StrokeKey, u32)>) {
+        let replaced_layers = std::mem::replace(&mut self.layers, layers);
+        let replaced_current_layer = std::mem::replace(&mut self.current_layer, current_layer);
+
+        let replaced_layer_ids = layer_ids
+            .into_iter()
+            .filter_map(|(key, layer_id)| {
+                if let Some(layer_comp) = self.layer_components.get_mut(key) {
+                    let replaced = layer_comp.layer_id;
+                    layer_comp.layer_id = layer_id;
+                    self.set_regenerate_flag(key, true);
+
+                    Some((key, replaced))
+                } else {
+                    log::debug!(
+                        "get layer_comp in swap_layers() returned None for stroke with key {:?}",
+                        key
+                    );
+                    None
+                }
+            })
+            .collect();
+
+        // The strokes on layers with a changed opacity need to be rendered again
+        let changed_opacity_layers = self
+            .layers
+            .iter()
+            .filter(|layer| {
+                replaced_layers
+                    .iter()
+                    .find(|replaced| replaced.id == layer.id)
+                    .map_or(false, |replaced| replaced.opacity != layer.opacity)
+            })
+            .map(|layer| layer.id)
+            .collect::<Vec<u32>>();
+        changed_opacity_layers.into_iter().for_each(|layer_id| {
+            self.keys_on_layer(layer_id)
+                .into_iter()
+                .for_each(|key| self.set_regenerate_flag(key, true));
+        });
+
+        // Strokes on layers which are now hidden or locked can't stay selected
+        self.uneditable_layers()
+            .into_iter()
+            .for_each(|layer_id| self.deselect_keys_on_layer(layer_id));
+
+        (replaced_layers, replaced_current_layer, replaced_layer_ids)
+    }
+
+    fn gen_layers_entry(&self, keys: &[StrokeKey]) -> HistoryEntry {
+        HistoryEntry::Layers {
+            layers: self.layers.clone(),
+            current_layer: self.current_layer,
+            layer_ids: keys.iter().map(|&key| (key, self.layer_of(key))).collect(),
+        }
+    }
+
+    pub fn keys_on_layer(&self, layer_id: u32) -> Vec<StrokeKey> {
+        self.layer_components
+            .iter()
+            .filter_map(|(key, layer_comp)| {
+                if layer_comp.layer_id == layer_id {
+                    Some(key)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The ids of the layers which are hidden or locked
+    pub fn uneditable_layers(&self) -> Vec<u32> {
+        self.layers
+            .iter()
+            .filter_map(|layer| {
+                if !layer.visible || layer.locked {
+                    Some(layer.id)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Wether the layer of the stroke is visible
+    pub fn layer_visible(&self, key: StrokeKey) -> bool {
+        self.layer(self.layer_of(key))
+            .map_or(true, |layer| layer.visible)
+    }
+
+    /// Wether the stroke can be modified by the selector and the eraser. False when its layer is hidden or locked.
+    pub fn layer_editable(&self, key: StrokeKey) -> bool {
+        self.layer(self.layer_of(key))
+            .map_or(true, |layer| layer.visible && !layer.locked)
+    }
+
+    /// Sorts the keys by the position of their layer. Highlighter strokes are put behind the other strokes of their layer,
+    /// so that they don't cover them. The sort is stable, so the order within a layer is kept otherwise
+    pub fn sort_keys_by_layer(&self, keys: &mut [StrokeKey]) {
+        keys.sort_by_key(|&key| {
+            (
+                self.layer_position(self.layer_of(key))
+                    .unwrap_or(usize::MAX),
+                !self.is_highlighter(key),
+            )
+        });
+    }
+
+    /// Wraps the svgs in a group with the layer opacity, if the stroke is on a translucent layer
+    pub fn apply_layer_opacity_to_svgs(
+        &self,
+        key: StrokeKey,
+        svgs: Vec<render::Svg>,
+    ) -> Vec<render::Svg> {
+        let opacity = self
+            .layer(self.layer_of(key))
+            .map_or(1.0, |layer| layer.opacity);
+
+        if opacity >= 1.0 {
+            return svgs;
+        }
+
+        svgs.into_iter()
+            .map(|svg| render::Svg {
+                svg_data: format!("<g opacity=\"{}\">\n{}\n</g>", opacity, svg.svg_data),
+                bounds: svg.bounds,
+            })
+            .collect()
+    }
+
+    /// Makes sure every stroke has a layer component with a existing layer. Needed for sheets from older versions.
+    pub fn validate_layers(&mut self) {
+        if self.layers.is_empty() {
+            self.layers = default_layers();
+        }
+        if self.layer(self.current_layer).is_none() {
+            self.current_layer = self.layers[0].id;
+        }
+
+        let bottom_layer = self.layers[0].id;
+        let keys = self.strokes.keys().collect::<Vec<StrokeKey>>();
+
+        keys.into_iter().for_each(|key| {
+            let layer_exists = self.layer_components.get(key).map_or(false, |layer_comp| {
+                self.layer(layer_comp.layer_id).is_some()
+            });
+
+            if !layer_exists {
+                self.layer_components
+                    .insert(key, LayerComponent::new(bottom_layer));
+            }
+        });
+    }
+
+    fn deselect_keys_on_layer(&mut self, layer_id: u32) {
+        self.keys_on_layer(layer_id).iter().for_each(|&key| {
+            if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                selection_comp.selected = false;
+            }
+        });
+    }
+}
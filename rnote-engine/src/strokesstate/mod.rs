@@ -1,31 +1,45 @@
 pub mod chrono_comp;
+pub mod group_comp;
+pub mod history;
+pub mod keytree;
+pub mod layer_comp;
 pub mod render_comp;
 pub mod selection_comp;
 pub mod trash_comp;
+pub mod zorder_comp;
 
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 
 use chrono_comp::ChronoComponent;
+use group_comp::{Group, GroupComponent};
+use history::{History, HistoryEntry};
+use keytree::KeyTree;
+use layer_comp::{Layer, LayerComponent};
 use p2d::query::PointQuery;
 use render_comp::RenderComponent;
 use selection_comp::SelectionComponent;
 use trash_comp::TrashComponent;
+use zorder_comp::ZOrderComponent;
 
 use crate::compose::geometry::{self, AABBHelpers};
-use crate::compose::transformable::Transformable;
+use crate::compose::transformable::{MirrorAxis, Transformable};
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::shaper::Shaper;
 use crate::pens::tools::DragProximityTool;
 use crate::pens::PenStyle;
 use crate::render::{self, Renderer};
 use crate::strokes::bitmapimage::BitmapImage;
+use crate::strokes::brushstroke::{BrushStroke, BrushStrokeStyle};
 use crate::strokes::element::Element;
+use crate::strokes::shaperecognizer::ShapeRecognizer;
+use crate::strokes::shapestroke::ShapeStroke;
 use crate::strokes::strokestyle::StrokeStyle;
+use crate::strokes::textstroke::TextStroke;
 use crate::strokes::vectorimage::VectorImage;
 use crate::surfaceflags::SurfaceFlags;
 
 use p2d::bounding_volume::{BoundingSphere, BoundingVolume, AABB};
-use rayon::iter::{ParallelBridge, ParallelIterator};
 use serde::{Deserialize, Serialize};
 use slotmap::{HopSlotMap, SecondaryMap};
 
@@ -37,10 +51,14 @@ The Entities are the StrokeKey's, which represent a stroke. There are different
     * 'selection_components': Hold state wether the strokes are selected
     * 'chrono_components': Hold state about the time, chronological ordering
     * 'render_components': Hold state about the current rendering of the strokes.
+    * 'layer_components': Hold state on which layer the strokes are. The layers themselves are held in 'layers', which also defines their ordering.
+    * 'zorder_components': Hold the z-order of the strokes, which defines the order of rendering within a layer.
+    * 'group_components': Hold the innermost group the strokes are part of. The groups themselves are held in 'groups', which also defines their nesting.
 
 The systems are implemented as methods on StrokesState, loosely categorized to the different components (but often modify others as well).
+Systems which modify the strokes or their trashed state record the change in the 'history', which is used for undo and redo.
 Most systems take a key or a slice of keys, and iterate with them over the different components.
-There also is a different category of methods which return filtered keys, e.g. `.keys_sorted_chrono` returns the keys in chronological ordering,
+There also is a different category of methods which return filtered keys, e.g. `.keys_sorted_chrono` returns the keys in chronological ordering, `.keys_sorted_z` in z-order,
     `.stoke_keys_in_order_rendering` returns keys in the order which they should be rendered.
 */
 
@@ -87,11 +105,37 @@ pub struct StrokesState {
     chrono_components: SecondaryMap<StrokeKey, ChronoComponent>,
     #[serde(rename = "render_components")]
     render_components: SecondaryMap<StrokeKey, RenderComponent>,
+    #[serde(rename = "layer_components")]
+    layer_components: SecondaryMap<StrokeKey, LayerComponent>,
+    #[serde(rename = "zorder_components")]
+    zorder_components: SecondaryMap<StrokeKey, ZOrderComponent>,
+    #[serde(rename = "group_components")]
+    group_components: SecondaryMap<StrokeKey, GroupComponent>,
 
     // Other state
     /// value is equal chrono_component of the newest inserted or modified stroke.
     #[serde(rename = "chrono_counter")]
     chrono_counter: u32,
+    /// value is equal the highest z of all zorder_components.
+    #[serde(rename = "z_counter")]
+    z_counter: u32,
+    /// The layers, ordered from bottom to top
+    #[serde(rename = "layers")]
+    layers: Vec<Layer>,
+    /// The layer new strokes are inserted into
+    #[serde(rename = "current_layer")]
+    current_layer: u32,
+    /// The groups, by their id
+    #[serde(rename = "groups")]
+    groups: HashMap<u32, Group>,
+    #[serde(skip)]
+    history: History,
+    /// The spatial index of the strokes. Must be kept in sync when strokes are inserted, removed or their bounds change
+    #[serde(skip)]
+    key_tree: KeyTree,
+    /// The keys of the strokes which currently hold a rendering, so that clearing the renderings does not need to visit every stroke
+    #[serde(skip)]
+    rendered_keys: HashSet<StrokeKey>,
 
     #[serde(skip)]
     pub tasks_tx: futures::channel::mpsc::UnboundedSender<StateTask>,
@@ -114,8 +158,18 @@ impl Default for StrokesState {
             selection_components: SecondaryMap::new(),
             chrono_components: SecondaryMap::new(),
             render_components: SecondaryMap::new(),
+            layer_components: SecondaryMap::new(),
+            zorder_components: SecondaryMap::new(),
+            group_components: SecondaryMap::new(),
 
             chrono_counter: 0,
+            z_counter: 0,
+            layers: layer_comp::default_layers(),
+            current_layer: Layer::ID_DEFAULT,
+            groups: HashMap::new(),
+            history: History::default(),
+            key_tree: KeyTree::default(),
+            rendered_keys: HashSet::new(),
 
             tasks_tx,
             tasks_rx: Some(tasks_rx),
@@ -125,6 +179,9 @@ impl Default for StrokesState {
 }
 
 impl StrokesState {
+    /// The tolerance of the size of resized strokes
+    const RESIZE_TOLERANCE: f64 = 1e-6;
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -136,7 +193,21 @@ impl StrokesState {
         self.selection_components = strokes_state.selection_components;
         self.chrono_components = strokes_state.chrono_components;
         self.render_components = strokes_state.render_components;
+        self.rendered_keys = strokes_state.rendered_keys;
+        self.layer_components = strokes_state.layer_components;
+        self.zorder_components = strokes_state.zorder_components;
+        self.group_components = strokes_state.group_components;
         self.chrono_counter = strokes_state.chrono_counter;
+        self.z_counter = strokes_state.z_counter;
+        self.layers = strokes_state.layers;
+        self.current_layer = strokes_state.current_layer;
+        self.groups = strokes_state.groups;
+
+        self.validate_layers();
+        self.validate_z_order();
+        self.validate_groups();
+        self.rebuild_key_tree();
+        self.history.clear();
     }
 
     /// processes the received task from tasks_rx.
@@ -210,6 +281,15 @@ impl StrokesState {
                     surface_flags.sheet_changed = true;
                     surface_flags.selection_changed = true;
                 }
+                StrokeStyle::TextStroke(textstroke) => {
+                    let inserted = self.insert_stroke(StrokeStyle::TextStroke(textstroke));
+
+                    self.regenerate_rendering_for_stroke_threaded(inserted, renderer, zoom);
+
+                    surface_flags.redraw = true;
+                    surface_flags.resize = true;
+                    surface_flags.sheet_changed = true;
+                }
             },
             StateTask::Quit => {
                 surface_flags.quit = true;
@@ -220,8 +300,11 @@ impl StrokesState {
     }
 
     pub fn insert_stroke(&mut self, stroke: StrokeStyle) -> StrokeKey {
+        let bounds = stroke.bounds();
         let key = self.strokes.insert(stroke);
+        self.key_tree.insert_with_bounds(key, bounds);
         self.chrono_counter += 1;
+        self.z_counter += 1;
 
         let mut render_comp = RenderComponent::default();
         // set flag for rendering regeneration
@@ -233,6 +316,15 @@ impl StrokesState {
         self.render_components.insert(key, render_comp);
         self.chrono_components
             .insert(key, ChronoComponent::new(self.chrono_counter));
+        self.layer_components
+            .insert(key, LayerComponent::new(self.current_layer));
+        self.zorder_components
+            .insert(key, ZOrderComponent::new(self.z_counter));
+        self.group_components.insert(key, GroupComponent::default());
+
+        self.history.record(HistoryEntry::TrashState {
+            states: vec![(key, true)],
+        });
 
         key
     }
@@ -242,6 +334,11 @@ impl StrokesState {
         self.selection_components.remove(key);
         self.chrono_components.remove(key);
         self.render_components.remove(key);
+        self.rendered_keys.remove(&key);
+        self.layer_components.remove(key);
+        self.zorder_components.remove(key);
+        self.group_components.remove(key);
+        self.key_tree.remove(key);
 
         self.strokes.remove(key)
     }
@@ -256,6 +353,7 @@ impl StrokesState {
         if let Some(StrokeStyle::BrushStroke(ref mut brushstroke)) = self.strokes.get_mut(key) {
             brushstroke.push_elem(element);
         }
+        self.update_key_tree_for_stroke(key);
 
         self.append_rendering_new_elem_threaded(key, renderer, zoom);
     }
@@ -271,52 +369,454 @@ impl StrokesState {
         if let Some(StrokeStyle::ShapeStroke(ref mut shapestroke)) = self.strokes.get_mut(key) {
             shapestroke.update_shape(shaper, element);
         }
+        self.update_key_tree_for_stroke(key);
 
         self.append_rendering_new_elem_threaded(key, renderer, zoom);
     }
 
+    pub fn shape_stroke(&self, key: StrokeKey) -> Option<&ShapeStroke> {
+        if let Some(StrokeStyle::ShapeStroke(shapestroke)) = self.strokes.get(key) {
+            Some(shapestroke)
+        } else {
+            None
+        }
+    }
+
+    /// Modifies the shape stroke with the given function and regenerates its rendering.
+    /// The function returns None if it did not change the stroke, then nothing is recorded.
+    /// Returns None if the stroke is not a shape stroke or was not changed
+    pub fn modify_shape_stroke<F, R>(
+        &mut self,
+        key: StrokeKey,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        modify: F,
+    ) -> Option<R>
+    where
+        F: FnOnce(&mut ShapeStroke) -> Option<R>,
+    {
+        let mut shapestroke = self.shape_stroke(key)?.clone();
+        let result = modify(&mut shapestroke)?;
+
+        self.record_strokes(&[key]);
+        if let Some(stroke) = self.strokes.get_mut(key) {
+            *stroke = StrokeStyle::ShapeStroke(shapestroke);
+        }
+        self.update_geometry_for_stroke(key);
+
+        self.regenerate_rendering_for_stroke_threaded(key, renderer, zoom);
+
+        Some(result)
+    }
+
+    pub fn brush_stroke(&self, key: StrokeKey) -> Option<&BrushStroke> {
+        if let Some(StrokeStyle::BrushStroke(brushstroke)) = self.strokes.get(key) {
+            Some(brushstroke)
+        } else {
+            None
+        }
+    }
+
+    /// Modifies the brush stroke with the given function and regenerates its rendering.
+    /// The function returns None if it did not change the stroke, then nothing is recorded.
+    /// Returns None if the stroke is not a brush stroke or was not changed
+    pub fn modify_brush_stroke<F, R>(
+        &mut self,
+        key: StrokeKey,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        modify: F,
+    ) -> Option<R>
+    where
+        F: FnOnce(&mut BrushStroke) -> Option<R>,
+    {
+        let mut brushstroke = self.brush_stroke(key)?.clone();
+        let result = modify(&mut brushstroke)?;
+
+        self.record_strokes(&[key]);
+        if let Some(stroke) = self.strokes.get_mut(key) {
+            *stroke = StrokeStyle::BrushStroke(brushstroke);
+        }
+        self.update_geometry_for_stroke(key);
+
+        self.regenerate_rendering_for_stroke_threaded(key, renderer, zoom);
+
+        Some(result)
+    }
+
+    /// Splits the brush stroke at the node with the given index. The split off part is inserted as a new stroke,
+    /// which keeps the layer, z-order and group of the original stroke. Returns the key of the new stroke
+    pub fn split_brush_stroke(
+        &mut self,
+        key: StrokeKey,
+        node: usize,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> Option<StrokeKey> {
+        let mut brushstroke = self.brush_stroke(key)?.clone();
+        let split_off = brushstroke.split_at_node(node)?;
+
+        self.history.begin_group();
+        self.record_strokes(&[key]);
+        if let Some(stroke) = self.strokes.get_mut(key) {
+            *stroke = StrokeStyle::BrushStroke(brushstroke);
+        }
+        self.update_geometry_for_stroke(key);
+
+        let layer_comp = self.layer_components.get(key).copied();
+        let zorder_comp = self.zorder_components.get(key).copied();
+        let group_comp = self.group_components.get(key).copied();
+
+        let new_key = self.insert_stroke(StrokeStyle::BrushStroke(split_off));
+        if let Some(layer_comp) = layer_comp {
+            self.layer_components.insert(new_key, layer_comp);
+        }
+        if let Some(zorder_comp) = zorder_comp {
+            self.zorder_components.insert(new_key, zorder_comp);
+        }
+        if let Some(group_comp) = group_comp {
+            self.group_components.insert(new_key, group_comp);
+        }
+        self.history.end_group();
+
+        self.regenerate_rendering_for_stroke_threaded(key, Arc::clone(&renderer), zoom);
+        self.regenerate_rendering_for_stroke_threaded(new_key, renderer, zoom);
+
+        Some(new_key)
+    }
+
+    /// Joins the brush strokes in the given order into the first one, connecting their nearest ends. The other strokes are trashed.
+    /// Returns false if there are not at least two brush strokes
+    pub fn join_brush_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let keys = keys
+            .iter()
+            .copied()
+            .filter(|&key| self.brush_stroke(key).is_some())
+            .collect::<Vec<StrokeKey>>();
+        let (first, others) = match keys.split_first() {
+            Some((&first, others)) if !others.is_empty() => (first, others),
+            _ => return false,
+        };
+
+        let mut joined = self.brush_stroke(first).unwrap().clone();
+        others.iter().for_each(|&key| {
+            if let Some(brushstroke) = self.brush_stroke(key) {
+                joined.join(brushstroke);
+            }
+        });
+
+        self.history.begin_group();
+        self.record_strokes(&[first]);
+        if let Some(stroke) = self.strokes.get_mut(first) {
+            *stroke = StrokeStyle::BrushStroke(joined);
+        }
+        self.update_geometry_for_stroke(first);
+
+        self.record_trash_state(others);
+        others.iter().for_each(|&key| {
+            if let Some(trash_comp) = self.trash_components.get_mut(key) {
+                trash_comp.trashed = true;
+            }
+            if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                selection_comp.selected = false;
+            }
+        });
+        self.history.end_group();
+
+        self.regenerate_rendering_for_stroke_threaded(first, renderer, zoom);
+
+        true
+    }
+
+    pub fn text_stroke(&self, key: StrokeKey) -> Option<&TextStroke> {
+        if let Some(StrokeStyle::TextStroke(textstroke)) = self.strokes.get(key) {
+            Some(textstroke)
+        } else {
+            None
+        }
+    }
+
+    /// The topmost text stroke at the position, which is not trashed and on an editable layer
+    pub fn text_stroke_key_at_pos(&self, pos: na::Vector2<f64>) -> Option<StrokeKey> {
+        let pos_bounds = AABB::new(na::Point2::from(pos), na::Point2::from(pos));
+        let uneditable_layers = self.uneditable_layers();
+
+        self.keys_intersecting_bounds(pos_bounds)
+            .into_iter()
+            .rev()
+            .find(|&key| {
+                !uneditable_layers.contains(&self.layer_of(key))
+                    && self.text_stroke(key).map_or(false, |textstroke| {
+                        textstroke
+                            .bounds
+                            .contains_local_point(&na::Point2::from(pos))
+                    })
+            })
+    }
+
+    /// Modifies the text stroke with the given function and regenerates its rendering. Returns None if the stroke is not a text stroke.
+    /// Consecutive modifications with the same merge id are merged, e.g. a run of typed characters
+    pub fn modify_text_stroke<F, R>(
+        &mut self,
+        key: StrokeKey,
+        merge_id: String,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        modify: F,
+    ) -> Option<R>
+    where
+        F: FnOnce(&mut TextStroke) -> R,
+    {
+        let entry = HistoryEntry::Strokes {
+            strokes: vec![(key, StrokeStyle::TextStroke(self.text_stroke(key)?.clone()))],
+        };
+        self.history.record_merged(entry, merge_id);
+
+        let result =
+            if let Some(StrokeStyle::TextStroke(ref mut textstroke)) = self.strokes.get_mut(key) {
+                modify(textstroke)
+            } else {
+                return None;
+            };
+        self.update_geometry_for_stroke(key);
+
+        self.regenerate_rendering_for_stroke_threaded(key, renderer, zoom);
+
+        Some(result)
+    }
+
+    /// Modifies the strokes with the given function, which returns wether it changed the stroke.
+    /// The changed strokes are recorded and their rendering is regenerated. Returns true if any stroke was changed
+    pub fn modify_strokes<F>(
+        &mut self,
+        keys: &[StrokeKey],
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        modify: F,
+    ) -> bool
+    where
+        F: FnMut(&mut StrokeStyle) -> bool,
+    {
+        self.modify_strokes_w_merge_id(keys, None, renderer, zoom, modify)
+    }
+
+    /// Modifies the strokes like `modify_strokes()`, but consecutive modifications with the same merge id of the same strokes are merged.
+    /// Used for changes in many small steps, e.g. while dragging a slider
+    pub fn modify_strokes_merged<F>(
+        &mut self,
+        keys: &[StrokeKey],
+        merge_id: &str,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        modify: F,
+    ) -> bool
+    where
+        F: FnMut(&mut StrokeStyle) -> bool,
+    {
+        self.modify_strokes_w_merge_id(keys, Some(merge_id), renderer, zoom, modify)
+    }
+
+    fn modify_strokes_w_merge_id<F>(
+        &mut self,
+        keys: &[StrokeKey],
+        merge_id: Option<&str>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+        mut modify: F,
+    ) -> bool
+    where
+        F: FnMut(&mut StrokeStyle) -> bool,
+    {
+        let mut replaced = vec![];
+
+        keys.iter().for_each(|&key| {
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                let original = stroke.clone();
+
+                if modify(stroke) {
+                    replaced.push((key, original));
+                }
+            }
+        });
+
+        if replaced.is_empty() {
+            return false;
+        }
+
+        let modified_keys = replaced.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        let entry = HistoryEntry::Strokes { strokes: replaced };
+        match merge_id {
+            Some(merge_id) => self.history.record_merged(entry, merge_id.to_string()),
+            None => self.history.record(entry),
+        }
+
+        modified_keys.iter().for_each(|&key| {
+            self.update_geometry_for_stroke(key);
+        });
+        self.regenerate_rendering_for_strokes_threaded(&modified_keys, renderer, zoom);
+
+        true
+    }
+
+    /// Replaces the brush strokes for which the recognizer finds a shape with the recognized shape strokes.
+    /// Returns the keys of the replaced strokes together with the keys of the shape strokes that took their place
+    pub fn beautify_strokes(
+        &mut self,
+        keys: &[StrokeKey],
+        recognizer: &ShapeRecognizer,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> Vec<(StrokeKey, StrokeKey)> {
+        let recognized = keys
+            .iter()
+            .filter_map(|&key| match self.strokes.get(key) {
+                Some(StrokeStyle::BrushStroke(brushstroke))
+                    if !self.trashed(key).unwrap_or(true) =>
+                {
+                    brushstroke
+                        .recognize_shape(recognizer)
+                        .map(|shapestroke| (key, shapestroke))
+                }
+                _ => None,
+            })
+            .collect::<Vec<(StrokeKey, ShapeStroke)>>();
+
+        if recognized.is_empty() {
+            return vec![];
+        }
+
+        self.history.begin_group();
+
+        let replaced_keys = recognized.iter().map(|(key, _)| *key).collect::<Vec<_>>();
+        self.record_trash_state(&replaced_keys);
+
+        // The shape strokes take the place of the brush strokes, so they keep their layer, z-order and group
+        let replacements = recognized
+            .into_iter()
+            .map(|(original_key, shapestroke)| {
+                if let Some(trash_comp) = self.trash_components.get_mut(original_key) {
+                    trash_comp.trashed = true;
+
+                    if let Some(chrono_comp) = self.chrono_components.get_mut(original_key) {
+                        self.chrono_counter += 1;
+                        chrono_comp.t = self.chrono_counter;
+                    }
+                }
+
+                let layer_comp = self.layer_components.get(original_key).copied();
+                let zorder_comp = self.zorder_components.get(original_key).copied();
+                let group_comp = self.group_components.get(original_key).copied();
+
+                let key = self.insert_stroke(StrokeStyle::ShapeStroke(shapestroke));
+
+                if let Some(layer_comp) = layer_comp {
+                    self.layer_components.insert(key, layer_comp);
+                }
+                if let Some(zorder_comp) = zorder_comp {
+                    self.zorder_components.insert(key, zorder_comp);
+                }
+                if let Some(group_comp) = group_comp {
+                    self.group_components.insert(key, group_comp);
+                }
+
+                self.regenerate_rendering_for_stroke_threaded(key, Arc::clone(&renderer), zoom);
+
+                (original_key, key)
+            })
+            .collect();
+
+        self.history.end_group();
+
+        replacements
+    }
+
     /// Clears every stroke and every component
     pub fn clear(&mut self) {
         self.chrono_counter = 0;
+        self.z_counter = 0;
 
         self.strokes.clear();
         self.trash_components.clear();
         self.selection_components.clear();
         self.chrono_components.clear();
         self.render_components.clear();
+        self.rendered_keys.clear();
+        self.layer_components.clear();
+        self.zorder_components.clear();
+        self.group_components.clear();
+        self.key_tree.clear();
+
+        self.layers = layer_comp::default_layers();
+        self.current_layer = Layer::ID_DEFAULT;
+        self.groups.clear();
+
+        self.history.clear();
     }
 
     /// Returns the stroke keys in the order that they should be rendered. Does not return the selection keys!
     pub fn keys_as_rendered(&self) -> Vec<StrokeKey> {
-        let keys_sorted_chrono = self.keys_sorted_chrono();
+        let keys_sorted_z = self.keys_sorted_z();
 
-        keys_sorted_chrono
-            .iter()
-            .filter_map(|&key| {
-                if self.does_render(key).unwrap_or(false)
-                    && !(self.trashed(key).unwrap_or(false))
-                    && !(self.selected(key).unwrap_or(false))
-                {
-                    Some(key)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<StrokeKey>>()
+        let mut keys = keys_sorted_z
+            .into_iter()
+            .filter(|&key| self.is_rendered_as_stroke(key))
+            .collect::<Vec<StrokeKey>>();
+
+        self.sort_keys_by_layer(&mut keys);
+        keys
     }
 
+    /// Returns the stroke keys intersecting the bounds in the order that they should be rendered. Does not return the selection keys!
     pub fn keys_intersecting_bounds(&self, bounds: AABB) -> Vec<StrokeKey> {
-        self.keys_as_rendered()
-            .iter()
-            .filter_map(|&key| {
-                let stroke = self.strokes.get(key)?;
-                if stroke.bounds().intersects(&bounds) {
-                    Some(key)
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<StrokeKey>>()
+        let mut keys = self
+            .key_tree
+            .keys_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| self.is_rendered_as_stroke(key))
+            .collect::<Vec<StrokeKey>>();
+
+        self.sort_keys_z(&mut keys);
+        self.sort_keys_by_layer(&mut keys);
+        keys
+    }
+
+    /// Returns the keys of all not trashed strokes intersecting the bounds, in the order of the layers and the z order.
+    /// Unlike `keys_intersecting_bounds()` this includes the strokes of hidden layers and the selection, e.g. for exporting all layers
+    pub fn keys_intersecting_bounds_all_layers(&self, bounds: AABB) -> Vec<StrokeKey> {
+        let mut keys = self
+            .key_tree
+            .keys_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .collect::<Vec<StrokeKey>>();
+
+        self.sort_keys_z(&mut keys);
+        self.sort_keys_by_layer(&mut keys);
+        keys
+    }
+
+    /// Wether the stroke is rendered and is not part of the selection
+    fn is_rendered_as_stroke(&self, key: StrokeKey) -> bool {
+        self.does_render(key).unwrap_or(false)
+            && !(self.trashed(key).unwrap_or(false))
+            && !(self.selected(key).unwrap_or(false))
+            && self.layer_visible(key)
+    }
+
+    /// Wether the stroke is a brush stroke with the highlighter style
+    fn is_highlighter(&self, key: StrokeKey) -> bool {
+        matches!(
+            self.strokes.get(key),
+            Some(StrokeStyle::BrushStroke(BrushStroke {
+                style: BrushStrokeStyle::Highlighter { .. },
+                ..
+            }))
+        )
     }
 
     pub fn clone_strokes_for_keys(&self, keys: &[StrokeKey]) -> Vec<StrokeStyle> {
@@ -439,12 +939,26 @@ impl StrokesState {
     pub fn import_state(&mut self, strokes_state: &Self) {
         self.clear();
         self.chrono_counter = strokes_state.chrono_counter;
+        self.z_counter = strokes_state.z_counter;
 
         self.strokes = strokes_state.strokes.clone();
         self.trash_components = strokes_state.trash_components.clone();
         self.selection_components = strokes_state.selection_components.clone();
         self.chrono_components = strokes_state.chrono_components.clone();
         self.render_components = strokes_state.render_components.clone();
+        self.rendered_keys = strokes_state.rendered_keys.clone();
+        self.layer_components = strokes_state.layer_components.clone();
+        self.zorder_components = strokes_state.zorder_components.clone();
+        self.group_components = strokes_state.group_components.clone();
+        self.layers = strokes_state.layers.clone();
+        self.current_layer = strokes_state.current_layer;
+        self.groups = strokes_state.groups.clone();
+
+        self.validate_layers();
+        self.validate_z_order();
+        self.validate_groups();
+        self.rebuild_key_tree();
+        self.history.clear();
     }
 
     pub fn update_geometry_for_stroke(&mut self, key: StrokeKey) {
@@ -462,12 +976,17 @@ impl StrokesState {
                 StrokeStyle::BitmapImage(ref mut bitmapimage) => {
                     bitmapimage.update_geometry();
                 }
+                StrokeStyle::TextStroke(ref mut textstroke) => {
+                    textstroke.update_geometry();
+                }
             }
 
             // set flag for rendering regeneration
             if let Some(render_comp) = self.render_components.get_mut(key) {
                 render_comp.regenerate_flag = true;
             }
+
+            self.update_key_tree_for_stroke(key);
         } else {
             log::debug!(
                 "get stroke in update_stroke_geometry() returned None in complete_stroke() for key {:?}",
@@ -567,17 +1086,14 @@ impl StrokesState {
     }
 
     pub fn gen_svgs_for_bounds(&self, bounds: AABB) -> Vec<render::Svg> {
-        let keys = self.keys_as_rendered();
+        let keys = self.keys_intersecting_bounds(bounds);
 
         keys.iter()
             .filter_map(|&key| {
                 let stroke = self.strokes.get(key)?;
-                if !stroke.bounds().intersects(&bounds) {
-                    return None;
-                }
 
                 match stroke.gen_svgs(na::vector![0.0, 0.0]) {
-                    Ok(svgs) => Some(svgs),
+                    Ok(svgs) => Some(self.apply_layer_opacity_to_svgs(key, svgs)),
                     Err(e) => {
                         log::error!(
                             "stroke.gen_svgs() failed in gen_svg_for_bounds() with Err {}",
@@ -600,7 +1116,7 @@ impl StrokesState {
                 let stroke = self.strokes.get(key)?;
 
                 match stroke.gen_svgs(na::vector![0.0, 0.0]) {
-                    Ok(svgs) => Some(svgs),
+                    Ok(svgs) => Some(self.apply_layer_opacity_to_svgs(key, svgs)),
                     Err(e) => {
                         log::error!(
                             "stroke.gen_svgs() failed in gen_svg_all_strokes() with Err {}",
@@ -621,6 +1137,8 @@ impl StrokesState {
         offset: na::Vector2<f64>,
         zoom: f64,
     ) {
+        self.record_strokes(strokes);
+
         strokes.iter().for_each(|&key| {
             if let Some(stroke) = self.strokes.get_mut(key) {
                 stroke.translate(offset);
@@ -633,6 +1151,7 @@ impl StrokesState {
                     match render::images_to_rendernode(&render_comp.images, zoom) {
                         Ok(Some(rendernode)) => {
                             render_comp.rendernode = Some(rendernode);
+                            self.rendered_keys.insert(key);
                         }
                         Ok(None) => {}
                         Err(e) => log::error!(
@@ -643,6 +1162,8 @@ impl StrokesState {
                 }
             }
         });
+
+        self.update_key_tree_for_strokes(strokes);
     }
 
     /// Rotates the stroke with angle (rad) around the center
@@ -654,6 +1175,8 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        self.record_strokes(strokes);
+
         strokes.iter().for_each(|&key| {
             if let Some(stroke) = self.strokes.get_mut(key) {
                 stroke.rotate(angle, center);
@@ -661,6 +1184,52 @@ impl StrokesState {
                 self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
             }
         });
+
+        self.update_key_tree_for_strokes(strokes);
+    }
+
+    /// Mirrors the strokes along the axis going through the center
+    pub fn mirror_strokes(
+        &mut self,
+        strokes: &[StrokeKey],
+        axis: MirrorAxis,
+        center: na::Point2<f64>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) {
+        self.record_strokes(strokes);
+
+        strokes.iter().for_each(|&key| {
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                stroke.mirror(axis, center);
+
+                self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
+            }
+        });
+
+        self.update_key_tree_for_strokes(strokes);
+    }
+
+    /// Shears the strokes relative to the center
+    pub fn shear_strokes(
+        &mut self,
+        strokes: &[StrokeKey],
+        shear: na::Vector2<f64>,
+        center: na::Point2<f64>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) {
+        self.record_strokes(strokes);
+
+        strokes.iter().for_each(|&key| {
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                stroke.shear(shear, center);
+
+                self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
+            }
+        });
+
+        self.update_key_tree_for_strokes(strokes);
     }
 
     // Resizes the strokes to new bounds
@@ -672,6 +1241,8 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
+        self.record_strokes(strokes);
+
         strokes.iter().for_each(|&key| {
             if let Some(stroke) = self.strokes.get_mut(key) {
                 let old_stroke_bounds = stroke.bounds();
@@ -692,6 +1263,163 @@ impl StrokesState {
                 self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
             }
         });
+
+        self.update_key_tree_for_strokes(strokes);
+    }
+
+    /// Moves the strokes, so that the upper left corner of their bounds is at the position. Returns true if the strokes were moved
+    pub fn move_strokes_to(
+        &mut self,
+        strokes: &[StrokeKey],
+        pos: na::Vector2<f64>,
+        zoom: f64,
+    ) -> bool {
+        let offset = match self.gen_bounds(strokes) {
+            Some(bounds) => pos - bounds.mins.coords,
+            None => return false,
+        };
+        if offset == na::Vector2::zeros() {
+            return false;
+        }
+
+        self.translate_strokes(strokes, offset, zoom);
+        true
+    }
+
+    /// Resizes the strokes, so that their bounds have the size. The upper left corner of the bounds stays in place.
+    /// With a locked aspectratio the strokes are resized to fit into the size. Returns true if the strokes were resized
+    pub fn resize_strokes_to(
+        &mut self,
+        strokes: &[StrokeKey],
+        size: na::Vector2<f64>,
+        lock_aspectratio: bool,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let start_bounds = match self.gen_bounds(strokes) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        let size = if lock_aspectratio {
+            geometry::scale_w_locked_aspectratio(start_bounds.extents(), size)
+        } else {
+            size
+        };
+        if !size.iter().all(|&extent| extent > 0.0)
+            || (size - start_bounds.extents()).abs().max() < Self::RESIZE_TOLERANCE
+        {
+            return false;
+        }
+
+        // The geometry of the strokes scales, but the margins of their bounds (e.g. their widths) don't
+        let geometries_w_margins = strokes
+            .iter()
+            .filter_map(|&key| {
+                let stroke = self.strokes.get(key)?;
+                let bounds = stroke.bounds();
+                let margin = stroke.bounds_margin().min(bounds.half_extents().min());
+
+                Some((bounds.loosened(-margin), margin))
+            })
+            .collect::<Vec<(AABB, f64)>>();
+
+        let mut scale = na::Vector2::<f64>::zeros();
+        for dim in 0..2 {
+            // The geometries relative to the upper left corner, which stays in place
+            let geometries_w_margins = geometries_w_margins
+                .iter()
+                .map(|(geometry, margin)| {
+                    (
+                        geometry.mins[dim] - start_bounds.mins[dim],
+                        geometry.maxs[dim] - start_bounds.mins[dim],
+                        *margin,
+                    )
+                })
+                .collect::<Vec<(f64, f64, f64)>>();
+
+            scale[dim] = match Self::solve_resize_scale(&geometries_w_margins, size[dim]) {
+                Some(scale) => scale,
+                None => return false,
+            };
+        }
+
+        self.history.begin_group();
+        self.record_strokes(strokes);
+
+        strokes.iter().for_each(|&key| {
+            if let Some(stroke) = self.strokes.get_mut(key) {
+                let center = stroke.bounds().center();
+                let new_center =
+                    start_bounds.mins + (center - start_bounds.mins).component_mul(&scale);
+
+                stroke.translate(new_center - center);
+                stroke.scale(scale);
+
+                self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
+            }
+        });
+        self.update_key_tree_for_strokes(strokes);
+
+        // The margins don't scale, so the upper left corner of the bounds has moved by them
+        self.move_strokes_to(strokes, start_bounds.mins.coords, zoom);
+
+        self.history.end_group();
+        true
+    }
+
+    /// Solves for the scale of the geometries along a dimension, so that their bounds including the margins get the extent.
+    /// The geometries are given by their start and end relative to the anchor the scale is applied from, and the margin around them.
+    ///
+    /// The extent of the bounds is the largest scaled end plus its margin minus the smallest scaled start minus its margin,
+    /// which grows piecewise linear and convex with the scale. Newton's method therefore lands on the solution exactly,
+    /// after at most as many steps as there are pieces.
+    fn solve_resize_scale(geometries_w_margins: &[(f64, f64, f64)], extent: f64) -> Option<f64> {
+        // The extent of the bounds at the scale, and its slope
+        let extent_w_slope = |scale: f64| {
+            let (end, end_slope) = geometries_w_margins.iter().fold(
+                (f64::NEG_INFINITY, 0.0),
+                |acc, &(_, end, margin)| {
+                    let scaled_end = scale * end + margin;
+                    if scaled_end > acc.0 {
+                        (scaled_end, end)
+                    } else {
+                        acc
+                    }
+                },
+            );
+            let (start, start_slope) = geometries_w_margins.iter().fold(
+                (f64::INFINITY, 0.0),
+                |acc, &(start, _, margin)| {
+                    let scaled_start = scale * start - margin;
+                    if scaled_start < acc.0 {
+                        (scaled_start, start)
+                    } else {
+                        acc
+                    }
+                },
+            );
+
+            (end - start, end_slope - start_slope)
+        };
+
+        let mut scale = 1.0;
+        for _ in 0..=2 * geometries_w_margins.len() {
+            let (current_extent, slope) = extent_w_slope(scale);
+
+            if (current_extent - extent).abs() < Self::RESIZE_TOLERANCE {
+                return Some(scale);
+            }
+            // The geometries have no extent, or the margins alone are larger than the extent
+            if slope <= 0.0 {
+                return None;
+            }
+            scale -= (current_extent - extent) / slope;
+            if !scale.is_finite() || scale <= 0.0 {
+                return None;
+            }
+        }
+
+        Some(scale)
     }
 
     /// Returns all strokes below the y_pos
@@ -729,41 +1457,453 @@ impl StrokesState {
             ],
         );
 
-        self.strokes
-            .iter_mut()
-            .par_bridge()
-            .filter_map(|(key, stroke)| match stroke {
-                StrokeStyle::BrushStroke(brushstroke) => {
-                    if brushstroke.bounds().intersects(&tool_bounds) {
-                        brushstroke.elements.iter_mut().for_each(|element| {
-                            if sphere
-                                .contains_local_point(&na::Point2::from(element.inputdata.pos()))
-                            {
-                                // Zero when right at drag_proximity_tool position, One when right at the radius
-                                let distance_ratio = (1.0
-                                    - (element.inputdata.pos() - drag_proximity_tool.pos)
-                                        .magnitude()
-                                        / drag_proximity_tool.radius)
-                                    .clamp(0.0, 1.0);
-
-                                element.inputdata.set_pos(
-                                    element.inputdata.pos()
-                                        + drag_proximity_tool.offset * distance_ratio,
-                                );
-                            }
-                        });
-                        Some(key)
-                    } else {
-                        None
+        let affected_keys = self
+            .key_tree
+            .keys_intersecting_bounds(tool_bounds)
+            .into_iter()
+            .filter(|&key| matches!(self.strokes.get(key), Some(StrokeStyle::BrushStroke(_))))
+            .collect::<Vec<StrokeKey>>();
+        self.record_strokes(&affected_keys);
+
+        affected_keys.iter().for_each(|&key| {
+            if let Some(StrokeStyle::BrushStroke(brushstroke)) = self.strokes.get_mut(key) {
+                brushstroke.elements.iter_mut().for_each(|element| {
+                    if sphere.contains_local_point(&na::Point2::from(element.inputdata.pos())) {
+                        // Zero when right at drag_proximity_tool position, One when right at the radius
+                        let distance_ratio = (1.0
+                            - (element.inputdata.pos() - drag_proximity_tool.pos).magnitude()
+                                / drag_proximity_tool.radius)
+                            .clamp(0.0, 1.0);
+
+                        element.inputdata.set_pos(
+                            element.inputdata.pos() + drag_proximity_tool.offset * distance_ratio,
+                        );
                     }
+                });
+            }
+
+            self.update_geometry_for_stroke(key);
+            self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::color::Color;
+    use crate::compose::shapes;
+    use crate::compose::smooth::SmoothOptions;
+    use crate::compose::transformable::Transform;
+    use crate::pens::selector::{Selector, SelectorStyle};
+    use crate::strokes::inputdata::InputData;
+    use crate::strokes::shapestroke::{Shape, ShapeDrawStyle};
+
+    const TOLERANCE: f64 = 1e-6;
+
+    /// A smooth rectangle with the geometry from the mins to the maxs
+    fn rectangle_stroke(mins: na::Vector2<f64>, maxs: na::Vector2<f64>, width: f64) -> StrokeStyle {
+        let mut shapestroke = ShapeStroke {
+            shape: Shape::Rectangle(shapes::Rectangle {
+                cuboid: p2d::shape::Cuboid::new((maxs - mins) * 0.5),
+                transform: Transform::new_w_isometry(na::Isometry2::new((mins + maxs) * 0.5, 0.0)),
+                corner_radius: 0.0,
+            }),
+            drawstyle: ShapeDrawStyle::Smooth {
+                options: SmoothOptions {
+                    width,
+                    ..SmoothOptions::default()
+                },
+            },
+            ..ShapeStroke::default()
+        };
+        shapestroke.update_geometry();
+
+        StrokeStyle::ShapeStroke(shapestroke)
+    }
+
+    fn strokes_state_w_rectangles() -> (StrokesState, Vec<StrokeKey>) {
+        let mut strokes_state = StrokesState::new();
+        let keys = vec![
+            strokes_state.insert_stroke(rectangle_stroke(
+                na::vector![10.0, 20.0],
+                na::vector![110.0, 70.0],
+                2.0,
+            )),
+            strokes_state.insert_stroke(rectangle_stroke(
+                na::vector![50.0, 40.0],
+                na::vector![150.0, 60.0],
+                8.0,
+            )),
+        ];
+        // Inserting is not part of the tested steps
+        strokes_state.history_mut().clear();
+
+        (strokes_state, keys)
+    }
+
+    fn renderer() -> Arc<RwLock<Renderer>> {
+        Arc::new(RwLock::new(Renderer::default()))
+    }
+
+    fn assert_vector_eq(actual: na::Vector2<f64>, expected: na::Vector2<f64>) {
+        assert!(
+            (actual - expected).abs().max() < TOLERANCE,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn selection_to_front_records_z_order() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        strokes_state.set_selected(keys[0], true);
+
+        strokes_state.selection_to_front();
+        assert_eq!(strokes_state.keys_sorted_z(), vec![keys[1], keys[0]]);
+
+        match strokes_state.history_mut().pop_undo() {
+            Some(HistoryEntry::ZOrder { z_orders }) => {
+                strokes_state.swap_z_order(z_orders);
+            }
+            entry => panic!("expected a z-order entry, got {:?}", entry),
+        }
+        assert_eq!(strokes_state.keys_sorted_z(), keys);
+    }
+
+    #[test]
+    fn keys_on_all_layers_include_hidden_layers() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let bounds = AABB::new(na::point![0.0, 0.0], na::point![200.0, 100.0]);
+
+        let top_layer = strokes_state.add_layer(String::from("Top"));
+        strokes_state.set_layer_of(keys[1], top_layer);
+        strokes_state.set_layer_visible(top_layer, false);
+        strokes_state.set_selected(keys[0], true);
+
+        assert_eq!(
+            strokes_state.keys_intersecting_bounds_all_layers(bounds),
+            keys
+        );
+
+        strokes_state.set_trashed(keys[1], true);
+        assert_eq!(
+            strokes_state.keys_intersecting_bounds_all_layers(bounds),
+            vec![keys[0]]
+        );
+    }
+
+    #[test]
+    fn tap_selects_stroke_on_top_layer() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+
+        // The stroke with the lower z order is drawn above the other one on the top layer
+        let top_layer = strokes_state.add_layer(String::from("Top"));
+        strokes_state.set_layer_of(keys[0], top_layer);
+
+        let selector = Selector {
+            style: SelectorStyle::Single,
+            path: vec![InputData::new(na::vector![60.0, 50.0], 1.0)],
+            ..Selector::default()
+        };
+        strokes_state.update_selection_for_selector(&selector, None);
+
+        assert_eq!(strokes_state.selection_keys_as_rendered(), vec![keys[0]]);
+    }
+
+    #[test]
+    fn unchanged_shape_stroke_is_not_recorded() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+
+        // Adding a vertex to a rectangle fails
+        let added = strokes_state.modify_shape_stroke(keys[0], renderer(), 1.0, |shapestroke| {
+            shapestroke.add_vertex(na::vector![0.0, 0.0]).then(|| ())
+        });
+
+        assert!(added.is_none());
+        assert!(!strokes_state.history().can_undo());
+    }
+
+    #[test]
+    fn equal_z_is_sorted_deterministically() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        // Like the pieces of a split stroke
+        let z = strokes_state.zorder_components[keys[0]];
+        strokes_state.zorder_components.insert(keys[1], z);
+
+        let mut forward = keys.clone();
+        let mut reversed = keys.iter().rev().copied().collect::<Vec<StrokeKey>>();
+        strokes_state.sort_keys_z(&mut forward);
+        strokes_state.sort_keys_z(&mut reversed);
+
+        assert_eq!(forward, reversed);
+    }
+
+    /// Undoes the latest step in the history, which must consist of layers entries
+    fn undo_layers(strokes_state: &mut StrokesState) {
+        match strokes_state.history_mut().pop_undo() {
+            Some(HistoryEntry::Layers {
+                layers,
+                current_layer,
+                layer_ids,
+            }) => {
+                strokes_state.swap_layers(layers, current_layer, layer_ids);
+            }
+            entry => panic!("expected a layers entry, got {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn layer_changes_are_recorded() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let bottom_layer = strokes_state.current_layer();
+
+        let top_layer = strokes_state.add_layer(String::from("Top"));
+        strokes_state.set_layer_of(keys[1], top_layer);
+        // Dragging the opacity slider is undone in one step
+        for opacity in [0.8, 0.6, 0.4] {
+            strokes_state.set_layer_opacity(top_layer, opacity);
+        }
+        strokes_state.set_layer_visible(top_layer, false);
+
+        undo_layers(&mut strokes_state);
+        assert!(strokes_state.layer(top_layer).unwrap().visible);
+
+        undo_layers(&mut strokes_state);
+        assert_eq!(strokes_state.layer(top_layer).unwrap().opacity, 1.0);
+
+        undo_layers(&mut strokes_state);
+        assert_eq!(strokes_state.layer_of(keys[1]), bottom_layer);
+
+        undo_layers(&mut strokes_state);
+        assert!(strokes_state.layer(top_layer).is_none());
+    }
+
+    fn shape_width(strokes_state: &StrokesState, key: StrokeKey) -> f64 {
+        match strokes_state.strokes.get(key) {
+            Some(StrokeStyle::ShapeStroke(shapestroke)) => shapestroke.drawstyle.width(),
+            _ => panic!("stroke with key {:?} is not a shape stroke", key),
+        }
+    }
+
+    /// Undoes the latest step in the history, which must be a strokes entry
+    fn undo_strokes(strokes_state: &mut StrokesState) {
+        match strokes_state.history_mut().pop_undo() {
+            Some(HistoryEntry::Strokes { strokes }) => {
+                strokes_state.swap_strokes(strokes);
+            }
+            entry => panic!("expected a strokes entry, got {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn width_changes_of_one_interaction_are_undone_in_one_step() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        keys.iter()
+            .for_each(|&key| strokes_state.set_selected(key, true));
+
+        // Like holding a button of the width spinbutton, then pressing it again
+        for width in [3.0, 4.0, 5.0] {
+            assert!(strokes_state.change_selection_width(width, renderer(), 1.0));
+        }
+        strokes_state.history_mut().end_merge();
+        assert!(strokes_state.change_selection_width(6.0, renderer(), 1.0));
+        assert_eq!(shape_width(&strokes_state, keys[0]), 6.0);
+
+        undo_strokes(&mut strokes_state);
+        assert_eq!(shape_width(&strokes_state, keys[0]), 5.0);
+
+        undo_strokes(&mut strokes_state);
+        assert_eq!(shape_width(&strokes_state, keys[0]), 2.0);
+        assert_eq!(shape_width(&strokes_state, keys[1]), 8.0);
+        assert!(strokes_state.history_mut().pop_undo().is_none());
+    }
+
+    #[test]
+    fn color_changes_are_undone_separately() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        strokes_state.set_selected(keys[0], true);
+        let original = shape_stroke_color(&strokes_state, keys[0]);
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0, 1.0);
+
+        assert!(strokes_state.change_selection_stroke_color(red, renderer(), 1.0));
+        assert!(strokes_state.change_selection_stroke_color(blue, renderer(), 1.0));
+        // Applying the same color again changes nothing and is not recorded
+        assert!(!strokes_state.change_selection_stroke_color(blue, renderer(), 1.0));
+
+        undo_strokes(&mut strokes_state);
+        assert_eq!(shape_stroke_color(&strokes_state, keys[0]), Some(red));
+
+        undo_strokes(&mut strokes_state);
+        assert_eq!(shape_stroke_color(&strokes_state, keys[0]), original);
+        assert!(strokes_state.history_mut().pop_undo().is_none());
+    }
+
+    fn shape_stroke_color(strokes_state: &StrokesState, key: StrokeKey) -> Option<Color> {
+        match strokes_state.strokes.get(key) {
+            Some(StrokeStyle::ShapeStroke(shapestroke)) => shapestroke.drawstyle.stroke_color(),
+            _ => panic!("stroke with key {:?} is not a shape stroke", key),
+        }
+    }
+
+    #[test]
+    fn stroke_color_changes_text_color() {
+        let mut strokes_state = StrokesState::new();
+        let key = strokes_state.insert_stroke(StrokeStyle::TextStroke(TextStroke::new(
+            String::from("a"),
+            na::vector![0.0, 0.0],
+            Default::default(),
+        )));
+        strokes_state.set_selected(key, true);
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+
+        assert!(strokes_state.change_selection_stroke_color(red, renderer(), 1.0));
+        assert_eq!(
+            strokes_state.text_stroke(key).unwrap().text_style.color,
+            red
+        );
+    }
+
+    #[test]
+    fn reapplying_drawstyle_is_not_recorded() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        strokes_state.set_selected(keys[1], true);
+        let shaper = Shaper::default();
+
+        strokes_state.change_selection_shape_drawstyle(&shaper, renderer(), 1.0);
+        strokes_state.history_mut().clear();
+
+        assert!(!strokes_state.change_selection_shape_drawstyle(&shaper, renderer(), 1.0));
+        assert!(!strokes_state.history().can_undo());
+    }
+
+    #[test]
+    fn changing_drawstyle_keeps_width() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        strokes_state.set_selected(keys[1], true);
+
+        let mut shaper = Shaper::default();
+        shaper.drawstyle = crate::pens::shaper::ShaperDrawStyle::Rough;
+        assert!(strokes_state.change_selection_shape_drawstyle(&shaper, renderer(), 1.0));
+
+        match strokes_state.strokes.get(keys[1]) {
+            Some(StrokeStyle::ShapeStroke(shapestroke)) => {
+                assert!(matches!(
+                    shapestroke.drawstyle,
+                    ShapeDrawStyle::Rough { .. }
+                ));
+                assert_eq!(shapestroke.drawstyle.width(), 8.0);
+            }
+            _ => panic!("stroke is not a shape stroke"),
+        }
+    }
+
+    #[test]
+    fn typing_run_is_undone_in_one_step() {
+        let mut strokes_state = StrokesState::new();
+        let key = strokes_state.insert_stroke(StrokeStyle::TextStroke(TextStroke::new(
+            String::from("a"),
+            na::vector![0.0, 0.0],
+            Default::default(),
+        )));
+
+        for (text, merge_id) in [("b", "typing-0"), ("c", "typing-0"), ("d", "typing-1")] {
+            strokes_state.modify_text_stroke(
+                key,
+                String::from(merge_id),
+                renderer(),
+                1.0,
+                |textstroke| textstroke.insert_text_at_cursor(text, textstroke.len_chars()),
+            );
+        }
+        assert_eq!(strokes_state.text_stroke(key).unwrap().text, "abcd");
+
+        for expected in ["abc", "a"] {
+            match strokes_state.history_mut().pop_undo() {
+                Some(HistoryEntry::Strokes { strokes }) => {
+                    strokes_state.swap_strokes(strokes);
                 }
-                _ => None,
-            })
-            .collect::<Vec<StrokeKey>>()
-            .iter()
-            .for_each(|&key| {
-                self.update_geometry_for_stroke(key);
-                self.regenerate_rendering_for_stroke(key, Arc::clone(&renderer), zoom);
-            });
+                entry => panic!("expected a strokes entry, got {:?}", entry),
+            }
+            assert_eq!(strokes_state.text_stroke(key).unwrap().text, expected);
+        }
+    }
+
+    #[test]
+    fn solve_resize_scale_with_margins() {
+        // Only the end of the second geometry is at the bounds once the scale is large enough
+        let geometries_w_margins = [(0.0, 10.0, 1.0), (5.0, 20.0, 0.0)];
+
+        let scale = StrokesState::solve_resize_scale(&geometries_w_margins, 41.0).unwrap();
+        assert!((scale - 2.0).abs() < TOLERANCE, "scale {}", scale);
+
+        // The margins alone are already wider
+        assert!(StrokesState::solve_resize_scale(&geometries_w_margins, 1.5).is_none());
+    }
+
+    #[test]
+    fn resize_strokes_to_exact_size() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let start_bounds = strokes_state.gen_bounds(&keys).unwrap();
+
+        for size in [na::vector![300.0, 120.0], na::vector![40.0, 25.0]] {
+            assert!(strokes_state.resize_strokes_to(&keys, size, false, renderer(), 1.0));
+
+            let bounds = strokes_state.gen_bounds(&keys).unwrap();
+            assert_vector_eq(bounds.mins.coords, start_bounds.mins.coords);
+            assert_vector_eq(bounds.extents(), size);
+        }
+
+        // Resizing to the current size does nothing
+        let size = strokes_state.gen_bounds(&keys).unwrap().extents();
+        assert!(!strokes_state.resize_strokes_to(&keys, size, false, renderer(), 1.0));
+    }
+
+    #[test]
+    fn resize_strokes_to_w_locked_aspectratio() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let start_bounds = strokes_state.gen_bounds(&keys).unwrap();
+        let size = na::vector![1000.0, 100.0];
+
+        assert!(strokes_state.resize_strokes_to(&keys, size, true, renderer(), 1.0));
+
+        let expected_size = geometry::scale_w_locked_aspectratio(start_bounds.extents(), size);
+        let bounds = strokes_state.gen_bounds(&keys).unwrap();
+        assert_vector_eq(bounds.extents(), expected_size);
+    }
+
+    #[test]
+    fn move_strokes_to_exact_position() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let pos = na::vector![-12.5, 300.25];
+
+        assert!(strokes_state.move_strokes_to(&keys, pos, 1.0));
+
+        assert_vector_eq(strokes_state.gen_bounds(&keys).unwrap().mins.coords, pos);
+        assert!(!strokes_state.move_strokes_to(&keys, pos, 1.0));
+    }
+
+    #[test]
+    fn rotate_strokes_by_exact_angle() {
+        let (mut strokes_state, keys) = strokes_state_w_rectangles();
+        let start_bounds = strokes_state.gen_bounds(&keys).unwrap();
+
+        strokes_state.rotate_strokes(
+            &keys,
+            std::f64::consts::FRAC_PI_2,
+            start_bounds.center(),
+            renderer(),
+            1.0,
+        );
+
+        // A quarter turn around the center swaps the extents
+        let bounds = strokes_state.gen_bounds(&keys).unwrap();
+        assert_vector_eq(bounds.center().coords, start_bounds.center().coords);
+        assert_vector_eq(
+            bounds.extents(),
+            na::vector![start_bounds.extents()[1], start_bounds.extents()[0]],
+        );
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::sync::{Arc, RwLock};
 
 use super::StateTask;
@@ -110,6 +111,7 @@ impl StrokesState {
                     match render::images_to_rendernode(&images, zoom) {
                         Ok(Some(rendernode)) => {
                             render_comp.rendernode = Some(rendernode);
+                            self.rendered_keys.insert(key);
                             render_comp.regenerate_flag = false;
                             render_comp.images = images;
                         }
@@ -200,6 +202,39 @@ impl StrokesState {
         })
     }
 
+    /// Clears the rendering of the strokes outside of the viewport and returns the keys of the strokes inside it.
+    /// All keys are returned when there is no viewport
+    fn clear_rendering_outside_viewport(&mut self, viewport: Option<AABB>) -> Vec<StrokeKey> {
+        if let Some(viewport) = viewport {
+            // Loosening the bounds to avoid strokes popping up
+            let keys_in_view = self
+                .key_tree
+                .keys_intersecting_bounds(viewport.expand(viewport.extents()))
+                .into_iter()
+                .collect::<HashSet<StrokeKey>>();
+
+            // Only the strokes which hold a rendering need to be cleared
+            let keys_to_clear = self
+                .rendered_keys
+                .difference(&keys_in_view)
+                .copied()
+                .collect::<Vec<StrokeKey>>();
+
+            for key in keys_to_clear {
+                if let Some(render_comp) = self.render_components.get_mut(key) {
+                    render_comp.rendernode = None;
+                    render_comp.images = vec![];
+                    render_comp.regenerate_flag = true;
+                }
+                self.rendered_keys.remove(&key);
+            }
+
+            keys_in_view.into_iter().collect()
+        } else {
+            self.render_components.keys().collect()
+        }
+    }
+
     pub fn regenerate_rendering_current_view(
         &mut self,
         viewport: Option<AABB>,
@@ -207,7 +242,7 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
-        let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
+        let keys = self.clear_rendering_outside_viewport(viewport);
 
         keys.iter().for_each(|&key| {
             self.update_geometry_for_stroke(key);
@@ -215,17 +250,7 @@ impl StrokesState {
             if let (Some(stroke), Some(render_comp)) =
                 (self.strokes.get(key), self.render_components.get_mut(key))
             {
-                // skip if stroke is not in viewport or does not need regeneration
-                if let Some(viewport) = viewport {
-                    // Loosening the bounds to avoid strokes popping up
-                    if !viewport.expand(viewport.extents()).intersects(&stroke.bounds()) {
-                        render_comp.rendernode = None;
-                        render_comp.images = vec![];
-                        render_comp.regenerate_flag = true;
-
-                        return;
-                    }
-                }
+                // skip if stroke does not need regeneration
                 if !force_regenerate && !render_comp.regenerate_flag {
                     return;
                 }
@@ -235,6 +260,7 @@ impl StrokesState {
                         match render::images_to_rendernode(&images, zoom) {
                             Ok(Some(rendernode)) => {
                                 render_comp.rendernode = Some(rendernode);
+                                self.rendered_keys.insert(key);
                                 render_comp.images = images;
                                 render_comp.regenerate_flag = false;
                             }
@@ -266,23 +292,11 @@ impl StrokesState {
         renderer: Arc<RwLock<Renderer>>,
         zoom: f64,
     ) {
-        let keys = self.render_components.keys().collect::<Vec<StrokeKey>>();
+        let keys = self.clear_rendering_outside_viewport(viewport);
 
         keys.iter().for_each(|&key| {
-            if let (Some(stroke), Some(render_comp)) =
-                (self.strokes.get(key), self.render_components.get_mut(key))
-            {
-                // skip if stroke is not in viewport or does not need regeneration
-                if let Some(viewport) = viewport {
-                    // Loosening the bounds to avoid strokes popping up
-                    if !viewport.expand(viewport.extents()).intersects(&stroke.bounds()) {
-                        render_comp.rendernode = None;
-                        render_comp.images = vec![];
-                        render_comp.regenerate_flag = true;
-
-                        return;
-                    }
-                }
+            if let Some(render_comp) = self.render_components.get(key) {
+                // skip if stroke does not need regeneration
                 if !force_regenerate && !render_comp.regenerate_flag {
                     return;
                 }
@@ -291,7 +305,7 @@ impl StrokesState {
                 self.regenerate_rendering_for_stroke_threaded(key, Arc::clone(&renderer), zoom)
             } else {
                 log::debug!(
-                    "get stroke, render_comp returned None in regenerate_rendering_current_view_threaded() for stroke with key {:?}",
+                    "get render_comp returned None in regenerate_rendering_current_view_threaded() for stroke with key {:?}",
                     key
                 );
             }
@@ -342,6 +356,7 @@ impl StrokesState {
                                     ) {
                                         Ok(rendernode) => {
                                             render_comp.rendernode = rendernode;
+                                            self.rendered_keys.insert(key);
                                             render_comp.images.append(&mut images);
                                             render_comp.regenerate_flag = false;
                                         }
@@ -358,12 +373,14 @@ impl StrokesState {
                 // regenerate everything for strokes that don't support generating svgs for the last added elements
                 StrokeStyle::ShapeStroke(_)
                 | StrokeStyle::VectorImage(_)
-                | StrokeStyle::BitmapImage(_) => {
+                | StrokeStyle::BitmapImage(_)
+                | StrokeStyle::TextStroke(_) => {
                     match stroke.gen_images(zoom, renderer) {
                         Ok(images) => {
                             match render::images_to_rendernode(&images, zoom) {
                                 Ok(Some(rendernode)) => {
                                     render_comp.rendernode = Some(rendernode);
+                                    self.rendered_keys.insert(key);
                                     render_comp.regenerate_flag = false;
                                     render_comp.images = images;
                                 }
@@ -450,7 +467,8 @@ impl StrokesState {
                     // regenerate everything for strokes that don't support generating svgs for the last added elements
                     StrokeStyle::ShapeStroke(_)
                     | StrokeStyle::VectorImage(_)
-                    | StrokeStyle::BitmapImage(_) => {
+                    | StrokeStyle::BitmapImage(_)
+                    | StrokeStyle::TextStroke(_) => {
                         match stroke.gen_images(zoom, renderer) {
                             Ok(images) => {
                                 tasks_tx.unbounded_send(StateTask::UpdateStrokeWithImages {
@@ -489,6 +507,7 @@ impl StrokesState {
             match render::images_to_rendernode(&images, zoom) {
                 Ok(Some(rendernode)) => {
                     render_comp.rendernode = Some(rendernode);
+                    self.rendered_keys.insert(key);
                     render_comp.regenerate_flag = false;
                     render_comp.images = images;
                 }
@@ -516,6 +535,7 @@ impl StrokesState {
             match render::append_images_to_rendernode(render_comp.rendernode.as_ref(), &images, zoom) {
                 Ok(rendernode) => {
                     render_comp.rendernode = rendernode;
+                    self.rendered_keys.insert(key);
                     render_comp.regenerate_flag = false;
                     render_comp.images.append(&mut images);
                 }
@@ -529,9 +549,8 @@ impl StrokesState {
 
     /// Updates the cached rendernodes to the current zoom. Used to display the scaled (pixelated) images until new ones are generated with one of the regenerate_*_threaded funcs
     pub fn update_rendernodes_current_zoom(&mut self, zoom: f64) {
-        self.render_components
-            .iter_mut()
-            .for_each(|(_key, render_comp)| {
+        for &key in self.rendered_keys.iter() {
+            if let Some(render_comp) = self.render_components.get_mut(key) {
                 match render::images_to_rendernode(&render_comp.images, zoom) {
                     Ok(Some(rendernode)) => {
                         render_comp.rendernode = Some(rendernode);
@@ -542,20 +561,38 @@ impl StrokesState {
                         e
                     ),
                 }
-            });
+            }
+        }
     }
 
     /// Draws the strokes without the selection
     pub fn draw_strokes(&self, snapshot: &Snapshot, viewport: Option<AABB>) {
-        self.keys_as_rendered().iter().for_each(|&key| {
-            if let (Some(stroke), Some(render_comp)) =
-                (self.strokes.get(key), self.render_components.get(key))
-            {
-                // skip if stroke is not in viewport
-                if let Some(viewport) = viewport {
-                    if !viewport.intersects(&stroke.bounds()) {
-                        return;
+        // The keys are sorted by layer, so the opacity of a translucent layer is pushed once for all its strokes
+        let mut current_layer: Option<u32> = None;
+        let mut opacity_pushed = false;
+
+        let keys = if let Some(viewport) = viewport {
+            self.keys_intersecting_bounds(viewport)
+        } else {
+            self.keys_as_rendered()
+        };
+
+        keys.iter().for_each(|&key| {
+            if let Some(render_comp) = self.render_components.get(key) {
+                let layer_id = self.layer_of(key);
+                if current_layer != Some(layer_id) {
+                    if opacity_pushed {
+                        snapshot.pop();
+                        opacity_pushed = false;
                     }
+
+                    if let Some(layer) = self.layer(layer_id) {
+                        if layer.opacity < 1.0 {
+                            snapshot.push_opacity(layer.opacity);
+                            opacity_pushed = true;
+                        }
+                    }
+                    current_layer = Some(layer_id);
                 }
 
                 if let Some(rendernode) = render_comp.rendernode.as_ref() {
@@ -563,6 +600,10 @@ impl StrokesState {
                 }
             }
         });
+
+        if opacity_pushed {
+            snapshot.pop();
+        }
     }
 
     /// Draws the selection
@@ -692,6 +733,14 @@ impl StrokesState {
                         snapshot,
                     );
                 }
+                StrokeStyle::TextStroke(textstroke) => {
+                    visual_debug::draw_bounds(
+                        textstroke.bounds,
+                        visual_debug::COLOR_STROKE_BOUNDS,
+                        zoom,
+                        snapshot,
+                    );
+                }
             }
             // Pop Blur and opacity for hidden strokes
             if let (Some(render_comp), Some(trash_comp)) = (
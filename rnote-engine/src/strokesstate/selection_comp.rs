@@ -1,7 +1,17 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
 use super::{StrokeKey, StrokeStyle, StrokesState};
+use crate::compose::color::Color;
 use crate::compose::geometry::AABBHelpers;
+use crate::compose::transformable::MirrorAxis;
 use crate::drawbehaviour::DrawBehaviour;
-use crate::pens::selector::{self, Selector};
+use crate::pens::brush::Brush;
+use crate::pens::selector::{SelectionOperation, Selector, SelectorStyle};
+use crate::pens::shaper::Shaper;
+use crate::render::Renderer;
+use crate::strokes::shaperecognizer::ShapeRecognizer;
 use crate::{compose, render};
 
 use geo::line_string;
@@ -33,6 +43,48 @@ impl SelectionComponent {
     }
 }
 
+/// How the selected strokes are aligned to the bounds of the selection
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SelectionAlignment {
+    Left,
+    Center,
+    Right,
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl SelectionAlignment {
+    /// The offset which aligns the bounds to the target bounds
+    fn offset(self, bounds: AABB, target: AABB) -> na::Vector2<f64> {
+        match self {
+            Self::Left => na::vector![target.mins[0] - bounds.mins[0], 0.0],
+            Self::Center => na::vector![target.center()[0] - bounds.center()[0], 0.0],
+            Self::Right => na::vector![target.maxs[0] - bounds.maxs[0], 0.0],
+            Self::Top => na::vector![0.0, target.mins[1] - bounds.mins[1]],
+            Self::Middle => na::vector![0.0, target.center()[1] - bounds.center()[1]],
+            Self::Bottom => na::vector![0.0, target.maxs[1] - bounds.maxs[1]],
+        }
+    }
+}
+
+/// The direction in which the selected strokes are distributed
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SelectionDistribution {
+    Horizontal,
+    Vertical,
+}
+
+impl SelectionDistribution {
+    /// The index of the dimension along which is distributed
+    fn dim(self) -> usize {
+        match self {
+            Self::Horizontal => 0,
+            Self::Vertical => 1,
+        }
+    }
+}
+
 impl StrokesState {
     /// Returns false if selecting is unsupported
     pub fn can_select(&self, key: StrokeKey) -> bool {
@@ -51,8 +103,12 @@ impl StrokesState {
         }
     }
 
-    /// Sets if the stroke is currently selected
+    /// Sets if the stroke is currently selected. Strokes on hidden or locked layers can't be selected
     pub fn set_selected(&mut self, key: StrokeKey, selected: bool) {
+        if selected && !self.layer_editable(key) {
+            return;
+        }
+
         if let Some(selection_comp) = self.selection_components.get_mut(key) {
             selection_comp.selected = selected;
 
@@ -76,21 +132,25 @@ impl StrokesState {
 
     /// Returns the selection keys in the order that they should be rendered. Does not return the stroke keys!
     pub fn selection_keys_as_rendered(&self) -> Vec<StrokeKey> {
-        let keys_sorted_chrono = self.keys_sorted_chrono();
+        let keys_sorted_z = self.keys_sorted_z();
 
-        keys_sorted_chrono
+        let mut keys = keys_sorted_z
             .iter()
             .filter_map(|&key| {
                 if self.does_render(key).unwrap_or(false)
                     && !(self.trashed(key).unwrap_or(false))
                     && (self.selected(key).unwrap_or(false))
+                    && self.layer_visible(key)
                 {
                     Some(key)
                 } else {
                     None
                 }
             })
-            .collect::<Vec<StrokeKey>>()
+            .collect::<Vec<StrokeKey>>();
+
+        self.sort_keys_by_layer(&mut keys);
+        keys
     }
 
     pub fn selection_len(&self) -> usize {
@@ -107,6 +167,8 @@ impl StrokesState {
             SelectionComponent::SELECTION_DUPLICATION_OFFSET_Y
         ];
 
+        self.history.begin_group();
+
         let old_selected = self.selection_keys_as_rendered();
         self.set_selected_keys(&old_selected, false);
 
@@ -119,116 +181,633 @@ impl StrokesState {
             })
             .collect::<Vec<StrokeKey>>();
 
+        self.duplicate_groups(&old_selected, &new_selected);
+
         // Offsetting the new selected stroke to make the duplication apparent to the user
         self.translate_strokes(&new_selected, offset, zoom);
+
+        self.history.end_group();
     }
 
-    /// Returns true if selection has changed
-    pub fn update_selection_for_selector(&mut self, selector: &Selector, viewport: Option<AABB>) {
-        let selector_polygon = match selector.style {
-            selector::SelectorStyle::Polygon => {
-                let selector_path_points = selector
-                    .path
-                    .par_iter()
-                    .map(|inputdata| geo::Coordinate {
-                        x: inputdata.pos()[0],
-                        y: inputdata.pos()[1],
-                    })
-                    .collect::<Vec<geo::Coordinate<f64>>>();
+    /// Replaces the selected brush strokes with the shapes they were drawn as. The shape strokes become selected in their place
+    pub fn beautify_selection(
+        &mut self,
+        recognizer: &ShapeRecognizer,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.beautify_strokes(&selection_keys, recognizer, renderer, zoom)
+            .into_iter()
+            .for_each(|(original_key, new_key)| {
+                if let Some(selection_comp) = self.selection_components.get_mut(original_key) {
+                    selection_comp.selected = false;
+                }
+                self.set_selected(new_key, true);
+            });
+    }
 
-                geo::Polygon::new(selector_path_points.into(), vec![])
+    /// Changes the stroke color of the selected brush and shape strokes and the color of the selected text strokes. Returns true if any stroke was changed
+    pub fn change_selection_stroke_color(
+        &mut self,
+        color: Color,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::BrushStroke(brushstroke)
+                if brushstroke.style.stroke_color() != Some(color) =>
+            {
+                brushstroke.style.set_stroke_color(Some(color));
+                true
             }
-            selector::SelectorStyle::Rectangle => {
-                if let (Some(first), Some(last)) = (selector.path.first(), selector.path.last()) {
-                    let selector_path_points = line_string![
-                        (x: first.pos()[0], y: first.pos()[1]),
-                        (x: first.pos()[0], y: last.pos()[1]),
-                        (x: last.pos()[0], y: last.pos()[1]),
-                        (x: last.pos()[0], y: first.pos()[1]),
-                        (x: first.pos()[0], y: first.pos()[1]),
-                    ];
-
-                    geo::Polygon::new(selector_path_points, vec![])
-                } else {
-                    return;
+            StrokeStyle::ShapeStroke(shapestroke)
+                if shapestroke.drawstyle.stroke_color() != Some(color) =>
+            {
+                shapestroke.drawstyle.set_stroke_color(Some(color));
+                true
+            }
+            StrokeStyle::TextStroke(textstroke) if textstroke.text_style.color != color => {
+                textstroke.text_style.color = color;
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Changes the fill color of the selected shape strokes. Returns true if any stroke was changed
+    pub fn change_selection_fill_color(
+        &mut self,
+        color: Option<Color>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::ShapeStroke(shapestroke)
+                if shapestroke.drawstyle.fill_color() != color =>
+            {
+                shapestroke.drawstyle.set_fill_color(color);
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Changes the width of the selected brush and shape strokes.
+    /// Consecutive changes are merged until `History::end_merge()` is called. Returns true if any stroke was changed
+    pub fn change_selection_width(
+        &mut self,
+        width: f64,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes_merged(
+            &selection_keys,
+            "selection-width",
+            renderer,
+            zoom,
+            |stroke| match stroke {
+                StrokeStyle::BrushStroke(brushstroke) if brushstroke.style.width() != width => {
+                    brushstroke.style.set_width(width);
+                    true
+                }
+                StrokeStyle::ShapeStroke(shapestroke) if shapestroke.drawstyle.width() != width => {
+                    shapestroke.drawstyle.set_width(width);
+                    true
                 }
+                _ => false,
+            },
+        )
+    }
+
+    /// Changes the style of the selected brush strokes to the current style and options of the brush. Returns true if any stroke was changed
+    pub fn change_selection_brush_style(
+        &mut self,
+        brush: &Brush,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::BrushStroke(brushstroke) => {
+                let old_style = brushstroke.style.clone();
+                brushstroke.change_style(brush);
+
+                brushstroke.style != old_style
+            }
+            _ => false,
+        })
+    }
+
+    /// Changes the draw style of the selected shape strokes to the current draw style and options of the shaper. Returns true if any stroke was changed
+    pub fn change_selection_shape_drawstyle(
+        &mut self,
+        shaper: &Shaper,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::ShapeStroke(shapestroke) => {
+                let old_drawstyle = shapestroke.drawstyle.clone();
+                shapestroke.change_drawstyle(shaper);
+
+                shapestroke.drawstyle != old_drawstyle
             }
+            _ => false,
+        })
+    }
+
+    /// Aligns the selected strokes to the bounds of the selection. Grouped strokes are moved together. Returns true if any stroke was moved
+    pub fn align_selection(&mut self, alignment: SelectionAlignment, zoom: f64) -> bool {
+        let selection_bounds = if let Some(selection_bounds) = self.gen_selection_bounds() {
+            selection_bounds
+        } else {
+            return false;
         };
 
-        self.keys_sorted_chrono().iter().for_each(|&key| {
-            let stroke = if let Some(stroke) = self.strokes.get(key) {
-                stroke
-            } else {
-                return;
-            };
-            // skip if stroke is trashed
-            if let Some(trash_comp) = self.trash_components.get(key) {
-                if trash_comp.trashed {
-                    return;
+        let offsets = self
+            .selection_items()
+            .into_iter()
+            .filter_map(|item| {
+                let offset = alignment.offset(self.gen_bounds(&item)?, selection_bounds);
+
+                if offset == na::Vector2::zeros() {
+                    None
+                } else {
+                    Some((item, offset))
                 }
+            })
+            .collect::<Vec<(Vec<StrokeKey>, na::Vector2<f64>)>>();
+
+        self.translate_items(offsets, zoom)
+    }
+
+    /// Distributes the selected strokes, so that the gaps between them are equal. The outermost strokes stay in place.
+    /// Grouped strokes are moved together. Returns true if any stroke was moved
+    pub fn distribute_selection(&mut self, distribution: SelectionDistribution, zoom: f64) -> bool {
+        let dim = distribution.dim();
+
+        let mut items = self
+            .selection_items()
+            .into_iter()
+            .filter_map(|item| {
+                let bounds = self.gen_bounds(&item)?;
+                Some((item, bounds))
+            })
+            .collect::<Vec<(Vec<StrokeKey>, AABB)>>();
+
+        // Distributing needs at least one item between the outermost ones
+        if items.len() < 3 {
+            return false;
+        }
+
+        items.sort_by(|(_, first), (_, second)| {
+            first.center()[dim]
+                .partial_cmp(&second.center()[dim])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        let start = items
+            .iter()
+            .map(|(_, bounds)| bounds.mins[dim])
+            .fold(f64::INFINITY, f64::min);
+        let end = items
+            .iter()
+            .map(|(_, bounds)| bounds.maxs[dim])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let items_extents = items
+            .iter()
+            .map(|(_, bounds)| bounds.extents()[dim])
+            .sum::<f64>();
+        let gap = (end - start - items_extents) / (items.len() - 1) as f64;
+
+        let mut pos = start;
+        let offsets = items
+            .into_iter()
+            .filter_map(|(item, bounds)| {
+                let mut offset = na::Vector2::zeros();
+                offset[dim] = pos - bounds.mins[dim];
+                pos += bounds.extents()[dim] + gap;
+
+                if offset == na::Vector2::zeros() {
+                    None
+                } else {
+                    Some((item, offset))
+                }
+            })
+            .collect::<Vec<(Vec<StrokeKey>, na::Vector2<f64>)>>();
+
+        self.translate_items(offsets, zoom)
+    }
+
+    /// Mirrors the selection along the axis going through the center of the selection bounds. Returns true if the selection was mirrored
+    pub fn mirror_selection(
+        &mut self,
+        axis: MirrorAxis,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+        let center = if let Some(selection_bounds) = self.gen_bounds(&selection_keys) {
+            selection_bounds.center()
+        } else {
+            return false;
+        };
+
+        self.mirror_strokes(&selection_keys, axis, center, renderer, zoom);
+        true
+    }
+
+    /// Moves the selection, so that the upper left corner of its bounds is at the position. Returns true if the selection was moved
+    pub fn move_selection_to(&mut self, pos: na::Vector2<f64>, zoom: f64) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.move_strokes_to(&selection_keys, pos, zoom)
+    }
+
+    /// Resizes the selection, so that its bounds have the size. The upper left corner of the bounds stays in place.
+    /// Returns true if the selection was resized
+    pub fn resize_selection_to(
+        &mut self,
+        size: na::Vector2<f64>,
+        lock_aspectratio: bool,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.resize_strokes_to(&selection_keys, size, lock_aspectratio, renderer, zoom)
+    }
+
+    /// Rotates the selection with angle (rad) around the center of its bounds. Returns true if the selection was rotated
+    pub fn rotate_selection(
+        &mut self,
+        angle: f64,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+        let center = match self.gen_bounds(&selection_keys) {
+            Some(selection_bounds) => selection_bounds.center(),
+            None => return false,
+        };
+        if angle == 0.0 {
+            return false;
+        }
+
+        self.rotate_strokes(&selection_keys, angle, center, renderer, zoom);
+        true
+    }
+
+    /// The key of the selected stroke, if the selection consists only of a single shape stroke
+    pub fn selected_single_shape_stroke(&self) -> Option<StrokeKey> {
+        match self.selection_keys_as_rendered()[..] {
+            [key] => self.shape_stroke(key).map(|_| key),
+            _ => None,
+        }
+    }
+
+    /// The editing handles of the selected shape stroke. Empty if the selection is not a single shape stroke
+    pub fn selection_shape_handles(&self) -> Vec<na::Vector2<f64>> {
+        self.selected_single_shape_stroke()
+            .and_then(|key| self.shape_stroke(key))
+            .map(|shapestroke| shapestroke.handles())
+            .unwrap_or_default()
+    }
+
+    /// Moves an editing handle of the selected shape stroke to the position.
+    /// Returns the new index of the moved handle, or None if no handle was moved
+    pub fn move_selection_shape_handle(
+        &mut self,
+        index: usize,
+        pos: na::Vector2<f64>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> Option<usize> {
+        let key = self.selected_single_shape_stroke()?;
+
+        self.modify_shape_stroke(key, renderer, zoom, |shapestroke| {
+            Some(shapestroke.move_handle(index, pos))
+        })
+    }
+
+    /// The key of the selected stroke, if the selection consists only of a single brush stroke
+    pub fn selected_single_brush_stroke(&self) -> Option<StrokeKey> {
+        match self.selection_keys_as_rendered()[..] {
+            [key] => self.brush_stroke(key).map(|_| key),
+            _ => None,
+        }
+    }
+
+    /// The nodes of the selected brush stroke. Empty if the selection is not a single brush stroke
+    pub fn selection_brush_nodes(&self) -> Vec<na::Vector2<f64>> {
+        self.selected_single_brush_stroke()
+            .and_then(|key| self.brush_stroke(key))
+            .map(|brushstroke| brushstroke.nodes())
+            .unwrap_or_default()
+    }
+
+    /// The pressure of a node of the selected brush stroke
+    pub fn selection_brush_node_pressure(&self, node: usize) -> Option<f64> {
+        self.selected_single_brush_stroke()
+            .and_then(|key| self.brush_stroke(key))
+            .and_then(|brushstroke| brushstroke.elements.get(node))
+            .map(|element| element.inputdata.pressure())
+    }
+
+    /// Moves a node of the selected brush stroke to the position. Returns true if the node was moved
+    pub fn move_selection_brush_node(
+        &mut self,
+        node: usize,
+        pos: na::Vector2<f64>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let key = if let Some(key) = self.selected_single_brush_stroke() {
+            key
+        } else {
+            return false;
+        };
+
+        self.modify_brush_stroke(key, renderer, zoom, |brushstroke| {
+            brushstroke.move_node(node, pos).then(|| ())
+        })
+        .is_some()
+    }
+
+    /// Sets the pressure of a node of the selected brush stroke. Returns true if the pressure was set
+    pub fn set_selection_brush_node_pressure(
+        &mut self,
+        node: usize,
+        pressure: f64,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let key = if let Some(key) = self.selected_single_brush_stroke() {
+            key
+        } else {
+            return false;
+        };
+
+        self.modify_brush_stroke(key, renderer, zoom, |brushstroke| {
+            brushstroke.set_node_pressure(node, pressure).then(|| ())
+        })
+        .is_some()
+    }
+
+    /// Removes a node of the selected brush stroke. Returns true if the node was removed
+    pub fn remove_selection_brush_node(
+        &mut self,
+        node: usize,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let key = if let Some(key) = self.selected_single_brush_stroke() {
+            key
+        } else {
+            return false;
+        };
+
+        self.modify_brush_stroke(key, renderer, zoom, |brushstroke| {
+            brushstroke.remove_node(node)
+        })
+        .is_some()
+    }
+
+    /// Splits the selected brush stroke at a node. Both parts stay selected. Returns true if the stroke was split
+    pub fn split_selection_brush_stroke(
+        &mut self,
+        node: usize,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let key = if let Some(key) = self.selected_single_brush_stroke() {
+            key
+        } else {
+            return false;
+        };
+
+        if let Some(new_key) = self.split_brush_stroke(key, node, renderer, zoom) {
+            self.set_selected(new_key, true);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Joins the selected brush strokes in the order they are rendered. Returns true if the strokes were joined
+    pub fn join_selection_brush_strokes(
+        &mut self,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.join_brush_strokes(&selection_keys, renderer, zoom)
+    }
+
+    /// Simplifies the selected brush strokes, see BrushStroke::simplify(). Returns true if any stroke was simplified
+    pub fn simplify_selection_brush_strokes(
+        &mut self,
+        tolerance: f64,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::BrushStroke(brushstroke) => brushstroke.simplify(tolerance),
+            _ => false,
+        })
+    }
+
+    /// Switches the draw style of the selected shape strokes between smooth and rough. Returns true if any stroke was changed
+    pub fn switch_selection_shape_drawstyle(
+        &mut self,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) -> bool {
+        let selection_keys = self.selection_keys_as_rendered();
+
+        self.modify_strokes(&selection_keys, renderer, zoom, |stroke| match stroke {
+            StrokeStyle::ShapeStroke(shapestroke) => {
+                shapestroke.switch_drawstyle();
+                true
             }
-            // skip if stroke is not in viewport
-            if let Some(viewport) = viewport {
-                if !viewport.intersects(&stroke.bounds()) {
+            _ => false,
+        })
+    }
+
+    /// The selected strokes, split into the items which are arranged as a whole.
+    /// Strokes in the same outermost group form a single item, every other stroke is an item on its own
+    fn selection_items(&self) -> Vec<Vec<StrokeKey>> {
+        let mut items = vec![];
+        let mut group_items = HashMap::<u32, Vec<StrokeKey>>::new();
+
+        self.selection_keys_as_rendered()
+            .into_iter()
+            .for_each(|key| match self.top_group_of(key) {
+                Some(group_id) => group_items.entry(group_id).or_default().push(key),
+                None => items.push(vec![key]),
+            });
+        items.extend(group_items.into_values());
+
+        items
+    }
+
+    /// Translates each item by its offset. Returns true if any item was translated
+    fn translate_items(
+        &mut self,
+        offsets: Vec<(Vec<StrokeKey>, na::Vector2<f64>)>,
+        zoom: f64,
+    ) -> bool {
+        if offsets.is_empty() {
+            return false;
+        }
+
+        self.history.begin_group();
+        offsets.into_iter().for_each(|(item, offset)| {
+            self.translate_strokes(&item, offset, zoom);
+        });
+        self.history.end_group();
+
+        true
+    }
+
+    /// Updates the selection with the strokes matched by the selector.
+    /// Depending on the selection operation of the selector, the matched strokes replace, are added to or are subtracted from the current selection
+    pub fn update_selection_for_selector(&mut self, selector: &Selector, viewport: Option<AABB>) {
+        let matched_keys = match selector.style {
+            SelectorStyle::Polygon | SelectorStyle::Rectangle | SelectorStyle::Touch => {
+                if let Some(selector_polygon) = gen_selector_polygon(selector) {
+                    self.keys_matching_selector_polygon(
+                        &selector_polygon,
+                        matches!(selector.style, SelectorStyle::Touch),
+                        viewport,
+                    )
+                } else {
                     return;
                 }
             }
-            if let Some(selection_comp) = self.selection_components.get_mut(key) {
-                // default to not selected, check for if selected
-                selection_comp.selected = false;
-
-                match stroke {
-                    StrokeStyle::BrushStroke(brushstroke) => {
-                        if selector_polygon.contains(&brushstroke.bounds.to_geo_polygon()) {
-                            selection_comp.selected = true;
-                        } else if selector_polygon.intersects(&brushstroke.bounds.to_geo_polygon())
-                        {
-                            for &hitbox_elem in brushstroke.hitboxes.iter() {
-                                if !selector_polygon.contains(&hitbox_elem.to_geo_polygon()) {
-                                    return;
-                                }
-                            }
-                            selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
-                        }
-                    }
-                    StrokeStyle::ShapeStroke(shapestroke) => {
-                        if selector_polygon.contains(&shapestroke.bounds.to_geo_polygon()) {
-                            selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
-                        }
-                    }
-                    StrokeStyle::VectorImage(vectorimage) => {
-                        if selector_polygon.contains(&vectorimage.bounds.to_geo_polygon()) {
-                            selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
-                        }
-                    }
-                    StrokeStyle::BitmapImage(bitmapimage) => {
-                        if selector_polygon.contains(&bitmapimage.bounds.to_geo_polygon()) {
-                            selection_comp.selected = true;
-
-                            if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                self.chrono_counter += 1;
-                                chrono_comp.t = self.chrono_counter;
-                            }
+            SelectorStyle::Single => selector
+                .path
+                .last()
+                .and_then(|inputdata| self.topmost_key_at_pos(inputdata.pos(), viewport))
+                .into_iter()
+                .collect::<Vec<StrokeKey>>(),
+        };
+
+        // Matching a member of a group matches the whole group
+        let matched_keys = self
+            .keys_with_groups(&matched_keys)
+            .into_iter()
+            .filter(|&key| !self.trashed(key).unwrap_or(true))
+            .collect::<Vec<StrokeKey>>();
+
+        match selector.operation() {
+            SelectionOperation::Replace => {
+                let old_selected = self
+                    .selection_components
+                    .iter()
+                    .filter_map(|(key, selection_comp)| {
+                        if selection_comp.selected {
+                            Some(key)
+                        } else {
+                            None
                         }
-                    }
-                }
+                    })
+                    .collect::<Vec<StrokeKey>>();
+                self.set_selected_keys(&old_selected, false);
+                self.set_selected_keys(&matched_keys, true);
             }
-        });
+            SelectionOperation::Add => {
+                self.set_selected_keys(&matched_keys, true);
+            }
+            SelectionOperation::Subtract => {
+                self.set_selected_keys(&matched_keys, false);
+            }
+        }
+    }
+
+    /// The keys of the strokes which can be selected by the selector polygon, in chronological order.
+    /// When touching, the strokes only need to intersect with the polygon, else they need to be fully contained in it
+    fn keys_matching_selector_polygon(
+        &self,
+        selector_polygon: &geo::Polygon<f64>,
+        touching: bool,
+        viewport: Option<AABB>,
+    ) -> Vec<StrokeKey> {
+        let selector_bounds = if let Some(rect) = selector_polygon.bounding_rect() {
+            AABB::new(
+                na::point![rect.min().x, rect.min().y],
+                na::point![rect.max().x, rect.max().y],
+            )
+        } else {
+            return vec![];
+        };
+
+        let mut keys = self.key_tree.keys_intersecting_bounds(selector_bounds);
+        self.sort_keys_chrono(&mut keys);
+
+        keys.into_iter()
+            .filter(|&key| {
+                self.selectable(key, viewport)
+                    && self.strokes.get(key).map_or(false, |stroke| {
+                        selector_polygon_matches_stroke(selector_polygon, stroke, touching)
+                    })
+            })
+            .collect()
+    }
+
+    /// The key of the topmost stroke at the position which can be selected. Brush strokes are matched by their hitboxes
+    fn topmost_key_at_pos(
+        &self,
+        pos: na::Vector2<f64>,
+        viewport: Option<AABB>,
+    ) -> Option<StrokeKey> {
+        let tap_bounds = AABB::from_half_extents(
+            na::Point2::from(pos),
+            na::Vector2::repeat(Selector::SINGLE_SELECTION_TOLERANCE),
+        );
+
+        let mut keys = self.key_tree.keys_intersecting_bounds(tap_bounds);
+        self.sort_keys_z(&mut keys);
+        self.sort_keys_by_layer(&mut keys);
+
+        keys.into_iter().rev().find(|&key| {
+            self.selectable(key, viewport)
+                && self.strokes.get(key).map_or(false, |stroke| match stroke {
+                    StrokeStyle::BrushStroke(brushstroke) => brushstroke
+                        .hitboxes
+                        .iter()
+                        .any(|hitbox_elem| hitbox_elem.intersects(&tap_bounds)),
+                    _ => stroke.bounds().intersects(&tap_bounds),
+                })
+        })
+    }
+
+    /// Wether the stroke can be selected by the selector. Trashed strokes, strokes on hidden or locked layers and strokes outside of the viewport are skipped
+    fn selectable(&self, key: StrokeKey, viewport: Option<AABB>) -> bool {
+        let stroke = if let Some(stroke) = self.strokes.get(key) {
+            stroke
+        } else {
+            return false;
+        };
+
+        !self.trashed(key).unwrap_or(true)
+            && self.can_select(key)
+            && self.layer_editable(key)
+            && viewport.map_or(true, |viewport| viewport.intersects(&stroke.bounds()))
     }
 
     /// the svgs of the current selection, without xml header or svg root
@@ -303,3 +882,71 @@ impl StrokesState {
         Ok(())
     }
 }
+
+/// Generates the polygon of the selector path
+fn gen_selector_polygon(selector: &Selector) -> Option<geo::Polygon<f64>> {
+    match selector.style {
+        SelectorStyle::Polygon | SelectorStyle::Touch => {
+            let selector_path_points = selector
+                .path
+                .par_iter()
+                .map(|inputdata| geo::Coordinate {
+                    x: inputdata.pos()[0],
+                    y: inputdata.pos()[1],
+                })
+                .collect::<Vec<geo::Coordinate<f64>>>();
+
+            Some(geo::Polygon::new(selector_path_points.into(), vec![]))
+        }
+        SelectorStyle::Rectangle => {
+            let (first, last) = (selector.path.first()?, selector.path.last()?);
+            let selector_path_points = line_string![
+                (x: first.pos()[0], y: first.pos()[1]),
+                (x: first.pos()[0], y: last.pos()[1]),
+                (x: last.pos()[0], y: last.pos()[1]),
+                (x: last.pos()[0], y: first.pos()[1]),
+                (x: first.pos()[0], y: first.pos()[1]),
+            ];
+
+            Some(geo::Polygon::new(selector_path_points, vec![]))
+        }
+        SelectorStyle::Single => None,
+    }
+}
+
+/// Wether the selector polygon matches the stroke. When touching, the stroke only needs to intersect with the polygon, else it needs to be fully contained in it.
+/// Brush strokes are checked with their hitboxes
+fn selector_polygon_matches_stroke(
+    selector_polygon: &geo::Polygon<f64>,
+    stroke: &StrokeStyle,
+    touching: bool,
+) -> bool {
+    let bounds_polygon = stroke.bounds().to_geo_polygon();
+
+    match stroke {
+        StrokeStyle::BrushStroke(brushstroke) => {
+            if touching {
+                selector_polygon.intersects(&bounds_polygon)
+                    && brushstroke.hitboxes.iter().any(|hitbox_elem| {
+                        selector_polygon.intersects(&hitbox_elem.to_geo_polygon())
+                    })
+            } else {
+                selector_polygon.contains(&bounds_polygon)
+                    || (selector_polygon.intersects(&bounds_polygon)
+                        && brushstroke.hitboxes.iter().all(|hitbox_elem| {
+                            selector_polygon.contains(&hitbox_elem.to_geo_polygon())
+                        }))
+            }
+        }
+        StrokeStyle::ShapeStroke(_)
+        | StrokeStyle::VectorImage(_)
+        | StrokeStyle::BitmapImage(_)
+        | StrokeStyle::TextStroke(_) => {
+            if touching {
+                selector_polygon.intersects(&bounds_polygon)
+            } else {
+                selector_polygon.contains(&bounds_polygon)
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use std::sync::{Arc, RwLock};
+
 use crate::drawbehaviour::DrawBehaviour;
 use crate::pens::eraser::Eraser;
+use crate::render::Renderer;
 use crate::strokes::strokestyle::StrokeStyle;
 
 use super::{StrokeKey, StrokesState};
@@ -39,6 +42,8 @@ impl StrokesState {
     }
 
     pub fn set_trashed(&mut self, key: StrokeKey, trash: bool) {
+        self.record_trash_state(&[key]);
+
         if let Some(trash_comp) = self.trash_components.get_mut(key) {
             trash_comp.trashed = trash;
 
@@ -54,32 +59,11 @@ impl StrokesState {
         }
     }
 
-    /// Resize needed after calling this
-    pub fn undo_last_stroke(&mut self) -> Option<StrokeKey> {
-        let last_stroke_key = self.last_stroke_key();
-        if let Some(last_stroke_key) = last_stroke_key {
-            self.set_trashed(last_stroke_key, true);
-
-            Some(last_stroke_key)
-        } else {
-            None
-        }
-    }
-
-    /// Resize needed after calling this
-    pub fn redo_last_stroke(&mut self) -> Option<StrokeKey> {
-        let last_trashed_key = self.last_trashed_key();
-        if let Some(last_trashed_key) = last_trashed_key {
-            self.set_trashed(last_trashed_key, false);
-
-            Some(last_trashed_key)
-        } else {
-            None
-        }
-    }
-
     pub fn trash_selection(&mut self) {
-        self.selection_keys_as_rendered().iter().for_each(|&key| {
+        let selection_keys = self.selection_keys_as_rendered();
+        self.record_trash_state(&selection_keys);
+
+        selection_keys.iter().for_each(|&key| {
             if let Some(selection_comp) = self.selection_components.get_mut(key) {
                 if selection_comp.selected {
                     selection_comp.selected = false;
@@ -99,66 +83,153 @@ impl StrokesState {
 
     /// trash strokes that collide with the eraser
     pub fn trash_colliding_strokes(&mut self, eraser: &Eraser, viewport: Option<AABB>) {
-        if let Some(eraser_current_input) = eraser.current_input {
-            let eraser_bounds = AABB::new(
-                na::Point2::from(
-                    eraser_current_input.pos()
-                        - na::vector![eraser.width / 2.0, eraser.width / 2.0],
-                ),
-                na::Point2::from(
-                    eraser_current_input.pos()
-                        + na::vector![eraser.width / 2.0, eraser.width / 2.0],
-                ),
-            );
+        let trashed_keys = self
+            .keys_erasable_by(eraser, viewport)
+            .into_iter()
+            .filter(|&key| {
+                self.strokes
+                    .get(key)
+                    .map_or(false, |stroke| eraser_collides_with_stroke(eraser, stroke))
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        self.trash_erased_keys(&trashed_keys);
+    }
 
-            self.strokes.iter_mut().for_each(|(key, stroke)| {
-                if let Some(viewport) = viewport {
-                    if !viewport.intersects(&stroke.bounds()) {
-                        return;
+    /// Removes the parts of brush strokes which collide with the eraser, splitting them into new strokes. Other strokes are trashed whole
+    pub fn split_colliding_strokes(
+        &mut self,
+        eraser: &Eraser,
+        viewport: Option<AABB>,
+        renderer: Arc<RwLock<Renderer>>,
+        zoom: f64,
+    ) {
+        let mut trashed_keys = vec![];
+        let mut split_strokes = vec![];
+
+        self.keys_erasable_by(eraser, viewport)
+            .into_iter()
+            .for_each(|key| match self.strokes.get(key) {
+                Some(StrokeStyle::BrushStroke(brushstroke)) => {
+                    if let Some(pieces) = brushstroke.split_colliding_elements(eraser) {
+                        trashed_keys.push(key);
+                        split_strokes.push((key, pieces));
                     }
                 }
-                match stroke {
-                    StrokeStyle::BrushStroke(brushstroke) => {
-                        // First check markerstroke bounds, then conditionally check hitbox
-                        if eraser_bounds.intersects(&brushstroke.bounds) {
-                            for hitbox_elem in brushstroke.hitboxes.iter() {
-                                if eraser_bounds.intersects(hitbox_elem) {
-                                    if let Some(trash_comp) = self.trash_components.get_mut(key) {
-                                        trash_comp.trashed = true;
-
-                                        if let Some(chrono_comp) =
-                                            self.chrono_components.get_mut(key)
-                                        {
-                                            self.chrono_counter += 1;
-                                            chrono_comp.t = self.chrono_counter;
-                                        }
-                                    }
-
-                                    return;
-                                }
-                            }
-                        }
+                Some(stroke) => {
+                    if eraser_collides_with_stroke(eraser, stroke) {
+                        trashed_keys.push(key);
                     }
-                    StrokeStyle::ShapeStroke(shapestroke) => {
-                        if eraser_bounds.intersects(&shapestroke.bounds) {
-                            if let Some(trash_comp) = self.trash_components.get_mut(key) {
-                                trash_comp.trashed = true;
-
-                                if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
-                                    self.chrono_counter += 1;
-                                    chrono_comp.t = self.chrono_counter;
-                                }
-                            }
-                        }
+                }
+                None => {}
+            });
+
+        self.trash_erased_keys(&trashed_keys);
+
+        // The pieces take the place of the original stroke, so they keep its layer, z-order and group
+        split_strokes
+            .into_iter()
+            .for_each(|(original_key, pieces)| {
+                let layer_comp = self.layer_components.get(original_key).copied();
+                let zorder_comp = self.zorder_components.get(original_key).copied();
+                let group_comp = self.group_components.get(original_key).copied();
+
+                pieces.into_iter().for_each(|piece| {
+                    let key = self.insert_stroke(StrokeStyle::BrushStroke(piece));
+
+                    if let Some(layer_comp) = layer_comp {
+                        self.layer_components.insert(key, layer_comp);
                     }
-                    StrokeStyle::VectorImage(_vectorimage) => {
-                        // Ignore VectorImage when trashing with the Eraser
+                    if let Some(zorder_comp) = zorder_comp {
+                        self.zorder_components.insert(key, zorder_comp);
                     }
-                    StrokeStyle::BitmapImage(_bitmapimage) => {
-                        // Ignore BitmapImage when trashing with the Eraser
+                    if let Some(group_comp) = group_comp {
+                        self.group_components.insert(key, group_comp);
                     }
-                }
+
+                    self.regenerate_rendering_for_stroke_threaded(key, Arc::clone(&renderer), zoom);
+                });
             });
+    }
+
+    /// The keys of the strokes which are near the eraser and can be erased: not trashed, on a editable layer and in the viewport
+    fn keys_erasable_by(&self, eraser: &Eraser, viewport: Option<AABB>) -> Vec<StrokeKey> {
+        let eraser_bounds = if let Some(eraser_bounds) = eraser.eraser_bounds() {
+            eraser_bounds
+        } else {
+            return vec![];
+        };
+        let uneditable_layers = self.uneditable_layers();
+
+        self.key_tree
+            .keys_intersecting_bounds(eraser_bounds)
+            .into_iter()
+            .filter(|&key| {
+                let stroke = if let Some(stroke) = self.strokes.get(key) {
+                    stroke
+                } else {
+                    return false;
+                };
+                if let Some(viewport) = viewport {
+                    if !viewport.intersects(&stroke.bounds()) {
+                        return false;
+                    }
+                }
+                // skip if stroke is already trashed
+                if let Some(trash_comp) = self.trash_components.get(key) {
+                    if trash_comp.trashed {
+                        return false;
+                    }
+                }
+                // skip if the layer of the stroke is hidden or locked
+                if let Some(layer_comp) = self.layer_components.get(key) {
+                    if uneditable_layers.contains(&layer_comp.layer_id) {
+                        return false;
+                    }
+                }
+
+                true
+            })
+            .collect()
+    }
+
+    fn trash_erased_keys(&mut self, keys: &[StrokeKey]) {
+        if keys.is_empty() {
+            return;
+        }
+
+        self.record_trash_state(keys);
+        keys.iter().for_each(|&key| {
+            if let Some(trash_comp) = self.trash_components.get_mut(key) {
+                trash_comp.trashed = true;
+
+                if let Some(chrono_comp) = self.chrono_components.get_mut(key) {
+                    self.chrono_counter += 1;
+                    chrono_comp.t = self.chrono_counter;
+                }
+            }
+            if let Some(selection_comp) = self.selection_components.get_mut(key) {
+                selection_comp.selected = false;
+            }
+        });
+    }
+}
+
+/// Wether the eraser tip collides with the stroke. Images only collide when the eraser is configured to erase them
+fn eraser_collides_with_stroke(eraser: &Eraser, stroke: &StrokeStyle) -> bool {
+    match stroke {
+        StrokeStyle::BrushStroke(brushstroke) => {
+            // First check brushstroke bounds, then conditionally check hitbox
+            eraser.tip_intersects_bounds(brushstroke.bounds)
+                && brushstroke
+                    .hitboxes
+                    .iter()
+                    .any(|&hitbox_elem| eraser.tip_intersects_bounds(hitbox_elem))
+        }
+        StrokeStyle::ShapeStroke(shapestroke) => eraser.tip_intersects_bounds(shapestroke.bounds),
+        StrokeStyle::TextStroke(textstroke) => eraser.tip_intersects_bounds(textstroke.bounds),
+        StrokeStyle::VectorImage(_) | StrokeStyle::BitmapImage(_) => {
+            eraser.erase_images && eraser.tip_intersects_bounds(stroke.bounds())
         }
     }
 }
@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+
+use super::history::HistoryEntry;
+use super::{StrokeKey, StrokesState};
+use serde::{Deserialize, Serialize};
+
+/// The z-order of a stroke. Other than the chrono component it is only changed explicitly, so strokes keep their position when they are modified or selected.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default, rename = "zorder_component")]
+pub struct ZOrderComponent {
+    /// Strokes with a higher z are drawn above strokes with a lower z
+    #[serde(rename = "z")]
+    pub z: u32,
+}
+
+impl Default for ZOrderComponent {
+    fn default() -> Self {
+        Self { z: 0 }
+    }
+}
+
+impl ZOrderComponent {
+    pub fn new(z: u32) -> Self {
+        Self { z }
+    }
+}
+
+/// Systems that are related to the z-order.
+impl StrokesState {
+    pub fn z(&self, key: StrokeKey) -> Option<u32> {
+        if let Some(zorder_comp) = self.zorder_components.get(key) {
+            Some(zorder_comp.z)
+        } else {
+            log::debug!(
+                "get zorder_comp in z() returned None for stroke with key {:?}",
+                key
+            );
+            None
+        }
+    }
+
+    /// Returns the keys in z-order, as in first: gets drawn first, last: gets drawn last
+    pub fn keys_sorted_z(&self) -> Vec<StrokeKey> {
+        let mut keys = self.strokes.keys().collect::<Vec<StrokeKey>>();
+        self.sort_keys_z(&mut keys);
+        keys
+    }
+
+    /// Sorts the keys in z-order. Keys without a z-order component are put first.
+    /// Strokes can share the same z (e.g. the pieces of a split stroke), their order is then decided by their keys so that it does not change between sorts
+    pub fn sort_keys_z(&self, keys: &mut [StrokeKey]) {
+        keys.sort_by_key(|&key| {
+            (
+                self.zorder_components
+                    .get(key)
+                    .map(|zorder_comp| zorder_comp.z),
+                key,
+            )
+        });
+    }
+
+    /// Moves the selection to the top
+    pub fn selection_to_front(&mut self) {
+        let selection_keys = self.selection_keys_set();
+        if selection_keys.is_empty() {
+            return;
+        }
+
+        let (mut order, selection_order): (Vec<StrokeKey>, Vec<StrokeKey>) = self
+            .keys_sorted_z()
+            .into_iter()
+            .partition(|key| !selection_keys.contains(key));
+        order.extend(selection_order);
+
+        self.apply_z_order(&order);
+    }
+
+    /// Moves the selection to the bottom
+    pub fn selection_to_back(&mut self) {
+        let selection_keys = self.selection_keys_set();
+        if selection_keys.is_empty() {
+            return;
+        }
+
+        let (mut order, rest_order): (Vec<StrokeKey>, Vec<StrokeKey>) = self
+            .keys_sorted_z()
+            .into_iter()
+            .partition(|key| selection_keys.contains(key));
+        order.extend(rest_order);
+
+        self.apply_z_order(&order);
+    }
+
+    /// Moves the selection above the next stroke which overlaps it
+    pub fn raise_selection(&mut self) {
+        let selection_keys = self.selection_keys_set();
+        let overlapping_keys = self.keys_overlapping_selection(&selection_keys);
+        let order = self.keys_sorted_z();
+
+        let lowest_selected =
+            if let Some(position) = order.iter().position(|key| selection_keys.contains(key)) {
+                position
+            } else {
+                return;
+            };
+
+        // The first overlapping stroke above the lowest selected stroke
+        if let Some(&target) = order[lowest_selected..]
+            .iter()
+            .find(|key| overlapping_keys.contains(key))
+        {
+            self.move_selection_next_to(order, &selection_keys, target, true);
+        }
+    }
+
+    /// Moves the selection below the next stroke which overlaps it
+    pub fn lower_selection(&mut self) {
+        let selection_keys = self.selection_keys_set();
+        let overlapping_keys = self.keys_overlapping_selection(&selection_keys);
+        let order = self.keys_sorted_z();
+
+        let highest_selected =
+            if let Some(position) = order.iter().rposition(|key| selection_keys.contains(key)) {
+                position
+            } else {
+                return;
+            };
+
+        // The first overlapping stroke below the highest selected stroke
+        if let Some(&target) = order[..highest_selected]
+            .iter()
+            .rev()
+            .find(|key| overlapping_keys.contains(key))
+        {
+            self.move_selection_next_to(order, &selection_keys, target, false);
+        }
+    }
+
+    /// Makes sure every stroke has a z-order component. Strokes from older versions are ordered by their chrono component
+    pub fn validate_z_order(&mut self) {
+        let missing_keys = self
+            .keys_sorted_chrono()
+            .into_iter()
+            .filter(|&key| self.zorder_components.get(key).is_none())
+            .collect::<Vec<StrokeKey>>();
+
+        missing_keys.into_iter().for_each(|key| {
+            self.z_counter += 1;
+            self.zorder_components
+                .insert(key, ZOrderComponent::new(self.z_counter));
+        });
+    }
+
+    /// Records the current z-order of the strokes
+    pub fn record_z_order(&mut self, keys: &[StrokeKey]) {
+        let z_orders = keys
+            .iter()
+            .filter_map(|&key| Some((key, self.zorder_components.get(key)?.z)))
+            .collect::<Vec<(StrokeKey, u32)>>();
+
+        if !z_orders.is_empty() {
+            self.history.record(HistoryEntry::ZOrder { z_orders });
+        }
+    }
+
+    /// Swaps the z-order of the strokes, returns the replaced z-order
+    pub fn swap_z_order(&mut self, z_orders: Vec<(StrokeKey, u32)>) -> Vec<(StrokeKey, u32)> {
+        z_orders
+            .into_iter()
+            .filter_map(|(key, z)| {
+                if let Some(zorder_comp) = self.zorder_components.get_mut(key) {
+                    let replaced = zorder_comp.z;
+                    zorder_comp.z = z;
+                    // New strokes must still be inserted on top
+                    self.z_counter = self.z_counter.max(z);
+
+                    Some((key, replaced))
+                } else {
+                    log::debug!(
+                        "get zorder_comp in swap_z_order() returned None for stroke with key {:?}",
+                        key
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Renumbers the z-order of the strokes in the order of the keys. Records the previous z-order in the history
+    fn apply_z_order(&mut self, order: &[StrokeKey]) {
+        self.record_z_order(order);
+        self.z_counter = 0;
+
+        order.iter().for_each(|&key| {
+            self.z_counter += 1;
+            self.zorder_components
+                .insert(key, ZOrderComponent::new(self.z_counter));
+        });
+    }
+
+    fn selection_keys_set(&self) -> HashSet<StrokeKey> {
+        self.selection_keys_as_rendered().into_iter().collect()
+    }
+
+    /// The visible, not selected strokes which overlap the selection bounds
+    fn keys_overlapping_selection(
+        &self,
+        selection_keys: &HashSet<StrokeKey>,
+    ) -> HashSet<StrokeKey> {
+        if let Some(selection_bounds) = self.gen_selection_bounds() {
+            self.keys_intersecting_bounds(selection_bounds)
+                .into_iter()
+                .filter(|key| !selection_keys.contains(key))
+                .collect()
+        } else {
+            HashSet::new()
+        }
+    }
+
+    /// Moves the selection directly above or below the target in the z-order, keeping the order within the selection
+    fn move_selection_next_to(
+        &mut self,
+        order: Vec<StrokeKey>,
+        selection_keys: &HashSet<StrokeKey>,
+        target: StrokeKey,
+        above: bool,
+    ) {
+        let (selection_order, mut new_order): (Vec<StrokeKey>, Vec<StrokeKey>) = order
+            .into_iter()
+            .partition(|key| selection_keys.contains(key));
+
+        if let Some(target_position) = new_order.iter().position(|&key| key == target) {
+            let insert_position = if above {
+                target_position + 1
+            } else {
+                target_position
+            };
+            new_order.splice(insert_position..insert_position, selection_order);
+
+            self.apply_z_order(&new_order);
+        }
+    }
+}
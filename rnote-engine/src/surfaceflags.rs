@@ -11,6 +11,8 @@ pub struct SurfaceFlags {
     pub pen_changed: bool,
     pub sheet_changed: bool,
     pub selection_changed: bool,
+    /// Starts the animations of the pens, if they are not running already
+    pub animate: bool,
 }
 
 impl Default for SurfaceFlags {
@@ -24,6 +26,7 @@ impl Default for SurfaceFlags {
             pen_changed: false,
             sheet_changed: false,
             selection_changed: false,
+            animate: false,
         }
     }
 }
@@ -43,5 +46,6 @@ impl SurfaceFlags {
         self.pen_changed |= other.pen_changed;
         self.sheet_changed |= other.sheet_changed;
         self.selection_changed |= other.selection_changed;
+        self.animate |= other.animate;
     }
 }
@@ -616,7 +616,7 @@ pub struct XoppStroke {
     pub width: Vec<f64>,
     /// The stroke coordinates ( as points where a vec (1.0, 0.0) has length of 1 / 72inch )
     pub coords: Vec<na::Vector2<f64>>,
-    /// Optional timestamp
+    /// Optional timestamp, as seconds since the unix epoch
     pub timestamp: Option<u64>,
     /// Optional audio filename
     pub audio_filename: Option<String>,
@@ -713,6 +713,12 @@ impl XmlWritable for XoppStroke {
                 .collect::<Vec<String>>()
                 .join(" "),
         );
+        if let Some(timestamp) = self.timestamp {
+            w.write_attribute("ts", format!("{}", timestamp).as_str());
+        }
+        if let Some(audio_filename) = &self.audio_filename {
+            w.write_attribute("fn", audio_filename);
+        }
         w.write_text(
             &self
                 .coords
@@ -9,7 +9,12 @@ mod imp {
     use adw::subclass::prelude::AdwApplicationImpl;
     use gtk4::{gio, glib, prelude::*, subclass::prelude::*};
     use once_cell::sync::Lazy;
+    use rnote_engine::compose::pressurecurve::PressureCurveStyle;
+    use rnote_engine::compose::rough::roughoptions::FillStyle;
+    use rnote_engine::compose::shapes::ArrowHead;
+    use rnote_engine::compose::smooth::{DashPattern, LineCap};
     use rnote_engine::compose::textured::TexturedDotsDistribution;
+    use rnote_engine::pens::stabilizer::StabilizerMode;
     use rnote_engine::{
         pens::PenStyle,
         sheet::format::MeasureUnit,
@@ -20,8 +25,9 @@ mod imp {
         appmenu::AppMenu, appwindow::RnoteAppWindow, canvas::Canvas, canvas::ExpandMode,
         canvasmenu::CanvasMenu, colorpicker::colorsetter::ColorSetter, colorpicker::ColorPicker,
         config, mainheader::MainHeader, penssidebar::brushpage::BrushPage,
-        penssidebar::eraserpage::EraserPage, penssidebar::selectorpage::SelectorPage,
-        penssidebar::shaperpage::ShaperPage, penssidebar::toolspage::ToolsPage,
+        penssidebar::eraserpage::EraserPage, penssidebar::laserpage::LaserPage,
+        penssidebar::selectorpage::SelectorPage, penssidebar::shaperpage::ShaperPage,
+        penssidebar::toolspage::ToolsPage, penssidebar::typewriterpage::TypewriterPage,
         penssidebar::PensSideBar, selectionmodifier::modifiernode::ModifierNode,
         selectionmodifier::SelectionModifier, settingspanel::penshortcutrow::PenShortcutRow,
         settingspanel::SettingsPanel, unitentry::UnitEntry, utils,
@@ -112,6 +118,8 @@ mod imp {
             EraserPage::static_type();
             SelectorPage::static_type();
             ToolsPage::static_type();
+            TypewriterPage::static_type();
+            LaserPage::static_type();
             PenStyle::static_type();
             WorkspaceBrowser::static_type();
             FileRow::static_type();
@@ -120,6 +128,12 @@ mod imp {
             PatternStyle::static_type();
             UnitEntry::static_type();
             TexturedDotsDistribution::static_type();
+            ArrowHead::static_type();
+            DashPattern::static_type();
+            LineCap::static_type();
+            FillStyle::static_type();
+            PressureCurveStyle::static_type();
+            StabilizerMode::static_type();
             PenShortcutRow::static_type();
 
             // Load the resources
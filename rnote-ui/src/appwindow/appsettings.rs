@@ -124,6 +124,11 @@ impl RnoteAppWindow {
             )
             .build();
 
+        // undo depth
+        self.app_settings()
+            .bind("undo-depth", &self.canvas(), "undo-depth")
+            .build();
+
         // Brush page
         self.app_settings()
             .bind(
@@ -4,12 +4,17 @@ use crate::{
     utils,
     {canvas::Canvas, dialogs},
 };
+use rnote_engine::compose::transformable::MirrorAxis;
 use rnote_engine::pens::brush::BrushStyle;
+use rnote_engine::pens::eraser::{EraserMode, EraserShape};
 use rnote_engine::pens::selector::SelectorStyle;
 use rnote_engine::pens::shaper::{ShaperDrawStyle, ShaperStyle};
 use rnote_engine::pens::tools::ToolStyle;
-use rnote_engine::pens::{brush, selector, shaper, tools, PenEvent, PenStyle};
+use rnote_engine::pens::{brush, eraser, selector, shaper, tools, PenEvent, PenStyle};
 use rnote_engine::render::{self, RendererBackend};
+use rnote_engine::strokes::brushstroke::BrushStroke;
+use rnote_engine::strokes::textstroke::TextAlignment;
+use rnote_engine::strokesstate::selection_comp::{SelectionAlignment, SelectionDistribution};
 
 use gettextrs::gettext;
 use gtk4::PrintStatus;
@@ -89,10 +94,63 @@ impl RnoteAppWindow {
         self.add_action(&action_selection_trash);
         let action_selection_duplicate = gio::SimpleAction::new("selection-duplicate", None);
         self.add_action(&action_selection_duplicate);
+        let action_selection_beautify = gio::SimpleAction::new("selection-beautify", None);
+        self.add_action(&action_selection_beautify);
         let action_selection_select_all = gio::SimpleAction::new("selection-select-all", None);
         self.add_action(&action_selection_select_all);
         let action_selection_deselect_all = gio::SimpleAction::new("selection-deselect-all", None);
         self.add_action(&action_selection_deselect_all);
+        let action_selection_raise = gio::SimpleAction::new("selection-raise", None);
+        self.add_action(&action_selection_raise);
+        let action_selection_lower = gio::SimpleAction::new("selection-lower", None);
+        self.add_action(&action_selection_lower);
+        let action_selection_to_front = gio::SimpleAction::new("selection-to-front", None);
+        self.add_action(&action_selection_to_front);
+        let action_selection_to_back = gio::SimpleAction::new("selection-to-back", None);
+        self.add_action(&action_selection_to_back);
+        let action_selection_align = gio::SimpleAction::new(
+            "selection-align",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_align);
+        let action_selection_distribute = gio::SimpleAction::new(
+            "selection-distribute",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_distribute);
+        let action_selection_mirror = gio::SimpleAction::new(
+            "selection-mirror",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_mirror);
+        let action_selection_group = gio::SimpleAction::new("selection-group", None);
+        self.add_action(&action_selection_group);
+        let action_selection_ungroup = gio::SimpleAction::new("selection-ungroup", None);
+        self.add_action(&action_selection_ungroup);
+        let action_selection_switch_shape_drawstyle =
+            gio::SimpleAction::new("selection-switch-shape-drawstyle", None);
+        self.add_action(&action_selection_switch_shape_drawstyle);
+        let action_selection_node_editing = gio::PropertyAction::new(
+            "selection-node-editing",
+            &self.canvas().selection_modifier(),
+            "node-editing",
+        );
+        self.add_action(&action_selection_node_editing);
+        let action_selection_node_remove = gio::SimpleAction::new("selection-node-remove", None);
+        self.add_action(&action_selection_node_remove);
+        let action_selection_node_split = gio::SimpleAction::new("selection-node-split", None);
+        self.add_action(&action_selection_node_split);
+        let action_selection_node_pressure = gio::SimpleAction::new(
+            "selection-node-pressure",
+            Some(&glib::VariantType::new("s").unwrap()),
+        );
+        self.add_action(&action_selection_node_pressure);
+        let action_selection_join_brushstrokes =
+            gio::SimpleAction::new("selection-join-brushstrokes", None);
+        self.add_action(&action_selection_join_brushstrokes);
+        let action_selection_simplify_brushstrokes =
+            gio::SimpleAction::new("selection-simplify-brushstrokes", None);
+        self.add_action(&action_selection_simplify_brushstrokes);
         let action_clear_sheet = gio::SimpleAction::new("clear-sheet", None);
         self.add_action(&action_clear_sheet);
         let action_new_sheet = gio::SimpleAction::new("new-sheet", None);
@@ -148,6 +206,9 @@ impl RnoteAppWindow {
             Some(&glib::VariantType::new("s").unwrap()),
         );
         self.add_action(&action_selector_style);
+        let action_eraser_mode =
+            gio::SimpleAction::new("eraser-mode", Some(&glib::VariantType::new("s").unwrap()));
+        self.add_action(&action_eraser_mode);
         let action_tool_style =
             gio::SimpleAction::new("tool-style", Some(&glib::VariantType::new("s").unwrap()));
         self.add_action(&action_tool_style);
@@ -434,6 +495,12 @@ impl RnoteAppWindow {
                     "tools_style" => {
                         Some(PenStyle::ToolsStyle)
                     }
+                    "typewriter_style" => {
+                        Some(PenStyle::TypewriterStyle)
+                    }
+                    "laser_style" => {
+                        Some(PenStyle::LaserStyle)
+                    }
                     _ => {
                         log::error!("invalid target for action_current_pen, `{}`", pen_style);
                         None
@@ -486,6 +553,12 @@ impl RnoteAppWindow {
                     "tools_style" => {
                         Some(PenEvent::ChangeStyleOverride(Some(PenStyle::ToolsStyle)))
                     }
+                    "typewriter_style" => {
+                        Some(PenEvent::ChangeStyleOverride(Some(PenStyle::TypewriterStyle)))
+                    }
+                    "laser_style" => {
+                        Some(PenEvent::ChangeStyleOverride(Some(PenStyle::LaserStyle)))
+                    }
                     "none" => {
                         Some(PenEvent::ChangeStyleOverride(None))
                     }
@@ -512,6 +585,7 @@ impl RnoteAppWindow {
         action_brush_style.connect_activate(
         clone!(@weak self as appwindow => move |_action_brush_style, target| {
             let brush_style = target.unwrap().str().unwrap();
+            let old_brush_style = appwindow.canvas().pens().borrow().brush.style;
 
             match brush_style {
                 "marker" => {
@@ -529,9 +603,26 @@ impl RnoteAppWindow {
                     appwindow.canvas().pens().borrow_mut().brush.textured_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
                     appwindow.canvas().pens().borrow_mut().brush.textured_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
                 },
+                "highlighter" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Highlighter;
+                    appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
+                "calligraphy" => {
+                    appwindow.canvas().pens().borrow_mut().brush.style = brush::BrushStyle::Calligraphy;
+                    appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.width = appwindow.penssidebar().brush_page().width_spinbutton().value();
+                    appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.stroke_color = Some(appwindow.penssidebar().brush_page().colorpicker().current_color());
+                },
                 _ => { log::error!("set invalid state of action `brush-style`")}
             }
 
+            // The action is also activated when refreshing the ui, so only actual changes are applied to the selection
+            if appwindow.canvas().pens().borrow().brush.style != old_brush_style {
+                let brush = appwindow.canvas().pens().borrow().brush.clone();
+                appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                    strokes_state.change_selection_brush_style(&brush, renderer, zoom)
+                });
+            }
 
             adw::prelude::ActionGroupExt::activate_action(&appwindow, "refresh-ui-for-sheet", None);
         }),
@@ -552,9 +643,27 @@ impl RnoteAppWindow {
                 "ellipse" => {
                     appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Ellipse;
                 },
+                "arrow" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Arrow;
+                },
+                "polyline" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Polyline;
+                },
+                "polygon" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Polygon;
+                },
+                "arc" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::Arc;
+                },
+                "cubic_bezier" => {
+                    appwindow.canvas().pens().borrow_mut().shaper.style = shaper::ShaperStyle::CubicBezier;
+                },
                 _ => { log::error!("set invalid state of action `shaper-style`")}
             }
 
+            let fillable = appwindow.canvas().pens().borrow().shaper.style.fillable();
+            appwindow.penssidebar().shaper_page().fill_revealer().set_reveal_child(fillable);
+
 
             adw::prelude::ActionGroupExt::activate_action(&appwindow, "refresh-ui-for-sheet", None);
         }),
@@ -564,6 +673,7 @@ impl RnoteAppWindow {
         action_shaper_drawstyle.connect_activate(
         clone!(@weak self as appwindow => move |_action_shaper_drawstyle, target| {
             let shaper_drawstyle = target.unwrap().str().unwrap();
+            let old_shaper_drawstyle = appwindow.canvas().pens().borrow().shaper.drawstyle;
 
             match shaper_drawstyle {
                 "smooth" => {
@@ -581,6 +691,14 @@ impl RnoteAppWindow {
                 _ => { log::error!("set invalid state of action `shaper-drawstyle`")}
             }
 
+            // The action is also activated when refreshing the ui, so only actual changes are applied to the selection
+            if appwindow.canvas().pens().borrow().shaper.drawstyle != old_shaper_drawstyle {
+                let shaper = appwindow.canvas().pens().borrow().shaper.clone();
+                appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                    strokes_state.change_selection_shape_drawstyle(&shaper, renderer, zoom)
+                });
+            }
+
             adw::prelude::ActionGroupExt::activate_action(&appwindow, "refresh-ui-for-sheet", None);
         }));
 
@@ -596,6 +714,12 @@ impl RnoteAppWindow {
                 "rectangle" => {
                     appwindow.canvas().pens().borrow_mut().selector.style = selector::SelectorStyle::Rectangle;
                 },
+                "touch" => {
+                    appwindow.canvas().pens().borrow_mut().selector.style = selector::SelectorStyle::Touch;
+                },
+                "single" => {
+                    appwindow.canvas().pens().borrow_mut().selector.style = selector::SelectorStyle::Single;
+                },
                 _ => { log::error!("set invalid state of action `selector-style`")}
             }
 
@@ -603,6 +727,25 @@ impl RnoteAppWindow {
         }),
         );
 
+        // Eraser Mode
+        action_eraser_mode.connect_activate(
+        clone!(@weak self as appwindow => move |_action_eraser_mode, target| {
+            let eraser_mode = target.unwrap().str().unwrap();
+
+            match eraser_mode {
+                "trash-colliding-strokes" => {
+                    appwindow.canvas().pens().borrow_mut().eraser.mode = eraser::EraserMode::TrashCollidingStrokes;
+                },
+                "split-colliding-strokes" => {
+                    appwindow.canvas().pens().borrow_mut().eraser.mode = eraser::EraserMode::SplitCollidingStrokes;
+                },
+                _ => { log::error!("set invalid state of action `eraser-mode`")}
+            }
+
+            adw::prelude::ActionGroupExt::activate_action(&appwindow, "refresh-ui-for-sheet", None);
+        }),
+        );
+
         // Tool Style
         action_tool_style.connect_activate(
         clone!(@weak self as appwindow => move |_action_tool_style, target| {
@@ -655,6 +798,16 @@ impl RnoteAppWindow {
                         appwindow.narrow_tools_toggle().set_active(true);
                         appwindow.penssidebar().sidebar_stack().set_visible_child_name("tools_page");
                     }
+                    PenStyle::TypewriterStyle => {
+                        appwindow.mainheader().typewriter_toggle().set_active(true);
+                        appwindow.narrow_typewriter_toggle().set_active(true);
+                        appwindow.penssidebar().sidebar_stack().set_visible_child_name("typewriter_page");
+                    }
+                    PenStyle::LaserStyle => {
+                        appwindow.mainheader().laser_toggle().set_active(true);
+                        appwindow.narrow_laser_toggle().set_active(true);
+                        appwindow.penssidebar().sidebar_stack().set_visible_child_name("laser_page");
+                    }
                 }
 
                 // Brush
@@ -665,12 +818,24 @@ impl RnoteAppWindow {
                 appwindow.penssidebar().brush_page().texturedstyle_radius_y_spinbutton()
                     .set_value(pens.brush.textured_options.radii[1]);
                 appwindow.penssidebar().brush_page().set_texturedstyle_distribution_variant(pens.brush.textured_options.distribution);
+                appwindow.penssidebar().brush_page().texturedstyle_tilt_shading_switch().set_active(pens.brush.textured_options.tilt_shading);
+                appwindow.penssidebar().brush_page().calligraphystyle_nib_angle_spinbutton().set_value(pens.brush.calligraphy_options.nib_angle.to_degrees());
+                appwindow.penssidebar().brush_page().calligraphystyle_thickness_spinbutton().set_value(pens.brush.calligraphy_options.thickness);
+                appwindow.penssidebar().brush_page().calligraphystyle_follow_tilt_switch().set_active(pens.brush.calligraphy_options.follow_tilt);
+                appwindow.penssidebar().brush_page().recognize_shapes_togglebutton().set_active(pens.brush.recognize_shapes);
+                appwindow.penssidebar().brush_page().refresh_pressurecurve(pens.brush.pressure_curve);
+                appwindow.penssidebar().brush_page().set_stabilizer_mode_variant(pens.brush.stabilizer.mode);
+                appwindow.penssidebar().brush_page().stabilizer_strength_spinbutton().set_value(pens.brush.stabilizer.strength);
+                appwindow.penssidebar().brush_page().set_linestyle_dash_pattern_variant(pens.brush.smooth_options.dash_pattern);
+                appwindow.penssidebar().brush_page().set_linestyle_line_cap_variant(pens.brush.smooth_options.line_cap);
+                appwindow.penssidebar().brush_page().linestyle_dash_offset_spinbutton().set_value(pens.brush.smooth_options.dash_offset);
                 match pens.brush.style {
                     BrushStyle::Marker => {
                         appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_marker_row()));
                         appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.smooth_options.width);
                         appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.smooth_options.stroke_color);
                         appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().linestyle_menubutton().set_sensitive(true);
                         appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-marker-symbolic"));
                     },
                     BrushStyle::Solid => {
@@ -678,6 +843,7 @@ impl RnoteAppWindow {
                         appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.smooth_options.width);
                         appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.smooth_options.stroke_color);
                         appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().linestyle_menubutton().set_sensitive(true);
                         appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-solid-symbolic"));
                     },
                     BrushStyle::Textured => {
@@ -685,8 +851,27 @@ impl RnoteAppWindow {
                         appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.textured_options.width);
                         appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.textured_options.stroke_color);
                         appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(true);
+                        appwindow.penssidebar().brush_page().styleconfig_stack().set_visible_child_name("textured");
+                        appwindow.penssidebar().brush_page().linestyle_menubutton().set_sensitive(false);
                         appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-textured-symbolic"));
                     },
+                    BrushStyle::Highlighter => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_highlighter_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.smooth_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.smooth_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().linestyle_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-highlighter-symbolic"));
+                    },
+                    BrushStyle::Calligraphy => {
+                        appwindow.penssidebar().brush_page().brushstyle_listbox().select_row(Some(&appwindow.penssidebar().brush_page().brushstyle_calligraphy_row()));
+                        appwindow.penssidebar().brush_page().width_spinbutton().set_value(pens.brush.calligraphy_options.width);
+                        appwindow.penssidebar().brush_page().colorpicker().set_current_color(pens.brush.calligraphy_options.stroke_color);
+                        appwindow.penssidebar().brush_page().styleconfig_menubutton().set_sensitive(true);
+                        appwindow.penssidebar().brush_page().styleconfig_stack().set_visible_child_name("calligraphy");
+                        appwindow.penssidebar().brush_page().linestyle_menubutton().set_sensitive(false);
+                        appwindow.penssidebar().brush_page().brushstyle_image().set_icon_name(Some("pen-brush-style-calligraphy-symbolic"));
+                    },
                 }
 
                 // Shaper
@@ -702,6 +887,17 @@ impl RnoteAppWindow {
                 appwindow.penssidebar().shaper_page()
                     .roughconfig_multistroke_switch()
                     .set_active(!pens.shaper.rough_options.disable_multistroke);
+                appwindow.penssidebar().shaper_page()
+                    .set_roughconfig_fillstyle_variant(pens.shaper.rough_options.fill_style);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_hachureangle_spinbutton()
+                    .set_value(pens.shaper.rough_options.hachure_angle);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_hachuregap_spinbutton()
+                    .set_value(pens.shaper.rough_options.hachure_gap);
+                appwindow.penssidebar().shaper_page()
+                    .roughconfig_fillweight_spinbutton()
+                    .set_value(pens.shaper.rough_options.fill_weight);
 
                 match pens.shaper.style {
                     ShaperStyle::Line => {
@@ -713,7 +909,28 @@ impl RnoteAppWindow {
                     ShaperStyle::Ellipse => {
                         appwindow.penssidebar().shaper_page().ellipse_toggle().set_active(true);
                     }
+                    ShaperStyle::Arrow => {
+                        appwindow.penssidebar().shaper_page().arrow_toggle().set_active(true);
+                    }
+                    ShaperStyle::Polyline => {
+                        appwindow.penssidebar().shaper_page().polyline_toggle().set_active(true);
+                    }
+                    ShaperStyle::Polygon => {
+                        appwindow.penssidebar().shaper_page().polygon_toggle().set_active(true);
+                    }
+                    ShaperStyle::Arc => {
+                        appwindow.penssidebar().shaper_page().arc_toggle().set_active(true);
+                    }
+                    ShaperStyle::CubicBezier => {
+                        appwindow.penssidebar().shaper_page().cubbez_toggle().set_active(true);
+                    }
                 }
+                appwindow.penssidebar().shaper_page().set_arrowconfig_start_head_variant(pens.shaper.arrow_start_head);
+                appwindow.penssidebar().shaper_page().set_arrowconfig_end_head_variant(pens.shaper.arrow_end_head);
+                appwindow.penssidebar().shaper_page().arrowconfig_head_length_spinbutton().set_value(pens.shaper.arrow_head_length);
+                appwindow.penssidebar().shaper_page().set_linestyle_dash_pattern_variant(pens.shaper.smooth_options.dash_pattern);
+                appwindow.penssidebar().shaper_page().set_linestyle_line_cap_variant(pens.shaper.smooth_options.line_cap);
+                appwindow.penssidebar().shaper_page().linestyle_dash_offset_spinbutton().set_value(pens.shaper.smooth_options.dash_offset);
                 match pens.shaper.drawstyle {
                     ShaperDrawStyle::Smooth => {
                         appwindow.penssidebar().shaper_page().drawstyle_smooth_toggle().set_active(true);
@@ -729,12 +946,24 @@ impl RnoteAppWindow {
                     },
                 }
                 // Eraser
+                match pens.eraser.mode {
+                    EraserMode::TrashCollidingStrokes => appwindow.penssidebar().eraser_page().erasermode_trash_toggle().set_active(true),
+                    EraserMode::SplitCollidingStrokes => appwindow.penssidebar().eraser_page().erasermode_split_toggle().set_active(true),
+                }
+                match pens.eraser.shape {
+                    EraserShape::Square => appwindow.penssidebar().eraser_page().erasershape_square_toggle().set_active(true),
+                    EraserShape::Circle => appwindow.penssidebar().eraser_page().erasershape_circle_toggle().set_active(true),
+                }
                 appwindow.penssidebar().eraser_page().width_spinbutton().set_value(pens.eraser.width);
+                appwindow.penssidebar().eraser_page().pressure_sensitive_togglebutton().set_active(pens.eraser.pressure_sensitive);
+                appwindow.penssidebar().eraser_page().erase_images_togglebutton().set_active(pens.eraser.erase_images);
 
                 // Selector
                 match pens.selector.style {
                     SelectorStyle::Polygon => appwindow.penssidebar().selector_page().selectorstyle_polygon_toggle().set_active(true),
                     SelectorStyle::Rectangle => appwindow.penssidebar().selector_page().selectorstyle_rect_toggle().set_active(true),
+                    SelectorStyle::Touch => appwindow.penssidebar().selector_page().selectorstyle_touch_toggle().set_active(true),
+                    SelectorStyle::Single => appwindow.penssidebar().selector_page().selectorstyle_single_toggle().set_active(true),
                 }
 
                 // Tools
@@ -743,6 +972,28 @@ impl RnoteAppWindow {
                     ToolStyle::DragProximity => appwindow.penssidebar().tools_page().toolstyle_dragproximity_toggle().set_active(true),
                 }
 
+                // Typewriter
+                appwindow.penssidebar().typewriter_page().font_button().set_font(&pens.typewriter.text_style.font_family);
+                appwindow.penssidebar().typewriter_page().font_size_spinbutton().set_value(pens.typewriter.text_style.font_size);
+                match pens.typewriter.text_style.alignment {
+                    TextAlignment::Start => appwindow.penssidebar().typewriter_page().alignment_start_toggle().set_active(true),
+                    TextAlignment::Center => appwindow.penssidebar().typewriter_page().alignment_center_toggle().set_active(true),
+                    TextAlignment::End => appwindow.penssidebar().typewriter_page().alignment_end_toggle().set_active(true),
+                }
+                if let Some(max_width) = pens.typewriter.text_style.max_width {
+                    appwindow.penssidebar().typewriter_page().max_width_spinbutton().set_value(max_width);
+                }
+                appwindow.penssidebar().typewriter_page().wrap_togglebutton().set_active(pens.typewriter.text_style.max_width.is_some());
+                appwindow.penssidebar().typewriter_page().colorpicker().set_current_color(Some(pens.typewriter.text_style.color));
+
+                // Laser
+                appwindow.penssidebar().laser_page().width_spinbutton().set_value(pens.laser.width);
+                appwindow.penssidebar().laser_page().fade_time_spinbutton().set_value(pens.laser.fade_time);
+                appwindow.penssidebar().laser_page().colorpicker().set_current_color(Some(pens.laser.color));
+
+                // Layers panel
+                appwindow.layers_panel().refresh_for_sheet(&appwindow);
+
                 // Settings panel
                 appwindow.settings_panel().refresh_for_sheet(&appwindow);
             }),
@@ -770,6 +1021,22 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Beautify Selection
+        action_selection_beautify.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_beautify, _| {
+                let shape_recognizer = appwindow.canvas().pens().borrow().brush.shape_recognizer;
+                appwindow.canvas().sheet().borrow_mut().strokes_state.beautify_selection(
+                    &shape_recognizer,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                appwindow.canvas().regenerate_content(false, true);
+            }),
+        );
+
         // select all strokes
         action_selection_select_all.connect_activate(
             clone!(@weak self as appwindow => move |_action_selection_select_all, _| {
@@ -792,6 +1059,276 @@ impl RnoteAppWindow {
             }),
         );
 
+        // Raise selection
+        action_selection_raise.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_raise, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.raise_selection();
+
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Lower selection
+        action_selection_lower.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_lower, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.lower_selection();
+
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Selection to front
+        action_selection_to_front.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_to_front, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.selection_to_front();
+
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Selection to back
+        action_selection_to_back.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_to_back, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.selection_to_back();
+
+                appwindow.canvas().set_unsaved_changes(true);
+                appwindow.canvas().queue_draw();
+            }),
+        );
+
+        // Align selection
+        action_selection_align.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_align, target| {
+                let alignment = match target.unwrap().str().unwrap() {
+                    "left" => SelectionAlignment::Left,
+                    "center" => SelectionAlignment::Center,
+                    "right" => SelectionAlignment::Right,
+                    "top" => SelectionAlignment::Top,
+                    "middle" => SelectionAlignment::Middle,
+                    "bottom" => SelectionAlignment::Bottom,
+                    _ => {
+                        log::error!("activated invalid target of action `selection-align`");
+                        return;
+                    }
+                };
+
+                let aligned = appwindow.canvas().sheet().borrow_mut().strokes_state.align_selection(
+                    alignment,
+                    appwindow.canvas().zoom(),
+                );
+
+                if aligned {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+                }
+            }),
+        );
+
+        // Distribute selection
+        action_selection_distribute.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_distribute, target| {
+                let distribution = match target.unwrap().str().unwrap() {
+                    "horizontal" => SelectionDistribution::Horizontal,
+                    "vertical" => SelectionDistribution::Vertical,
+                    _ => {
+                        log::error!("activated invalid target of action `selection-distribute`");
+                        return;
+                    }
+                };
+
+                let distributed = appwindow.canvas().sheet().borrow_mut().strokes_state.distribute_selection(
+                    distribution,
+                    appwindow.canvas().zoom(),
+                );
+
+                if distributed {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+                }
+            }),
+        );
+
+        // Mirror selection
+        action_selection_mirror.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_mirror, target| {
+                let axis = match target.unwrap().str().unwrap() {
+                    "horizontal" => MirrorAxis::Horizontal,
+                    "vertical" => MirrorAxis::Vertical,
+                    _ => {
+                        log::error!("activated invalid target of action `selection-mirror`");
+                        return;
+                    }
+                };
+
+                let mirrored = appwindow.canvas().sheet().borrow_mut().strokes_state.mirror_selection(
+                    axis,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if mirrored {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Switch the draw style of the selected shapes
+        action_selection_switch_shape_drawstyle.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_switch_shape_drawstyle, _| {
+                let switched = appwindow.canvas().sheet().borrow_mut().strokes_state.switch_selection_shape_drawstyle(
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if switched {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Remove the selected node of the brush stroke
+        action_selection_node_remove.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_node_remove, _| {
+                let node = if let Some(node) = appwindow.canvas().selection_modifier().selected_handle() {
+                    node
+                } else {
+                    return;
+                };
+
+                let removed = appwindow.canvas().sheet().borrow_mut().strokes_state.remove_selection_brush_node(
+                    node,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if removed {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Split the brush stroke at the selected node
+        action_selection_node_split.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_node_split, _| {
+                let node = if let Some(node) = appwindow.canvas().selection_modifier().selected_handle() {
+                    node
+                } else {
+                    return;
+                };
+
+                let split = appwindow.canvas().sheet().borrow_mut().strokes_state.split_selection_brush_stroke(
+                    node,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if split {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Change the pressure of the selected node
+        action_selection_node_pressure.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_node_pressure, target| {
+                let step = match target.unwrap().str().unwrap() {
+                    "increase" => BrushStroke::NODE_PRESSURE_STEP,
+                    "decrease" => -BrushStroke::NODE_PRESSURE_STEP,
+                    _ => {
+                        log::error!("activated invalid target of action `selection-node-pressure`");
+                        return;
+                    }
+                };
+                let node = if let Some(node) = appwindow.canvas().selection_modifier().selected_handle() {
+                    node
+                } else {
+                    return;
+                };
+                let pressure = if let Some(pressure) = appwindow.canvas().sheet().borrow().strokes_state.selection_brush_node_pressure(node) {
+                    pressure
+                } else {
+                    return;
+                };
+
+                let changed = appwindow.canvas().sheet().borrow_mut().strokes_state.set_selection_brush_node_pressure(
+                    node,
+                    pressure + step,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if changed {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Join the selected brush strokes
+        action_selection_join_brushstrokes.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_join_brushstrokes, _| {
+                let joined = appwindow.canvas().sheet().borrow_mut().strokes_state.join_selection_brush_strokes(
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if joined {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Simplify the selected brush strokes, which reduces their nodes
+        action_selection_simplify_brushstrokes.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_simplify_brushstrokes, _| {
+                let simplified = appwindow.canvas().sheet().borrow_mut().strokes_state.simplify_selection_brush_strokes(
+                    BrushStroke::SIMPLIFY_TOLERANCE_DEFAULT,
+                    appwindow.canvas().renderer(),
+                    appwindow.canvas().zoom(),
+                );
+
+                if simplified {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+
+        // Group selection
+        action_selection_group.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_group, _| {
+                if appwindow.canvas().sheet().borrow_mut().strokes_state.group_selection().is_some() {
+                    appwindow.canvas().set_unsaved_changes(true);
+                }
+            }),
+        );
+
+        // Ungroup selection
+        action_selection_ungroup.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_ungroup, _| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.ungroup_selection();
+
+                appwindow.canvas().set_unsaved_changes(true);
+            }),
+        );
+
         // Clear sheet
         action_clear_sheet.connect_activate(clone!(@weak self as appwindow => move |_, _| {
             dialogs::dialog_clear_sheet(&appwindow);
@@ -799,16 +1336,20 @@ impl RnoteAppWindow {
 
         // Undo stroke
         action_undo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
-            appwindow.canvas().sheet().borrow_mut().strokes_state.undo_last_stroke();
-            appwindow.canvas().resize_sheet_autoexpand();
-            appwindow.canvas().update_background_rendernode(true);
+            let undone = appwindow.canvas().sheet().borrow_mut().undo();
+
+            if undone {
+                appwindow.refresh_after_history_change();
+            }
         }));
 
         // Redo stroke
         action_redo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
-            appwindow.canvas().sheet().borrow_mut().strokes_state.redo_last_stroke();
-            appwindow.canvas().resize_sheet_autoexpand();
-            appwindow.canvas().update_background_rendernode(true);
+            let redone = appwindow.canvas().sheet().borrow_mut().redo();
+
+            if redone {
+                appwindow.refresh_after_history_change();
+            }
         }));
 
         // Zoom reset
@@ -1080,6 +1621,19 @@ impl RnoteAppWindow {
     }));
     }
 
+    /// Updates the canvas and the UI after the sheet was changed by undo or redo
+    fn refresh_after_history_change(&self) {
+        self.canvas().resize_sheet_autoexpand();
+        self.canvas()
+            .selection_modifier()
+            .update_state(&self.canvas());
+        self.canvas().regenerate_background(false);
+        self.canvas().regenerate_content(false, true);
+        self.canvas().set_unsaved_changes(true);
+
+        adw::prelude::ActionGroupExt::activate_action(self, "refresh-ui-for-sheet", None);
+    }
+
     pub fn setup_action_accels(&self) {
         let app = self.application().unwrap().downcast::<RnoteApp>().unwrap();
 
@@ -1103,6 +1657,8 @@ impl RnoteAppWindow {
         app.set_accels_for_action("win.selection-duplicate", &["<Ctrl>d"]);
         app.set_accels_for_action("win.selection-select-all", &["<Ctrl>a"]);
         app.set_accels_for_action("win.selection-deselect-all", &["Escape"]);
+        app.set_accels_for_action("win.selection-group", &["<Ctrl>g"]);
+        app.set_accels_for_action("win.selection-ungroup", &["<Ctrl><Shift>g"]);
         //app.set_accels_for_action("win.pen-style-override::eraser_style", &["d"]);
         app.set_accels_for_action("win.clipboard-copy-selection", &["<Ctrl>c"]);
         app.set_accels_for_action("win.clipboard-paste-selection", &["<Ctrl>v"]);
@@ -15,8 +15,9 @@ mod imp {
 
     use crate::audioplayer::RnoteAudioPlayer;
     use crate::{
-        app::RnoteApp, canvas::Canvas, config, dialogs, mainheader::MainHeader,
-        penssidebar::PensSideBar, settingspanel::SettingsPanel, workspacebrowser::WorkspaceBrowser,
+        app::RnoteApp, canvas::Canvas, config, dialogs, layerspanel::LayersPanel,
+        mainheader::MainHeader, penssidebar::PensSideBar, settingspanel::SettingsPanel,
+        workspacebrowser::WorkspaceBrowser,
     };
 
     #[derive(Debug, CompositeTemplate)]
@@ -40,6 +41,8 @@ mod imp {
         #[template_child]
         pub canvas: TemplateChild<Canvas>,
         #[template_child]
+        pub layers_panel: TemplateChild<LayersPanel>,
+        #[template_child]
         pub settings_panel: TemplateChild<SettingsPanel>,
         #[template_child]
         pub sidebar_scroller: TemplateChild<ScrolledWindow>,
@@ -78,6 +81,10 @@ mod imp {
         #[template_child]
         pub narrow_tools_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub narrow_typewriter_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub narrow_laser_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub penssidebar: TemplateChild<PensSideBar>,
     }
 
@@ -96,6 +103,7 @@ mod imp {
                 canvas_box: TemplateChild::<gtk4::Box>::default(),
                 canvas_scroller: TemplateChild::<ScrolledWindow>::default(),
                 canvas: TemplateChild::<Canvas>::default(),
+                layers_panel: TemplateChild::<LayersPanel>::default(),
                 settings_panel: TemplateChild::<SettingsPanel>::default(),
                 sidebar_scroller: TemplateChild::<ScrolledWindow>::default(),
                 sidebar_grid: TemplateChild::<Grid>::default(),
@@ -115,6 +123,8 @@ mod imp {
                 narrow_eraser_toggle: TemplateChild::<ToggleButton>::default(),
                 narrow_selector_toggle: TemplateChild::<ToggleButton>::default(),
                 narrow_tools_toggle: TemplateChild::<ToggleButton>::default(),
+                narrow_typewriter_toggle: TemplateChild::<ToggleButton>::default(),
+                narrow_laser_toggle: TemplateChild::<ToggleButton>::default(),
                 penssidebar: TemplateChild::<PensSideBar>::default(),
             }
         }
@@ -188,6 +198,18 @@ mod imp {
                     adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"tools_style".to_variant()));
                 }
             }));
+
+            self.narrow_typewriter_toggle.connect_toggled(clone!(@weak obj as appwindow => move |narrow_typewriter_toggle| {
+                if narrow_typewriter_toggle.is_active() {
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+                }
+            }));
+
+            self.narrow_laser_toggle.connect_toggled(clone!(@weak obj as appwindow => move |narrow_laser_toggle| {
+                if narrow_laser_toggle.is_active() {
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"laser_style".to_variant()));
+                }
+            }));
         }
 
         fn properties() -> &'static [glib::ParamSpec] {
@@ -537,6 +559,10 @@ impl RnoteAppWindow {
         imp::RnoteAppWindow::from_instance(self).canvas.get()
     }
 
+    pub fn layers_panel(&self) -> LayersPanel {
+        imp::RnoteAppWindow::from_instance(self).layers_panel.get()
+    }
+
     pub fn settings_panel(&self) -> SettingsPanel {
         imp::RnoteAppWindow::from_instance(self)
             .settings_panel
@@ -623,6 +649,18 @@ impl RnoteAppWindow {
             .get()
     }
 
+    pub fn narrow_typewriter_toggle(&self) -> ToggleButton {
+        imp::RnoteAppWindow::from_instance(self)
+            .narrow_typewriter_toggle
+            .get()
+    }
+
+    pub fn narrow_laser_toggle(&self) -> ToggleButton {
+        imp::RnoteAppWindow::from_instance(self)
+            .narrow_laser_toggle
+            .get()
+    }
+
     pub fn penssidebar(&self) -> PensSideBar {
         imp::RnoteAppWindow::from_instance(self).penssidebar.get()
     }
@@ -694,6 +732,9 @@ impl RnoteAppWindow {
                 .update_state(&self.canvas());
             self.queue_resize();
         }
+        if surface_flags.animate {
+            self.canvas().start_pens_animation();
+        }
 
         false
     }
@@ -704,6 +745,7 @@ impl RnoteAppWindow {
             log::error!("failed to init audio_player with Err {}", e);
         }
         self.imp().workspacebrowser.get().init(self);
+        self.imp().layers_panel.get().init(self);
         self.imp().settings_panel.get().init(self);
         self.imp().mainheader.get().init(self);
         self.imp().mainheader.get().canvasmenu().init(self);
@@ -714,6 +756,8 @@ impl RnoteAppWindow {
         self.imp().penssidebar.get().eraser_page().init(self);
         self.imp().penssidebar.get().selector_page().init(self);
         self.imp().penssidebar.get().tools_page().init(self);
+        self.imp().penssidebar.get().typewriter_page().init(self);
+        self.imp().penssidebar.get().laser_page().init(self);
         self.imp().canvas.get().init(self);
         //StrokesState::init(self);
         self.imp().canvas.get().selection_modifier().init(self);
@@ -303,10 +303,10 @@ impl RnoteAudioPlayer {
     pub fn play_brush_begin(&self, brush_style: BrushStyle) {
         if self.enabled {
             match brush_style {
-                BrushStyle::Marker => {
+                BrushStyle::Marker | BrushStyle::Highlighter => {
                     self.play_marker_sound();
                 }
-                BrushStyle::Solid | BrushStyle::Textured => {
+                BrushStyle::Solid | BrushStyle::Textured | BrushStyle::Calligraphy => {
                     self.play_pencil_sound_w_timeout(Self::PLAY_TIMEOUT_TIME);
                 }
             }
@@ -316,8 +316,8 @@ impl RnoteAudioPlayer {
     pub fn play_brush_motion(&self, brush_style: BrushStyle) {
         if self.enabled {
             match brush_style {
-                BrushStyle::Marker => {}
-                BrushStyle::Solid | BrushStyle::Textured => {
+                BrushStyle::Marker | BrushStyle::Highlighter => {}
+                BrushStyle::Solid | BrushStyle::Textured | BrushStyle::Calligraphy => {
                     self.play_pencil_sound_w_timeout(Self::PLAY_TIMEOUT_TIME);
                 }
             }
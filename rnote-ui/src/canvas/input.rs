@@ -1,5 +1,7 @@
 use gtk4::{gdk, prelude::*, GestureDrag, GestureStylus};
+use rnote_engine::pens::selector::SelectionOperation;
 use rnote_engine::pens::shortcuts::ShortcutKey;
+use rnote_engine::pens::typewriter::KeyboardKey;
 use rnote_engine::pens::{PenEvent, PenStyle};
 use std::collections::VecDeque;
 
@@ -13,10 +15,7 @@ pub fn transform_inputdata(
     zoom: f64,
 ) {
     data_entries.iter_mut().for_each(|inputdata| {
-        *inputdata = InputData::new(
-            inputdata.pos().scale(1.0 / zoom) + offset,
-            inputdata.pressure(),
-        )
+        inputdata.set_pos(inputdata.pos().scale(1.0 / zoom) + offset);
     });
 }
 
@@ -80,17 +79,17 @@ pub fn debug_drag_gesture(drag_gesture: &GestureDrag) {
 /// Retreive inputdata from a (emulated) pointer
 /// X and Y is already available from closure, and should not retreived from .axis() (because of gtk weirdness)
 pub fn retreive_pointer_inputdata(
-    _mouse_drawing_gesture: &GestureDrag,
+    mouse_drawing_gesture: &GestureDrag,
     x: f64,
     y: f64,
 ) -> VecDeque<InputData> {
     let mut data_entries: VecDeque<InputData> = VecDeque::with_capacity(1);
     //std::thread::sleep(std::time::Duration::from_millis(100));
 
-    data_entries.push_back(InputData::new(
-        na::vector![x, y],
-        InputData::PRESSURE_DEFAULT,
-    ));
+    let mut inputdata = InputData::new(na::vector![x, y], InputData::PRESSURE_DEFAULT);
+    inputdata.set_timestamp(retreive_event_timestamp(mouse_drawing_gesture));
+
+    data_entries.push_back(inputdata);
     data_entries
 }
 
@@ -136,6 +135,20 @@ pub fn retreive_stylus_shortcut_key(stylus_drawing_gesture: &GestureStylus) -> O
     shortcut_key
 }
 
+/// Retreives the selection operation for the selector from the pressed modifier keys.
+/// Shift adds to the current selection, Ctrl subtracts from it
+pub fn retreive_modifier_selection_operation(
+    modifier: gdk::ModifierType,
+) -> Option<SelectionOperation> {
+    if modifier.contains(gdk::ModifierType::SHIFT_MASK) {
+        Some(SelectionOperation::Add)
+    } else if modifier.contains(gdk::ModifierType::CONTROL_MASK) {
+        Some(SelectionOperation::Subtract)
+    } else {
+        None
+    }
+}
+
 /// Retreives available input axes, defaults if not available.
 /// X and Y is already available from closure, and should not retreived from .axis() (because of gtk weirdness)
 pub fn retreive_stylus_inputdata(
@@ -153,15 +166,60 @@ pub fn retreive_stylus_inputdata(
         InputData::PRESSURE_DEFAULT
     };
 
-    data_entries.push_back(InputData::new(na::vector![x, y], pressure));
+    let mut inputdata = InputData::new(na::vector![x, y], pressure);
+
+    // Tilt is reported in degrees between -90 and 90, the rotation in degrees between 0 and 360
+    let tilt_x = stylus_drawing_gesture.axis(gdk::AxisUse::Xtilt);
+    let tilt_y = stylus_drawing_gesture.axis(gdk::AxisUse::Ytilt);
+    if let (Some(tilt_x), Some(tilt_y)) = (tilt_x, tilt_y) {
+        inputdata.set_tilt(Some(na::vector![tilt_x, tilt_y]));
+    }
+    inputdata.set_rotation(
+        stylus_drawing_gesture
+            .axis(gdk::AxisUse::Rotation)
+            .map(|rotation| rotation.to_radians()),
+    );
+    inputdata.set_distance(stylus_drawing_gesture.axis(gdk::AxisUse::Distance));
+    inputdata.set_timestamp(retreive_event_timestamp(stylus_drawing_gesture));
+
+    data_entries.push_back(inputdata);
 
     data_entries
 }
 
+/// Retreives the timestamp of the current event of the gesture in seconds. The event time is in milliseconds from a monotonic clock
+pub fn retreive_event_timestamp(gesture: &impl IsA<gtk4::Gesture>) -> Option<f64> {
+    match gesture.current_event_time() {
+        0 => None,
+        time => Some(f64::from(time) / 1000.0),
+    }
+}
+
+/// Retreives the name of the device of the stylus gesture, to look up its default pressure curve
+pub fn retreive_stylus_device_name(stylus_drawing_gesture: &GestureStylus) -> Option<String> {
+    stylus_drawing_gesture
+        .device()
+        .map(|device| device.name().to_string())
+}
+
+/// Process the device the input comes from
+pub fn process_input_device(device_name: Option<String>, appwindow: &RnoteAppWindow) {
+    let pressure_curve_changed = appwindow
+        .canvas()
+        .pens()
+        .borrow_mut()
+        .set_input_device(device_name);
+
+    if pressure_curve_changed {
+        adw::prelude::ActionGroupExt::activate_action(appwindow, "refresh-ui-for-sheet", None);
+    }
+}
+
 /// Process "Pen down"
 pub fn process_pen_down(
     data_entries: VecDeque<InputData>,
     shortcut_key: Option<ShortcutKey>,
+    selection_operation: Option<SelectionOperation>,
     appwindow: &RnoteAppWindow,
 ) {
     let pen_event = match shortcut_key {
@@ -174,9 +232,22 @@ pub fn process_pen_down(
                 .canvas()
                 .set_cursor(Some(&appwindow.canvas().motion_cursor()));
 
+            // Keyboard input is needed for the typewriter
+            appwindow.canvas().grab_focus();
+
             // We hide the selection modifier here already, but actually only deselect all strokes when ending the stroke (for performance reasons)
             appwindow.canvas().selection_modifier().set_visible(false);
 
+            // The override is reset by the pens when the stroke is finished
+            if let Some(selection_operation) = selection_operation {
+                appwindow
+                    .canvas()
+                    .pens()
+                    .borrow_mut()
+                    .selector
+                    .operation_override = Some(selection_operation);
+            }
+
             PenEvent::DownEvent {
                 data_entries,
                 shortcut_key,
@@ -267,3 +338,51 @@ pub fn process_pen_up(
 
     appwindow.handle_surface_flags(surface_flags);
 }
+
+/// Maps the gdk key to the keyboard key of the engine. Keys pressed together with Ctrl or Alt are left to the shortcuts
+pub fn retreive_keyboard_key(gdk_key: gdk::keys::Key, modifier: gdk::ModifierType) -> KeyboardKey {
+    if modifier.intersects(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::ALT_MASK) {
+        return KeyboardKey::Unsupported;
+    }
+
+    match gdk_key {
+        gdk::keys::constants::BackSpace => KeyboardKey::BackSpace,
+        gdk::keys::constants::Delete | gdk::keys::constants::KP_Delete => KeyboardKey::Delete,
+        gdk::keys::constants::Return | gdk::keys::constants::KP_Enter => KeyboardKey::Linefeed,
+        gdk::keys::constants::Tab => KeyboardKey::HorizontalTab,
+        gdk::keys::constants::Left | gdk::keys::constants::KP_Left => KeyboardKey::NavLeft,
+        gdk::keys::constants::Right | gdk::keys::constants::KP_Right => KeyboardKey::NavRight,
+        gdk::keys::constants::Up | gdk::keys::constants::KP_Up => KeyboardKey::NavUp,
+        gdk::keys::constants::Down | gdk::keys::constants::KP_Down => KeyboardKey::NavDown,
+        gdk::keys::constants::Home | gdk::keys::constants::KP_Home => KeyboardKey::Home,
+        gdk::keys::constants::End | gdk::keys::constants::KP_End => KeyboardKey::End,
+        gdk::keys::constants::Escape => KeyboardKey::Escape,
+        _ => gdk_key
+            .to_unicode()
+            .filter(|c| !c.is_control())
+            .map_or(KeyboardKey::Unsupported, KeyboardKey::Unicode),
+    }
+}
+
+/// Process a pressed key. Returns true if the key was handled by the current pen
+pub fn process_keyboard_key_pressed(keyboard_key: KeyboardKey, appwindow: &RnoteAppWindow) -> bool {
+    // Only the typewriter receives keys, everything else is left to the shortcuts of the app
+    if keyboard_key == KeyboardKey::Unsupported
+        || appwindow.canvas().pens().borrow().style_w_override() != PenStyle::TypewriterStyle
+        || !appwindow.canvas().pens().borrow().typewriter.is_active()
+    {
+        return false;
+    }
+
+    let surface_flags = appwindow.canvas().pens().borrow_mut().handle_event(
+        PenEvent::KeyPressed(keyboard_key),
+        &mut *appwindow.canvas().sheet().borrow_mut(),
+        Some(appwindow.canvas().viewport_in_sheet_coords()),
+        appwindow.canvas().zoom(),
+        appwindow.canvas().renderer(),
+    );
+
+    appwindow.handle_surface_flags(surface_flags);
+
+    true
+}
@@ -14,11 +14,12 @@ mod imp {
     use rnote_engine::pens::{PenStyle, Pens};
     use rnote_engine::render::Renderer;
     use rnote_engine::sheet::Sheet;
+    use rnote_engine::strokesstate::history::History;
     use rnote_engine::strokesstate::render_comp::visual_debug;
 
     use gtk4::{
-        gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*, GestureDrag, GestureStylus,
-        PropagationPhase, Snapshot, Widget,
+        gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*, EventControllerKey,
+        GestureDrag, GestureStylus, PropagationPhase, Snapshot, Widget,
     };
     use gtk4::{AccessibleRole, Adjustment, Scrollable, ScrollablePolicy};
 
@@ -36,11 +37,13 @@ mod imp {
         pub hscroll_policy: Cell<ScrollablePolicy>,
         pub vscroll_policy: Cell<ScrollablePolicy>,
         pub zoom_timeout_id: RefCell<Option<glib::SourceId>>,
+        pub pens_animation_tick_id: RefCell<Option<gtk4::TickCallbackId>>,
         pub cursor: gdk::Cursor,
         pub motion_cursor: gdk::Cursor,
         pub stylus_drawing_gesture: GestureStylus,
         pub mouse_drawing_gesture: GestureDrag,
         pub touch_drawing_gesture: GestureDrag,
+        pub key_controller: EventControllerKey,
         pub selection_modifier: SelectionModifier,
         pub return_to_center_toast: RefCell<Option<adw::Toast>>,
 
@@ -84,6 +87,11 @@ mod imp {
                 .propagation_phase(PropagationPhase::Bubble)
                 .build();
 
+            let key_controller = EventControllerKey::builder()
+                .name("key_controller")
+                .propagation_phase(PropagationPhase::Capture)
+                .build();
+
             // Gesture grouping
             mouse_drawing_gesture.group_with(&stylus_drawing_gesture);
             touch_drawing_gesture.group_with(&stylus_drawing_gesture);
@@ -121,7 +129,9 @@ mod imp {
                 stylus_drawing_gesture,
                 mouse_drawing_gesture,
                 touch_drawing_gesture,
+                key_controller,
                 zoom_timeout_id: RefCell::new(None),
+                pens_animation_tick_id: RefCell::new(None),
                 return_to_center_toast: RefCell::new(None),
 
                 selection_modifier: SelectionModifier::default(),
@@ -177,6 +187,7 @@ mod imp {
             obj.add_controller(&self.stylus_drawing_gesture);
             obj.add_controller(&self.mouse_drawing_gesture);
             obj.add_controller(&self.touch_drawing_gesture);
+            obj.add_controller(&self.key_controller);
         }
 
         fn dispose(&self, obj: &Self::Type) {
@@ -275,6 +286,16 @@ mod imp {
                         true,
                         glib::ParamFlags::READWRITE,
                     ),
+                    // the maximum number of undo steps of the sheet history
+                    glib::ParamSpecUInt::new(
+                        "undo-depth",
+                        "undo-depth",
+                        "undo-depth",
+                        History::MAX_DEPTH_MIN as u32,
+                        History::MAX_DEPTH_MAX as u32,
+                        History::MAX_DEPTH_DEFAULT as u32,
+                        glib::ParamFlags::READWRITE,
+                    ),
                     // Scrollable properties
                     glib::ParamSpecOverride::for_interface::<Scrollable>("hscroll-policy"),
                     glib::ParamSpecOverride::for_interface::<Scrollable>("vscroll-policy"),
@@ -301,6 +322,9 @@ mod imp {
                 "touch-drawing" => self.touch_drawing.get().to_value(),
                 "pdf-import-width" => self.pdf_import_width.get().to_value(),
                 "pdf-import-as-vector" => self.pdf_import_as_vector.get().to_value(),
+                "undo-depth" => {
+                    (self.sheet.borrow().strokes_state.history().max_depth() as u32).to_value()
+                }
                 _ => unimplemented!(),
             }
         }
@@ -417,6 +441,18 @@ mod imp {
 
                     self.pdf_import_as_vector.replace(pdf_import_as_vector);
                 }
+                "undo-depth" => {
+                    let undo_depth = value
+                        .get::<u32>()
+                        .expect("The value needs to be of type `u32`.")
+                        .clamp(History::MAX_DEPTH_MIN as u32, History::MAX_DEPTH_MAX as u32);
+
+                    self.sheet
+                        .borrow_mut()
+                        .strokes_state
+                        .history_mut()
+                        .set_max_depth(undo_depth as usize);
+                }
                 _ => unimplemented!(),
             }
         }
@@ -571,7 +607,11 @@ mod imp {
                             );
                         }
                     }
-                    PenStyle::BrushStyle | PenStyle::ShaperStyle | PenStyle::ToolsStyle => {}
+                    PenStyle::BrushStyle
+                    | PenStyle::ShaperStyle
+                    | PenStyle::ToolsStyle
+                    | PenStyle::TypewriterStyle
+                    | PenStyle::LaserStyle => {}
                 }
             }
 
@@ -599,6 +639,7 @@ use rnote_engine::pens::Pens;
 use rnote_engine::render::{self, Renderer};
 use rnote_engine::sheet::Sheet;
 use rnote_engine::strokes::inputdata::InputData;
+use rnote_engine::strokesstate::StrokesState;
 
 use gettextrs::gettext;
 use num_derive::{FromPrimitive, ToPrimitive};
@@ -610,7 +651,10 @@ use std::sync::{Arc, RwLock};
 use std::time;
 
 use gtk4::{gdk, glib, glib::clone, prelude::*, subclass::prelude::*};
-use gtk4::{gio, Adjustment, DropTarget, EventSequenceState, PropagationPhase, Snapshot, Widget};
+use gtk4::{
+    gio, Adjustment, DropTarget, EventControllerLegacy, EventSequenceState, Inhibit,
+    PropagationPhase, Snapshot, Widget,
+};
 use p2d::bounding_volume::{BoundingVolume, AABB};
 
 #[derive(
@@ -739,6 +783,14 @@ impl Canvas {
         self.set_property("pdf-import-as-vector", as_vector.to_value());
     }
 
+    pub fn undo_depth(&self) -> u32 {
+        self.property::<u32>("undo-depth")
+    }
+
+    pub fn set_undo_depth(&self, undo_depth: u32) {
+        self.set_property("undo-depth", undo_depth.to_value());
+    }
+
     pub fn unsaved_changes(&self) -> bool {
         self.property::<bool>("unsaved-changes")
     }
@@ -934,8 +986,10 @@ impl Canvas {
             input::transform_inputdata(&mut data_entries, canvas.transform_canvas_coords_to_sheet_coords(na::vector![0.0, 0.0]), canvas.zoom());
 
             let shortcut_key = input::retreive_stylus_shortcut_key(&stylus_drawing_gesture);
+            let selection_operation = input::retreive_modifier_selection_operation(stylus_drawing_gesture.current_event_state());
 
-            input::process_pen_down(data_entries, shortcut_key, &appwindow);
+            input::process_input_device(input::retreive_stylus_device_name(stylus_drawing_gesture), &appwindow);
+            input::process_pen_down(data_entries, shortcut_key, selection_operation, &appwindow);
         }));
 
         self.imp().stylus_drawing_gesture.connect_motion(clone!(@weak self as canvas, @weak appwindow => move |stylus_drawing_gesture, x, y| {
@@ -981,8 +1035,9 @@ impl Canvas {
             input::transform_inputdata(&mut data_entries, canvas.transform_canvas_coords_to_sheet_coords(na::vector![0.0, 0.0]), canvas.zoom());
 
             let shortcut_key = input::retreive_mouse_shortcut_key(&mouse_drawing_gesture);
+            let selection_operation = input::retreive_modifier_selection_operation(mouse_drawing_gesture.current_event_state());
 
-            input::process_pen_down(data_entries, shortcut_key, &appwindow);
+            input::process_pen_down(data_entries, shortcut_key, selection_operation, &appwindow);
         }));
 
         self.imp().mouse_drawing_gesture.connect_drag_update(clone!(@weak self as canvas, @weak appwindow => move |mouse_drawing_gesture, x, y| {
@@ -1029,7 +1084,7 @@ impl Canvas {
                 let mut data_entries = input::retreive_pointer_inputdata(touch_drawing_gesture, x, y);
                 input::transform_inputdata(&mut data_entries, canvas.transform_canvas_coords_to_sheet_coords(na::vector![0.0, 0.0]), canvas.zoom());
 
-                input::process_pen_down(data_entries, None, &appwindow);
+                input::process_pen_down(data_entries, None, None, &appwindow);
             }),
         );
 
@@ -1061,6 +1116,13 @@ impl Canvas {
             }),
         );
 
+        // Keyboard input
+        self.imp().key_controller.connect_key_pressed(clone!(@weak appwindow => @default-return Inhibit(false), move |_key_controller, gdk_key, _keycode, modifier| {
+            let keyboard_key = input::retreive_keyboard_key(gdk_key, modifier);
+
+            Inhibit(input::process_keyboard_key_pressed(keyboard_key, &appwindow))
+        }));
+
         // Drop Target
         let drop_target = DropTarget::builder()
             .name("canvas_drop_target")
@@ -1379,6 +1441,76 @@ impl Canvas {
             ));
     }
 
+    /// Starts ticking the animations of the pens every frame, until they are finished. Does nothing if they are already running.
+    pub fn start_pens_animation(&self) {
+        if self.imp().pens_animation_tick_id.borrow().is_some() {
+            return;
+        }
+
+        let tick_id = self.add_tick_callback(|canvas, _frame_clock| {
+            let animating = canvas.pens().borrow_mut().tick_animations();
+            canvas.queue_draw();
+
+            if animating {
+                glib::Continue(true)
+            } else {
+                // The callback is removed when returning false, so the id must not be removed again
+                canvas.imp().pens_animation_tick_id.borrow_mut().take();
+                glib::Continue(false)
+            }
+        });
+
+        self.imp()
+            .pens_animation_tick_id
+            .borrow_mut()
+            .replace(tick_id);
+    }
+
+    /// Changes the style of the selected strokes with the given function, if the selection is not empty.
+    /// The function returns wether any stroke was changed. Returns true if the selection was changed
+    pub fn change_selection_style<F>(&self, change: F) -> bool
+    where
+        F: FnOnce(&mut StrokesState, Arc<RwLock<Renderer>>, f64) -> bool,
+    {
+        if self.sheet().borrow().strokes_state.selection_len() == 0 {
+            return false;
+        }
+
+        let renderer = self.renderer();
+        let zoom = self.zoom();
+        let changed = change(&mut self.sheet().borrow_mut().strokes_state, renderer, zoom);
+
+        if changed {
+            self.set_unsaved_changes(true);
+            self.selection_modifier().update_state(self);
+            self.queue_draw();
+        }
+
+        changed
+    }
+
+    /// Starts a new undo step whenever an interaction with the widget begins.
+    /// Changes which are merged in the history (e.g. while holding a button of a spinbutton) are then undone per interaction
+    pub fn end_history_merge_on_interaction(&self, widget: &impl IsA<Widget>) {
+        let interaction_controller = EventControllerLegacy::new();
+        interaction_controller.set_propagation_phase(PropagationPhase::Capture);
+
+        interaction_controller.connect_event(
+            clone!(@weak self as canvas => @default-return Inhibit(false), move |_interaction_controller, event| {
+                if matches!(
+                    event.event_type(),
+                    gdk::EventType::ButtonPress | gdk::EventType::TouchBegin | gdk::EventType::KeyPress
+                ) {
+                    canvas.sheet().borrow_mut().strokes_state.history_mut().end_merge();
+                }
+
+                Inhibit(false)
+            }),
+        );
+
+        widget.add_controller(&interaction_controller);
+    }
+
     /// Update rendernodes of the background. Used when the background itself did not change, but for example the format
     pub fn update_background_rendernode(&self, redraw: bool) {
         let sheet_bounds = self.sheet().borrow().bounds();
@@ -0,0 +1,118 @@
+mod imp {
+    use std::cell::Cell;
+
+    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate};
+    use gtk4::{Entry, SpinButton, ToggleButton};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/layerrow.ui")]
+    pub struct LayerRow {
+        pub layer_id: Cell<u32>,
+
+        #[template_child]
+        pub visible_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub name_entry: TemplateChild<Entry>,
+        #[template_child]
+        pub opacity_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub locked_toggle: TemplateChild<ToggleButton>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LayerRow {
+        const NAME: &'static str = "LayerRow";
+        type Type = super::LayerRow;
+        type ParentType = gtk4::ListBoxRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LayerRow {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+
+            self.visible_toggle
+                .get()
+                .connect_toggled(move |visible_toggle| {
+                    if visible_toggle.is_active() {
+                        visible_toggle.set_icon_name("view-reveal-symbolic");
+                    } else {
+                        visible_toggle.set_icon_name("view-conceal-symbolic");
+                    }
+                });
+
+            self.locked_toggle
+                .get()
+                .connect_toggled(move |locked_toggle| {
+                    if locked_toggle.is_active() {
+                        locked_toggle.set_icon_name("padlock-symbolic");
+                    } else {
+                        locked_toggle.set_icon_name("padlock-open-symbolic");
+                    }
+                });
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for LayerRow {}
+    impl ListBoxRowImpl for LayerRow {}
+}
+
+use gtk4::{glib, subclass::prelude::*, Entry, SpinButton, ToggleButton};
+use gtk4::{prelude::*, Widget};
+use rnote_engine::strokesstate::layer_comp::Layer;
+
+glib::wrapper! {
+    pub struct LayerRow(ObjectSubclass<imp::LayerRow>)
+        @extends gtk4::ListBoxRow, Widget,
+        @implements gtk4::Accessible, gtk4::Buildable, gtk4::ConstraintTarget, gtk4::Actionable;
+}
+
+impl LayerRow {
+    /// A new row showing the layer. The opacity is shown in %
+    pub fn new(layer: &Layer) -> Self {
+        let layer_row: Self = glib::Object::new(&[]).expect("Failed to create LayerRow");
+
+        layer_row.imp().layer_id.set(layer.id);
+        layer_row.visible_toggle().set_active(layer.visible);
+        layer_row.name_entry().set_text(&layer.name);
+        layer_row
+            .opacity_spinbutton()
+            .set_value((layer.opacity * 100.0).round());
+        layer_row.locked_toggle().set_active(layer.locked);
+
+        layer_row
+    }
+
+    pub fn layer_id(&self) -> u32 {
+        self.imp().layer_id.get()
+    }
+
+    pub fn visible_toggle(&self) -> ToggleButton {
+        self.imp().visible_toggle.get()
+    }
+
+    pub fn name_entry(&self) -> Entry {
+        self.imp().name_entry.get()
+    }
+
+    pub fn opacity_spinbutton(&self) -> SpinButton {
+        self.imp().opacity_spinbutton.get()
+    }
+
+    pub fn locked_toggle(&self) -> ToggleButton {
+        self.imp().locked_toggle.get()
+    }
+}
@@ -0,0 +1,278 @@
+pub mod layerrow;
+
+mod imp {
+    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, Widget};
+    use gtk4::{Button, ListBox};
+
+    #[derive(Debug, Default, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/layerspanel.ui")]
+    pub struct LayersPanel {
+        #[template_child]
+        pub layers_listbox: TemplateChild<ListBox>,
+        #[template_child]
+        pub add_layer_button: TemplateChild<Button>,
+        #[template_child]
+        pub remove_layer_button: TemplateChild<Button>,
+        #[template_child]
+        pub raise_layer_button: TemplateChild<Button>,
+        #[template_child]
+        pub lower_layer_button: TemplateChild<Button>,
+        #[template_child]
+        pub move_selection_button: TemplateChild<Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LayersPanel {
+        const NAME: &'static str = "LayersPanel";
+        type Type = super::LayersPanel;
+        type ParentType = Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LayersPanel {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for LayersPanel {}
+}
+
+use gettextrs::gettext;
+use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, Button, ListBox, Widget};
+
+use crate::appwindow::RnoteAppWindow;
+use layerrow::LayerRow;
+
+glib::wrapper! {
+    pub struct LayersPanel(ObjectSubclass<imp::LayersPanel>)
+        @extends Widget;
+}
+
+impl Default for LayersPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LayersPanel {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create LayersPanel")
+    }
+
+    pub fn layers_listbox(&self) -> ListBox {
+        self.imp().layers_listbox.get()
+    }
+
+    pub fn add_layer_button(&self) -> Button {
+        self.imp().add_layer_button.get()
+    }
+
+    pub fn remove_layer_button(&self) -> Button {
+        self.imp().remove_layer_button.get()
+    }
+
+    pub fn raise_layer_button(&self) -> Button {
+        self.imp().raise_layer_button.get()
+    }
+
+    pub fn lower_layer_button(&self) -> Button {
+        self.imp().lower_layer_button.get()
+    }
+
+    pub fn move_selection_button(&self) -> Button {
+        self.imp().move_selection_button.get()
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        self.layers_listbox().connect_row_selected(
+            clone!(@weak appwindow => move |_layers_listbox, row| {
+                if let Some(layer_row) = row.and_then(|row| row.downcast_ref::<LayerRow>()) {
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.set_current_layer(layer_row.layer_id());
+                }
+            }),
+        );
+
+        self.add_layer_button().connect_clicked(
+            clone!(@weak self as layers_panel, @weak appwindow => move |_add_layer_button| {
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let strokes_state = &mut sheet.borrow_mut().strokes_state;
+
+                    let name = gettext("Layer {}").replace("{}", &(strokes_state.layers().len() + 1).to_string());
+                    let layer_id = strokes_state.add_layer(name);
+                    strokes_state.set_current_layer(layer_id);
+                }
+
+                appwindow.canvas().set_unsaved_changes(true);
+                layers_panel.refresh_for_sheet(&appwindow);
+            }),
+        );
+
+        self.remove_layer_button().connect_clicked(
+            clone!(@weak self as layers_panel, @weak appwindow => move |_remove_layer_button| {
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let strokes_state = &mut sheet.borrow_mut().strokes_state;
+
+                    let current_layer = strokes_state.current_layer();
+                    strokes_state.remove_layer(current_layer);
+                }
+
+                Self::update_canvas(&appwindow);
+                layers_panel.refresh_for_sheet(&appwindow);
+            }),
+        );
+
+        self.raise_layer_button().connect_clicked(
+            clone!(@weak self as layers_panel, @weak appwindow => move |_raise_layer_button| {
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let strokes_state = &mut sheet.borrow_mut().strokes_state;
+
+                    let current_layer = strokes_state.current_layer();
+                    strokes_state.raise_layer(current_layer);
+                }
+
+                Self::update_canvas(&appwindow);
+                layers_panel.refresh_for_sheet(&appwindow);
+            }),
+        );
+
+        self.lower_layer_button().connect_clicked(
+            clone!(@weak self as layers_panel, @weak appwindow => move |_lower_layer_button| {
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let strokes_state = &mut sheet.borrow_mut().strokes_state;
+
+                    let current_layer = strokes_state.current_layer();
+                    strokes_state.lower_layer(current_layer);
+                }
+
+                Self::update_canvas(&appwindow);
+                layers_panel.refresh_for_sheet(&appwindow);
+            }),
+        );
+
+        self.move_selection_button().connect_clicked(
+            clone!(@weak appwindow => move |_move_selection_button| {
+                {
+                    let sheet = appwindow.canvas().sheet();
+                    let strokes_state = &mut sheet.borrow_mut().strokes_state;
+
+                    let current_layer = strokes_state.current_layer();
+                    strokes_state.move_selection_to_layer(current_layer);
+                }
+
+                Self::update_canvas(&appwindow);
+            }),
+        );
+    }
+
+    /// Rebuilds the rows from the layers of the sheet, the top layer first. Selects the row of the current layer
+    pub fn refresh_for_sheet(&self, appwindow: &RnoteAppWindow) {
+        let layers_listbox = self.layers_listbox();
+        while let Some(child) = layers_listbox.first_child() {
+            layers_listbox.remove(&child);
+        }
+
+        let (layers, current_layer) = {
+            let sheet = appwindow.canvas().sheet();
+            let sheet = sheet.borrow();
+
+            (
+                sheet.strokes_state.layers().to_vec(),
+                sheet.strokes_state.current_layer(),
+            )
+        };
+
+        for layer in layers.iter().rev() {
+            let layer_row = LayerRow::new(layer);
+            self.connect_layer_row(&layer_row, appwindow);
+
+            layers_listbox.append(&layer_row);
+            if layer.id == current_layer {
+                layers_listbox.select_row(Some(&layer_row));
+            }
+        }
+
+        // The last layer can't be removed
+        self.remove_layer_button().set_sensitive(layers.len() > 1);
+    }
+
+    fn connect_layer_row(&self, layer_row: &LayerRow, appwindow: &RnoteAppWindow) {
+        let layer_id = layer_row.layer_id();
+
+        layer_row.visible_toggle().connect_toggled(
+            clone!(@weak appwindow => move |visible_toggle| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_layer_visible(layer_id, visible_toggle.is_active());
+
+                Self::update_canvas(&appwindow);
+            }),
+        );
+
+        layer_row.locked_toggle().connect_toggled(
+            clone!(@weak appwindow => move |locked_toggle| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_layer_locked(layer_id, locked_toggle.is_active());
+
+                Self::update_canvas(&appwindow);
+            }),
+        );
+
+        layer_row.opacity_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |opacity_spinbutton| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_layer_opacity(layer_id, opacity_spinbutton.value() / 100.0);
+
+                Self::update_canvas(&appwindow);
+            }),
+        );
+
+        layer_row.name_entry().connect_activate(
+            clone!(@weak appwindow => move |name_entry| {
+                appwindow.canvas().sheet().borrow_mut().strokes_state.set_layer_name(layer_id, name_entry.text().to_string());
+
+                appwindow.canvas().set_unsaved_changes(true);
+            }),
+        );
+
+        // The name is also applied when the entry loses the focus without being activated
+        let name_focus_controller = gtk4::EventControllerFocus::new();
+        name_focus_controller.connect_leave(
+            clone!(@weak appwindow, @weak layer_row => move |_name_focus_controller| {
+                let name = layer_row.name_entry().text().to_string();
+                let changed = appwindow.canvas().sheet().borrow().strokes_state.layer(layer_id).map_or(false, |layer| layer.name != name);
+
+                if changed {
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.set_layer_name(layer_id, name);
+                    appwindow.canvas().set_unsaved_changes(true);
+                }
+            }),
+        );
+        layer_row
+            .name_entry()
+            .add_controller(&name_focus_controller);
+    }
+
+    /// Updates the canvas after the layers or the layers of strokes were changed
+    fn update_canvas(appwindow: &RnoteAppWindow) {
+        appwindow.canvas().set_unsaved_changes(true);
+        appwindow
+            .canvas()
+            .selection_modifier()
+            .update_state(&appwindow.canvas());
+        appwindow.canvas().regenerate_content(false, true);
+    }
+}
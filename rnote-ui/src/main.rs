@@ -10,6 +10,7 @@ pub mod colorpicker;
 pub mod config;
 pub mod dialogs;
 pub mod globals;
+pub mod layerspanel;
 pub mod mainheader;
 pub mod penssidebar;
 pub mod selectionmodifier;
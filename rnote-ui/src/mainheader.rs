@@ -45,6 +45,10 @@ mod imp {
         #[template_child]
         pub tools_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub typewriter_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub laser_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub canvasmenu: TemplateChild<CanvasMenu>,
         #[template_child]
         pub appmenu: TemplateChild<AppMenu>,
@@ -179,6 +183,14 @@ impl MainHeader {
         imp::MainHeader::from_instance(self).tools_toggle.get()
     }
 
+    pub fn typewriter_toggle(&self) -> ToggleButton {
+        imp::MainHeader::from_instance(self).typewriter_toggle.get()
+    }
+
+    pub fn laser_toggle(&self) -> ToggleButton {
+        imp::MainHeader::from_instance(self).laser_toggle.get()
+    }
+
     pub fn canvasmenu(&self) -> CanvasMenu {
         imp::MainHeader::from_instance(self).canvasmenu.get()
     }
@@ -234,6 +246,7 @@ impl MainHeader {
             clone!(@weak appwindow => move |_add_page_button| {
                 let format_height = appwindow.canvas().sheet().borrow().format.height;
                 let new_sheet_height = appwindow.canvas().sheet().borrow().height + format_height;
+                appwindow.canvas().sheet().borrow_mut().record_bounds();
                 appwindow.canvas().sheet().borrow_mut().height = new_sheet_height;
 
                 appwindow.canvas().update_background_rendernode(true);
@@ -242,6 +255,7 @@ impl MainHeader {
 
         self.imp().resize_to_format_button.get().connect_clicked(
             clone!(@weak appwindow => move |_resize_to_format_button| {
+                appwindow.canvas().sheet().borrow_mut().record_bounds();
                 appwindow.canvas().resize_sheet_to_fit_strokes();
                 appwindow.canvas().update_background_rendernode(true);
             }),
@@ -277,6 +291,18 @@ impl MainHeader {
             }
         }));
 
+        self.imp().typewriter_toggle.get().connect_toggled(clone!(@weak appwindow => move |typewriter_toggle| {
+            if typewriter_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+            }
+        }));
+
+        self.imp().laser_toggle.get().connect_toggled(clone!(@weak appwindow => move |laser_toggle| {
+            if laser_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"laser_style".to_variant()));
+            }
+        }));
+
         self.imp()
             .undo_button
             .get()
@@ -1,7 +1,9 @@
 mod imp {
     use crate::colorpicker::ColorPicker;
     use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton};
-    use gtk4::{Image, ListBox, MenuButton, Popover};
+    use gtk4::{
+        Button, DrawingArea, Image, ListBox, MenuButton, Popover, Stack, Switch, ToggleButton,
+    };
 
     #[derive(Default, Debug, CompositeTemplate)]
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/brushpage.ui")]
@@ -23,10 +25,16 @@ mod imp {
         #[template_child]
         pub brushstyle_textured_row: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub brushstyle_highlighter_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub brushstyle_calligraphy_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
         pub styleconfig_menubutton: TemplateChild<MenuButton>,
         #[template_child]
         pub styleconfig_popover: TemplateChild<Popover>,
         #[template_child]
+        pub styleconfig_stack: TemplateChild<Stack>,
+        #[template_child]
         pub texturedstyle_density_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub texturedstyle_radius_x_spinbutton: TemplateChild<SpinButton>,
@@ -34,6 +42,64 @@ mod imp {
         pub texturedstyle_radius_y_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub texturedstyle_distribution_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub texturedstyle_tilt_shading_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub calligraphystyle_nib_angle_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub calligraphystyle_thickness_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub calligraphystyle_follow_tilt_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub stabilizer_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub stabilizer_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub stabilizer_mode_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub stabilizer_strength_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub pressurecurve_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub pressurecurve_preview: TemplateChild<DrawingArea>,
+        #[template_child]
+        pub pressurecurve_style_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub pressurecurve_custom_cp1_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub pressurecurve_custom_cp1_x_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_custom_cp1_y_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_custom_cp2_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub pressurecurve_custom_cp2_x_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_custom_cp2_y_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_min_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_max_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressurecurve_opacity_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub pressurecurve_save_device_default_button: TemplateChild<Button>,
+        #[template_child]
+        pub pressurecurve_reset_device_default_button: TemplateChild<Button>,
+        #[template_child]
+        pub linestyle_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub linestyle_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub linestyle_dash_pattern_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub linestyle_line_cap_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub linestyle_dash_offset_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub recognize_shapes_togglebutton: TemplateChild<ToggleButton>,
     }
 
     #[glib::object_subclass]
@@ -68,11 +134,17 @@ mod imp {
 
 use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
 use adw::prelude::*;
-use gtk4::{gdk, Image, ListBox, MenuButton, Popover};
+use gettextrs::gettext;
+use gtk4::{gdk, DrawingArea, Image, ListBox, MenuButton, Popover, Stack, Switch, ToggleButton};
 use gtk4::{glib, glib::clone, subclass::prelude::*, SpinButton};
+use rnote_engine::compose::calligraphy::CalligraphyOptions;
 use rnote_engine::compose::color::Color;
+use rnote_engine::compose::pressurecurve::{PressureCurve, PressureCurveStyle};
+use rnote_engine::compose::smooth::{DashPattern, LineCap, SmoothOptions};
 use rnote_engine::compose::textured::{TexturedDotsDistribution, TexturedOptions};
 use rnote_engine::pens::brush::BrushStyle;
+use rnote_engine::pens::stabilizer::{Stabilizer, StabilizerMode};
+use rnote_engine::strokes::brushstroke::BrushStrokeStyle;
 
 glib::wrapper! {
     pub struct BrushPage(ObjectSubclass<imp::BrushPage>)
@@ -137,6 +209,18 @@ impl BrushPage {
             .get()
     }
 
+    pub fn brushstyle_highlighter_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_highlighter_row
+            .get()
+    }
+
+    pub fn brushstyle_calligraphy_row(&self) -> adw::ActionRow {
+        imp::BrushPage::from_instance(self)
+            .brushstyle_calligraphy_row
+            .get()
+    }
+
     pub fn styleconfig_menubutton(&self) -> MenuButton {
         imp::BrushPage::from_instance(self)
             .styleconfig_menubutton
@@ -155,6 +239,26 @@ impl BrushPage {
             .clone()
     }
 
+    pub fn texturedstyle_tilt_shading_switch(&self) -> Switch {
+        self.imp().texturedstyle_tilt_shading_switch.get()
+    }
+
+    pub fn styleconfig_stack(&self) -> Stack {
+        self.imp().styleconfig_stack.get()
+    }
+
+    pub fn calligraphystyle_nib_angle_spinbutton(&self) -> SpinButton {
+        self.imp().calligraphystyle_nib_angle_spinbutton.get()
+    }
+
+    pub fn calligraphystyle_thickness_spinbutton(&self) -> SpinButton {
+        self.imp().calligraphystyle_thickness_spinbutton.get()
+    }
+
+    pub fn calligraphystyle_follow_tilt_switch(&self) -> Switch {
+        self.imp().calligraphystyle_follow_tilt_switch.get()
+    }
+
     pub fn texturedstyle_density_spinbutton(&self) -> SpinButton {
         imp::BrushPage::from_instance(self)
             .texturedstyle_density_spinbutton
@@ -173,6 +277,149 @@ impl BrushPage {
             .clone()
     }
 
+    pub fn recognize_shapes_togglebutton(&self) -> ToggleButton {
+        imp::BrushPage::from_instance(self)
+            .recognize_shapes_togglebutton
+            .get()
+    }
+
+    pub fn stabilizer_strength_spinbutton(&self) -> SpinButton {
+        self.imp().stabilizer_strength_spinbutton.get()
+    }
+
+    pub fn set_stabilizer_mode_variant(&self, mode: StabilizerMode) {
+        let stabilizer_mode_listmodel = self
+            .imp()
+            .stabilizer_mode_row
+            .get()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.imp()
+            .stabilizer_mode_row
+            .get()
+            .set_selected(stabilizer_mode_listmodel.find_position(mode as i32));
+        self.stabilizer_strength_spinbutton()
+            .set_sensitive(mode != StabilizerMode::Off);
+    }
+
+    pub fn pressurecurve_preview(&self) -> DrawingArea {
+        self.imp().pressurecurve_preview.get()
+    }
+
+    pub fn pressurecurve_custom_cp1_x_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_custom_cp1_x_spinbutton.get()
+    }
+
+    pub fn pressurecurve_custom_cp1_y_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_custom_cp1_y_spinbutton.get()
+    }
+
+    pub fn pressurecurve_custom_cp2_x_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_custom_cp2_x_spinbutton.get()
+    }
+
+    pub fn pressurecurve_custom_cp2_y_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_custom_cp2_y_spinbutton.get()
+    }
+
+    pub fn pressurecurve_min_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_min_spinbutton.get()
+    }
+
+    pub fn pressurecurve_max_spinbutton(&self) -> SpinButton {
+        self.imp().pressurecurve_max_spinbutton.get()
+    }
+
+    pub fn pressurecurve_opacity_switch(&self) -> Switch {
+        self.imp().pressurecurve_opacity_switch.get()
+    }
+
+    pub fn set_pressurecurve_style_variant(&self, style: PressureCurveStyle) {
+        let pressurecurve_style_listmodel = self
+            .imp()
+            .pressurecurve_style_row
+            .get()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.imp()
+            .pressurecurve_style_row
+            .get()
+            .set_selected(pressurecurve_style_listmodel.find_position(style as i32));
+
+        self.imp()
+            .pressurecurve_custom_cp1_row
+            .get()
+            .set_sensitive(style == PressureCurveStyle::Custom);
+        self.imp()
+            .pressurecurve_custom_cp2_row
+            .get()
+            .set_sensitive(style == PressureCurveStyle::Custom);
+    }
+
+    /// Updates the widgets of the pressure curve popover
+    pub fn refresh_pressurecurve(&self, pressure_curve: PressureCurve) {
+        self.set_pressurecurve_style_variant(pressure_curve.style);
+        self.pressurecurve_custom_cp1_x_spinbutton()
+            .set_value(pressure_curve.custom_cp1[0]);
+        self.pressurecurve_custom_cp1_y_spinbutton()
+            .set_value(pressure_curve.custom_cp1[1]);
+        self.pressurecurve_custom_cp2_x_spinbutton()
+            .set_value(pressure_curve.custom_cp2[0]);
+        self.pressurecurve_custom_cp2_y_spinbutton()
+            .set_value(pressure_curve.custom_cp2[1]);
+        self.pressurecurve_min_spinbutton()
+            .set_value(pressure_curve.min);
+        self.pressurecurve_max_spinbutton()
+            .set_value(pressure_curve.max);
+        self.pressurecurve_opacity_switch()
+            .set_active(pressure_curve.map_to_opacity);
+        self.pressurecurve_preview().queue_draw();
+    }
+
+    pub fn linestyle_menubutton(&self) -> MenuButton {
+        self.imp().linestyle_menubutton.get()
+    }
+
+    pub fn linestyle_dash_offset_spinbutton(&self) -> SpinButton {
+        self.imp().linestyle_dash_offset_spinbutton.get()
+    }
+
+    pub fn set_linestyle_dash_pattern_variant(&self, dash_pattern: DashPattern) {
+        let linestyle_dash_pattern_listmodel = self
+            .imp()
+            .linestyle_dash_pattern_row
+            .get()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.imp()
+            .linestyle_dash_pattern_row
+            .get()
+            .set_selected(linestyle_dash_pattern_listmodel.find_position(dash_pattern as i32));
+        self.linestyle_dash_offset_spinbutton()
+            .set_sensitive(dash_pattern != DashPattern::Solid);
+    }
+
+    pub fn set_linestyle_line_cap_variant(&self, line_cap: LineCap) {
+        let linestyle_line_cap_listmodel = self
+            .imp()
+            .linestyle_line_cap_row
+            .get()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.imp()
+            .linestyle_line_cap_row
+            .get()
+            .set_selected(linestyle_line_cap_listmodel.find_position(line_cap as i32));
+    }
+
     pub fn set_texturedstyle_distribution_variant(&self, distribution: TexturedDotsDistribution) {
         let texturedstyle_distribution_listmodel = self
             .imp()
@@ -200,24 +447,54 @@ impl BrushPage {
             clone!(@weak appwindow => move |colorpicker, _paramspec| {
                 let color = Color::from(colorpicker.property::<gdk::RGBA>("current-color"));
                 let brush_style = appwindow.canvas().pens().borrow_mut().brush.style;
+                let old_color = BrushStrokeStyle::from_brush(&appwindow.canvas().pens().borrow().brush, None).stroke_color();
 
                 match brush_style {
                     BrushStyle::Marker => appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Solid => appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(color),
                     BrushStyle::Textured => appwindow.canvas().pens().borrow_mut().brush.textured_options.stroke_color = Some(color),
+                    BrushStyle::Highlighter => appwindow.canvas().pens().borrow_mut().brush.smooth_options.stroke_color = Some(color),
+                    BrushStyle::Calligraphy => appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.stroke_color = Some(color),
+                }
+
+                // Refreshing the page sets the color of the brush, so only actual changes are applied to the selection
+                if old_color != Some(color) {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_stroke_color(color, renderer, zoom)
+                    });
                 }
             }),
         );
 
+        appwindow
+            .canvas()
+            .end_history_merge_on_interaction(&self.width_spinbutton());
         self.width_spinbutton().connect_value_changed(
             clone!(@weak appwindow => move |brush_widthscale_spinbutton| {
                 let brush_style = appwindow.canvas().pens().borrow_mut().brush.style;
+                let old_width = BrushStrokeStyle::from_brush(&appwindow.canvas().pens().borrow().brush, None).width();
 
                 match brush_style {
                     BrushStyle::Marker => appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Solid => appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = brush_widthscale_spinbutton.value(),
                     BrushStyle::Textured => appwindow.canvas().pens().borrow_mut().brush.textured_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Highlighter => appwindow.canvas().pens().borrow_mut().brush.smooth_options.width = brush_widthscale_spinbutton.value(),
+                    BrushStyle::Calligraphy => appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.width = brush_widthscale_spinbutton.value(),
                 }
+
+                // Refreshing the page sets the width of the brush, so only actual changes are applied to the selection
+                let width = brush_widthscale_spinbutton.value();
+                if old_width != width {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_width(width, renderer, zoom)
+                    });
+                }
+            }),
+        );
+
+        self.recognize_shapes_togglebutton().connect_toggled(
+            clone!(@weak appwindow => move |recognize_shapes_togglebutton| {
+                appwindow.canvas().pens().borrow_mut().brush.recognize_shapes = recognize_shapes_togglebutton.is_active();
             }),
         );
 
@@ -237,12 +514,273 @@ impl BrushPage {
                         2 => {
                             adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"textured".to_variant()));
                         }
+                        // Highlighter
+                        3 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"highlighter".to_variant()));
+                        }
+                        // Calligraphy
+                        4 => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "brush-style", Some(&"calligraphy".to_variant()));
+                        }
                         _ => {}
                     }
                 }
             }),
         );
 
+        // Stabilizer
+        self.stabilizer_strength_spinbutton()
+            .set_increments(0.05, 0.2);
+        self.stabilizer_strength_spinbutton().set_range(0.0, 1.0);
+        self.stabilizer_strength_spinbutton()
+            .set_value(Stabilizer::STRENGTH_DEFAULT);
+        self.set_stabilizer_mode_variant(appwindow.canvas().pens().borrow().brush.stabilizer.mode);
+
+        self.stabilizer_strength_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |stabilizer_strength_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.stabilizer.strength = stabilizer_strength_spinbutton.value();
+            }),
+        );
+
+        self.imp().stabilizer_mode_row.get().connect_selected_item_notify(clone!(@weak self as brushpage, @weak appwindow => move |stabilizer_mode_row| {
+            if let Some(selected_item) = stabilizer_mode_row.selected_item() {
+                let mode = match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "off" => StabilizerMode::Off,
+                    "moving-average" => StabilizerMode::MovingAverage,
+                    "pulled-string" => StabilizerMode::PulledString,
+                    "exponential" => StabilizerMode::Exponential,
+                    _ => {
+                        log::error!(
+                            "invalid nick string when selecting a mode in stabilizer_mode_row"
+                        );
+                        return;
+                    }
+                };
+
+                appwindow.canvas().pens().borrow_mut().brush.stabilizer.mode = mode;
+                brushpage.stabilizer_strength_spinbutton().set_sensitive(mode != StabilizerMode::Off);
+            }
+        }));
+
+        // Line style
+        self.set_linestyle_dash_pattern_variant(
+            appwindow
+                .canvas()
+                .pens()
+                .borrow()
+                .brush
+                .smooth_options
+                .dash_pattern,
+        );
+        self.set_linestyle_line_cap_variant(
+            appwindow
+                .canvas()
+                .pens()
+                .borrow()
+                .brush
+                .smooth_options
+                .line_cap,
+        );
+        self.linestyle_dash_offset_spinbutton()
+            .set_increments(0.5, 2.0);
+        self.linestyle_dash_offset_spinbutton().set_range(
+            SmoothOptions::DASH_OFFSET_MIN,
+            SmoothOptions::DASH_OFFSET_MAX,
+        );
+        self.linestyle_dash_offset_spinbutton().set_value(0.0);
+
+        self.imp().linestyle_dash_pattern_row.get().connect_selected_item_notify(clone!(@weak self as brushpage, @weak appwindow => move |linestyle_dash_pattern_row| {
+            if let Some(selected_item) = linestyle_dash_pattern_row.selected_item() {
+                let dash_pattern = match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "solid" => DashPattern::Solid,
+                    "dashed" => DashPattern::Dashed,
+                    "dotted" => DashPattern::Dotted,
+                    "dash-dotted" => DashPattern::DashDotted,
+                    _ => {
+                        log::error!(
+                            "invalid nick string when selecting a dash pattern in linestyle_dash_pattern_row"
+                        );
+                        return;
+                    }
+                };
+
+                appwindow.canvas().pens().borrow_mut().brush.smooth_options.dash_pattern = dash_pattern;
+                brushpage.linestyle_dash_offset_spinbutton().set_sensitive(dash_pattern != DashPattern::Solid);
+            }
+        }));
+
+        self.imp().linestyle_line_cap_row.get().connect_selected_item_notify(clone!(@weak appwindow => move |linestyle_line_cap_row| {
+            if let Some(selected_item) = linestyle_line_cap_row.selected_item() {
+                let line_cap = match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "round" => LineCap::Round,
+                    "square" => LineCap::Square,
+                    "butt" => LineCap::Butt,
+                    _ => {
+                        log::error!(
+                            "invalid nick string when selecting a line cap in linestyle_line_cap_row"
+                        );
+                        return;
+                    }
+                };
+
+                appwindow.canvas().pens().borrow_mut().brush.smooth_options.line_cap = line_cap;
+            }
+        }));
+
+        self.linestyle_dash_offset_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |linestyle_dash_offset_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.smooth_options.dash_offset = linestyle_dash_offset_spinbutton.value();
+            }),
+        );
+
+        // Pressure curve
+        self.pressurecurve_preview().set_draw_func(
+            clone!(@weak appwindow => move |pressurecurve_preview, cx, width, height| {
+                let pressure_curve = appwindow.canvas().pens().borrow().brush.pressure_curve;
+                let color = pressurecurve_preview.style_context().color();
+                let (width, height) = (f64::from(width), f64::from(height));
+                let margin = 6.0;
+                let (graph_width, graph_height) = (width - 2.0 * margin, height - 2.0 * margin);
+                let n_samples = 64;
+
+                cx.set_source_rgba(f64::from(color.red()), f64::from(color.green()), f64::from(color.blue()), 0.3);
+                cx.set_line_width(1.0);
+                cx.rectangle(margin, margin, graph_width, graph_height);
+                if let Err(e) = cx.stroke() {
+                    log::error!("cx.stroke() failed in pressurecurve_preview draw func with Err {}", e);
+                }
+
+                cx.set_source_rgba(f64::from(color.red()), f64::from(color.green()), f64::from(color.blue()), f64::from(color.alpha()));
+                cx.set_line_width(2.0);
+                for i in 0..=n_samples {
+                    let pressure = f64::from(i) / f64::from(n_samples);
+                    let x = margin + pressure * graph_width;
+                    let y = margin + (1.0 - pressure_curve.apply(pressure)) * graph_height;
+
+                    if i == 0 {
+                        cx.move_to(x, y);
+                    } else {
+                        cx.line_to(x, y);
+                    }
+                }
+                if let Err(e) = cx.stroke() {
+                    log::error!("cx.stroke() failed in pressurecurve_preview draw func with Err {}", e);
+                }
+            }),
+        );
+
+        for spinbutton in [
+            self.pressurecurve_custom_cp1_x_spinbutton(),
+            self.pressurecurve_custom_cp1_y_spinbutton(),
+            self.pressurecurve_custom_cp2_x_spinbutton(),
+            self.pressurecurve_custom_cp2_y_spinbutton(),
+            self.pressurecurve_min_spinbutton(),
+            self.pressurecurve_max_spinbutton(),
+        ] {
+            spinbutton.set_increments(0.05, 0.2);
+            spinbutton.set_range(0.0, 1.0);
+        }
+        self.refresh_pressurecurve(appwindow.canvas().pens().borrow().brush.pressure_curve);
+
+        self.imp().pressurecurve_style_row.get().connect_selected_item_notify(clone!(@weak self as brushpage, @weak appwindow => move |pressurecurve_style_row| {
+            if let Some(selected_item) = pressurecurve_style_row.selected_item() {
+                let style = match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "linear" => PressureCurveStyle::Linear,
+                    "ease-in" => PressureCurveStyle::EaseIn,
+                    "ease-out" => PressureCurveStyle::EaseOut,
+                    "ease-in-out" => PressureCurveStyle::EaseInOut,
+                    "custom" => PressureCurveStyle::Custom,
+                    _ => {
+                        log::error!(
+                            "invalid nick string when selecting a style in pressurecurve_style_row"
+                        );
+                        return;
+                    }
+                };
+
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.style = style;
+                brushpage.imp().pressurecurve_custom_cp1_row.get().set_sensitive(style == PressureCurveStyle::Custom);
+                brushpage.imp().pressurecurve_custom_cp2_row.get().set_sensitive(style == PressureCurveStyle::Custom);
+                brushpage.pressurecurve_preview().queue_draw();
+            }
+        }));
+
+        self.pressurecurve_custom_cp1_x_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.custom_cp1[0] = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_custom_cp1_y_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.custom_cp1[1] = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_custom_cp2_x_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.custom_cp2[0] = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_custom_cp2_y_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.custom_cp2[1] = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_min_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.min = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_max_spinbutton().connect_value_changed(
+            clone!(@weak self as brushpage, @weak appwindow => move |spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.max = spinbutton.value();
+                brushpage.pressurecurve_preview().queue_draw();
+            }),
+        );
+        self.pressurecurve_opacity_switch().connect_state_notify(
+            clone!(@weak appwindow => move |pressurecurve_opacity_switch| {
+                appwindow.canvas().pens().borrow_mut().brush.pressure_curve.map_to_opacity = pressurecurve_opacity_switch.state();
+            }),
+        );
+        self.imp().pressurecurve_save_device_default_button.get().connect_clicked(
+            clone!(@weak appwindow => move |_pressurecurve_save_device_default_button| {
+                if !appwindow.canvas().pens().borrow_mut().save_pressure_curve_for_input_device() {
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("No input device was used yet").to_variant()));
+                }
+            }),
+        );
+        self.imp().pressurecurve_reset_device_default_button.get().connect_clicked(
+            clone!(@weak appwindow => move |_pressurecurve_reset_device_default_button| {
+                if appwindow.canvas().pens().borrow_mut().remove_pressure_curve_for_input_device().is_none() {
+                    adw::prelude::ActionGroupExt::activate_action(&appwindow, "error-toast", Some(&gettext("No curve was saved for the current input device").to_variant()));
+                }
+            }),
+        );
+
         // Textured style
         // Density
         self.imp()
@@ -355,5 +893,48 @@ impl BrushPage {
                 appwindow.canvas().regenerate_background(true);
             }
         }));
+
+        // Tilt shading
+        self.texturedstyle_tilt_shading_switch().connect_state_notify(
+            clone!(@weak appwindow => move |texturedstyle_tilt_shading_switch| {
+                appwindow.canvas().pens().borrow_mut().brush.textured_options.tilt_shading = texturedstyle_tilt_shading_switch.state();
+            }),
+        );
+
+        // Calligraphy style
+        // Nib angle
+        self.calligraphystyle_nib_angle_spinbutton()
+            .set_increments(1.0, 15.0);
+        self.calligraphystyle_nib_angle_spinbutton()
+            .set_range(0.0, 180.0);
+        self.calligraphystyle_nib_angle_spinbutton()
+            .set_value(CalligraphyOptions::NIB_ANGLE_DEFAULT.to_degrees());
+
+        self.calligraphystyle_nib_angle_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |calligraphystyle_nib_angle_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.nib_angle = calligraphystyle_nib_angle_spinbutton.value().to_radians();
+            }),
+        );
+
+        // Thickness
+        self.calligraphystyle_thickness_spinbutton()
+            .set_increments(0.05, 0.2);
+        self.calligraphystyle_thickness_spinbutton()
+            .set_range(0.0, 1.0);
+        self.calligraphystyle_thickness_spinbutton()
+            .set_value(CalligraphyOptions::THICKNESS_DEFAULT);
+
+        self.calligraphystyle_thickness_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |calligraphystyle_thickness_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.thickness = calligraphystyle_thickness_spinbutton.value();
+            }),
+        );
+
+        // Follow tilt
+        self.calligraphystyle_follow_tilt_switch().connect_state_notify(
+            clone!(@weak appwindow => move |calligraphystyle_follow_tilt_switch| {
+                appwindow.canvas().pens().borrow_mut().brush.calligraphy_options.follow_tilt = calligraphystyle_follow_tilt_switch.state();
+            }),
+        );
     }
 }
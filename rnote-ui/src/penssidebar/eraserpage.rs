@@ -1,11 +1,25 @@
 mod imp {
-    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton};
+    use gtk4::{
+        glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton, ToggleButton,
+    };
 
     #[derive(Default, Debug, CompositeTemplate)]
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/eraserpage.ui")]
     pub struct EraserPage {
+        #[template_child]
+        pub erasermode_trash_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub erasermode_split_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub erasershape_square_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub erasershape_circle_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub pressure_sensitive_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub erase_images_togglebutton: TemplateChild<ToggleButton>,
     }
 
     #[glib::object_subclass]
@@ -39,8 +53,8 @@ mod imp {
 }
 
 use crate::appwindow::RnoteAppWindow;
-use gtk4::{glib, glib::clone, subclass::prelude::*, SpinButton};
-use rnote_engine::pens::eraser::Eraser;
+use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, SpinButton, ToggleButton};
+use rnote_engine::pens::eraser::{Eraser, EraserShape};
 
 glib::wrapper! {
     pub struct EraserPage(ObjectSubclass<imp::EraserPage>)
@@ -58,11 +72,77 @@ impl EraserPage {
         glib::Object::new(&[]).expect("Failed to create EraserPage")
     }
 
+    pub fn erasermode_trash_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .erasermode_trash_toggle
+            .get()
+    }
+
+    pub fn erasermode_split_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .erasermode_split_toggle
+            .get()
+    }
+
+    pub fn erasershape_square_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .erasershape_square_toggle
+            .get()
+    }
+
+    pub fn erasershape_circle_toggle(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .erasershape_circle_toggle
+            .get()
+    }
+
     pub fn width_spinbutton(&self) -> SpinButton {
         imp::EraserPage::from_instance(self).width_spinbutton.get()
     }
 
+    pub fn pressure_sensitive_togglebutton(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .pressure_sensitive_togglebutton
+            .get()
+    }
+
+    pub fn erase_images_togglebutton(&self) -> ToggleButton {
+        imp::EraserPage::from_instance(self)
+            .erase_images_togglebutton
+            .get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
+        // Trashing whole strokes / splitting strokes toggles
+        self.erasermode_trash_toggle().connect_toggled(clone!(@weak appwindow => move |erasermode_trash_toggle| {
+            if erasermode_trash_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "eraser-mode", Some(&"trash-colliding-strokes".to_variant()));
+            }
+        }));
+
+        self.erasermode_split_toggle().connect_toggled(clone!(@weak appwindow => move |erasermode_split_toggle| {
+            if erasermode_split_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "eraser-mode", Some(&"split-colliding-strokes".to_variant()));
+            }
+        }));
+
+        // Square / circle tip toggles
+        self.erasershape_square_toggle().connect_toggled(
+            clone!(@weak appwindow => move |erasershape_square_toggle| {
+                if erasershape_square_toggle.is_active() {
+                    appwindow.canvas().pens().borrow_mut().eraser.shape = EraserShape::Square;
+                }
+            }),
+        );
+
+        self.erasershape_circle_toggle().connect_toggled(
+            clone!(@weak appwindow => move |erasershape_circle_toggle| {
+                if erasershape_circle_toggle.is_active() {
+                    appwindow.canvas().pens().borrow_mut().eraser.shape = EraserShape::Circle;
+                }
+            }),
+        );
+
         self.width_spinbutton().set_increments(1.0, 5.0);
         self.width_spinbutton()
             .set_range(Eraser::WIDTH_MIN, Eraser::WIDTH_MAX);
@@ -73,5 +153,17 @@ impl EraserPage {
                 appwindow.canvas().pens().borrow_mut().eraser.width = width_spinbutton.value();
             }),
         );
+
+        self.pressure_sensitive_togglebutton().connect_toggled(
+            clone!(@weak appwindow => move |pressure_sensitive_togglebutton| {
+                appwindow.canvas().pens().borrow_mut().eraser.pressure_sensitive = pressure_sensitive_togglebutton.is_active();
+            }),
+        );
+
+        self.erase_images_togglebutton().connect_toggled(
+            clone!(@weak appwindow => move |erase_images_togglebutton| {
+                appwindow.canvas().pens().borrow_mut().eraser.erase_images = erase_images_togglebutton.is_active();
+            }),
+        );
     }
 }
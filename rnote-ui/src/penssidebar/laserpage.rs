@@ -0,0 +1,120 @@
+mod imp {
+    use crate::colorpicker::ColorPicker;
+    use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate, SpinButton};
+
+    #[derive(Default, Debug, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/laserpage.ui")]
+    pub struct LaserPage {
+        #[template_child]
+        pub width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub fade_time_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub colorpicker: TemplateChild<ColorPicker>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for LaserPage {
+        const NAME: &'static str = "LaserPage";
+        type Type = super::LaserPage;
+        type ParentType = gtk4::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for LaserPage {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for LaserPage {}
+}
+
+use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
+use gtk4::{gdk, glib, glib::clone, prelude::*, subclass::prelude::*, SpinButton};
+use rnote_engine::compose::color::Color;
+use rnote_engine::pens::laser::Laser;
+
+glib::wrapper! {
+    pub struct LaserPage(ObjectSubclass<imp::LaserPage>)
+        @extends gtk4::Widget;
+}
+
+impl Default for LaserPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LaserPage {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create LaserPage")
+    }
+
+    pub fn width_spinbutton(&self) -> SpinButton {
+        imp::LaserPage::from_instance(self).width_spinbutton.get()
+    }
+
+    pub fn fade_time_spinbutton(&self) -> SpinButton {
+        imp::LaserPage::from_instance(self)
+            .fade_time_spinbutton
+            .get()
+    }
+
+    pub fn colorpicker(&self) -> ColorPicker {
+        imp::LaserPage::from_instance(self).colorpicker.get()
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        // Width
+        self.width_spinbutton().set_increments(1.0, 5.0);
+        self.width_spinbutton()
+            .set_range(Laser::WIDTH_MIN, Laser::WIDTH_MAX);
+        self.width_spinbutton().set_value(Laser::WIDTH_DEFAULT);
+
+        self.width_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |width_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().laser.width = width_spinbutton.value();
+            }),
+        );
+
+        // Fade time
+        self.fade_time_spinbutton().set_increments(0.1, 1.0);
+        self.fade_time_spinbutton()
+            .set_range(Laser::FADE_TIME_MIN, Laser::FADE_TIME_MAX);
+        self.fade_time_spinbutton()
+            .set_value(Laser::FADE_TIME_DEFAULT);
+
+        self.fade_time_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |fade_time_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().laser.fade_time = fade_time_spinbutton.value();
+            }),
+        );
+
+        // Color
+        self.colorpicker()
+            .set_current_color(Some(Laser::COLOR_DEFAULT));
+
+        self.colorpicker().connect_notify_local(
+            Some("current-color"),
+            clone!(@weak appwindow => move |colorpicker, _paramspec| {
+                let color = Color::from(colorpicker.property::<gdk::RGBA>("current-color"));
+
+                appwindow.canvas().pens().borrow_mut().laser.color = color;
+            }),
+        );
+    }
+}
@@ -1,11 +1,15 @@
 pub mod brushpage;
 pub mod eraserpage;
+pub mod laserpage;
 pub mod selectorpage;
 pub mod shaperpage;
 pub mod toolspage;
+pub mod typewriterpage;
 
 mod imp {
+    use super::laserpage::LaserPage;
     use super::toolspage::ToolsPage;
+    use super::typewriterpage::TypewriterPage;
     use super::{
         brushpage::BrushPage, eraserpage::EraserPage, selectorpage::SelectorPage,
         shaperpage::ShaperPage,
@@ -40,6 +44,14 @@ mod imp {
         pub tools_stackpage: TemplateChild<StackPage>,
         #[template_child]
         pub tools_page: TemplateChild<ToolsPage>,
+        #[template_child]
+        pub typewriter_stackpage: TemplateChild<StackPage>,
+        #[template_child]
+        pub typewriter_page: TemplateChild<TypewriterPage>,
+        #[template_child]
+        pub laser_stackpage: TemplateChild<StackPage>,
+        #[template_child]
+        pub laser_page: TemplateChild<LaserPage>,
     }
 
     #[glib::object_subclass]
@@ -80,7 +92,9 @@ use shaperpage::ShaperPage;
 
 use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*, Stack, StackPage};
 
+use self::laserpage::LaserPage;
 use self::toolspage::ToolsPage;
+use self::typewriterpage::TypewriterPage;
 
 glib::wrapper! {
     pub struct PensSideBar(ObjectSubclass<imp::PensSideBar>)
@@ -142,6 +156,24 @@ impl PensSideBar {
         imp::PensSideBar::from_instance(self).tools_page.get()
     }
 
+    pub fn typewriter_stackpage(&self) -> StackPage {
+        imp::PensSideBar::from_instance(self)
+            .typewriter_stackpage
+            .get()
+    }
+
+    pub fn typewriter_page(&self) -> TypewriterPage {
+        imp::PensSideBar::from_instance(self).typewriter_page.get()
+    }
+
+    pub fn laser_stackpage(&self) -> StackPage {
+        imp::PensSideBar::from_instance(self).laser_stackpage.get()
+    }
+
+    pub fn laser_page(&self) -> LaserPage {
+        imp::PensSideBar::from_instance(self).laser_page.get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         self.imp().sidebar_stack.get().connect_visible_child_name_notify(
             clone!(@weak appwindow => move |sidebar_stack| {
@@ -162,6 +194,12 @@ impl PensSideBar {
                         "tools_page" => {
                             adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"tools_style".to_variant()));
                         }
+                        "typewriter_page" => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"typewriter_style".to_variant()));
+                        }
+                        "laser_page" => {
+                            adw::prelude::ActionGroupExt::activate_action(&appwindow, "pen-style", Some(&"laser_style".to_variant()));
+                        }
                         _ => {}
                     };
                 };
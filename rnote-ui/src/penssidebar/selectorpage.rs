@@ -1,6 +1,10 @@
 mod imp {
-    use gtk4::ToggleButton;
+    use std::cell::Cell;
+
     use gtk4::{glib, prelude::*, subclass::prelude::*, CompositeTemplate};
+    use gtk4::{Button, MenuButton, Popover, SpinButton, Switch, ToggleButton};
+
+    use crate::unitentry::UnitEntry;
 
     #[derive(Default, Debug, CompositeTemplate)]
     #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/selectorpage.ui")]
@@ -10,7 +14,33 @@ mod imp {
         #[template_child]
         pub selectorstyle_rect_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub selectorstyle_touch_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub selectorstyle_single_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
         pub resize_lock_aspectratio_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub transform_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub transform_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub transform_x_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_y_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_width_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_height_unitentry: TemplateChild<UnitEntry>,
+        #[template_child]
+        pub transform_rotation_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub transform_lock_aspectratio_switch: TemplateChild<Switch>,
+        #[template_child]
+        pub transform_apply_button: TemplateChild<Button>,
+        /// Whether the position entries were edited since they were refreshed
+        pub position_edited: Cell<bool>,
+        /// Whether the size entries were edited since they were refreshed
+        pub size_edited: Cell<bool>,
     }
 
     #[glib::object_subclass]
@@ -43,9 +73,14 @@ mod imp {
     impl WidgetImpl for SelectorPage {}
 }
 
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+
 use crate::appwindow::RnoteAppWindow;
-use gtk4::ToggleButton;
+use crate::unitentry::UnitEntry;
 use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*};
+use gtk4::{Button, MenuButton, Popover, SpinButton, Switch, ToggleButton};
 
 glib::wrapper! {
     pub struct SelectorPage(ObjectSubclass<imp::SelectorPage>)
@@ -75,14 +110,80 @@ impl SelectorPage {
             .get()
     }
 
+    pub fn selectorstyle_touch_toggle(&self) -> ToggleButton {
+        imp::SelectorPage::from_instance(self)
+            .selectorstyle_touch_toggle
+            .get()
+    }
+
+    pub fn selectorstyle_single_toggle(&self) -> ToggleButton {
+        imp::SelectorPage::from_instance(self)
+            .selectorstyle_single_toggle
+            .get()
+    }
+
     pub fn resize_lock_aspectratio_togglebutton(&self) -> ToggleButton {
         imp::SelectorPage::from_instance(self)
             .resize_lock_aspectratio_togglebutton
             .get()
     }
 
+    pub fn transform_menubutton(&self) -> MenuButton {
+        imp::SelectorPage::from_instance(self)
+            .transform_menubutton
+            .get()
+    }
+
+    pub fn transform_popover(&self) -> Popover {
+        imp::SelectorPage::from_instance(self)
+            .transform_popover
+            .get()
+    }
+
+    pub fn transform_x_unitentry(&self) -> UnitEntry {
+        imp::SelectorPage::from_instance(self)
+            .transform_x_unitentry
+            .get()
+    }
+
+    pub fn transform_y_unitentry(&self) -> UnitEntry {
+        imp::SelectorPage::from_instance(self)
+            .transform_y_unitentry
+            .get()
+    }
+
+    pub fn transform_width_unitentry(&self) -> UnitEntry {
+        imp::SelectorPage::from_instance(self)
+            .transform_width_unitentry
+            .get()
+    }
+
+    pub fn transform_height_unitentry(&self) -> UnitEntry {
+        imp::SelectorPage::from_instance(self)
+            .transform_height_unitentry
+            .get()
+    }
+
+    pub fn transform_rotation_spinbutton(&self) -> SpinButton {
+        imp::SelectorPage::from_instance(self)
+            .transform_rotation_spinbutton
+            .get()
+    }
+
+    pub fn transform_lock_aspectratio_switch(&self) -> Switch {
+        imp::SelectorPage::from_instance(self)
+            .transform_lock_aspectratio_switch
+            .get()
+    }
+
+    pub fn transform_apply_button(&self) -> Button {
+        imp::SelectorPage::from_instance(self)
+            .transform_apply_button
+            .get()
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
-        // selecting with Polygon / Rect toggles
+        // selecting with Polygon / Rect / Touch / Single toggles
         self.selectorstyle_polygon_toggle().connect_toggled(clone!(@weak appwindow => move |selectorstyle_polygon_toggle| {
             if selectorstyle_polygon_toggle.is_active() {
                 adw::prelude::ActionGroupExt::activate_action(&appwindow, "selector-style", Some(&"polygon".to_variant()));
@@ -95,6 +196,18 @@ impl SelectorPage {
             }
         }));
 
+        self.selectorstyle_touch_toggle().connect_toggled(clone!(@weak appwindow => move |selectorstyle_touch_toggle| {
+            if selectorstyle_touch_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "selector-style", Some(&"touch".to_variant()));
+            }
+        }));
+
+        self.selectorstyle_single_toggle().connect_toggled(clone!(@weak appwindow => move |selectorstyle_single_toggle| {
+            if selectorstyle_single_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "selector-style", Some(&"single".to_variant()));
+            }
+        }));
+
         self.resize_lock_aspectratio_togglebutton()
             .bind_property(
                 "active",
@@ -103,5 +216,178 @@ impl SelectorPage {
             )
             .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
             .build();
+
+        // Transform
+        for unitentry in [
+            self.transform_x_unitentry(),
+            self.transform_y_unitentry(),
+            self.transform_width_unitentry(),
+            self.transform_height_unitentry(),
+        ] {
+            unitentry.value_spinner().set_digits(2);
+        }
+
+        self.transform_rotation_spinbutton()
+            .set_increments(1.0, 15.0);
+        self.transform_rotation_spinbutton()
+            .set_range(-360.0, 360.0);
+        self.transform_rotation_spinbutton().set_value(0.0);
+
+        self.transform_lock_aspectratio_switch()
+            .bind_property(
+                "active",
+                &appwindow.canvas().selection_modifier(),
+                "resize-lock-aspectratio",
+            )
+            .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
+            .build();
+
+        self.transform_popover().connect_show(
+            clone!(@weak self as selectorpage, @weak appwindow => move |_transform_popover| {
+                selectorpage.refresh_transform_entries(&appwindow);
+            }),
+        );
+
+        // Only the edited parts of the transform are applied, so that the selection is not changed by the rounding of the entries
+        for unitentry in [self.transform_x_unitentry(), self.transform_y_unitentry()] {
+            unitentry.connect_local(
+                "measurement-changed",
+                false,
+                clone!(@weak self as selectorpage => @default-return None, move |_args| {
+                    selectorpage.imp().position_edited.set(true);
+                    None
+                }),
+            );
+        }
+        for unitentry in [
+            self.transform_width_unitentry(),
+            self.transform_height_unitentry(),
+        ] {
+            unitentry.connect_local(
+                "measurement-changed",
+                false,
+                clone!(@weak self as selectorpage => @default-return None, move |_args| {
+                    selectorpage.imp().size_edited.set(true);
+                    None
+                }),
+            );
+        }
+
+        // Keeping the aspectratio of the selection when the width or height is changed.
+        // Guarded, because updating the other entry emits its changed signal again
+        let updating_size = Rc::new(Cell::new(false));
+
+        self.transform_width_unitentry().connect_local(
+            "measurement-changed",
+            false,
+            clone!(@strong updating_size, @weak self as selectorpage, @weak appwindow => @default-return None, move |_args| {
+                if selectorpage.transform_lock_aspectratio_switch().is_active() && !updating_size.get() {
+                    if let Some(selection_bounds) = appwindow.canvas().sheet().borrow().strokes_state.gen_selection_bounds() {
+                        let ratio = selection_bounds.extents()[1] / selection_bounds.extents()[0];
+
+                        updating_size.set(true);
+                        selectorpage.transform_height_unitentry().set_value_in_px(selectorpage.transform_width_unitentry().value_in_px() * ratio);
+                        updating_size.set(false);
+                    }
+                }
+                None
+            }),
+        );
+
+        self.transform_height_unitentry().connect_local(
+            "measurement-changed",
+            false,
+            clone!(@strong updating_size, @weak self as selectorpage, @weak appwindow => @default-return None, move |_args| {
+                if selectorpage.transform_lock_aspectratio_switch().is_active() && !updating_size.get() {
+                    if let Some(selection_bounds) = appwindow.canvas().sheet().borrow().strokes_state.gen_selection_bounds() {
+                        let ratio = selection_bounds.extents()[0] / selection_bounds.extents()[1];
+
+                        updating_size.set(true);
+                        selectorpage.transform_width_unitentry().set_value_in_px(selectorpage.transform_height_unitentry().value_in_px() * ratio);
+                        updating_size.set(false);
+                    }
+                }
+                None
+            }),
+        );
+
+        self.transform_apply_button().connect_clicked(
+            clone!(@weak self as selectorpage, @weak appwindow => move |_transform_apply_button| {
+                let pos = na::vector![
+                    selectorpage.transform_x_unitentry().value_in_px(),
+                    selectorpage.transform_y_unitentry().value_in_px()
+                ];
+                let size = na::vector![
+                    selectorpage.transform_width_unitentry().value_in_px(),
+                    selectorpage.transform_height_unitentry().value_in_px()
+                ];
+                let angle = selectorpage.transform_rotation_spinbutton().value().to_radians();
+                let lock_aspectratio = selectorpage.transform_lock_aspectratio_switch().is_active();
+                let position_edited = selectorpage.imp().position_edited.get();
+                let size_edited = selectorpage.imp().size_edited.get();
+                let renderer = appwindow.canvas().renderer();
+                let zoom = appwindow.canvas().zoom();
+
+                let changed = {
+                    let sheet = appwindow.canvas().sheet();
+                    let mut sheet = sheet.borrow_mut();
+
+                    sheet.strokes_state.history_mut().begin_group();
+                    // Positioning last, so that the bounds end up at the position even when they are changed by the rotation
+                    let resized = size_edited && sheet.strokes_state.resize_selection_to(size, lock_aspectratio, Arc::clone(&renderer), zoom);
+                    let rotated = sheet.strokes_state.rotate_selection(angle, renderer, zoom);
+                    let moved = position_edited && sheet.strokes_state.move_selection_to(pos, zoom);
+                    sheet.strokes_state.history_mut().end_group();
+
+                    resized || rotated || moved
+                };
+
+                if changed {
+                    appwindow.canvas().set_unsaved_changes(true);
+                    appwindow.canvas().selection_modifier().update_state(&appwindow.canvas());
+                    appwindow.canvas().regenerate_content(false, true);
+                }
+
+                selectorpage.refresh_transform_entries(&appwindow);
+            }),
+        );
+    }
+
+    /// Sets the entries of the transform popover to the current selection bounds
+    pub fn refresh_transform_entries(&self, appwindow: &RnoteAppWindow) {
+        let dpi = appwindow.canvas().sheet().borrow().format.dpi;
+        let selection_bounds = appwindow
+            .canvas()
+            .sheet()
+            .borrow()
+            .strokes_state
+            .gen_selection_bounds();
+
+        self.transform_apply_button()
+            .set_sensitive(selection_bounds.is_some());
+
+        for unitentry in [
+            self.transform_x_unitentry(),
+            self.transform_y_unitentry(),
+            self.transform_width_unitentry(),
+            self.transform_height_unitentry(),
+        ] {
+            unitentry.set_dpi(dpi);
+        }
+        self.transform_rotation_spinbutton().set_value(0.0);
+
+        if let Some(selection_bounds) = selection_bounds {
+            // Setting the position first, the size entries might be adjusted to each other through the locked aspectratio
+            self.transform_x_unitentry()
+                .set_value_in_px(selection_bounds.mins[0]);
+            self.transform_y_unitentry()
+                .set_value_in_px(selection_bounds.mins[1]);
+            self.transform_width_unitentry()
+                .set_value_in_px(selection_bounds.extents()[0]);
+            self.transform_height_unitentry()
+                .set_value_in_px(selection_bounds.extents()[1]);
+        }
+        self.imp().position_edited.set(false);
+        self.imp().size_edited.set(false);
     }
 }
@@ -23,6 +23,14 @@ mod imp {
         #[template_child]
         pub roughconfig_multistroke_switch: TemplateChild<Switch>,
         #[template_child]
+        pub roughconfig_fillstyle_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub roughconfig_hachureangle_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub roughconfig_hachuregap_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub roughconfig_fillweight_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
         pub width_spinbutton: TemplateChild<SpinButton>,
         #[template_child]
         pub stroke_colorpicker: TemplateChild<ColorPicker>,
@@ -38,6 +46,36 @@ mod imp {
         pub rectangle_toggle: TemplateChild<ToggleButton>,
         #[template_child]
         pub ellipse_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub arrow_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub polyline_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub polygon_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub arc_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub cubbez_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub arrowconfig_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub arrowconfig_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub arrowconfig_start_head_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub arrowconfig_end_head_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub arrowconfig_head_length_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub linestyle_menubutton: TemplateChild<MenuButton>,
+        #[template_child]
+        pub linestyle_popover: TemplateChild<Popover>,
+        #[template_child]
+        pub linestyle_dash_pattern_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub linestyle_line_cap_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub linestyle_dash_offset_spinbutton: TemplateChild<SpinButton>,
     }
 
     #[glib::object_subclass]
@@ -71,11 +109,15 @@ mod imp {
 }
 
 use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
+use adw::prelude::*;
 use gtk4::{gdk, MenuButton, Popover, Revealer, SpinButton, Switch, ToggleButton};
-use gtk4::{glib, glib::clone, prelude::*, subclass::prelude::*};
+use gtk4::{glib, glib::clone, subclass::prelude::*};
 use rnote_engine::compose::color::Color;
-use rnote_engine::compose::rough::roughoptions::{self, RoughOptions};
+use rnote_engine::compose::rough::roughoptions::{self, FillStyle, RoughOptions};
+use rnote_engine::compose::shapes::{Arrow, ArrowHead};
+use rnote_engine::compose::smooth::{DashPattern, LineCap, SmoothOptions};
 use rnote_engine::pens::shaper::ShaperDrawStyle;
+use rnote_engine::strokes::shapestroke::ShapeDrawStyle;
 
 glib::wrapper! {
     pub struct ShaperPage(ObjectSubclass<imp::ShaperPage>)
@@ -152,6 +194,41 @@ impl ShaperPage {
             .get()
     }
 
+    pub fn roughconfig_fillstyle_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_fillstyle_row
+            .get()
+    }
+
+    pub fn roughconfig_hachureangle_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_hachureangle_spinbutton
+            .get()
+    }
+
+    pub fn roughconfig_hachuregap_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_hachuregap_spinbutton
+            .get()
+    }
+
+    pub fn roughconfig_fillweight_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .roughconfig_fillweight_spinbutton
+            .get()
+    }
+
+    pub fn set_roughconfig_fillstyle_variant(&self, fill_style: FillStyle) {
+        let fillstyle_listmodel = self
+            .roughconfig_fillstyle_row()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.roughconfig_fillstyle_row()
+            .set_selected(fillstyle_listmodel.find_position(fill_style as i32));
+    }
+
     pub fn stroke_colorpicker(&self) -> ColorPicker {
         imp::ShaperPage::from_instance(self)
             .stroke_colorpicker
@@ -182,6 +259,174 @@ impl ShaperPage {
         imp::ShaperPage::from_instance(self).ellipse_toggle.get()
     }
 
+    pub fn arrow_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).arrow_toggle.get()
+    }
+
+    pub fn polyline_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).polyline_toggle.get()
+    }
+
+    pub fn polygon_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).polygon_toggle.get()
+    }
+
+    pub fn arc_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).arc_toggle.get()
+    }
+
+    pub fn cubbez_toggle(&self) -> ToggleButton {
+        imp::ShaperPage::from_instance(self).cubbez_toggle.get()
+    }
+
+    pub fn arrowconfig_menubutton(&self) -> MenuButton {
+        imp::ShaperPage::from_instance(self)
+            .arrowconfig_menubutton
+            .get()
+    }
+
+    pub fn arrowconfig_popover(&self) -> Popover {
+        imp::ShaperPage::from_instance(self)
+            .arrowconfig_popover
+            .get()
+    }
+
+    pub fn arrowconfig_start_head_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .arrowconfig_start_head_row
+            .get()
+    }
+
+    pub fn arrowconfig_end_head_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .arrowconfig_end_head_row
+            .get()
+    }
+
+    pub fn arrowconfig_head_length_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .arrowconfig_head_length_spinbutton
+            .get()
+    }
+
+    pub fn set_arrowconfig_start_head_variant(&self, head: ArrowHead) {
+        Self::set_arrow_head_row_variant(&self.arrowconfig_start_head_row(), head);
+    }
+
+    pub fn set_arrowconfig_end_head_variant(&self, head: ArrowHead) {
+        Self::set_arrow_head_row_variant(&self.arrowconfig_end_head_row(), head);
+    }
+
+    fn set_arrow_head_row_variant(row: &adw::ComboRow, head: ArrowHead) {
+        let arrow_head_listmodel = row
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        row.set_selected(arrow_head_listmodel.find_position(head as i32));
+    }
+
+    fn arrow_head_from_row(row: &adw::ComboRow) -> Option<ArrowHead> {
+        let selected_item = row.selected_item()?;
+
+        match selected_item
+            .downcast::<adw::EnumListItem>()
+            .unwrap()
+            .nick()
+            .as_str()
+        {
+            "none" => Some(ArrowHead::None),
+            "open" => Some(ArrowHead::Open),
+            "filled" => Some(ArrowHead::Filled),
+            _ => None,
+        }
+    }
+
+    pub fn linestyle_menubutton(&self) -> MenuButton {
+        imp::ShaperPage::from_instance(self)
+            .linestyle_menubutton
+            .get()
+    }
+
+    pub fn linestyle_popover(&self) -> Popover {
+        imp::ShaperPage::from_instance(self).linestyle_popover.get()
+    }
+
+    pub fn linestyle_dash_pattern_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .linestyle_dash_pattern_row
+            .get()
+    }
+
+    pub fn linestyle_line_cap_row(&self) -> adw::ComboRow {
+        imp::ShaperPage::from_instance(self)
+            .linestyle_line_cap_row
+            .get()
+    }
+
+    pub fn linestyle_dash_offset_spinbutton(&self) -> SpinButton {
+        imp::ShaperPage::from_instance(self)
+            .linestyle_dash_offset_spinbutton
+            .get()
+    }
+
+    pub fn set_linestyle_dash_pattern_variant(&self, dash_pattern: DashPattern) {
+        let dash_pattern_listmodel = self
+            .linestyle_dash_pattern_row()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.linestyle_dash_pattern_row()
+            .set_selected(dash_pattern_listmodel.find_position(dash_pattern as i32));
+        self.linestyle_dash_offset_spinbutton()
+            .set_sensitive(dash_pattern != DashPattern::Solid);
+    }
+
+    pub fn set_linestyle_line_cap_variant(&self, line_cap: LineCap) {
+        let line_cap_listmodel = self
+            .linestyle_line_cap_row()
+            .model()
+            .unwrap()
+            .downcast::<adw::EnumListModel>()
+            .unwrap();
+        self.linestyle_line_cap_row()
+            .set_selected(line_cap_listmodel.find_position(line_cap as i32));
+    }
+
+    fn dash_pattern_from_row(row: &adw::ComboRow) -> Option<DashPattern> {
+        let selected_item = row.selected_item()?;
+
+        match selected_item
+            .downcast::<adw::EnumListItem>()
+            .unwrap()
+            .nick()
+            .as_str()
+        {
+            "solid" => Some(DashPattern::Solid),
+            "dashed" => Some(DashPattern::Dashed),
+            "dotted" => Some(DashPattern::Dotted),
+            "dash-dotted" => Some(DashPattern::DashDotted),
+            _ => None,
+        }
+    }
+
+    fn line_cap_from_row(row: &adw::ComboRow) -> Option<LineCap> {
+        let selected_item = row.selected_item()?;
+
+        match selected_item
+            .downcast::<adw::EnumListItem>()
+            .unwrap()
+            .nick()
+            .as_str()
+        {
+            "round" => Some(LineCap::Round),
+            "square" => Some(LineCap::Square),
+            "butt" => Some(LineCap::Butt),
+            _ => None,
+        }
+    }
+
     pub fn init(&self, appwindow: &RnoteAppWindow) {
         // Width
         self.width_spinbutton().set_increments(0.1, 2.0);
@@ -189,14 +434,26 @@ impl ShaperPage {
             .set_range(Self::WIDTH_MIN, Self::WIDTH_MAX);
         self.width_spinbutton().set_value(Self::WIDTH_DEFAULT);
 
+        appwindow
+            .canvas()
+            .end_history_merge_on_interaction(&self.width_spinbutton());
         self.width_spinbutton().connect_value_changed(
             clone!(@weak appwindow => move |width_spinbutton| {
                 let shaper_style = appwindow.canvas().pens().borrow_mut().shaper.drawstyle;
+                let old_width = ShapeDrawStyle::from_shaper(&appwindow.canvas().pens().borrow().shaper, None).width();
 
                 match shaper_style {
                     ShaperDrawStyle::Smooth => appwindow.canvas().pens().borrow_mut().shaper.smooth_options.width = width_spinbutton.value(),
                     ShaperDrawStyle::Rough => appwindow.canvas().pens().borrow_mut().shaper.rough_options.stroke_width = width_spinbutton.value(),
                 }
+
+                // Refreshing the page sets the width of the shaper, so only actual changes are applied to the selection
+                let width = width_spinbutton.value();
+                if old_width != width {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_width(width, renderer, zoom)
+                    });
+                }
             }),
         );
 
@@ -206,11 +463,19 @@ impl ShaperPage {
             clone!(@weak appwindow => move |stroke_colorpicker, _paramspec| {
                 let color = Color::from(stroke_colorpicker.property::<gdk::RGBA>("current-color"));
                 let shaper_style = appwindow.canvas().pens().borrow_mut().shaper.drawstyle;
+                let old_color = ShapeDrawStyle::from_shaper(&appwindow.canvas().pens().borrow().shaper, None).stroke_color();
 
                 match shaper_style {
                     ShaperDrawStyle::Smooth => appwindow.canvas().pens().borrow_mut().shaper.smooth_options.stroke_color = Some(color),
                     ShaperDrawStyle::Rough => appwindow.canvas().pens().borrow_mut().shaper.rough_options.stroke_color= Some(color),
                 }
+
+                // Refreshing the page sets the color of the shaper, so only actual changes are applied to the selection
+                if old_color != Some(color) {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_stroke_color(color, renderer, zoom)
+                    });
+                }
             }),
         );
 
@@ -220,11 +485,19 @@ impl ShaperPage {
             clone!(@weak appwindow => move |fill_colorpicker, _paramspec| {
                 let color = Color::from(fill_colorpicker.property::<gdk::RGBA>("current-color"));
                 let shaper_style = appwindow.canvas().pens().borrow_mut().shaper.drawstyle;
+                let old_color = ShapeDrawStyle::from_shaper(&appwindow.canvas().pens().borrow().shaper, None).fill_color();
 
                 match shaper_style {
                     ShaperDrawStyle::Smooth => appwindow.canvas().pens().borrow_mut().shaper.smooth_options.fill_color = Some(color),
                     ShaperDrawStyle::Rough => appwindow.canvas().pens().borrow_mut().shaper.rough_options.fill_color= Some(color),
                 }
+
+                // Refreshing the page sets the fill color of the shaper, so only actual changes are applied to the selection
+                if old_color != Some(color) {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_fill_color(Some(color), renderer, zoom)
+                    });
+                }
             }),
         );
 
@@ -296,6 +569,106 @@ impl ShaperPage {
             appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_multistroke(roughconfig_multistroke_switch.state());
         }));
 
+        // Fill style
+        self.set_roughconfig_fillstyle_variant(
+            appwindow
+                .canvas()
+                .pens()
+                .borrow()
+                .shaper
+                .rough_options
+                .fill_style,
+        );
+
+        self.roughconfig_fillstyle_row().connect_selected_item_notify(clone!(@weak appwindow => move |roughconfig_fillstyle_row| {
+            if let Some(selected_item) = roughconfig_fillstyle_row.selected_item() {
+                let fill_style = match selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .nick()
+                    .as_str()
+                {
+                    "solid" => FillStyle::Solid,
+                    "hachure" => FillStyle::Hachure,
+                    "zigzag" => FillStyle::Zigzag,
+                    "zigzag-line" => FillStyle::ZigzagLine,
+                    "crosshatch" => FillStyle::Crosshatch,
+                    "dots" => FillStyle::Dots,
+                    "sunburst" => FillStyle::Sunburst,
+                    "dashed" => FillStyle::Dashed,
+                    _ => {
+                        log::error!("invalid nick string when selecting a fill style in roughconfig_fillstyle_row");
+                        return;
+                    }
+                };
+
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.fill_style = fill_style;
+            }
+        }));
+
+        // Hachure angle
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_increments(1.0, 15.0);
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_range(
+                RoughOptions::HACHURE_ANGLE_MIN,
+                RoughOptions::HACHURE_ANGLE_MAX,
+            );
+        self.imp()
+            .roughconfig_hachureangle_spinbutton
+            .get()
+            .set_value(RoughOptions::HACHURE_ANGLE_DEFAULT);
+
+        self.imp().roughconfig_hachureangle_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_hachureangle_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_hachure_angle(roughconfig_hachureangle_spinbutton.value());
+            }),
+        );
+
+        // Hachure gap
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_increments(0.5, 2.0);
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_range(RoughOptions::HACHURE_GAP_MIN, RoughOptions::HACHURE_GAP_MAX);
+        self.imp()
+            .roughconfig_hachuregap_spinbutton
+            .get()
+            .set_value(RoughOptions::HACHURE_GAP_DEFAULT);
+
+        self.imp().roughconfig_hachuregap_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_hachuregap_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_hachure_gap(roughconfig_hachuregap_spinbutton.value());
+            }),
+        );
+
+        // Fill weight
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_increments(0.1, 1.0);
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_range(RoughOptions::FILL_WEIGHT_MIN, RoughOptions::FILL_WEIGHT_MAX);
+        self.imp()
+            .roughconfig_fillweight_spinbutton
+            .get()
+            .set_value(RoughOptions::FILL_WEIGHT_DEFAULT);
+
+        self.imp().roughconfig_fillweight_spinbutton.get().connect_value_changed(
+            clone!(@weak appwindow => move |roughconfig_fillweight_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.rough_options.set_fill_weight(roughconfig_fillweight_spinbutton.value());
+            }),
+        );
+
         // Smooth / Rough shape toggle
         self.drawstyle_smooth_toggle().connect_toggled(clone!(@weak appwindow => move |drawstyle_smooth_toggle| {
             if drawstyle_smooth_toggle.is_active() {
@@ -319,21 +692,106 @@ impl ShaperPage {
             .flags(glib::BindingFlags::DEFAULT)
             .build();
 
-        self.line_toggle()
-            .bind_property("active", &self.fill_revealer(), "reveal-child")
-            .flags(glib::BindingFlags::DEFAULT | glib::BindingFlags::INVERT_BOOLEAN)
-            .build();
-
-        self.rectangle_toggle()
-            .bind_property("active", &self.fill_revealer(), "reveal-child")
+        self.arrow_toggle()
+            .bind_property("active", &self.arrowconfig_menubutton(), "sensitive")
             .flags(glib::BindingFlags::DEFAULT)
             .build();
 
-        self.ellipse_toggle()
-            .bind_property("active", &self.fill_revealer(), "reveal-child")
-            .flags(glib::BindingFlags::DEFAULT)
+        // Arrow heads
+        self.set_arrowconfig_start_head_variant(
+            appwindow.canvas().pens().borrow().shaper.arrow_start_head,
+        );
+        self.set_arrowconfig_end_head_variant(
+            appwindow.canvas().pens().borrow().shaper.arrow_end_head,
+        );
+
+        self.arrowconfig_start_head_row().connect_selected_item_notify(clone!(@weak appwindow => move |arrowconfig_start_head_row| {
+            if let Some(head) = Self::arrow_head_from_row(arrowconfig_start_head_row) {
+                appwindow.canvas().pens().borrow_mut().shaper.arrow_start_head = head;
+            } else {
+                log::error!("invalid nick string when selecting an arrow start head in arrowconfig_start_head_row");
+            }
+        }));
+
+        self.arrowconfig_end_head_row().connect_selected_item_notify(clone!(@weak appwindow => move |arrowconfig_end_head_row| {
+            if let Some(head) = Self::arrow_head_from_row(arrowconfig_end_head_row) {
+                appwindow.canvas().pens().borrow_mut().shaper.arrow_end_head = head;
+            } else {
+                log::error!("invalid nick string when selecting an arrow end head in arrowconfig_end_head_row");
+            }
+        }));
+
+        // Arrow head length
+        self.arrowconfig_head_length_spinbutton()
+            .set_increments(1.0, 5.0);
+        self.arrowconfig_head_length_spinbutton()
+            .set_range(Arrow::HEAD_LENGTH_MIN, Arrow::HEAD_LENGTH_MAX);
+        self.arrowconfig_head_length_spinbutton()
+            .set_value(Arrow::HEAD_LENGTH_DEFAULT);
+
+        self.arrowconfig_head_length_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |arrowconfig_head_length_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.arrow_head_length = arrowconfig_head_length_spinbutton.value();
+            }),
+        );
+
+        // Line style
+        self.drawstyle_smooth_toggle()
+            .bind_property("active", &self.linestyle_menubutton(), "sensitive")
+            .flags(glib::BindingFlags::SYNC_CREATE)
             .build();
 
+        self.set_linestyle_dash_pattern_variant(
+            appwindow
+                .canvas()
+                .pens()
+                .borrow()
+                .shaper
+                .smooth_options
+                .dash_pattern,
+        );
+        self.set_linestyle_line_cap_variant(
+            appwindow
+                .canvas()
+                .pens()
+                .borrow()
+                .shaper
+                .smooth_options
+                .line_cap,
+        );
+
+        self.linestyle_dash_pattern_row().connect_selected_item_notify(clone!(@weak self as shaperpage, @weak appwindow => move |linestyle_dash_pattern_row| {
+            if let Some(dash_pattern) = Self::dash_pattern_from_row(linestyle_dash_pattern_row) {
+                appwindow.canvas().pens().borrow_mut().shaper.smooth_options.dash_pattern = dash_pattern;
+                shaperpage.linestyle_dash_offset_spinbutton().set_sensitive(dash_pattern != DashPattern::Solid);
+            } else {
+                log::error!("invalid nick string when selecting a dash pattern in linestyle_dash_pattern_row");
+            }
+        }));
+
+        self.linestyle_line_cap_row().connect_selected_item_notify(clone!(@weak appwindow => move |linestyle_line_cap_row| {
+            if let Some(line_cap) = Self::line_cap_from_row(linestyle_line_cap_row) {
+                appwindow.canvas().pens().borrow_mut().shaper.smooth_options.line_cap = line_cap;
+            } else {
+                log::error!("invalid nick string when selecting a line cap in linestyle_line_cap_row");
+            }
+        }));
+
+        // Dash offset
+        self.linestyle_dash_offset_spinbutton()
+            .set_increments(0.5, 2.0);
+        self.linestyle_dash_offset_spinbutton().set_range(
+            SmoothOptions::DASH_OFFSET_MIN,
+            SmoothOptions::DASH_OFFSET_MAX,
+        );
+        self.linestyle_dash_offset_spinbutton().set_value(0.0);
+
+        self.linestyle_dash_offset_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |linestyle_dash_offset_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().shaper.smooth_options.dash_offset = linestyle_dash_offset_spinbutton.value();
+            }),
+        );
+
         // Shape toggles
         self.line_toggle().connect_toggled(clone!(@weak self as shaperpage, @weak appwindow => move |line_toggle| {
             if line_toggle.is_active() {
@@ -352,5 +810,35 @@ impl ShaperPage {
                 adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"ellipse".to_variant()));
             }
         }));
+
+        self.arrow_toggle().connect_toggled(clone!(@weak appwindow => move |arrow_toggle| {
+            if arrow_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"arrow".to_variant()));
+            }
+        }));
+
+        self.polyline_toggle().connect_toggled(clone!(@weak appwindow => move |polyline_toggle| {
+            if polyline_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"polyline".to_variant()));
+            }
+        }));
+
+        self.polygon_toggle().connect_toggled(clone!(@weak appwindow => move |polygon_toggle| {
+            if polygon_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"polygon".to_variant()));
+            }
+        }));
+
+        self.arc_toggle().connect_toggled(clone!(@weak appwindow => move |arc_toggle| {
+            if arc_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"arc".to_variant()));
+            }
+        }));
+
+        self.cubbez_toggle().connect_toggled(clone!(@weak appwindow => move |cubbez_toggle| {
+            if cubbez_toggle.is_active() {
+                adw::prelude::ActionGroupExt::activate_action(&appwindow, "shaper-style", Some(&"cubic_bezier".to_variant()));
+            }
+        }));
     }
 }
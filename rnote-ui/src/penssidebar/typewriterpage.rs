@@ -0,0 +1,223 @@
+mod imp {
+    use crate::colorpicker::ColorPicker;
+    use gtk4::{
+        glib, prelude::*, subclass::prelude::*, CompositeTemplate, FontButton, SpinButton,
+        ToggleButton,
+    };
+
+    #[derive(Default, Debug, CompositeTemplate)]
+    #[template(resource = "/com/github/flxzt/rnote/ui/penssidebar/typewriterpage.ui")]
+    pub struct TypewriterPage {
+        #[template_child]
+        pub font_button: TemplateChild<FontButton>,
+        #[template_child]
+        pub font_size_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub alignment_start_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_center_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub alignment_end_toggle: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub wrap_togglebutton: TemplateChild<ToggleButton>,
+        #[template_child]
+        pub max_width_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub colorpicker: TemplateChild<ColorPicker>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for TypewriterPage {
+        const NAME: &'static str = "TypewriterPage";
+        type Type = super::TypewriterPage;
+        type ParentType = gtk4::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            Self::bind_template(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for TypewriterPage {
+        fn constructed(&self, obj: &Self::Type) {
+            self.parent_constructed(obj);
+        }
+
+        fn dispose(&self, obj: &Self::Type) {
+            while let Some(child) = obj.first_child() {
+                child.unparent();
+            }
+        }
+    }
+
+    impl WidgetImpl for TypewriterPage {}
+}
+
+use crate::{appwindow::RnoteAppWindow, colorpicker::ColorPicker};
+use gtk4::{
+    gdk, glib, glib::clone, prelude::*, subclass::prelude::*, FontButton, SpinButton, ToggleButton,
+};
+use rnote_engine::compose::color::Color;
+use rnote_engine::strokes::textstroke::{TextAlignment, TextStyle};
+
+glib::wrapper! {
+    pub struct TypewriterPage(ObjectSubclass<imp::TypewriterPage>)
+        @extends gtk4::Widget;
+}
+
+impl Default for TypewriterPage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypewriterPage {
+    pub fn new() -> Self {
+        glib::Object::new(&[]).expect("Failed to create TypewriterPage")
+    }
+
+    pub fn font_button(&self) -> FontButton {
+        imp::TypewriterPage::from_instance(self).font_button.get()
+    }
+
+    pub fn font_size_spinbutton(&self) -> SpinButton {
+        imp::TypewriterPage::from_instance(self)
+            .font_size_spinbutton
+            .get()
+    }
+
+    pub fn alignment_start_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_start_toggle
+            .get()
+    }
+
+    pub fn alignment_center_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_center_toggle
+            .get()
+    }
+
+    pub fn alignment_end_toggle(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .alignment_end_toggle
+            .get()
+    }
+
+    pub fn wrap_togglebutton(&self) -> ToggleButton {
+        imp::TypewriterPage::from_instance(self)
+            .wrap_togglebutton
+            .get()
+    }
+
+    pub fn max_width_spinbutton(&self) -> SpinButton {
+        imp::TypewriterPage::from_instance(self)
+            .max_width_spinbutton
+            .get()
+    }
+
+    pub fn colorpicker(&self) -> ColorPicker {
+        imp::TypewriterPage::from_instance(self).colorpicker.get()
+    }
+
+    pub fn init(&self, appwindow: &RnoteAppWindow) {
+        self.font_button().set_font(TextStyle::FONT_FAMILY_DEFAULT);
+
+        self.font_button().connect_font_set(
+            clone!(@weak appwindow => move |font_button| {
+                if let Some(font_family) = font_button.font_family() {
+                    appwindow.canvas().pens().borrow_mut().typewriter.text_style.font_family = font_family.name().to_string();
+                }
+            }),
+        );
+
+        self.font_size_spinbutton().set_increments(1.0, 4.0);
+        self.font_size_spinbutton()
+            .set_range(TextStyle::FONT_SIZE_MIN, TextStyle::FONT_SIZE_MAX);
+        // Must be after set_range() !
+        self.font_size_spinbutton()
+            .set_value(TextStyle::FONT_SIZE_DEFAULT);
+
+        self.font_size_spinbutton().connect_value_changed(
+            clone!(@weak appwindow => move |font_size_spinbutton| {
+                appwindow.canvas().pens().borrow_mut().typewriter.text_style.font_size = font_size_spinbutton.value();
+            }),
+        );
+
+        // Alignment toggles
+        self.alignment_start_toggle().connect_toggled(
+            clone!(@weak appwindow => move |alignment_start_toggle| {
+                if alignment_start_toggle.is_active() {
+                    appwindow.canvas().pens().borrow_mut().typewriter.text_style.alignment = TextAlignment::Start;
+                }
+            }),
+        );
+
+        self.alignment_center_toggle().connect_toggled(
+            clone!(@weak appwindow => move |alignment_center_toggle| {
+                if alignment_center_toggle.is_active() {
+                    appwindow.canvas().pens().borrow_mut().typewriter.text_style.alignment = TextAlignment::Center;
+                }
+            }),
+        );
+
+        self.alignment_end_toggle().connect_toggled(
+            clone!(@weak appwindow => move |alignment_end_toggle| {
+                if alignment_end_toggle.is_active() {
+                    appwindow.canvas().pens().borrow_mut().typewriter.text_style.alignment = TextAlignment::End;
+                }
+            }),
+        );
+
+        // Wrapping
+        self.max_width_spinbutton().set_increments(10.0, 100.0);
+        self.max_width_spinbutton()
+            .set_range(TextStyle::MAX_WIDTH_MIN, TextStyle::MAX_WIDTH_MAX);
+        // Must be after set_range() !
+        self.max_width_spinbutton()
+            .set_value(TextStyle::MAX_WIDTH_DEFAULT);
+
+        self.wrap_togglebutton()
+            .bind_property("active", &self.max_width_spinbutton(), "sensitive")
+            .flags(glib::BindingFlags::SYNC_CREATE)
+            .build();
+
+        self.wrap_togglebutton().connect_toggled(
+            clone!(@weak self as typewriterpage, @weak appwindow => move |wrap_togglebutton| {
+                appwindow.canvas().pens().borrow_mut().typewriter.text_style.max_width = if wrap_togglebutton.is_active() {
+                    Some(typewriterpage.max_width_spinbutton().value())
+                } else {
+                    None
+                };
+            }),
+        );
+
+        self.max_width_spinbutton().connect_value_changed(
+            clone!(@weak self as typewriterpage, @weak appwindow => move |max_width_spinbutton| {
+                if typewriterpage.wrap_togglebutton().is_active() {
+                    appwindow.canvas().pens().borrow_mut().typewriter.text_style.max_width = Some(max_width_spinbutton.value());
+                }
+            }),
+        );
+
+        self.colorpicker().connect_notify_local(
+            Some("current-color"),
+            clone!(@weak appwindow => move |colorpicker, _paramspec| {
+                let color = Color::from(colorpicker.property::<gdk::RGBA>("current-color"));
+                let old_color = appwindow.canvas().pens().borrow().typewriter.text_style.color;
+
+                appwindow.canvas().pens().borrow_mut().typewriter.text_style.color = color;
+
+                // Refreshing the page sets the color of the typewriter, so only actual changes are applied to the selection
+                if old_color != color {
+                    appwindow.canvas().change_selection_style(|strokes_state, renderer, zoom| {
+                        strokes_state.change_selection_stroke_color(color, renderer, zoom)
+                    });
+                }
+            }),
+        );
+    }
+}
@@ -1,7 +1,7 @@
 pub mod modifiernode;
 
 pub mod imp {
-    use std::cell::Cell;
+    use std::cell::{Cell, RefCell};
 
     use crate::canvas::Canvas;
     use crate::utils;
@@ -9,6 +9,7 @@ pub mod imp {
     use rnote_engine::compose;
     use rnote_engine::compose::color::Color;
     use rnote_engine::render;
+    use rnote_engine::strokesstate::StrokeKey;
 
     use super::modifiernode::ModifierNode;
 
@@ -33,14 +34,21 @@ pub mod imp {
         pub translate_node: TemplateChild<gtk4::Box>,
         #[template_child]
         pub rotate_node: TemplateChild<ModifierNode>,
+        #[template_child]
+        pub shear_node: TemplateChild<ModifierNode>,
 
         pub resize_lock_aspectratio: Cell<bool>,
+        pub node_editing: Cell<bool>,
 
         // Internal state for allocation, drawing
         pub(super) selection_bounds: Cell<Option<AABB>>,
         pub(super) start_rotation_center: Cell<Option<na::Point2<f64>>>,
         pub(super) start_rotation_angle: Cell<f64>,
         pub(super) current_rotation_angle: Cell<f64>,
+        pub(super) handles: RefCell<Vec<na::Vector2<f64>>>,
+        pub(super) selected_handle: Cell<Option<usize>>,
+        /// The stroke the handles belong to
+        pub(super) handles_key: Cell<Option<StrokeKey>>,
     }
 
     impl Default for SelectionModifier {
@@ -52,13 +60,18 @@ pub mod imp {
                 resize_br_node: TemplateChild::default(),
                 translate_node: TemplateChild::default(),
                 rotate_node: TemplateChild::default(),
+                shear_node: TemplateChild::default(),
 
                 resize_lock_aspectratio: Cell::new(false),
+                node_editing: Cell::new(false),
 
                 selection_bounds: Cell::new(None),
                 start_rotation_center: Cell::new(None),
                 start_rotation_angle: Cell::new(0.0),
                 current_rotation_angle: Cell::new(0.0),
+                handles: RefCell::new(vec![]),
+                selected_handle: Cell::new(None),
+                handles_key: Cell::new(None),
             }
         }
     }
@@ -119,6 +132,13 @@ pub mod imp {
                 .image()
                 .set_pixel_size(super::SelectionModifier::RESIZE_NODE_SIZE);
 
+            self.shear_node
+                .image()
+                .set_icon_name(Some("modifiernode-shear-symbolic"));
+            self.shear_node
+                .image()
+                .set_pixel_size(super::SelectionModifier::RESIZE_NODE_SIZE);
+
             self.translate_node.set_cursor(
                 gdk::Cursor::from_name("grab", gdk::Cursor::from_name("default", None).as_ref())
                     .as_ref(),
@@ -142,6 +162,14 @@ pub mod imp {
                         false,
                         glib::ParamFlags::READWRITE,
                     ),
+                    // Shows the nodes of a selected brush stroke as handles, so that they can be edited
+                    glib::ParamSpecBoolean::new(
+                        "node-editing",
+                        "node-editing",
+                        "node-editing",
+                        false,
+                        glib::ParamFlags::READWRITE,
+                    ),
                 ]
             });
             PROPERTIES.as_ref()
@@ -150,13 +178,14 @@ pub mod imp {
         fn property(&self, _obj: &Self::Type, _id: usize, pspec: &glib::ParamSpec) -> glib::Value {
             match pspec.name() {
                 "resize-lock-aspectratio" => self.resize_lock_aspectratio.get().to_value(),
+                "node-editing" => self.node_editing.get().to_value(),
                 _ => unimplemented!(),
             }
         }
 
         fn set_property(
             &self,
-            _obj: &Self::Type,
+            obj: &Self::Type,
             _id: usize,
             value: &glib::Value,
             pspec: &glib::ParamSpec,
@@ -170,6 +199,18 @@ pub mod imp {
                     self.resize_lock_aspectratio
                         .replace(resize_locked_aspectratio);
                 }
+                "node-editing" => {
+                    let node_editing = value
+                        .get::<bool>()
+                        .expect("The value needs to be of type `bool`.");
+
+                    self.node_editing.replace(node_editing);
+
+                    if let Some(canvas) = obj.parent() {
+                        obj.update_state(canvas.downcast_ref::<Canvas>().unwrap());
+                        canvas.queue_draw();
+                    }
+                }
                 _ => unimplemented!(),
             }
         }
@@ -186,6 +227,7 @@ pub mod imp {
 
                 self.draw_selection_overlay(widget, snapshot, bounds, canvas);
                 self.draw_rotation_indicator(widget, snapshot, bounds, canvas);
+                self.draw_handles(widget, snapshot, bounds, canvas);
 
                 // Clip everything outside the current view
                 snapshot.push_clip(&graphene::Rect::new(
@@ -200,6 +242,7 @@ pub mod imp {
                 widget.snapshot_child(&self.resize_bl_node.get(), snapshot);
                 widget.snapshot_child(&self.resize_br_node.get(), snapshot);
                 widget.snapshot_child(&self.rotate_node.get(), snapshot);
+                widget.snapshot_child(&self.shear_node.get(), snapshot);
                 widget.snapshot_child(&self.translate_node.get(), snapshot);
 
                 snapshot.pop();
@@ -400,6 +443,99 @@ pub mod imp {
                 }
             }
         }
+
+        fn draw_handles(
+            &self,
+            widget: &super::SelectionModifier,
+            snapshot: &Snapshot,
+            widget_bounds: AABB,
+            canvas: &Canvas,
+        ) {
+            const HANDLE_OUTLINE_COLOR: Color = Color {
+                r: 0.7,
+                g: 0.3,
+                b: 0.3,
+                a: 1.0,
+            };
+            const HANDLE_FILL: Color = Color {
+                r: 1.0,
+                g: 1.0,
+                b: 1.0,
+                a: 0.9,
+            };
+            const SELECTED_HANDLE_FILL: Color = Color {
+                r: 0.7,
+                g: 0.3,
+                b: 0.3,
+                a: 0.9,
+            };
+            const HANDLE_OUTLINE_WIDTH: f64 = 2.0;
+
+            let handles = self.handles.borrow();
+            if handles.is_empty() {
+                return;
+            }
+
+            let draw = || -> Result<(), anyhow::Error> {
+                let selected_handle = self.selected_handle.get();
+                let group = handles
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, handle)| {
+                        let pos = canvas.transform_sheet_coords_to_canvas_coords(*handle);
+                        let pos = canvas.translate_coordinates(widget, pos[0], pos[1])?;
+                        let fill = if selected_handle == Some(i) {
+                            SELECTED_HANDLE_FILL
+                        } else {
+                            HANDLE_FILL
+                        };
+
+                        Some((pos, fill))
+                    })
+                    .fold(element::Group::new(), |group, (pos, fill)| {
+                        group.add(
+                            element::Circle::new()
+                                .set("cx", pos.0)
+                                .set("cy", pos.1)
+                                .set("r", super::SelectionModifier::HANDLE_RADIUS)
+                                .set("stroke", HANDLE_OUTLINE_COLOR.to_css_color())
+                                .set("stroke-width", HANDLE_OUTLINE_WIDTH)
+                                .set("fill", fill.to_css_color()),
+                        )
+                    });
+
+                let svg_data = compose::svg_node_to_string(&group).map_err(|e| {
+                    anyhow::anyhow!(
+                        "node_to_string() failed in draw_handles() for selection_modifier, {}",
+                        e
+                    )
+                })?;
+
+                let svg = render::Svg {
+                    bounds: widget_bounds,
+                    svg_data,
+                };
+                let images =
+                    canvas
+                        .renderer()
+                        .read()
+                        .unwrap()
+                        .gen_images(1.0, vec![svg], widget_bounds)?;
+                if let Some(rendernode) = render::images_to_rendernode(&images, 1.0).context(
+                    "image_to_rendernode() in draw_handles() in selection_modifier failed",
+                )? {
+                    snapshot.append_node(&rendernode);
+                }
+                Ok(())
+            };
+
+            if let Err(e) = draw() {
+                log::error!(
+                    "draw_handles() for selection_modifier failed with Err {}",
+                    e
+                );
+            }
+        }
     }
 }
 
@@ -430,6 +566,10 @@ impl SelectionModifier {
     pub const RESIZE_NODE_SIZE: i32 = 18;
     // must not be < 2 * RESIZE_NODE_SIZE + its margins
     pub const SELECTION_BOUNDS_MIN: f64 = 60.0;
+    /// The radius of the editing handles of a selected shape or the nodes of a selected brush stroke, in px
+    pub const HANDLE_RADIUS: f64 = 6.0;
+    /// The radius around the handles in which they can be grabbed, in px
+    pub const HANDLE_GRAB_RADIUS: f64 = 12.0;
 
     pub fn new() -> Self {
         let selection_modifier: Self =
@@ -467,6 +607,10 @@ impl SelectionModifier {
             .get()
     }
 
+    pub fn shear_node(&self) -> ModifierNode {
+        imp::SelectionModifier::from_instance(self).shear_node.get()
+    }
+
     pub fn translate_node(&self) -> gtk4::Box {
         imp::SelectionModifier::from_instance(self)
             .translate_node
@@ -491,9 +635,108 @@ impl SelectionModifier {
             .set(bounds.map(|bounds| AABB::new_positive(bounds.mins, bounds.maxs)));
     }
 
+    pub fn node_editing(&self) -> bool {
+        self.property::<bool>("node-editing")
+    }
+
+    pub fn set_node_editing(&self, node_editing: bool) {
+        self.set_property::<bool>("node-editing", node_editing);
+    }
+
+    /// The index of the handle which was last grabbed
+    pub fn selected_handle(&self) -> Option<usize> {
+        self.imp().selected_handle.get()
+    }
+
+    /// The handles of the selected shape, or the nodes of the selected brush stroke when editing nodes
+    fn gen_handles(&self, canvas: &Canvas) -> Vec<na::Vector2<f64>> {
+        let sheet = canvas.sheet();
+        let sheet = sheet.borrow();
+        let strokes_state = &sheet.strokes_state;
+
+        if self.node_editing() && strokes_state.selected_single_brush_stroke().is_some() {
+            strokes_state.selection_brush_nodes()
+        } else {
+            strokes_state.selection_shape_handles()
+        }
+    }
+
+    /// The key of the stroke the handles belong to, see gen_handles()
+    fn gen_handles_key(&self, canvas: &Canvas) -> Option<StrokeKey> {
+        let sheet = canvas.sheet();
+        let sheet = sheet.borrow();
+        let strokes_state = &sheet.strokes_state;
+
+        if self.node_editing() {
+            strokes_state
+                .selected_single_brush_stroke()
+                .or_else(|| strokes_state.selected_single_shape_stroke())
+        } else {
+            strokes_state.selected_single_shape_stroke()
+        }
+    }
+
+    /// Moves the handle with the given index, see gen_handles(). Returns the new index of the handle
+    fn move_handle(&self, canvas: &Canvas, handle: usize, pos: na::Vector2<f64>) -> usize {
+        let node_editing = self.node_editing();
+        let sheet = canvas.sheet();
+        let mut sheet = sheet.borrow_mut();
+        let strokes_state = &mut sheet.strokes_state;
+
+        if node_editing && strokes_state.selected_single_brush_stroke().is_some() {
+            strokes_state.move_selection_brush_node(handle, pos, canvas.renderer(), canvas.zoom());
+            handle
+        } else {
+            strokes_state
+                .move_selection_shape_handle(handle, pos, canvas.renderer(), canvas.zoom())
+                .unwrap_or(handle)
+        }
+    }
+
+    /// Groups the history entries recorded while a node is dragged, so the drag is undone in one step
+    fn begin_drag(canvas: &Canvas) {
+        canvas
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .history_mut()
+            .begin_group();
+    }
+
+    fn end_drag(canvas: &Canvas) {
+        canvas
+            .sheet()
+            .borrow_mut()
+            .strokes_state
+            .history_mut()
+            .end_group();
+    }
+
     /// Updates the internal state for measuring the widgets size, allocation, etc.
     pub fn update_state(&self, canvas: &Canvas) {
-        self.set_selection_bounds(canvas.sheet().borrow().strokes_state.gen_selection_bounds());
+        let handles = self.gen_handles(canvas);
+        let handles_key = self.gen_handles_key(canvas);
+        // The selected handle belongs to another stroke, or it was removed
+        if handles_key != self.imp().handles_key.get()
+            || handles.len() != self.imp().handles.borrow().len()
+        {
+            self.imp().selected_handle.set(None);
+        }
+        self.imp().handles_key.set(handles_key);
+        // The handles can lie outside of the bounds of the shape (e.g. the control points of a bezier curve)
+        let selection_bounds = canvas
+            .sheet()
+            .borrow()
+            .strokes_state
+            .gen_selection_bounds()
+            .map(|mut selection_bounds| {
+                for handle in handles.iter() {
+                    selection_bounds.take_point(na::Point2::from(*handle));
+                }
+                selection_bounds
+            });
+        self.set_selection_bounds(selection_bounds);
+        self.imp().handles.replace(handles);
         self.set_visible(self.selection_bounds().is_some());
 
         if let Some(selection_bounds) = self.selection_bounds() {
@@ -533,6 +776,7 @@ impl SelectionModifier {
         self.init_resize_bl_node(appwindow);
         self.init_resize_br_node(appwindow);
         self.init_rotate_node(appwindow);
+        self.init_shear_node(appwindow);
         self.init_translate_node(appwindow);
     }
 
@@ -550,6 +794,7 @@ impl SelectionModifier {
         resize_tl_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -592,6 +837,8 @@ impl SelectionModifier {
         );
         resize_tl_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
                 start_bounds.set(None);
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
@@ -617,6 +864,7 @@ impl SelectionModifier {
         resize_tr_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -659,6 +907,8 @@ impl SelectionModifier {
         );
         resize_tr_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
                 start_bounds.set(None);
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
@@ -684,6 +934,7 @@ impl SelectionModifier {
         resize_bl_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -726,6 +977,8 @@ impl SelectionModifier {
         );
         resize_bl_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
                 start_bounds.set(None);
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
@@ -751,6 +1004,7 @@ impl SelectionModifier {
         resize_br_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
                 start_bounds.set(selection_modifier.selection_bounds());
 
                 selection_modifier.update_state(&appwindow.canvas());
@@ -793,6 +1047,8 @@ impl SelectionModifier {
         );
         resize_br_drag_gesture.connect_drag_end(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
                 start_bounds.set(None);
 
                 appwindow.canvas().sheet().borrow_mut().strokes_state.update_geometry_selection_strokes();
@@ -813,16 +1069,53 @@ impl SelectionModifier {
             .translate_node
             .add_controller(&translate_node_drag_gesture);
 
+        // The index of the handle that is dragged instead of translating the selection
+        let dragged_handle: Rc<Cell<Option<usize>>> = Rc::new(Cell::new(None));
+
         translate_node_drag_gesture.connect_drag_begin(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |translate_node_drag_gesture, _x, _y| {
+            clone!(@strong dragged_handle, @weak self as selection_modifier, @weak appwindow => move |translate_node_drag_gesture, _x, _y| {
                 translate_node_drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
 
                 selection_modifier.update_state(&appwindow.canvas());
+
+                if let Some(start_point) = translate_node_drag_gesture.start_point() {
+                    let current_pos = {
+                        let pos = selection_modifier.translate_node().translate_coordinates(&appwindow.canvas(), start_point.0, start_point.1).unwrap();
+                        appwindow.canvas().transform_canvas_coords_to_sheet_coords(na::vector![pos.0, pos.1])
+                    };
+                    let grab_radius = Self::HANDLE_GRAB_RADIUS / appwindow.canvas().total_zoom();
+
+                    dragged_handle.set(
+                        selection_modifier.imp().handles.borrow().iter().position(|handle| (handle - current_pos).magnitude() <= grab_radius)
+                    );
+                    if dragged_handle.get().is_some() {
+                        selection_modifier.imp().selected_handle.set(dragged_handle.get());
+                    }
+                }
             }),
         );
         translate_node_drag_gesture.connect_drag_update(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, x, y| {
+            clone!(@strong dragged_handle, @weak self as selection_modifier, @weak appwindow => move |translate_node_drag_gesture, x, y| {
                 let zoom = appwindow.canvas().zoom();
+
+                if let (Some(handle), Some(start_point)) = (dragged_handle.get(), translate_node_drag_gesture.start_point()) {
+                    // The selection bounds stay the same while dragging a handle, so the node does not move
+                    let current_pos = {
+                        let pos = selection_modifier.translate_node().translate_coordinates(&appwindow.canvas(), start_point.0 + x, start_point.1 + y).unwrap();
+                        appwindow.canvas().transform_canvas_coords_to_sheet_coords(na::vector![pos.0, pos.1])
+                    };
+
+                    // Rectangle corners change their index when they are dragged past the opposite edges
+                    let handle = selection_modifier.move_handle(&appwindow.canvas(), handle, current_pos);
+                    dragged_handle.set(Some(handle));
+                    selection_modifier.imp().selected_handle.set(Some(handle));
+                    selection_modifier.imp().handles.replace(selection_modifier.gen_handles(&appwindow.canvas()));
+
+                    appwindow.canvas().queue_draw();
+                    return;
+                }
+
                 let offset = na::vector![x.round() / zoom, y.round() / zoom];
 
                 let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
@@ -834,7 +1127,10 @@ impl SelectionModifier {
             }),
         );
         translate_node_drag_gesture.connect_drag_end(
-            clone!(@weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, _x, _y| {
+            clone!(@strong dragged_handle, @weak self as selection_modifier, @weak appwindow => move |_translate_node_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+                dragged_handle.set(None);
+
                 selection_modifier.update_state(&appwindow.canvas());
                 appwindow.canvas().queue_draw();
             }),
@@ -855,6 +1151,7 @@ impl SelectionModifier {
         rotate_node_drag_gesture.connect_drag_begin(
             clone!(@strong start_bounds, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
                 drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
                 selection_modifier.update_state(&appwindow.canvas());
 
                 start_bounds.set(selection_modifier.selection_bounds());
@@ -914,6 +1211,8 @@ impl SelectionModifier {
         );
         rotate_node_drag_gesture.connect_drag_end(
             clone!(@weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
                 selection_modifier.imp().start_rotation_center.set(None);
                 selection_modifier.imp().start_rotation_angle.set(0.0);
                 selection_modifier.imp().current_rotation_angle.set(0.0);
@@ -923,4 +1222,75 @@ impl SelectionModifier {
             }),
         );
     }
+
+    pub fn init_shear_node(&self, appwindow: &RnoteAppWindow) {
+        let shear_node_drag_gesture = GestureDrag::builder()
+            .name("shear_node_drag_gesture")
+            .propagation_phase(PropagationPhase::Capture)
+            .build();
+        self.imp()
+            .shear_node
+            .add_controller(&shear_node_drag_gesture);
+
+        let start_bounds: Rc<Cell<Option<AABB>>> = Rc::new(Cell::new(None));
+        let start_pos: Rc<Cell<Option<na::Vector2<f64>>>> = Rc::new(Cell::new(None));
+        let current_shear: Rc<Cell<f64>> = Rc::new(Cell::new(0.0));
+
+        shear_node_drag_gesture.connect_drag_begin(
+            clone!(@strong start_bounds, @strong start_pos, @strong current_shear, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, _x, _y| {
+                drag_gesture.set_state(EventSequenceState::Claimed);
+                Self::begin_drag(&appwindow.canvas());
+                selection_modifier.update_state(&appwindow.canvas());
+
+                start_bounds.set(selection_modifier.selection_bounds());
+                current_shear.set(0.0);
+                if let Some(start_point) = drag_gesture.start_point() {
+                    let pos = selection_modifier.shear_node().translate_coordinates(&appwindow.canvas(), start_point.0, start_point.1).unwrap();
+                    start_pos.set(Some(appwindow.canvas().transform_canvas_coords_to_sheet_coords(na::vector![pos.0, pos.1])));
+                }
+            }),
+        );
+        shear_node_drag_gesture.connect_drag_update(
+            clone!(@strong start_bounds, @strong start_pos, @strong current_shear, @weak self as selection_modifier, @weak appwindow => move |drag_gesture, x, y| {
+                let zoom = appwindow.canvas().zoom();
+
+                if let (Some(start_bounds), Some(start_pos), Some(start_point)) = (start_bounds.get(), start_pos.get(), drag_gesture.start_point()) {
+                    if start_bounds.extents()[1] <= 0.0 {
+                        return;
+                    }
+
+                    let current_pos = {
+                        let pos = selection_modifier.shear_node().translate_coordinates(&appwindow.canvas(), start_point.0 + x, start_point.1 + y).unwrap();
+                        appwindow.canvas().transform_canvas_coords_to_sheet_coords(na::vector![pos.0, pos.1])
+                    };
+
+                    // The top edge follows the drag, while the bottom edge stays in place
+                    let shear = -(current_pos[0] - start_pos[0]) / start_bounds.extents()[1];
+                    let shear_delta = shear - current_shear.get();
+                    let center = na::point![start_bounds.center()[0], start_bounds.maxs[1]];
+
+                    let selection_keys = appwindow.canvas().sheet().borrow().strokes_state.selection_keys_as_rendered();
+                    appwindow.canvas().sheet().borrow_mut().strokes_state.shear_strokes(&selection_keys, na::vector![shear_delta, 0.0], center, appwindow.canvas().renderer(), zoom);
+                    selection_modifier.update_state(&appwindow.canvas());
+
+                    current_shear.set(shear);
+
+                    selection_modifier.update_translate_node_size_request(&appwindow.canvas());
+                    appwindow.canvas().queue_draw();
+                }
+            }),
+        );
+        shear_node_drag_gesture.connect_drag_end(
+            clone!(@strong start_bounds, @strong start_pos, @strong current_shear, @weak self as selection_modifier, @weak appwindow => move |_drag_gesture, _x, _y| {
+                Self::end_drag(&appwindow.canvas());
+
+                start_bounds.set(None);
+                start_pos.set(None);
+                current_shear.set(0.0);
+
+                selection_modifier.update_state(&appwindow.canvas());
+                appwindow.canvas().queue_draw();
+            }),
+        );
+    }
 }
@@ -28,6 +28,8 @@ mod imp {
         #[template_child]
         pub general_pdf_import_as_bitmap_toggle: TemplateChild<ToggleButton>,
         #[template_child]
+        pub general_undo_depth_adj: TemplateChild<Adjustment>,
+        #[template_child]
         pub format_predefined_formats_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub format_orientation_row: TemplateChild<adw::ActionRow>,
@@ -454,6 +456,12 @@ impl SettingsPanel {
             .clone()
     }
 
+    pub fn general_undo_depth_adj(&self) -> Adjustment {
+        imp::SettingsPanel::from_instance(self)
+            .general_undo_depth_adj
+            .clone()
+    }
+
     pub fn general_pdf_import_as_vector_toggle(&self) -> ToggleButton {
         imp::SettingsPanel::from_instance(self)
             .general_pdf_import_as_vector_toggle
@@ -524,6 +532,8 @@ impl SettingsPanel {
     pub fn load_misc(&self, appwindow: &RnoteAppWindow) {
         self.general_pdf_import_width_adj()
             .set_value(appwindow.canvas().pdf_import_width());
+        self.general_undo_depth_adj()
+            .set_value(f64::from(appwindow.canvas().undo_depth()));
     }
 
     pub fn load_format(&self, appwindow: &RnoteAppWindow) {
@@ -620,6 +630,14 @@ impl SettingsPanel {
             .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
             .build();
 
+        // Undo depth
+        self.imp()
+            .general_undo_depth_adj
+            .get()
+            .bind_property("value", &appwindow.canvas(), "undo-depth")
+            .flags(glib::BindingFlags::SYNC_CREATE | glib::BindingFlags::BIDIRECTIONAL)
+            .build();
+
         // Pdf import as vector or bitmap
         self.imp()
             .general_pdf_import_as_vector_toggle
@@ -663,6 +681,7 @@ impl SettingsPanel {
         self.imp().format_apply_button.get().connect_clicked(
             clone!(@weak temporary_format, @weak appwindow => move |_format_apply_button| {
                 let temporary_format = temporary_format.borrow().clone();
+                appwindow.canvas().sheet().borrow_mut().record_format();
                 appwindow.canvas().sheet().borrow_mut().format = temporary_format;
 
                 appwindow.canvas().resize_sheet_to_fit_strokes();
@@ -673,12 +692,16 @@ impl SettingsPanel {
 
         // Background
         self.imp().background_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_color_choosebutton| {
+            appwindow.canvas().sheet().borrow_mut().record_background();
             appwindow.canvas().sheet().borrow_mut().background.color = Color::from(background_color_choosebutton.rgba());
             appwindow.canvas().regenerate_background(true);
         }));
 
         self.imp().background_patterns_row.get().connect_selected_item_notify(clone!(@weak self as settings_panel, @weak appwindow => move |background_patterns_row| {
             if let Some(selected_item) = background_patterns_row.selected_item() {
+                // Also emitted when the row is loaded with the sheet background, so only record actual changes
+                let old_pattern = appwindow.canvas().sheet().borrow().background.pattern;
+
                 match selected_item
                     .downcast::<adw::EnumListItem>()
                     .unwrap()
@@ -713,11 +736,19 @@ impl SettingsPanel {
                     }
                 };
 
+                let new_pattern = appwindow.canvas().sheet().borrow().background.pattern;
+                if new_pattern != old_pattern {
+                    appwindow.canvas().sheet().borrow_mut().background.pattern = old_pattern;
+                    appwindow.canvas().sheet().borrow_mut().record_background();
+                    appwindow.canvas().sheet().borrow_mut().background.pattern = new_pattern;
+                }
+
                 appwindow.canvas().regenerate_background(true);
             }
         }));
 
         self.imp().background_pattern_color_choosebutton.connect_color_set(clone!(@weak appwindow => move |background_pattern_color_choosebutton| {
+            appwindow.canvas().sheet().borrow_mut().record_background();
             appwindow.canvas().sheet().borrow_mut().background.pattern_color = Color::from(background_pattern_color_choosebutton.rgba());
             appwindow.canvas().regenerate_background(true);
         }));
@@ -729,6 +760,10 @@ impl SettingsPanel {
                     let mut pattern_size = appwindow.canvas().sheet().borrow().background.pattern_size;
                     pattern_size[0] = f64::from(settings_panel.background_pattern_width_unitentry().value_in_px());
 
+                    // Also emitted when the entry is loaded with the sheet background, so only record actual changes
+                    if pattern_size != appwindow.canvas().sheet().borrow().background.pattern_size {
+                        appwindow.canvas().sheet().borrow_mut().record_background();
+                    }
                     appwindow.canvas().sheet().borrow_mut().background.pattern_size = pattern_size;
                     appwindow.canvas().regenerate_background(true);
 
@@ -743,6 +778,10 @@ impl SettingsPanel {
                     let mut pattern_size = appwindow.canvas().sheet().borrow().background.pattern_size;
                     pattern_size[1] = f64::from(settings_panel.background_pattern_height_unitentry().value_in_px());
 
+                    // Also emitted when the entry is loaded with the sheet background, so only record actual changes
+                    if pattern_size != appwindow.canvas().sheet().borrow().background.pattern_size {
+                        appwindow.canvas().sheet().borrow_mut().record_background();
+                    }
                     appwindow.canvas().sheet().borrow_mut().background.pattern_size = pattern_size;
                     appwindow.canvas().regenerate_background(true);
 
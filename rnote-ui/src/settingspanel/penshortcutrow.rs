@@ -2,8 +2,8 @@ mod imp {
     use std::cell::RefCell;
 
     use adw::{prelude::*, subclass::prelude::*};
-    use gtk4::CheckButton;
     use gtk4::{glib, glib::clone, glib::subclass::*, subclass::prelude::*, CompositeTemplate};
+    use gtk4::{CheckButton, DropDown};
     use once_cell::sync::Lazy;
     use rnote_engine::pens::shortcuts::{ShortcutAction, ShortcutKey};
     use rnote_engine::pens::PenStyle;
@@ -19,6 +19,8 @@ mod imp {
         pub action: RefCell<ShortcutAction>,
         pub changepenstyle_model: ChangePenStyleListModel,
 
+        #[template_child]
+        pub selection_operation_dropdown: TemplateChild<DropDown>,
         #[template_child]
         pub permanent_checker: TemplateChild<CheckButton>,
     }
@@ -31,6 +33,7 @@ mod imp {
                     style: PenStyle::EraserStyle,
                     permanent: false,
                 }),
+                selection_operation_dropdown: TemplateChild::<DropDown>::default(),
                 permanent_checker: TemplateChild::<CheckButton>::default(),
                 changepenstyle_model: ChangePenStyleListModel::default(),
             }
@@ -65,35 +68,20 @@ mod imp {
             obj.set_factory(Some(&*icon_factory));
 
             obj.connect_selected_item_notify(move |obj| {
-                if let Some(selected_item) = obj.selected_item() {
-                    let new_pen_style = PenStyle::try_from(
-                        selected_item
-                            .downcast::<adw::EnumListItem>()
-                            .unwrap()
-                            .value() as u32,
-                    )
-                    .unwrap();
-
-                    match &mut *obj.imp().action.borrow_mut() {
-                        ShortcutAction::ChangePenStyle {
-                            ref mut style,
-                            permanent: _,
-                        } => {
-                            *style = new_pen_style;
-                        }
-                    }
-                    obj.emit_by_name::<()>("action-changed", &[]);
-                }
+                obj.update_action();
             });
 
+            self.selection_operation_dropdown
+                .get()
+                .connect_selected_notify(
+                    clone!(@weak obj => move |_selection_operation_dropdown| {
+                        obj.update_action();
+                    }),
+                );
+
             self.permanent_checker.get().connect_toggled(
-                clone!(@weak obj => move |permanent_checker| {
-                    match &mut *obj.imp().action.borrow_mut() {
-                        ShortcutAction::ChangePenStyle { style: _, ref mut permanent } => {
-                            *permanent = permanent_checker.is_active();
-                        }
-                    }
-                    obj.emit_by_name::<()>("action-changed", &[]);
+                clone!(@weak obj => move |_permanent_checker| {
+                    obj.update_action();
                 }),
             );
 
@@ -142,7 +130,9 @@ mod imp {
 }
 use adw::prelude::*;
 use gtk4::{glib, subclass::prelude::*};
+use rnote_engine::pens::selector::SelectionOperation;
 use rnote_engine::pens::shortcuts::{ShortcutAction, ShortcutKey};
+use rnote_engine::pens::PenStyle;
 
 glib::wrapper! {
     pub struct PenShortcutRow(ObjectSubclass<imp::PenShortcutRow>)
@@ -160,8 +150,63 @@ impl PenShortcutRow {
         match action {
             ShortcutAction::ChangePenStyle { style, permanent } => {
                 self.set_selected(self.imp().changepenstyle_model.find_position(style as i32));
+                self.imp().selection_operation_dropdown.set_selected(0);
+                self.imp()
+                    .selection_operation_dropdown
+                    .set_visible(style == PenStyle::SelectorStyle);
+                self.imp().permanent_checker.set_sensitive(true);
                 self.imp().permanent_checker.set_active(permanent);
             }
+            ShortcutAction::ChangeSelectionOperation { operation } => {
+                self.set_selected(
+                    self.imp()
+                        .changepenstyle_model
+                        .find_position(PenStyle::SelectorStyle as i32),
+                );
+                self.imp()
+                    .selection_operation_dropdown
+                    .set_selected(operation as u32);
+                self.imp().selection_operation_dropdown.set_visible(true);
+                // The selection operation only applies to the next selection
+                self.imp().permanent_checker.set_sensitive(false);
+                self.imp().permanent_checker.set_active(false);
+            }
+        }
+    }
+
+    /// Updates the action from the selected pen style, the selection operation and the permanent checker
+    fn update_action(&self) {
+        let pen_style = if let Some(selected_item) = self.selected_item() {
+            PenStyle::try_from(
+                selected_item
+                    .downcast::<adw::EnumListItem>()
+                    .unwrap()
+                    .value() as u32,
+            )
+            .unwrap()
+        } else {
+            return;
+        };
+        // The dropdown items are in the same order as the selection operation variants
+        let operation = match self.imp().selection_operation_dropdown.selected() {
+            1 => Some(SelectionOperation::Add),
+            2 => Some(SelectionOperation::Subtract),
+            _ => None,
+        };
+
+        let action = match (pen_style, operation) {
+            (PenStyle::SelectorStyle, Some(operation)) => {
+                ShortcutAction::ChangeSelectionOperation { operation }
+            }
+            _ => ShortcutAction::ChangePenStyle {
+                style: pen_style,
+                permanent: self.imp().permanent_checker.is_active(),
+            },
+        };
+
+        if action != self.action() {
+            *self.imp().action.borrow_mut() = action;
+            self.emit_by_name::<()>("action-changed", &[]);
         }
     }
 
@@ -281,6 +281,16 @@ impl UnitEntry {
         )
     }
 
+    pub fn set_value_in_px(&self, value_in_px: f64) {
+        self.set_value(format::MeasureUnit::convert_measurement(
+            value_in_px,
+            format::MeasureUnit::Px,
+            self.dpi(),
+            self.unit(),
+            self.dpi(),
+        ));
+    }
+
     pub fn convert_current_value(&self, desired_unit: format::MeasureUnit) {
         let converted_value = format::MeasureUnit::convert_measurement(
             self.value(),